@@ -0,0 +1,35 @@
+/* C interface to the gfalook renderer (built as libgfalook by `cargo build --release`). */
+#ifndef GFALOOK_H
+#define GFALOOK_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque handle to a parsed GFA graph. */
+typedef struct GfalookGraph GfalookGraph;
+
+/* Load a GFA file. Returns NULL on error. */
+GfalookGraph *gfalook_graph_load(const char *path);
+
+/* Free a graph from gfalook_graph_load. NULL is ignored. */
+void gfalook_graph_free(GfalookGraph *graph);
+
+/*
+ * Render with gfalook command-line options (e.g. {"-x", "800", "-m"}) into a new RGBA
+ * buffer of (*width) * (*height) * 4 bytes. A -r option renders only that window of the
+ * graph. Returns NULL if the options are invalid or rendering fails or panics.
+ */
+uint8_t *gfalook_render(const GfalookGraph *graph, int argc, const char *const *argv,
+                        uint32_t *width, uint32_t *height);
+
+/* Free a buffer from gfalook_render, passing the dimensions it reported. NULL is ignored. */
+void gfalook_buffer_free(uint8_t *buffer, uint32_t width, uint32_t height);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GFALOOK_H */
@@ -0,0 +1,175 @@
+//! `gfalook bench`: time each pipeline phase on a user's own graph, with the peak memory
+//! of each phase, so performance changes between versions can be measured.
+
+use super::error::GfalookError;
+use super::{
+    adjust_bins, buffer_to_image, cluster_paths_by_similarity, parse_gfa, path_bins,
+    path_depth_matrix, render, Args, GfaPath,
+};
+use clap::Parser;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+#[derive(Parser)]
+#[command(name = "gfalook bench", bin_name = "gfalook bench")]
+#[command(about = "Time each phase of the gfalook pipeline on a graph.", long_about = None)]
+pub struct BenchArgs {
+    /// Load the variation graph in GFA format from this FILE.
+    #[arg(short = 'i', long = "idx", value_name = "FILE")]
+    idx: PathBuf,
+
+    /// Run every phase N times and report the fastest, median and slowest run.
+    #[arg(short = 'r', long = "repeat", value_name = "N", default_value_t = 3)]
+    repeat: usize,
+
+    /// Skip the clustering phase, which is quadratic in the number of paths.
+    #[arg(long = "skip-cluster")]
+    skip_cluster: bool,
+
+    /// Rendering options as for a normal run, after `--` (e.g. `-- -x 2000 -m`).
+    #[arg(last = true, value_name = "OPTIONS")]
+    options: Vec<String>,
+}
+
+/// Timings and peak resident memory of one phase over all runs
+struct PhaseStats {
+    name: &'static str,
+    runs: Vec<Duration>,
+    peak_rss_kb: Option<u64>,
+}
+
+/// Run `f` `repeat` times, recording its wall time and the highest peak RSS of any run
+fn measure<T>(name: &'static str, repeat: usize, mut f: impl FnMut() -> T) -> (PhaseStats, T) {
+    let mut runs = Vec::with_capacity(repeat);
+    let mut peak_rss_kb = None;
+    let mut last = None;
+    for _ in 0..repeat.max(1) {
+        drop(last.take());
+        reset_peak_rss();
+        let start = Instant::now();
+        last = Some(f());
+        runs.push(start.elapsed());
+        peak_rss_kb = peak_rss_kb.max(peak_rss());
+    }
+    let stats = PhaseStats {
+        name,
+        runs,
+        peak_rss_kb,
+    };
+    (stats, last.unwrap())
+}
+
+/// Reset the kernel's peak RSS counter so the next reading covers only the next phase
+/// (Linux only; elsewhere the reading is cumulative or unavailable)
+fn reset_peak_rss() {
+    let _ = std::fs::write("/proc/self/clear_refs", "5");
+}
+
+/// Peak resident set size in KiB, where the platform reports it
+fn peak_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+/// Run the benchmark and print one TSV line per phase to stdout
+pub fn run(bench: &BenchArgs) -> Result<(), GfalookError> {
+    let mut argv = vec![
+        "gfalook".to_string(),
+        "-i".to_string(),
+        bench.idx.to_string_lossy().into_owned(),
+        "-o".to_string(),
+        "bench.png".to_string(),
+    ];
+    argv.extend(bench.options.iter().cloned());
+    let args = Args::try_parse_from(argv).map_err(|e| {
+        let msg = e.to_string();
+        let first_line = msg.lines().next().unwrap_or_default();
+        GfalookError::Usage(format!(
+            "invalid rendering options: {}",
+            first_line.trim_start_matches("error: ")
+        ))
+    })?;
+    let mut phases = Vec::new();
+
+    let (stats, graph) = measure("parse", bench.repeat, || parse_gfa(&bench.idx));
+    let graph =
+        graph.map_err(|e| GfalookError::from_io("failed to load GFA file", &bench.idx, e))?;
+    phases.push(stats);
+
+    let paths: Vec<_> = graph.paths.iter().collect();
+    let viz_width = args.width.min(graph.total_length as u32).max(1);
+    let bin_width = args
+        .bin_width
+        .unwrap_or_else(|| graph.total_length as f64 / viz_width as f64);
+    let drawn_bins = |path: &GfaPath| {
+        let mut bins = path_bins(path, &graph, bin_width, None);
+        adjust_bins(&args, &mut bins, None);
+        bins
+    };
+    let (stats, _) = measure("bin", bench.repeat, || {
+        path_depth_matrix(&paths, viz_width as usize, &drawn_bins)
+    });
+    phases.push(stats);
+
+    if !bench.skip_cluster {
+        let segment_lengths: Vec<u64> = graph.segments.iter().map(|s| s.sequence_len).collect();
+        let (stats, _) = measure("cluster", bench.repeat, || {
+            cluster_paths_by_similarity(
+                &paths,
+                &segment_lengths,
+                args.cluster_threshold,
+                args.cluster_all_nodes,
+                args.max_clusters,
+                args.dendrogram || args.use_upgma,
+                args.use_upgma,
+                args.upgma_threshold,
+                None,
+                args.min_cluster_size,
+                args.dbscan_min_pts,
+                args.cluster_bootstrap,
+                args.similarity_level,
+            )
+        });
+        phases.push(stats);
+    }
+
+    let (stats, buffer) = measure("render", bench.repeat, || render(&args, &graph));
+    let buffer = buffer?;
+    phases.push(stats);
+
+    let image = buffer_to_image(&buffer);
+    let (stats, encoded) = measure("encode", bench.repeat, || {
+        let mut png = std::io::Cursor::new(Vec::new());
+        image
+            .write_to(&mut png, image::ImageFormat::Png)
+            .map(|_| png.into_inner().len())
+    });
+    encoded.map_err(|e| GfalookError::Io(format!("failed to encode PNG: {}", e)))?;
+    phases.push(stats);
+
+    println!("phase\truns\tmin_ms\tmedian_ms\tmax_ms\tpeak_rss_mb");
+    for phase in &phases {
+        let mut ms: Vec<f64> = phase
+            .runs
+            .iter()
+            .map(|d| d.as_secs_f64() * 1000.0)
+            .collect();
+        ms.sort_by(|a, b| a.total_cmp(b));
+        let rss = phase
+            .peak_rss_kb
+            .map_or("NA".to_string(), |kb| format!("{:.1}", kb as f64 / 1024.0));
+        println!(
+            "{}\t{}\t{:.1}\t{:.1}\t{:.1}\t{}",
+            phase.name,
+            ms.len(),
+            ms[0],
+            ms[ms.len() / 2],
+            ms[ms.len() - 1],
+            rss
+        );
+    }
+    Ok(())
+}
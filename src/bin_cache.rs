@@ -0,0 +1,78 @@
+//! Reuse of per-path bins across --watch re-renders. Binning walks every base of every
+//! displayed path, while most edits (colors, row order, the selected rows) leave a path's
+//! bins as they were. Bins are keyed by the path's name and steps and by everything else
+//! the binning reads: the bin width, the segment layout and the highlighted nodes. Bins the
+//! last render did not use are dropped.
+
+use super::{BinInfo, GfaPath, Graph};
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+type Bins = FxHashMap<usize, BinInfo>;
+
+struct Cache {
+    /// Number of the current render
+    render: u64,
+    /// Bins by path name and step/binning hash, with the render that last used them
+    entries: FxHashMap<(String, u64), (u64, Bins)>,
+}
+
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+
+/// Start a render, dropping the bins the previous one did not use
+pub fn next_render() {
+    let mut cache = CACHE.lock().unwrap();
+    let cache = cache.get_or_insert_with(|| Cache {
+        render: 0,
+        entries: FxHashMap::default(),
+    });
+    cache.render += 1;
+    let render = cache.render;
+    cache.entries.retain(|_, (used, _)| *used + 1 >= render);
+}
+
+/// Hash of the binning inputs other than the path itself
+pub fn binning_key(graph: &Graph, bin_width: f64, highlight_nodes: Option<&FxHashSet<u64>>) -> u64 {
+    let mut hasher = FxHasher::default();
+    bin_width.to_bits().hash(&mut hasher);
+    graph.segment_offsets.hash(&mut hasher);
+    for segment in &graph.segments {
+        segment.sequence_len.hash(&mut hasher);
+        segment.n_count.hash(&mut hasher);
+    }
+    if let Some(nodes) = highlight_nodes {
+        let mut ids: Vec<u64> = nodes.iter().copied().collect();
+        ids.sort_unstable();
+        ids.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Bins of `path`, reused when an earlier render binned the same steps with the same
+/// `binning` key, or computed; without a key nothing is cached
+pub fn bins(binning: Option<u64>, path: &GfaPath, compute: impl FnOnce() -> Bins) -> Bins {
+    let Some(binning) = binning else {
+        return compute();
+    };
+    let mut hasher = FxHasher::default();
+    binning.hash(&mut hasher);
+    for step in &path.steps {
+        step.segment_id.hash(&mut hasher);
+        step.is_reverse.hash(&mut hasher);
+    }
+    let key = (path.name.clone(), hasher.finish());
+    if let Some(cache) = CACHE.lock().unwrap().as_mut() {
+        let render = cache.render;
+        if let Some((used, bins)) = cache.entries.get_mut(&key) {
+            *used = render;
+            return bins.clone();
+        }
+    }
+    // Bin outside the lock so that rows binned in parallel do not wait on each other
+    let bins = compute();
+    if let Some(cache) = CACHE.lock().unwrap().as_mut() {
+        cache.entries.insert(key, (cache.render, bins.clone()));
+    }
+    bins
+}
@@ -0,0 +1,202 @@
+//! Reuse of clustering results across renders. A result is keyed by everything the
+//! clustering reads (the clustered paths' steps, segment lengths, BED regions and
+//! clustering options), so palette, size or label changes skip the clustering stage.
+//! The last result is kept in memory for --watch re-renders and, with `--cache DIR`,
+//! on disk for later runs.
+
+use super::{Args, ClusteringBedRegions, ClusteringResult, Dendrogram, DendrogramNode, GfaPath};
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Bumped whenever the clustering or the file format changes
+const FORMAT: &str = "gfalook-cluster-cache 1";
+
+static LAST: Mutex<Option<(String, ClusteringResult)>> = Mutex::new(None);
+
+/// Key for clustering `paths` with the current options
+pub fn key(
+    args: &Args,
+    paths: &[&GfaPath],
+    segment_lengths: &[u64],
+    bed_regions: Option<&ClusteringBedRegions>,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(FORMAT.as_bytes());
+    let options = format!(
+        "{:?}|{}|{:?}|{}|{}|{:?}|{}|{}|{}|{:?}",
+        args.cluster_threshold,
+        args.cluster_all_nodes,
+        args.max_clusters,
+        args.dendrogram || args.use_upgma,
+        args.use_upgma,
+        args.upgma_threshold,
+        args.min_cluster_size,
+        args.dbscan_min_pts,
+        args.cluster_bootstrap,
+        args.similarity_level
+    );
+    hasher.update(options.as_bytes());
+    for len in segment_lengths {
+        hasher.update(len.to_le_bytes());
+    }
+    for path in paths {
+        hasher.update(path.name.as_bytes());
+        hasher.update([0]);
+        for step in &path.steps {
+            hasher.update(step.segment_id.to_le_bytes());
+            hasher.update([step.is_reverse as u8]);
+        }
+        if let Some(regions) = bed_regions.and_then(|bed| bed.path_regions.get(&path.name)) {
+            for region in regions {
+                hasher.update(region.start.to_le_bytes());
+                hasher.update(region.end.to_le_bytes());
+            }
+        }
+        hasher.update([0xff]);
+    }
+    hasher.finalize().iter().fold(String::new(), |mut hex, b| {
+        let _ = write!(hex, "{:02x}", b);
+        hex
+    })
+}
+
+/// A stored result for `key`, from memory or from the cache directory
+pub fn get(key: &str, dir: Option<&Path>) -> Option<ClusteringResult> {
+    if let Some((last_key, result)) = LAST.lock().unwrap().as_ref() {
+        if last_key == key {
+            return Some(result.clone());
+        }
+    }
+    let file = dir?.join(format!("{}.clusters", key));
+    let text = std::fs::read_to_string(&file).ok()?;
+    let result = decode(&text);
+    if result.is_none() {
+        super::logging::warning(&format!("ignoring unreadable cluster cache {:?}", file));
+    }
+    result
+}
+
+/// Remember `result` for `key` in memory and, when given, in the cache directory
+pub fn put(key: &str, dir: Option<&Path>, result: &ClusteringResult) {
+    *LAST.lock().unwrap() = Some((key.to_string(), result.clone()));
+    if let Some(dir) = dir {
+        let file = dir.join(format!("{}.clusters", key));
+        if let Err(e) =
+            std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&file, encode(result)))
+        {
+            super::logging::warning(&format!("could not write cluster cache {:?}: {}", file, e));
+        }
+    }
+}
+
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn optional(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "-".to_string())
+}
+
+/// One `name values...` line per field; floats use Rust's round-trip formatting
+fn encode(result: &ClusteringResult) -> String {
+    let mut text = format!("{}\n", FORMAT);
+    let _ = writeln!(text, "ordering {}", join(&result.ordering));
+    let _ = writeln!(text, "cluster_ids {}", join(&result.cluster_ids));
+    let _ = writeln!(text, "num_clusters {}", result.num_clusters);
+    let _ = writeln!(text, "representatives {}", join(&result.representatives));
+    let _ = writeln!(text, "cluster_sizes {}", join(&result.cluster_sizes));
+    let noise = result.noise_cluster.map(|c| c.to_string());
+    let _ = writeln!(text, "noise_cluster {}", optional(noise));
+    let stability = result.stability.as_deref().map(join);
+    let _ = writeln!(text, "stability {}", optional(stability));
+    match &result.dendrogram {
+        Some(dg) => {
+            let _ = writeln!(
+                text,
+                "dendrogram {} {}",
+                dg.max_height,
+                join(&dg.leaf_order)
+            );
+            for node in &dg.nodes {
+                let _ = writeln!(
+                    text,
+                    "node {} {} {} {}",
+                    node.left, node.right, node.height, node.size
+                );
+            }
+        }
+        None => text.push_str("dendrogram -\n"),
+    }
+    text
+}
+
+fn decode(text: &str) -> Option<ClusteringResult> {
+    let mut lines = text.lines();
+    if lines.next()? != FORMAT {
+        return None;
+    }
+    let mut field = |name: &str| -> Option<Vec<String>> {
+        let line = lines.next()?;
+        let mut words = line.split(' ').filter(|w| !w.is_empty());
+        (words.next()? == name).then(|| words.map(str::to_string).collect())
+    };
+    fn parse<T: std::str::FromStr>(words: &[String]) -> Option<Vec<T>> {
+        words.iter().map(|w| w.parse().ok()).collect()
+    }
+    fn parse_optional<T: std::str::FromStr>(words: &[String]) -> Option<Option<Vec<T>>> {
+        if words.first().is_some_and(|w| w == "-") {
+            Some(None)
+        } else {
+            parse(words).map(Some)
+        }
+    }
+
+    let ordering = parse(&field("ordering")?)?;
+    let cluster_ids = parse(&field("cluster_ids")?)?;
+    let num_clusters = *parse::<usize>(&field("num_clusters")?)?.first()?;
+    let representatives = parse(&field("representatives")?)?;
+    let cluster_sizes = parse(&field("cluster_sizes")?)?;
+    let noise_cluster =
+        parse_optional::<usize>(&field("noise_cluster")?)?.and_then(|v| v.first().copied());
+    let stability = parse_optional(&field("stability")?)?;
+    let dendrogram_words = field("dendrogram")?;
+    let dendrogram = if dendrogram_words.first().is_some_and(|w| w == "-") {
+        None
+    } else {
+        let (max_height, leaf_order) = dendrogram_words.split_first()?;
+        let mut nodes = Vec::new();
+        while let Some(words) = field("node") {
+            let [left, right, height, size] = words.as_slice() else {
+                return None;
+            };
+            nodes.push(DendrogramNode {
+                left: left.parse().ok()?,
+                right: right.parse().ok()?,
+                height: height.parse().ok()?,
+                size: size.parse().ok()?,
+            });
+        }
+        Some(Dendrogram {
+            nodes,
+            leaf_order: parse(leaf_order)?,
+            max_height: max_height.parse().ok()?,
+        })
+    };
+
+    Some(ClusteringResult {
+        ordering,
+        cluster_ids,
+        num_clusters,
+        representatives,
+        cluster_sizes,
+        dendrogram,
+        noise_cluster,
+        stability,
+    })
+}
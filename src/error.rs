@@ -0,0 +1,61 @@
+//! Errors that stop a run, each mapped to its own process exit code.
+
+use std::fmt;
+use std::path::Path;
+
+/// A fatal error, grouped by what the user has to fix
+#[derive(Debug)]
+pub enum GfalookError {
+    /// Invalid option or option combination (exit code 2, as for clap errors)
+    Usage(String),
+    /// An input file could be read but not understood (exit code 3)
+    Parse(String),
+    /// Filters left nothing to draw or cluster (exit code 4)
+    EmptySelection(String),
+    /// A file could not be opened, read or written (exit code 5)
+    Io(String),
+    /// The graph has no P or W lines, so there are no paths to draw (exit code 6)
+    NoPaths(String),
+    /// Stopped by Ctrl-C at a phase boundary (exit code 130, as for SIGINT)
+    Interrupted(String),
+}
+
+impl GfalookError {
+    /// Classify an I/O failure on `path`: malformed contents are parse errors, everything
+    /// else (missing file, permissions, full disk) is an I/O error
+    pub fn from_io(what: &str, path: &Path, e: std::io::Error) -> Self {
+        let msg = format!("{} {:?}: {}", what, path, e);
+        match e.kind() {
+            std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof => {
+                GfalookError::Parse(msg)
+            }
+            _ => GfalookError::Io(msg),
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            GfalookError::Usage(_) => 2,
+            GfalookError::Parse(_) => 3,
+            GfalookError::EmptySelection(_) => 4,
+            GfalookError::Io(_) => 5,
+            GfalookError::NoPaths(_) => 6,
+            GfalookError::Interrupted(_) => super::interrupt::EXIT_CODE,
+        }
+    }
+}
+
+impl fmt::Display for GfalookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GfalookError::Usage(msg)
+            | GfalookError::Parse(msg)
+            | GfalookError::EmptySelection(msg)
+            | GfalookError::Io(msg)
+            | GfalookError::NoPaths(msg)
+            | GfalookError::Interrupted(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for GfalookError {}
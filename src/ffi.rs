@@ -0,0 +1,161 @@
+//! C ABI for embedding gfalook in C/C++ programs such as genome browsers; see
+//! `include/gfalook.h`.
+//!
+//! A graph is loaded once and rendered any number of times with CLI-style options. Rendered
+//! buffers are owned by the caller and released with `gfalook_buffer_free`.
+
+use crate::{embedded_args, embedded_graph, parse_gfa, render, Graph};
+use std::ffi::{c_char, c_int, CStr};
+use std::path::PathBuf;
+
+/// Opaque graph handle returned by `gfalook_graph_load`
+pub struct GfalookGraph {
+    source: PathBuf,
+    graph: Graph,
+}
+
+/// Load a GFA file. Returns NULL if the path is not UTF-8 or the file cannot be parsed.
+///
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn gfalook_graph_load(path: *const c_char) -> *mut GfalookGraph {
+    if path.is_null() {
+        return std::ptr::null_mut();
+    }
+    let Ok(path) = CStr::from_ptr(path).to_str() else {
+        return std::ptr::null_mut();
+    };
+    let source = PathBuf::from(path);
+    match parse_gfa(&source) {
+        Ok(graph) => Box::into_raw(Box::new(GfalookGraph { source, graph })),
+        Err(e) => {
+            eprintln!("[gfalook] error: could not load {:?}: {}", source, e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Free a graph returned by `gfalook_graph_load`. NULL is ignored.
+///
+/// # Safety
+/// `graph` must come from `gfalook_graph_load` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn gfalook_graph_free(graph: *mut GfalookGraph) {
+    if !graph.is_null() {
+        drop(Box::from_raw(graph));
+    }
+}
+
+/// Render `graph` with `argc` CLI options (e.g. `{"-x", "800", "-m"}`) into a newly
+/// allocated RGBA buffer of `*width * *height * 4` bytes. Returns NULL and leaves the
+/// dimensions untouched if the options are invalid or rendering fails or panics. A -r
+/// option renders only that window of the graph, as on the command line.
+///
+/// # Safety
+/// `graph` must be a live handle, `argv` must hold `argc` valid NUL-terminated strings
+/// (or be NULL when `argc` is 0), and `width`/`height` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn gfalook_render(
+    graph: *const GfalookGraph,
+    argc: c_int,
+    argv: *const *const c_char,
+    width: *mut u32,
+    height: *mut u32,
+) -> *mut u8 {
+    if graph.is_null() || width.is_null() || height.is_null() {
+        return std::ptr::null_mut();
+    }
+    let graph = &*graph;
+    let options: Vec<String> = (0..argc.max(0) as usize)
+        .map(|i| CStr::from_ptr(*argv.add(i)).to_string_lossy().into_owned())
+        .collect();
+    let rendered = std::panic::catch_unwind(|| render_pixels(graph, options));
+    let Ok(Some((w, h, pixels))) = rendered else {
+        if rendered.is_err() {
+            eprintln!("[gfalook] error: rendering panicked");
+        }
+        return std::ptr::null_mut();
+    };
+    *width = w;
+    *height = h;
+    Box::into_raw(pixels) as *mut u8
+}
+
+/// Render `graph` with CLI-style options, restricted to their -r window, into its width,
+/// height and RGBA pixels; None after reporting invalid options or a failed render
+fn render_pixels(graph: &GfalookGraph, options: Vec<String>) -> Option<(u32, u32, Box<[u8]>)> {
+    let args = match embedded_args(&graph.source, "gfalook.png", options) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("[gfalook] {}", e.to_string().trim_end());
+            return None;
+        }
+    };
+    let rendered = embedded_graph(&args, &graph.graph)
+        .and_then(|ranged| render(&args, ranged.as_ref().unwrap_or(&graph.graph)));
+    let buffer = match rendered {
+        Ok(buffer) => buffer,
+        Err(e) => {
+            eprintln!("[gfalook] error: {}", e);
+            return None;
+        }
+    };
+    let width = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+    let height = u32::from_le_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]);
+    Some((width, height, buffer[8..].into()))
+}
+
+/// Free a buffer returned by `gfalook_render`, given the same dimensions. NULL is ignored.
+///
+/// # Safety
+/// `buffer` must come from `gfalook_render` with exactly these `width` and `height`.
+#[no_mangle]
+pub unsafe extern "C" fn gfalook_buffer_free(buffer: *mut u8, width: u32, height: u32) {
+    if !buffer.is_null() {
+        let len = width as usize * height as usize * 4;
+        let pixels = std::ptr::slice_from_raw_parts_mut(buffer, len);
+        drop(Box::from_raw(pixels));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    /// Render the test graph with these options, returning the dimensions and pixels
+    fn render_with(graph: *const GfalookGraph, options: &[&str]) -> (u32, u32, Vec<u8>) {
+        let options: Vec<CString> = options.iter().map(|o| CString::new(*o).unwrap()).collect();
+        let argv: Vec<*const c_char> = options.iter().map(|o| o.as_ptr()).collect();
+        let (mut width, mut height) = (0, 0);
+        unsafe {
+            let buffer = gfalook_render(
+                graph,
+                argv.len() as c_int,
+                argv.as_ptr(),
+                &mut width,
+                &mut height,
+            );
+            assert!(!buffer.is_null(), "render failed for {:?}", options);
+            let pixels =
+                std::slice::from_raw_parts(buffer, width as usize * height as usize * 4).to_vec();
+            gfalook_buffer_free(buffer, width, height);
+            (width, height, pixels)
+        }
+    }
+
+    #[test]
+    fn path_range_renders_only_the_window() {
+        let path = CString::new(concat!(env!("CARGO_MANIFEST_DIR"), "/test/chr6.C4.gfa")).unwrap();
+        let graph = unsafe { gfalook_graph_load(path.as_ptr()) };
+        assert!(!graph.is_null());
+        let full = render_with(graph, &["-x", "400"]);
+        let window = render_with(
+            graph,
+            &["-x", "400", "-r", "chm13#chr6:31825251-31908851:1000-5000"],
+        );
+        unsafe { gfalook_graph_free(graph) };
+        assert_ne!(full, window);
+    }
+}
@@ -0,0 +1,616 @@
+//! The GFA graph model and its single-pass parser, including the rGFA segment layout.
+
+use crate::logging;
+use log::{debug, info};
+use rustc_hash::FxHashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// A segment (node) in the graph
+#[derive(Debug, Clone)]
+pub(crate) struct Segment {
+    pub(crate) sequence_len: u64,
+    pub(crate) n_count: u64, // Number of uncalled bases (N's) in the sequence
+    pub(crate) rank: Option<u32>, // rGFA SR:i: tag, 0 for the reference backbone
+}
+
+/// An edge between two segments
+#[derive(Debug, Clone)]
+pub(crate) struct Edge {
+    pub(crate) from_id: u64,
+    pub(crate) from_rev: bool,
+    pub(crate) to_id: u64,
+    pub(crate) to_rev: bool,
+    /// Declared by an L line rather than only implied by consecutive path steps
+    pub(crate) from_link: bool,
+}
+
+impl Edge {
+    /// The canonical key this edge was deduplicated under
+    pub(crate) fn key(&self) -> EdgeKey {
+        (self.from_id, self.from_rev, self.to_id, self.to_rev)
+    }
+}
+
+/// A step in a path: (segment_id, is_reverse)
+#[derive(Debug, Clone)]
+pub(crate) struct PathStep {
+    pub(crate) segment_id: u64,
+    pub(crate) is_reverse: bool,
+}
+
+/// A path through the graph
+#[derive(Debug, Clone)]
+pub(crate) struct GfaPath {
+    pub(crate) name: String,
+    pub(crate) steps: Vec<PathStep>,
+    /// Index and count of this piece when --split-discontiguous broke the path up
+    pub(crate) piece: Option<(usize, usize)>,
+}
+
+/// Minimal graph representation for visualization
+pub(crate) struct Graph {
+    pub(crate) segments: Vec<Segment>,
+    pub(crate) segment_name_to_id: FxHashMap<String, u64>,
+    pub(crate) segment_offsets: Vec<u64>,
+    pub(crate) total_length: u64,
+    pub(crate) paths: Vec<GfaPath>,
+    pub(crate) edges: Vec<Edge>,
+    pub(crate) origin: RangeOrigin,
+    pub(crate) header: GfaHeader,
+    /// The segment id of each S line, in file order, when the rGFA layout renumbered them;
+    /// empty when ids follow the file
+    pub(crate) file_segments: Vec<u64>,
+}
+
+/// Tags of the GFA header (H lines) as written, e.g. `VN:Z:1.0`, in file order
+#[derive(Clone, Default)]
+pub(crate) struct GfaHeader {
+    pub(crate) tags: Vec<String>,
+}
+
+impl GfaHeader {
+    /// Value of the first `tag`
+    fn get(&self, tag: &str) -> Option<&str> {
+        self.tags.iter().find_map(|field| {
+            let (name, rest) = field.split_once(':')?;
+            (name == tag).then(|| rest.split_once(':').map_or(rest, |(_, value)| value))
+        })
+    }
+
+    /// GFA version (VN)
+    pub(crate) fn version(&self) -> Option<&str> {
+        self.get("VN")
+    }
+
+    /// Reference samples named by the reference-sense tag (RS), space-separated
+    pub(crate) fn reference_samples(&self) -> Vec<&str> {
+        self.get("RS")
+            .map(|value| value.split_whitespace().collect())
+            .unwrap_or_default()
+    }
+
+    /// The header tags for the image metadata, or None without an H line
+    pub(crate) fn summary(&self) -> Option<String> {
+        (!self.tags.is_empty()).then(|| self.tags.join("\t"))
+    }
+}
+
+/// Whether a path is a reference: named after one of the header's reference samples (alone
+/// or before a PanSN `#` separator), or, without an RS tag, walking only rGFA rank-0 segments
+pub(crate) fn is_reference_path(graph: &Graph, path: &GfaPath) -> bool {
+    let samples = graph.header.reference_samples();
+    if !samples.is_empty() {
+        let sample = path.name.split('#').next().unwrap_or(&path.name);
+        return samples.contains(&sample);
+    }
+    !path.steps.is_empty()
+        && path
+            .steps
+            .iter()
+            .all(|step| graph.segments[step.segment_id as usize].rank == Some(0))
+}
+
+/// Paths that [`is_reference_path`] picks out, in graph order
+pub(crate) fn reference_paths(graph: &Graph) -> Vec<&GfaPath> {
+    graph
+        .paths
+        .iter()
+        .filter(|path| is_reference_path(graph, path))
+        .collect()
+}
+
+/// Where --path-range cut the graph down to a window: the pangenomic position of its first
+/// bp, the position within each path of its first bp in the window, and the id its first
+/// segment had in the whole graph
+#[derive(Clone, Default)]
+pub(crate) struct RangeOrigin {
+    pub(crate) pangenomic: u64,
+    pub(crate) paths: FxHashMap<String, u64>,
+    pub(crate) first_segment: u64,
+}
+
+/// Edge endpoints and orientations: (from_id, from_rev, to_id, to_rev)
+pub(crate) type EdgeKey = (u64, bool, u64, bool);
+
+/// Canonical edge key for deduplication
+pub(crate) fn edge_key(from_id: u64, from_rev: bool, to_id: u64, to_rev: bool) -> EdgeKey {
+    // Normalize edge direction for deduplication
+    if from_id < to_id || (from_id == to_id && !from_rev) {
+        (from_id, from_rev, to_id, to_rev)
+    } else {
+        (to_id, !to_rev, from_id, !from_rev)
+    }
+}
+
+impl Graph {
+    /// The segment whose S line comes `index`-th (from 0) in the GFA file, as
+    /// --highlight-node-ids numbers them; None when it is outside a --path-range window
+    pub(crate) fn segment_at_file_index(&self, index: u64) -> Option<u64> {
+        let id = if self.file_segments.is_empty() {
+            index
+        } else {
+            *self.file_segments.get(index as usize)?
+        };
+        id.checked_sub(self.origin.first_segment)
+            .filter(|&id| id < self.segments.len() as u64)
+    }
+
+    pub(crate) fn new() -> Self {
+        Graph {
+            segments: Vec::new(),
+            segment_name_to_id: FxHashMap::default(),
+            segment_offsets: Vec::new(),
+            total_length: 0,
+            paths: Vec::new(),
+            edges: Vec::new(),
+            origin: RangeOrigin::default(),
+            header: GfaHeader::default(),
+            file_segments: Vec::new(),
+        }
+    }
+}
+
+/// Inputs [`open_gfa`] reads: plain GFA and its gzip, bgzip and zstd compressed forms
+pub(crate) const INPUT_FORMATS: [&str; 4] = ["gfa", "gfa.gz", "gfa.bgz", "gfa.zst"];
+
+/// Open a GFA file for reading, decompressing gzip (and bgzip, a series of gzip members)
+/// or zstd on the fly when the file starts with their magic bytes
+pub(crate) fn open_gfa(path: &Path) -> std::io::Result<Box<dyn BufRead + Send>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let magic = reader.fill_buf()?;
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(BufReader::new(
+            flate2::bufread::MultiGzDecoder::new(reader),
+        )))
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Ok(Box::new(BufReader::new(
+            zstd::stream::read::Decoder::with_buffer(reader)?,
+        )))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Value of the optional tag with this `TAG:TYPE:` prefix, e.g. `LN:i:`
+fn tag_value<'a>(tags: &[&'a str], prefix: &str) -> Option<&'a str> {
+    tags.iter().find_map(|tag| tag.strip_prefix(prefix))
+}
+
+/// rGFA stable coordinate of a segment: the stable sequence it comes from (SN) and its
+/// offset on it (SO)
+fn stable_coordinate(tags: &[&str]) -> Option<(String, u64)> {
+    let name = tag_value(tags, "SN:Z:")?;
+    let offset = tag_value(tags, "SO:i:")?.parse().ok()?;
+    Some((name.to_string(), offset))
+}
+
+/// One path per rGFA stable sequence, through its segments in offset order, for rGFA
+/// files that come without P or W lines
+fn stable_paths(stable: &[(String, u64)]) -> Vec<GfaPath> {
+    let mut paths: Vec<(&str, Vec<usize>)> = Vec::new();
+    let mut index: FxHashMap<&str, usize> = FxHashMap::default();
+    for (id, (name, _)) in stable.iter().enumerate() {
+        let i = *index.entry(name).or_insert_with(|| {
+            paths.push((name, Vec::new()));
+            paths.len() - 1
+        });
+        paths[i].1.push(id);
+    }
+    paths
+        .into_iter()
+        .map(|(name, mut ids)| {
+            ids.sort_by_key(|&id| stable[id].1);
+            GfaPath {
+                name: name.to_string(),
+                steps: ids
+                    .into_iter()
+                    .map(|id| PathStep {
+                        segment_id: id as u64,
+                        is_reverse: false,
+                    })
+                    .collect(),
+                piece: None,
+            }
+        })
+        .collect()
+}
+
+/// Layout order of an rGFA's segments: the rank-0 segments of each stable sequence by
+/// offset, each followed by the higher-rank segments that branch off it
+fn rgfa_order(graph: &Graph, stable: &[(String, u64)]) -> Vec<usize> {
+    let n = graph.segments.len();
+    let mut sequences: FxHashMap<&str, usize> = FxHashMap::default();
+    for (name, _) in stable {
+        let next = sequences.len();
+        sequences.entry(name).or_insert(next);
+    }
+    let mut backbone: Vec<usize> = (0..n)
+        .filter(|&id| graph.segments[id].rank == Some(0))
+        .collect();
+    backbone.sort_by_key(|&id| (sequences[stable[id].0.as_str()], stable[id].1));
+
+    let mut neighbours = vec![Vec::new(); n];
+    for edge in &graph.edges {
+        neighbours[edge.from_id as usize].push(edge.to_id as usize);
+        neighbours[edge.to_id as usize].push(edge.from_id as usize);
+    }
+    for list in &mut neighbours {
+        list.sort_unstable();
+        list.dedup();
+    }
+
+    let mut placed = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    for &id in &backbone {
+        placed[id] = true;
+        order.push(id);
+        // Depth-first through the non-reference segments reachable from here, lowest id first
+        let mut stack: Vec<usize> = neighbours[id].iter().rev().copied().collect();
+        while let Some(next) = stack.pop() {
+            if placed[next] || graph.segments[next].rank == Some(0) {
+                continue;
+            }
+            placed[next] = true;
+            order.push(next);
+            stack.extend(neighbours[next].iter().rev());
+        }
+    }
+    // Segments not connected to the backbone keep their file order
+    order.extend((0..n).filter(|&id| !placed[id]));
+    order
+}
+
+/// Renumber the segments so that `order` lists them from left to right, and lay them out
+/// in that order
+fn relabel_segments(graph: &mut Graph, order: &[usize]) {
+    let mut new_id = vec![0u64; order.len()];
+    for (position, &id) in order.iter().enumerate() {
+        new_id[id] = position as u64;
+    }
+    graph.segments = order.iter().map(|&id| graph.segments[id].clone()).collect();
+    for id in graph.segment_name_to_id.values_mut() {
+        *id = new_id[*id as usize];
+    }
+    for step in graph
+        .paths
+        .iter_mut()
+        .flat_map(|path| path.steps.iter_mut())
+    {
+        step.segment_id = new_id[step.segment_id as usize];
+    }
+    for edge in &mut graph.edges {
+        // Ids changed order, so normalize the key again
+        let (from_id, from_rev, to_id, to_rev) = edge_key(
+            new_id[edge.from_id as usize],
+            edge.from_rev,
+            new_id[edge.to_id as usize],
+            edge.to_rev,
+        );
+        *edge = Edge {
+            from_id,
+            from_rev,
+            to_id,
+            to_rev,
+            from_link: edge.from_link,
+        };
+    }
+    let mut offset = 0;
+    for (segment, segment_offset) in graph.segments.iter().zip(&mut graph.segment_offsets) {
+        *segment_offset = offset;
+        offset += segment.sequence_len;
+    }
+    graph.file_segments = new_id;
+}
+
+/// Steps of a P line's segment list, e.g. `1+,2-,3+`
+fn path_steps(spec: &str) -> Vec<(&str, bool)> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|seg| !seg.is_empty())
+        .map(|seg| {
+            if let Some(stripped) = seg.strip_suffix('+') {
+                (stripped, false)
+            } else if let Some(stripped) = seg.strip_suffix('-') {
+                (stripped, true)
+            } else {
+                (seg, false)
+            }
+        })
+        .collect()
+}
+
+/// Steps of a W line's walk, e.g. `>1<2>3`
+fn walk_steps(walk: &str) -> Vec<(&str, bool)> {
+    let starts: Vec<usize> = walk.match_indices(['>', '<']).map(|(i, _)| i).collect();
+    starts
+        .iter()
+        .enumerate()
+        .filter_map(|(k, &start)| {
+            let end = starts.get(k + 1).copied().unwrap_or(walk.len());
+            let name = &walk[start + 1..end];
+            (!name.is_empty()).then(|| (name, walk.as_bytes()[start] == b'<'))
+        })
+        .collect()
+}
+
+/// Segment references of P, W and L lines seen before the S lines they name, kept by
+/// name until the end of the file
+#[derive(Default)]
+struct PendingRecords {
+    /// Index in `graph.paths` and the steps of each path
+    paths: Vec<(usize, Vec<(String, bool)>)>,
+    /// Both ends of each link, with their orientations
+    links: Vec<(String, bool, String, bool)>,
+}
+
+impl PendingRecords {
+    /// Add the path `name` to `graph`, resolving its steps now when every segment is known
+    fn add_path(&mut self, graph: &mut Graph, name: String, steps: Vec<(&str, bool)>) {
+        let resolved: Option<Vec<PathStep>> = steps
+            .iter()
+            .map(|&(seg, is_reverse)| {
+                let &segment_id = graph.segment_name_to_id.get(seg)?;
+                Some(PathStep {
+                    segment_id,
+                    is_reverse,
+                })
+            })
+            .collect();
+        if resolved.is_none() {
+            let owned = steps
+                .into_iter()
+                .map(|(seg, is_reverse)| (seg.to_string(), is_reverse))
+                .collect();
+            self.paths.push((graph.paths.len(), owned));
+        }
+        graph.paths.push(GfaPath {
+            name,
+            steps: resolved.unwrap_or_default(),
+            piece: None,
+        });
+    }
+
+    /// Resolve the pending references now that every S line is read, returning how many
+    /// still name no segment
+    fn resolve(
+        self,
+        graph: &mut Graph,
+        edge_set: &mut std::collections::HashSet<EdgeKey>,
+    ) -> usize {
+        let mut missing = 0;
+        for (idx, steps) in self.paths {
+            graph.paths[idx].steps = steps
+                .iter()
+                .filter_map(|(seg, is_reverse)| {
+                    let id = graph.segment_name_to_id.get(seg);
+                    missing += id.is_none() as usize;
+                    Some(PathStep {
+                        segment_id: *id?,
+                        is_reverse: *is_reverse,
+                    })
+                })
+                .collect();
+        }
+        for (from, from_rev, to, to_rev) in self.links {
+            match (
+                graph.segment_name_to_id.get(&from),
+                graph.segment_name_to_id.get(&to),
+            ) {
+                (Some(&from_id), Some(&to_id)) => {
+                    edge_set.insert(edge_key(from_id, from_rev, to_id, to_rev));
+                }
+                _ => missing += 1,
+            }
+        }
+        missing
+    }
+}
+
+/// Parse a GFA file in a single streaming pass. Records may come in any order: path
+/// steps and links naming segments whose S line is still to come are resolved at the end
+pub(crate) fn parse_gfa(path: &Path) -> std::io::Result<Graph> {
+    let mut graph = Graph::new();
+
+    info!("Loading GFA file...");
+
+    // Use a set to deduplicate edges
+    let mut edge_set: std::collections::HashSet<EdgeKey> = std::collections::HashSet::new();
+    let mut pending = PendingRecords::default();
+    let mut unsized_segments = 0usize;
+    let mut stable = Vec::new();
+
+    let reader = open_gfa(path)?;
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with("S\t") {
+            let parts: Vec<&str> = line.split('\t').collect();
+            if parts.len() >= 3 {
+                let name = parts[1].to_string();
+                let seq = parts[2];
+                let tags = &parts[3..];
+                let (seq_len, n_count) = if seq == "*" {
+                    // No sequence: the length comes from the LN tag
+                    match tag_value(tags, "LN:i:").and_then(|value| value.parse().ok()) {
+                        Some(len) => (len, 0),
+                        None => {
+                            unsized_segments += 1;
+                            (1, 0)
+                        }
+                    }
+                } else {
+                    // Count uncalled bases (N's)
+                    let n_count = seq.bytes().filter(|&b| b == b'N' || b == b'n').count() as u64;
+                    (seq.len() as u64, n_count)
+                };
+                let rank = tag_value(tags, "SR:i:").and_then(|value| value.parse().ok());
+                stable.push(stable_coordinate(tags));
+                let id = graph.segments.len() as u64;
+                graph.segment_name_to_id.insert(name, id);
+                graph.segments.push(Segment {
+                    sequence_len: seq_len,
+                    n_count,
+                    rank,
+                });
+            }
+        } else if line.starts_with("P\t") {
+            let parts: Vec<&str> = line.split('\t').collect();
+            if parts.len() >= 3 {
+                pending.add_path(&mut graph, parts[1].to_string(), path_steps(parts[2]));
+            }
+        } else if line.starts_with("W\t") {
+            let parts: Vec<&str> = line.split('\t').collect();
+            if parts.len() >= 7 {
+                let (sample, hap, seq) = (parts[1], parts[2], parts[3]);
+                let path_name = format!("{}#{}#{}", sample, hap, seq);
+                pending.add_path(&mut graph, path_name, walk_steps(parts[6]));
+            }
+        } else if line.starts_with("L\t") {
+            // Parse edge: L<TAB>from<TAB>from_orient<TAB>to<TAB>to_orient<TAB>overlap
+            let parts: Vec<&str> = line.split('\t').collect();
+            if parts.len() >= 5 {
+                let (from_name, to_name) = (parts[1], parts[3]);
+                let from_rev = parts[2] == "-";
+                let to_rev = parts[4] == "-";
+                match (
+                    graph.segment_name_to_id.get(from_name),
+                    graph.segment_name_to_id.get(to_name),
+                ) {
+                    (Some(&from_id), Some(&to_id)) => {
+                        edge_set.insert(edge_key(from_id, from_rev, to_id, to_rev));
+                    }
+                    _ => pending.links.push((
+                        from_name.to_string(),
+                        from_rev,
+                        to_name.to_string(),
+                        to_rev,
+                    )),
+                }
+            }
+        } else if let Some(tags) = line.strip_prefix("H\t") {
+            graph.header.tags.extend(
+                tags.split('\t')
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string),
+            );
+        }
+    }
+    match graph.header.version() {
+        Some(version) if version.starts_with('2') => warning!(
+            "the graph declares GFA {}; only GFA 1 records (S, L, P, W) are read",
+            version
+        ),
+        Some(version) => info!("GFA version {}", version),
+        None => {}
+    }
+
+    // Calculate segment offsets (linear layout)
+    let mut offset = 0u64;
+    for seg in &graph.segments {
+        graph.segment_offsets.push(offset);
+        offset += seg.sequence_len;
+    }
+    graph.total_length = offset;
+
+    info!(
+        "Found {} segments, total length: {} bp",
+        graph.segments.len(),
+        graph.total_length
+    );
+
+    if !pending.paths.is_empty() || !pending.links.is_empty() {
+        debug!(
+            "Resolving {} paths and {} links read before their segments",
+            pending.paths.len(),
+            pending.links.len()
+        );
+    }
+    if unsized_segments > 0 {
+        warning!(
+            "{} segments have neither a sequence nor an LN:i: tag; each is drawn 1 bp long",
+            unsized_segments
+        );
+    }
+    let missing = pending.resolve(&mut graph, &mut edge_set);
+    if missing > 0 {
+        warning!(
+            "{} path steps and links name segments with no S line; they are left out",
+            missing
+        );
+    }
+
+    // Add edges from consecutive path steps (implicit edges)
+    let link_edges = edge_set.clone();
+    for path in &graph.paths {
+        for window in path.steps.windows(2) {
+            let from = &window[0];
+            let to = &window[1];
+            // Edge from end of 'from' to start of 'to'
+            // from_rev=true means we're going through from in reverse, so edge starts from beginning
+            // to_rev=true means we're entering to in reverse, so edge goes to end
+            edge_set.insert(edge_key(
+                from.segment_id,
+                from.is_reverse,
+                to.segment_id,
+                to.is_reverse,
+            ));
+        }
+    }
+
+    // Convert edge set to vector
+    for key in edge_set {
+        let (from_id, from_rev, to_id, to_rev) = key;
+        graph.edges.push(Edge {
+            from_id,
+            from_rev,
+            to_id,
+            to_rev,
+            from_link: link_edges.contains(&key),
+        });
+    }
+
+    // rGFA (minigraph): lay the segments out along the reference's stable coordinates
+    let ranked =
+        !graph.segments.is_empty() && graph.segments.iter().all(|segment| segment.rank.is_some());
+    if let (true, Some(stable)) = (ranked, stable.into_iter().collect::<Option<Vec<_>>>()) {
+        if graph.paths.is_empty() {
+            graph.paths = stable_paths(&stable);
+            info!(
+                "rGFA without paths: derived {} paths from the stable sequences (SN)",
+                graph.paths.len()
+            );
+        }
+        let order = rgfa_order(&graph, &stable);
+        relabel_segments(&mut graph, &order);
+        info!("rGFA: segments laid out by stable sequence (SN) and offset (SO)");
+    }
+
+    info!(
+        "Found {} paths, {} edges",
+        graph.paths.len(),
+        graph.edges.len()
+    );
+
+    Ok(graph)
+}
@@ -0,0 +1,184 @@
+//! `--export-dot`: write the displayed region's segments and edges as GraphViz DOT or,
+//! for `.json` files, Cytoscape JSON, with node sizes scaled by segment length and the
+//! colors of the 1D view, so the topology can be inspected in a 2D layout tool.
+
+use super::error::GfalookError;
+use super::{interrupt, logging, Edge, EdgeKey, GfaPath, Graph};
+use log::info;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Above this many nodes the export still happens, but layout tools struggle
+const LARGE_GRAPH_NODES: usize = 5000;
+
+/// Node diameter range in DOT inches; Cytoscape sizes are these times 40 pixels
+const MIN_SIZE: f64 = 0.2;
+const MAX_SIZE: f64 = 1.5;
+
+/// Segment and edge colors as seen in the image
+pub struct ExportColors<'a> {
+    pub nodes: &'a [Option<(u8, u8, u8)>],
+    pub edges: Option<&'a FxHashMap<EdgeKey, (u8, u8, u8)>>,
+    pub default: (u8, u8, u8),
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn dot_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn strand(is_reverse: bool) -> char {
+    if is_reverse {
+        '-'
+    } else {
+        '+'
+    }
+}
+
+/// Write the segments traversed by `display_paths` or touched by `edges`, and those edges
+pub fn write(
+    out: &Path,
+    graph: &Graph,
+    display_paths: &[&GfaPath],
+    edges: &[&Edge],
+    colors: &ExportColors,
+) -> Result<(), GfalookError> {
+    let mut in_region = vec![false; graph.segments.len()];
+    for step in display_paths.iter().flat_map(|p| p.steps.iter()) {
+        in_region[step.segment_id as usize] = true;
+    }
+    let mut seen_edges: FxHashSet<EdgeKey> = FxHashSet::default();
+    let edges: Vec<&Edge> = edges
+        .iter()
+        .copied()
+        .filter(|e| (e.from_id as usize) < in_region.len() && (e.to_id as usize) < in_region.len())
+        .filter(|e| seen_edges.insert(e.key()))
+        .collect();
+    for edge in &edges {
+        in_region[edge.from_id as usize] = true;
+        in_region[edge.to_id as usize] = true;
+    }
+    let nodes: Vec<usize> = (0..in_region.len()).filter(|&id| in_region[id]).collect();
+    if nodes.len() > LARGE_GRAPH_NODES {
+        logging::warning(&format!(
+            "--export-dot: writing {} nodes; layout tools are slow on graphs this large, \
+             consider a smaller region",
+            nodes.len()
+        ));
+    }
+
+    let mut names = vec![""; graph.segments.len()];
+    for (name, &id) in &graph.segment_name_to_id {
+        names[id as usize] = name;
+    }
+    let max_len = nodes
+        .iter()
+        .map(|&id| graph.segments[id].sequence_len)
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    // Square root so that area, not diameter, follows length
+    let size = |id: usize| {
+        let fraction = (graph.segments[id].sequence_len as f64 / max_len as f64).sqrt();
+        MIN_SIZE + (MAX_SIZE - MIN_SIZE) * fraction
+    };
+    let node_color = |id: usize| hex(colors.nodes[id].unwrap_or(colors.default));
+    let edge_color = |edge: &Edge| {
+        hex(colors
+            .edges
+            .and_then(|c| c.get(&edge.key()))
+            .copied()
+            .unwrap_or(colors.default))
+    };
+
+    let is_json = out
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    interrupt::write_atomically(out, |temp| {
+        let write_err = |e| GfalookError::from_io("failed to write graph export", out, e);
+        let mut w = BufWriter::new(File::create(temp).map_err(write_err)?);
+        if is_json {
+            writeln!(w, "{{\"elements\": {{\"nodes\": [").map_err(write_err)?;
+            for (i, &id) in nodes.iter().enumerate() {
+                writeln!(
+                    w,
+                    "  {{\"data\": {{\"id\": {}, \"length\": {}, \"size\": {:.1}, \"color\": \"{}\"}}}}{}",
+                    logging::json_string(names[id]),
+                    graph.segments[id].sequence_len,
+                    size(id) * 40.0,
+                    node_color(id),
+                    if i + 1 < nodes.len() { "," } else { "" }
+                )
+                .map_err(write_err)?;
+            }
+            writeln!(w, "], \"edges\": [").map_err(write_err)?;
+            for (i, edge) in edges.iter().enumerate() {
+                let (from, to) = (names[edge.from_id as usize], names[edge.to_id as usize]);
+                let id = format!(
+                    "{}{} {}{}",
+                    from,
+                    strand(edge.from_rev),
+                    to,
+                    strand(edge.to_rev)
+                );
+                writeln!(
+                    w,
+                    "  {{\"data\": {{\"id\": {}, \"source\": {}, \"target\": {}, \"source_strand\": \"{}\", \"target_strand\": \"{}\", \"color\": \"{}\"}}}}{}",
+                    logging::json_string(&id),
+                    logging::json_string(from),
+                    logging::json_string(to),
+                    strand(edge.from_rev),
+                    strand(edge.to_rev),
+                    edge_color(edge),
+                    if i + 1 < edges.len() { "," } else { "" }
+                )
+                .map_err(write_err)?;
+            }
+            writeln!(w, "]}}}}").map_err(write_err)?;
+        } else {
+            writeln!(w, "digraph gfalook {{").map_err(write_err)?;
+            writeln!(
+                w,
+                "  node [shape=circle, style=filled, fixedsize=true, fontsize=8];"
+            )
+            .map_err(write_err)?;
+            for &id in &nodes {
+                writeln!(
+                    w,
+                    "  {} [width={:.2}, fillcolor=\"{}\", tooltip=\"{} bp\"];",
+                    dot_string(names[id]),
+                    size(id),
+                    node_color(id),
+                    graph.segments[id].sequence_len
+                )
+                .map_err(write_err)?;
+            }
+            for edge in &edges {
+                writeln!(
+                    w,
+                    "  {} -> {} [color=\"{}\", taillabel=\"{}\", headlabel=\"{}\"];",
+                    dot_string(names[edge.from_id as usize]),
+                    dot_string(names[edge.to_id as usize]),
+                    edge_color(edge),
+                    strand(edge.from_rev),
+                    strand(edge.to_rev)
+                )
+                .map_err(write_err)?;
+            }
+            writeln!(w, "}}").map_err(write_err)?;
+        }
+        w.flush().map_err(write_err)
+    })?;
+    info!(
+        "Graph export ({} nodes, {} edges) saved to {:?}",
+        nodes.len(),
+        edges.len(),
+        out
+    );
+    Ok(())
+}
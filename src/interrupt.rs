@@ -0,0 +1,88 @@
+//! Ctrl-C handling: the first SIGINT or SIGTERM asks the run to stop at the next phase
+//! boundary, keeping the outputs already written; a second one removes any half-written
+//! output and exits at once.
+
+use super::error::GfalookError;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Exit code of a process stopped by SIGINT, as shells report it
+pub const EXIT_CODE: i32 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static TEMP_FILES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Start listening for SIGINT/SIGTERM (no-op on platforms without signal support)
+pub fn install() {
+    #[cfg(unix)]
+    {
+        use signal_hook::consts::{SIGINT, SIGTERM};
+        let mut signals = match signal_hook::iterator::Signals::new([SIGINT, SIGTERM]) {
+            Ok(signals) => signals,
+            Err(e) => {
+                super::logging::warning(&format!("could not install the Ctrl-C handler: {}", e));
+                return;
+            }
+        };
+        std::thread::spawn(move || {
+            for _ in signals.forever() {
+                if INTERRUPTED.swap(true, Ordering::SeqCst) {
+                    remove_temp_files();
+                    std::process::exit(EXIT_CODE);
+                }
+                super::logging::warning(
+                    "interrupted, stopping after the current phase (press Ctrl-C again to quit now)",
+                );
+            }
+        });
+    }
+}
+
+/// Whether the user asked the run to stop
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Stop at a phase boundary if the user asked to; `done` names what was completed
+pub fn check(done: &str) -> Result<(), GfalookError> {
+    if requested() {
+        Err(GfalookError::Interrupted(format!(
+            "interrupted after {}, outputs written so far were kept",
+            done
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Write `out` through a temporary file next to it that is renamed into place once
+/// `write` succeeds, so an interrupted or failed write never leaves a truncated output
+pub fn write_atomically<F>(out: &Path, write: F) -> Result<(), GfalookError>
+where
+    F: FnOnce(&Path) -> Result<(), GfalookError>,
+{
+    let file_name = out.file_name().map_or_else(
+        || "gfalook".into(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let temp = out.with_file_name(format!(".{}.partial", file_name));
+    TEMP_FILES.lock().unwrap().push(temp.clone());
+    let result = write(&temp).and_then(|_| {
+        std::fs::rename(&temp, out)
+            .map_err(|e| GfalookError::from_io("failed to move output into place", out, e))
+    });
+    TEMP_FILES.lock().unwrap().retain(|t| t != &temp);
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+fn remove_temp_files() {
+    if let Ok(files) = TEMP_FILES.lock() {
+        for file in files.iter() {
+            let _ = std::fs::remove_file(file);
+        }
+    }
+}
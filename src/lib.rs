@@ -0,0 +1,14415 @@
+//! gfalook: visualize a variation graph in 1D.
+//!
+//! The library holds the GFA parser, clustering and renderers; the `gfalook` binary and the
+//! C and Python bindings are thin front ends over it.
+
+#![allow(clippy::too_many_arguments)]
+#![allow(clippy::needless_range_loop)]
+
+/// Print a user-facing warning, as text or a JSON event depending on --log-format
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        logging::warning(&format!($($arg)*))
+    };
+}
+
+mod bench;
+mod bin_cache;
+mod cluster_cache;
+mod color;
+pub(crate) mod error;
+mod ffi;
+mod gfa;
+mod graph_export;
+mod interrupt;
+mod logging;
+mod npz;
+mod odgi_compat;
+mod out_auto;
+mod palettes;
+mod path_names;
+#[cfg(feature = "python")]
+mod python;
+mod svg_interactive;
+
+use clap::{ArgGroup, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use color::{chromosome_color, hsv_to_rgb, linear_to_srgb, parse_color, srgb_to_linear};
+use error::GfalookError;
+use gfa::{
+    edge_key, is_reference_path, open_gfa, parse_gfa, reference_paths, Edge, EdgeKey, GfaPath,
+    Graph, PathStep, RangeOrigin, Segment, INPUT_FORMATS,
+};
+use log::{debug, info};
+use logging::LogFormat;
+use path_names::{PathNameResolver, Resolution};
+use rayon::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+#[derive(Parser, Clone)]
+#[command(name = "gfalook")]
+#[command(about = "Visualize a variation graph in 1D.", long_about = None)]
+#[command(after_help = "Run `gfalook bench -i FILE` to time each pipeline phase on a graph.")]
+#[command(group(
+    ArgGroup::new("bin_color_mode")
+        .args([
+            "color_by_mean_depth",
+            "color_by_mean_inversion_rate",
+            "color_by_uncalled_bases",
+            "show_strand",
+            "highlight_node_ids",
+            "color_by_frequency_class",
+            "color_by_rank",
+        ])
+        .multiple(false)
+))]
+pub(crate) struct Args {
+    // === Input/Output ===
+    /// Load the variation graph in GFA format from this FILE, which may be gzip, bgzip or zstd compressed.
+    #[arg(
+        short = 'i',
+        long = "idx",
+        value_name = "FILE",
+        required_unless_present = "version_info",
+        default_value = "graph.gfa",
+        hide_default_value = true,
+        help_heading = "Input/Output"
+    )]
+    idx: PathBuf,
+
+    /// Write the visualization to this FILE (PNG or SVG based on extension).
+    #[arg(
+        short = 'o',
+        long = "out",
+        value_name = "FILE",
+        required_unless_present_any = ["out_auto", "version_info"],
+        conflicts_with = "out_auto",
+        default_value = "gfalook.png",
+        hide_default_value = true,
+        help_heading = "Input/Output"
+    )]
+    out: PathBuf,
+
+    /// Encoder quality from 1 to 100 for lossy JPEG (default 75) and AVIF (default 80) output; PNG, WebP and TIFF are lossless.
+    #[arg(
+        long = "quality",
+        value_name = "Q",
+        value_parser = clap::value_parser!(u8).range(1..=100),
+        help_heading = "Input/Output"
+    )]
+    quality: Option<u8>,
+
+    /// Name the PNG after the input and the options given, and write it to DIR (default: current directory).
+    #[arg(
+        long = "out-auto",
+        value_name = "DIR",
+        num_args = 0..=1,
+        default_missing_value = ".",
+        help_heading = "Input/Output"
+    )]
+    out_auto: Option<PathBuf>,
+
+    /// Draw a quick low-fidelity preview: at most 100 evenly sampled paths, 800 px wide, 10 px rows.
+    #[arg(long = "preview", help_heading = "Input/Output")]
+    preview: bool,
+
+    /// Print the version, compiled features, supported formats, size limits and thread count, then exit.
+    #[arg(long = "version-info", exclusive = true, help_heading = "Input/Output")]
+    version_info: bool,
+
+    /// Keep running and re-render whenever a color, annotation, path list or BED file changes.
+    #[arg(long = "watch", help_heading = "Input/Output")]
+    watch: bool,
+
+    /// Make the SVG pannable and zoomable in a browser (drag, mouse wheel, double-click to reset), with an id on each row and tick.
+    #[arg(long = "svg-interactive", help_heading = "Input/Output")]
+    svg_interactive: bool,
+
+    /// Link each SVG row and its name to this URL, with {path} replaced by the URL-encoded path name.
+    #[arg(
+        long = "link-template",
+        value_name = "URL",
+        help_heading = "Input/Output"
+    )]
+    link_template: Option<String>,
+
+    /// Draw one SVG rectangle per bin with data-path, data-bin and data-depth attributes (much larger output).
+    #[arg(long = "svg-data-attrs", help_heading = "Input/Output")]
+    svg_data_attrs: bool,
+
+    /// Also write the binned depth matrix (rows = displayed paths, columns = bins) to this FILE, as NumPy .npz or TSV.
+    #[arg(
+        long = "export-matrix",
+        value_name = "FILE",
+        help_heading = "Input/Output"
+    )]
+    export_matrix: Option<PathBuf>,
+
+    /// Also write a paths × nodes presence/absence matrix to this FILE, as Matrix Market (.mtx) or sparse TSV.
+    #[arg(
+        long = "export-pav",
+        value_name = "FILE",
+        help_heading = "Input/Output"
+    )]
+    export_pav: Option<PathBuf>,
+
+    /// Also write each node's length and the number of displayed paths traversing it to this FILE (TSV).
+    #[arg(
+        long = "node-frequency-out",
+        value_name = "FILE",
+        help_heading = "Input/Output"
+    )]
+    node_frequency_out: Option<PathBuf>,
+
+    /// Store the bp each path covers on a node in the --export-pav matrix instead of 1.
+    #[arg(
+        long = "pav-bp",
+        requires = "export_pav",
+        help_heading = "Input/Output"
+    )]
+    pav_bp: bool,
+
+    /// Restrict the --export-pav matrix to nodes whose coverage varies across paths.
+    #[arg(
+        long = "pav-variable-only",
+        requires = "export_pav",
+        help_heading = "Input/Output"
+    )]
+    pav_variable_only: bool,
+
+    /// Accept an odgi viz command line as is (-P/--progress, quiet by default) and report where gfalook behaves differently.
+    #[arg(long = "odgi-compat", help_heading = "Input/Output")]
+    odgi_compat: bool,
+
+    /// Also write a copy of the input GFA to this FILE with CL:z: color tags on segments (cluster medoid, highlight or depth colors), for Bandage.
+    #[arg(
+        long = "write-colored-gfa",
+        value_name = "FILE",
+        help_heading = "Input/Output"
+    )]
+    write_colored_gfa: Option<PathBuf>,
+
+    /// Write the left panel (dendrogram, bars and path names) to FILE on its own, and leave it out of the main image so that the two line up row for row.
+    #[arg(long = "names-out", value_name = "FILE", help_heading = "Input/Output")]
+    names_out: Option<PathBuf>,
+
+    /// Write only the requested data products (cluster TSVs, matrices, graph exports) and skip drawing the image.
+    #[arg(
+        long = "no-image",
+        conflicts_with_all = ["watch", "tile", "edges_out", "names_out"],
+        help_heading = "Input/Output"
+    )]
+    no_image: bool,
+
+    /// Draw a graph without P or W lines as a single row of all segments in id order,
+    /// colored by how many edges branch off each segment, instead of failing with exit code 6.
+    #[arg(long = "segments-only", help_heading = "Input/Output")]
+    segments_only: bool,
+
+    // === Image Size ===
+    /// Set the width in pixels of the output image.
+    #[arg(
+        short = 'x',
+        long = "width",
+        value_name = "N",
+        default_value_t = 1500,
+        help_heading = "Image Size"
+    )]
+    width: u32,
+
+    /// Choose the image width from the graph length so that each pixel covers --bp-per-pixel bp.
+    #[arg(
+        long = "auto-width",
+        conflicts_with = "width",
+        help_heading = "Image Size"
+    )]
+    auto_width: bool,
+
+    /// Base pairs per pixel column for --auto-width.
+    #[arg(
+        long = "bp-per-pixel",
+        value_name = "F",
+        default_value_t = 100.0,
+        requires = "auto_width",
+        help_heading = "Image Size"
+    )]
+    bp_per_pixel: f64,
+
+    /// Draft render of every Nth bin only, skipping the columns between, to iterate quickly on very wide images.
+    #[arg(
+        long = "downsample",
+        value_name = "N",
+        conflicts_with = "show_all_nodes",
+        help_heading = "Image Size"
+    )]
+    downsample: Option<u32>,
+
+    /// Set the height in pixels of the output image.
+    #[arg(
+        short = 'y',
+        long = "height",
+        value_name = "N",
+        default_value_t = 500,
+        help_heading = "Image Size"
+    )]
+    height: u32,
+
+    /// The height in pixels for a path.
+    #[arg(
+        short = 'a',
+        long = "path-height",
+        value_name = "N",
+        default_value_t = 10,
+        help_heading = "Image Size"
+    )]
+    path_height: u32,
+
+    /// The padding in pixels on the x-axis for a path.
+    #[arg(
+        short = 'X',
+        long = "path-x-padding",
+        value_name = "N",
+        default_value_t = 0,
+        help_heading = "Image Size"
+    )]
+    path_x_padding: u32,
+
+    /// Choose the path height automatically so the image is about N pixels tall.
+    #[arg(
+        long = "fit-height",
+        value_name = "N",
+        conflicts_with = "path_height",
+        help_heading = "Image Size"
+    )]
+    fit_height: Option<u32>,
+
+    /// Vertical gap in pixels between consecutive path rows.
+    #[arg(
+        long = "path-spacing",
+        value_name = "N",
+        default_value_t = 0,
+        help_heading = "Image Size"
+    )]
+    path_spacing: u32,
+
+    /// Blank border in pixels around the whole image.
+    #[arg(
+        long = "margin",
+        value_name = "N",
+        default_value_t = 0,
+        help_heading = "Image Size"
+    )]
+    margin: u32,
+
+    /// Vertical gap in pixels between the path rows, the x-axis and the edge panel.
+    #[arg(
+        long = "panel-gap",
+        value_name = "N",
+        default_value_t = 0,
+        help_heading = "Image Size"
+    )]
+    panel_gap: u32,
+
+    // === Clustering ===
+    /// Automatically order paths by similarity.
+    #[arg(
+        short = 'k',
+        long = "cluster-paths",
+        conflicts_with_all = ["paths_to_display", "prefix_merges"],
+        help_heading = "Clustering"
+    )]
+    cluster_paths: bool,
+
+    /// Similarity threshold for cluster detection (automatic if not specified).
+    #[arg(
+        long = "cluster-threshold",
+        value_name = "F",
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    cluster_threshold: Option<f64>,
+
+    /// Use all nodes for clustering instead of only variable nodes.
+    #[arg(
+        long = "cluster-all-nodes",
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    cluster_all_nodes: bool,
+
+    /// Unit to compare when clustering: each path, or the summed coverage of each PanSN haplotype or sample.
+    #[arg(
+        long = "similarity-level",
+        value_name = "LEVEL",
+        value_enum,
+        default_value_t = SimilarityLevel::Path,
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    similarity_level: SimilarityLevel,
+
+    /// Write the pairwise similarities of the clustered paths to this FILE, in the `odgi similarity -d` TSV layout.
+    #[arg(
+        long = "write-distances",
+        value_name = "FILE",
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    write_distances: Option<PathBuf>,
+
+    /// Gap in pixels between clusters.
+    #[arg(
+        long = "cluster-gap",
+        value_name = "N",
+        requires = "cluster_paths",
+        default_value_t = 10,
+        help_heading = "Clustering"
+    )]
+    cluster_gap: u32,
+
+    /// Maximum number of clusters allowed (automatic if not specified).
+    #[arg(
+        long = "max-clusters",
+        value_name = "N",
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    max_clusters: Option<usize>,
+
+    /// Show only one representative path (medoid) per cluster.
+    #[arg(
+        short = 'K',
+        long = "cluster-representatives",
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    cluster_representatives: bool,
+
+    /// Path that stands in for each cluster under -K: the medoid, the longest member, the first in input order, or the member closest to the cluster's consensus nodes.
+    #[arg(
+        long = "representative",
+        value_name = "RULE",
+        value_enum,
+        default_value_t = Representative::Medoid,
+        requires = "cluster_representatives",
+        help_heading = "Clustering"
+    )]
+    representative: Representative,
+
+    /// Draw a legend row mapping cluster bar colors to cluster ids and sizes.
+    #[arg(
+        long = "cluster-legend",
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    cluster_legend: bool,
+
+    /// Print each cluster's id vertically inside the cluster bar, on its first row, when rows are tall enough.
+    #[arg(
+        long = "cluster-bar-labels",
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    cluster_bar_labels: bool,
+
+    /// Outline each cluster's medoid row instead of hiding the other members.
+    #[arg(
+        long = "mark-medoids",
+        requires = "cluster_paths",
+        conflicts_with = "cluster_representatives",
+        help_heading = "Clustering"
+    )]
+    mark_medoids: bool,
+
+    /// Pool clusters with fewer than N members into one grey "unclustered" group at the bottom.
+    #[arg(
+        long = "min-cluster-size",
+        value_name = "N",
+        default_value_t = 1,
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    min_cluster_size: usize,
+
+    /// DBSCAN minPts: only paths with at least N paths (themselves included) within eps seed clusters; paths near no such path join the grey "unclustered" group.
+    #[arg(
+        long = "dbscan-min-pts",
+        value_name = "N",
+        default_value_t = 1,
+        requires = "cluster_paths",
+        conflicts_with_all = ["use_upgma", "cluster_within"],
+        help_heading = "Clustering"
+    )]
+    dbscan_min_pts: usize,
+
+    /// Hide clusters holding less than fraction F of the clustered paths; they are still listed in the cluster TSV.
+    #[arg(
+        long = "min-cluster-fraction",
+        value_name = "F",
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    min_cluster_fraction: Option<f64>,
+
+    /// Also draw each cluster's paths on their own as DIR/OUT.cluster-N.EXT, with the main image's coordinates and path colors.
+    #[arg(
+        long = "per-cluster-images",
+        value_name = "DIR",
+        requires = "cluster_paths",
+        conflicts_with = "no_image",
+        help_heading = "Clustering"
+    )]
+    per_cluster_images: Option<PathBuf>,
+
+    /// Resample the clustering nodes N times and report per-cluster stability in the medoids TSV.
+    #[arg(
+        long = "cluster-bootstrap",
+        value_name = "N",
+        default_value_t = 0,
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    cluster_bootstrap: usize,
+
+    /// Cluster paths separately in windows of SIZE bp and draw each window's cluster as a band on every row.
+    #[arg(
+        long = "cluster-windows",
+        value_name = "SIZE",
+        help_heading = "Clustering"
+    )]
+    cluster_windows: Option<u64>,
+
+    /// Fade each cluster bar toward the background in proportion to its instability.
+    #[arg(
+        long = "shade-cluster-stability",
+        requires = "cluster_bootstrap",
+        help_heading = "Clustering"
+    )]
+    shade_cluster_stability: bool,
+
+    /// Show dendrogram on the left (hierarchical clustering tree).
+    #[arg(
+        short = 'D',
+        long = "dendrogram",
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    dendrogram: bool,
+
+    /// Width of the dendrogram in pixels.
+    #[arg(
+        long = "dendrogram-width",
+        value_name = "PIXELS",
+        default_value = "100",
+        requires = "dendrogram",
+        help_heading = "Clustering"
+    )]
+    dendrogram_width: u32,
+
+    /// Use pure UPGMA hierarchical clustering instead of DBSCAN.
+    /// Clusters are determined by cutting the tree at a height threshold.
+    #[arg(
+        long = "use-upgma",
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    use_upgma: bool,
+
+    /// Height threshold for cutting UPGMA tree (0.0-1.0, default: auto-detect).
+    /// Lower values create more clusters, higher values create fewer.
+    #[arg(
+        long = "upgma-threshold",
+        value_name = "THRESHOLD",
+        requires = "use_upgma",
+        help_heading = "Clustering"
+    )]
+    upgma_threshold: Option<f64>,
+
+    /// BED file specifying regions to use for clustering (path_name, start, end).
+    /// Only bp within these regions contribute to clustering similarity.
+    /// Paths not in the BED file are rendered but excluded from clustering.
+    #[arg(
+        long = "cluster-bed",
+        value_name = "FILE",
+        requires = "cluster_paths",
+        help_heading = "Clustering"
+    )]
+    cluster_bed: Option<PathBuf>,
+
+    // === Path Selection ===
+    /// List of paths to display in the specified order, from FILE, stdin (`-`) or inline as NAME1,NAME2.
+    #[arg(
+        short = 'p',
+        long = "paths-to-display",
+        alias = "paths",
+        value_name = "FILE",
+        help_heading = "Path Selection"
+    )]
+    paths_to_display: Option<PathBuf>,
+
+    /// Fail instead of warning when a path named in an option or file is not in the graph.
+    #[arg(long = "strict", help_heading = "Path Selection")]
+    strict: bool,
+
+    /// Ignore paths starting with the given PREFIX.
+    #[arg(
+        short = 'I',
+        long = "ignore-prefix",
+        value_name = "PREFIX",
+        help_heading = "Path Selection"
+    )]
+    ignore_prefix: Option<String>,
+
+    /// Write each path left out by the path filters, with the reason, to OUT.dropped.tsv.
+    #[arg(long = "write-dropped", help_heading = "Path Selection")]
+    write_dropped: bool,
+
+    /// Nucleotide range to visualize: [PATH:]start-end, 0-based and end-exclusive, with * for an open end. Without PATH the positions are pangenomic.
+    #[arg(
+        short = 'r',
+        long = "path-range",
+        value_name = "STRING",
+        help_heading = "Path Selection"
+    )]
+    path_range: Option<String>,
+
+    /// Merge paths beginning with prefixes listed in FILE.
+    #[arg(
+        short = 'M',
+        long = "prefix-merges",
+        value_name = "FILE",
+        help_heading = "Path Selection"
+    )]
+    prefix_merges: Option<PathBuf>,
+
+    /// Cluster paths separately within the prefix groups listed in FILE (one prefix per line), laying the groups out in file order with a group bar and legend.
+    #[arg(
+        long = "cluster-within",
+        value_name = "FILE",
+        requires = "cluster_paths",
+        conflicts_with_all = ["annotation_file", "cluster_bed", "dendrogram", "min_cluster_size"],
+        help_heading = "Clustering"
+    )]
+    cluster_within: Option<PathBuf>,
+
+    // === Path Appearance ===
+    /// Don't show path borders.
+    #[arg(
+        short = 'n',
+        long = "no-path-borders",
+        help_heading = "Path Appearance"
+    )]
+    no_path_borders: bool,
+
+    /// Draw path borders in black (default is white).
+    #[arg(
+        short = 'b',
+        long = "black-path-borders",
+        help_heading = "Path Appearance"
+    )]
+    black_path_borders: bool,
+
+    /// Draw the reference paths (named by the RS header tag, or walking only rGFA rank-0
+    /// segments) like any other path instead of with a black border and a bold name.
+    #[arg(long = "no-reference-style", help_heading = "Path Appearance")]
+    no_reference_style: bool,
+
+    /// Pack all paths rather than displaying a single path per row.
+    #[arg(
+        short = 'R',
+        long = "pack-paths",
+        conflicts_with_all = [
+            "paths_to_display",
+            "compressed_mode",
+            "prefix_merges",
+            "cluster_paths",
+            "cluster_windows",
+            "annotation_file",
+            "color_path_names_background",
+        ],
+        help_heading = "Path Appearance"
+    )]
+    pack_paths: bool,
+
+    /// Show thin links of this relative width to connect path pieces.
+    #[arg(
+        short = 'L',
+        long = "link-path-pieces",
+        value_name = "FLOAT",
+        help_heading = "Path Appearance"
+    )]
+    link_path_pieces: Option<f64>,
+
+    /// Draw paths that traverse most of the nodes they share with PATH in the opposite orientation reverse-complemented, marked with an arrow. Defaults to the first path of a reference sample named by the GFA header (RS tag).
+    #[arg(
+        long = "orient-paths-by",
+        value_name = "PATH",
+        help_heading = "Path Appearance"
+    )]
+    orient_paths_by: Option<String>,
+
+    /// Draw the paths listed in FILE (one per line) reverse-complemented, marked with an arrow.
+    #[arg(
+        long = "flip-paths",
+        value_name = "FILE",
+        help_heading = "Path Appearance"
+    )]
+    flip_paths: Option<PathBuf>,
+
+    /// Split a path into labeled sub-rows wherever consecutive steps are more than BP apart in the layout.
+    #[arg(
+        long = "split-discontiguous",
+        value_name = "BP",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help_heading = "Path Appearance"
+    )]
+    split_discontiguous: Option<u64>,
+
+    /// Draw the subrange paths of one contig (`name:start-end`) as a single row, concatenated in start order.
+    #[arg(long = "join-subranges", help_heading = "Path Appearance")]
+    join_subranges: bool,
+
+    /// Draw arcs in a band above the rows connecting both sides of every jump of more than BP within a path.
+    #[arg(
+        long = "jump-arcs",
+        value_name = "BP",
+        conflicts_with = "compressed_mode",
+        help_heading = "Path Appearance"
+    )]
+    jump_arcs: Option<u64>,
+
+    /// Lightly shade the background of columns where the displayed paths traverse many different node sets.
+    #[arg(
+        long = "shade-variable-regions",
+        conflicts_with = "compressed_mode",
+        help_heading = "Path Appearance"
+    )]
+    shade_variable_regions: bool,
+
+    /// Add a strip between the paths and the edges shading each bin by the entropy of the node sets the displayed paths traverse.
+    #[arg(
+        long = "diversity-track",
+        conflicts_with = "compressed_mode",
+        help_heading = "Path Appearance"
+    )]
+    diversity_track: bool,
+
+    /// Underline the core bins, covered by every displayed path, with a thin bar below the paths, and write core and accessory bp to OUT.core.tsv.
+    #[arg(
+        long = "core-track",
+        conflicts_with = "compressed_mode",
+        help_heading = "Path Appearance"
+    )]
+    core_track: bool,
+
+    /// Add a small histogram below the paths counting the nodes traversed by 1, 2, ... N displayed paths.
+    #[arg(
+        long = "frequency-spectrum",
+        conflicts_with = "compressed_mode",
+        help_heading = "Path Appearance"
+    )]
+    frequency_spectrum: bool,
+
+    // === Path Names ===
+    /// Hide the path names on the left of the generated image.
+    #[arg(short = 'H', long = "hide-path-names", help_heading = "Path Names")]
+    hide_path_names: bool,
+
+    /// Color path names background with the same color as paths.
+    #[arg(
+        short = 'C',
+        long = "color-path-names-background",
+        help_heading = "Path Names"
+    )]
+    color_path_names_background: bool,
+
+    /// Maximum number of characters to display for each path name.
+    #[arg(
+        short = 'c',
+        long = "max-num-of-characters",
+        value_name = "N",
+        help_heading = "Path Names"
+    )]
+    max_num_of_characters: Option<usize>,
+
+    /// Which part of a long path name to cut when it exceeds the maximum length.
+    #[arg(
+        long = "truncate-names",
+        value_name = "MODE",
+        value_enum,
+        default_value_t = TruncateMode::End,
+        help_heading = "Path Names"
+    )]
+    truncate_names: TruncateMode,
+
+    // === Coloring ===
+    /// Color paths by their names looking at the prefix before the given character.
+    #[arg(
+        short = 's',
+        long = "color-by-prefix",
+        value_name = "CHAR",
+        help_heading = "Coloring"
+    )]
+    color_by_prefix: Option<char>,
+
+    /// Read per-path RGB colors from FILE, stdin (`-`) or inline as NAME=COLOR,NAME=COLOR.
+    #[arg(
+        short = 'F',
+        long = "path-colors",
+        value_name = "FILE",
+        help_heading = "Coloring"
+    )]
+    path_colors: Option<PathBuf>,
+
+    /// Color each path by its chromosome (chr1..chr22, X, Y, M), taken from the PanSN contig or from a FILE of path and chromosome columns.
+    #[arg(
+        long = "color-by-contig",
+        value_name = "FILE",
+        num_args = 0..=1,
+        conflicts_with_all = ["path_colors", "color_by_prefix"],
+        help_heading = "Coloring"
+    )]
+    color_by_contig: Option<Option<PathBuf>>,
+
+    /// Use red and blue coloring to display forward and reverse alignments.
+    #[arg(short = 'S', long = "show-strand", help_heading = "Coloring")]
+    show_strand: bool,
+
+    /// Change the color respect to the node strandness (black for forward, red for reverse).
+    #[arg(
+        short = 'z',
+        long = "color-by-mean-inversion-rate",
+        help_heading = "Coloring"
+    )]
+    color_by_mean_inversion_rate: bool,
+
+    /// Outline bins whose steps mix forward and reverse orientations (with -z or -S).
+    #[arg(long = "flag-mixed-bins", help_heading = "Coloring")]
+    flag_mixed_bins: bool,
+
+    /// Change the color with respect to the uncalled bases.
+    #[arg(
+        short = 'N',
+        long = "color-by-uncalled-bases",
+        help_heading = "Coloring"
+    )]
+    color_by_uncalled_bases: bool,
+
+    /// Color bins by how many displayed paths contain their nodes: core (green), shell (orange) or unique (purple).
+    #[arg(
+        long = "color-by-frequency-class",
+        conflicts_with = "compressed_mode",
+        help_heading = "Coloring"
+    )]
+    color_by_frequency_class: bool,
+
+    /// Color bins by the rGFA rank (SR tag) of their segments: the reference backbone in grey, segments of rank 1, 2, 3 and 4+ in red, blue, green and purple.
+    #[arg(
+        long = "color-by-rank",
+        conflicts_with = "compressed_mode",
+        help_heading = "Coloring"
+    )]
+    color_by_rank: bool,
+
+    /// Smallest share of displayed paths that makes a bin core under --color-by-frequency-class.
+    #[arg(
+        long = "core-min-fraction",
+        value_name = "F",
+        default_value_t = 1.0,
+        requires = "color_by_frequency_class",
+        help_heading = "Coloring"
+    )]
+    core_min_fraction: f64,
+
+    /// Largest number of displayed paths that still makes a bin unique under --color-by-frequency-class.
+    #[arg(
+        long = "unique-max-paths",
+        value_name = "N",
+        default_value_t = 1,
+        requires = "color_by_frequency_class",
+        help_heading = "Coloring"
+    )]
+    unique_max_paths: usize,
+
+    /// Color nodes listed in FILE, stdin (`-`) or inline as ID1,ID2 in red and all other nodes in grey.
+    #[arg(
+        short = 'J',
+        long = "highlight-node-ids",
+        value_name = "FILE",
+        help_heading = "Coloring"
+    )]
+    highlight_node_ids: Option<PathBuf>,
+
+    /// Draw the paths listed in FILE normally and fade all other paths to grey.
+    #[arg(
+        long = "highlight-paths",
+        value_name = "FILE",
+        help_heading = "Coloring"
+    )]
+    highlight_paths: Option<PathBuf>,
+
+    /// Draw rows from small clusters or rare annotation categories at reduced opacity.
+    #[arg(
+        long = "fade-by",
+        value_name = "BY",
+        value_enum,
+        conflicts_with = "compressed_mode",
+        help_heading = "Coloring"
+    )]
+    fade_by: Option<FadeBy>,
+
+    /// Background color as #RRGGBB, R,G,B or a CSS color name (text, axis and edges adapt to it).
+    #[arg(long = "background", value_name = "COLOR", help_heading = "Coloring")]
+    background: Option<String>,
+
+    /// Use a dark theme: black background with light text, axis and edges.
+    #[arg(long = "dark", help_heading = "Coloring")]
+    dark: bool,
+
+    /// Mix colors in linear light for darkness gradients, fading and dimming, so gradients keep their hue in print.
+    #[arg(long = "linear-blend", help_heading = "Coloring")]
+    linear_blend: bool,
+
+    // === Binned Mode ===
+    /// The bin width specifies the size of each bin in the binned mode.
+    #[arg(
+        short = 'w',
+        long = "bin-width",
+        value_name = "bp",
+        help_heading = "Binned Mode"
+    )]
+    bin_width: Option<f64>,
+
+    /// Automatically set width so each node/segment gets at least 1 pixel.
+    #[arg(long = "show-all-nodes", help_heading = "Binned Mode")]
+    show_all_nodes: bool,
+
+    /// Widen every node of at least N bp whose paths differ from both its neighbors' until it fills a pixel column of its own, so small variants stay visible; the x-axis is no longer linear.
+    #[arg(
+        long = "guarantee-min-feature",
+        value_name = "N",
+        conflicts_with_all = ["show_all_nodes", "downsample"],
+        help_heading = "Binned Mode"
+    )]
+    guarantee_min_feature: Option<u64>,
+
+    /// Shrink runs of nodes that every displayed path traverses alike, spanning at least F bins, by a factor of F, marking each with a break; the x-axis is no longer linear.
+    #[arg(
+        long = "compress-invariant",
+        value_name = "F",
+        conflicts_with_all = ["show_all_nodes", "show_orphans", "separate_components"],
+        help_heading = "Binned Mode"
+    )]
+    compress_invariant: Option<f64>,
+
+    /// Minimum width in pixels for each node (use with --show-all-nodes, default: 1).
+    #[arg(
+        long = "node-width",
+        value_name = "N",
+        default_value = "1",
+        help_heading = "Binned Mode"
+    )]
+    node_width: u32,
+
+    /// With --show-all-nodes, write an image wider than the width limit as numbered tiles (OUT.1.png, OUT.2.png, ...) split at segment boundaries instead of failing.
+    #[arg(
+        long = "tile",
+        requires = "show_all_nodes",
+        help_heading = "Binned Mode"
+    )]
+    tile: bool,
+
+    /// Change the color with respect to the mean coverage.
+    #[arg(
+        short = 'm',
+        long = "color-by-mean-depth",
+        help_heading = "Binned Mode"
+    )]
+    color_by_mean_depth: bool,
+
+    /// Use the colorbrewer palette specified by SCHEME:N.
+    #[arg(
+        short = 'B',
+        long = "colorbrewer-palette",
+        value_name = "SCHEME:N",
+        help_heading = "Binned Mode"
+    )]
+    colorbrewer_palette: Option<String>,
+
+    /// Use the colorbrewer palette for <0.5x and ~1x coverage bins.
+    #[arg(short = 'G', long = "no-grey-depth", help_heading = "Binned Mode")]
+    no_grey_depth: bool,
+
+    /// Rescale mean depth before the palette lookup so rows with duplicated traversals stay comparable.
+    #[arg(
+        long = "normalize-depth",
+        value_name = "MODE",
+        value_enum,
+        default_value_t = DepthNormalization::None,
+        requires = "color_by_mean_depth",
+        conflicts_with = "compressed_mode",
+        help_heading = "Binned Mode"
+    )]
+    normalize_depth: DepthNormalization,
+
+    /// Summarize inversion and uncalled bases per bin by their mean, their maximum, or as 1 when any base has them.
+    #[arg(
+        long = "bin-aggregate",
+        value_name = "MODE",
+        value_enum,
+        default_value_t = BinAggregate::Mean,
+        help_heading = "Binned Mode"
+    )]
+    bin_aggregate: BinAggregate,
+
+    /// Mark the bins a path skips between its first and last covered bin with light grey or a hatch.
+    #[arg(
+        long = "gap-style",
+        value_name = "STYLE",
+        value_enum,
+        default_value_t = GapStyle::None,
+        help_heading = "Binned Mode"
+    )]
+    gap_style: GapStyle,
+
+    /// Move segments that no path visits and no edge joins to a hatched block at the right end.
+    #[arg(long = "show-orphans", help_heading = "Binned Mode")]
+    show_orphans: bool,
+
+    /// Lay each weakly connected component of the graph out as one block, with a separator through the rows at each boundary and a labeled strip below.
+    #[arg(
+        long = "separate-components",
+        conflicts_with_all = ["show_orphans", "per_component"],
+        help_heading = "Binned Mode"
+    )]
+    separate_components: bool,
+
+    /// Draw each weakly connected component visited by a path as its own image (OUT.component-1.png, OUT.component-2.png, ...).
+    #[arg(
+        long = "per-component",
+        conflicts_with_all = ["tile", "no_image"],
+        help_heading = "Binned Mode"
+    )]
+    per_component: bool,
+
+    /// Smooth depth, inversion and uncalled-base statistics over a window of N bins before coloring.
+    #[arg(long = "smooth", value_name = "N", help_heading = "Binned Mode")]
+    smooth: Option<usize>,
+
+    /// Take the median instead of the mean over the --smooth window.
+    #[arg(
+        long = "smooth-median",
+        requires = "smooth",
+        help_heading = "Binned Mode"
+    )]
+    smooth_median: bool,
+
+    // === Gradient Mode ===
+    /// Change the color darkness based on nucleotide position.
+    #[arg(short = 'd', long = "change-darkness", help_heading = "Gradient Mode")]
+    change_darkness: bool,
+
+    /// Use the longest path length to change the color darkness.
+    #[arg(short = 'l', long = "longest-path", help_heading = "Gradient Mode")]
+    longest_path: bool,
+
+    /// Change the color darkness from white to black.
+    #[arg(short = 'u', long = "white-to-black", help_heading = "Gradient Mode")]
+    white_to_black: bool,
+
+    // === Special Modes ===
+    /// Compress the view vertically, summarizing path coverage.
+    #[arg(
+        short = 'O',
+        long = "compressed-mode",
+        conflicts_with_all = [
+            "cluster_paths",
+            "prefix_merges",
+            "cluster_windows",
+            "annotation_file",
+            "path_colors",
+            "highlight_paths",
+            "highlight_node_ids",
+            "show_strand",
+            "color_by_mean_inversion_rate",
+            "color_by_uncalled_bases",
+            "change_darkness",
+            "link_path_pieces",
+            "color_path_names_background",
+        ],
+        help_heading = "Special Modes"
+    )]
+    compressed_mode: bool,
+
+    /// Statistic each bin of the compressed row shows.
+    #[arg(
+        long = "compressed-stat",
+        value_name = "STAT",
+        value_enum,
+        default_value_t = CompressedStat::Depth,
+        requires = "compressed_mode",
+        help_heading = "Special Modes"
+    )]
+    compressed_stat: CompressedStat,
+
+    /// Extra compressed rows below the first, one per statistic (e.g. inversion,uncalled).
+    #[arg(
+        long = "compressed-rows",
+        value_name = "STAT,...",
+        value_enum,
+        value_delimiter = ',',
+        requires = "compressed_mode",
+        help_heading = "Special Modes"
+    )]
+    compressed_rows: Vec<CompressedStat>,
+
+    /// Apply alignment related visual motifs to paths which have this name prefix.
+    #[arg(
+        short = 'A',
+        long = "alignment-prefix",
+        value_name = "STRING",
+        help_heading = "Special Modes"
+    )]
+    alignment_prefix: Option<String>,
+
+    // === X-Axis ===
+    /// Show x-axis with coordinates. Use "pangenomic" for node-order coordinates or a path name for path-based coordinates.
+    #[arg(long = "x-axis", value_name = "COORD_SYSTEM", help_heading = "X-Axis")]
+    x_axis: Option<String>,
+
+    /// Number of ticks on the x-axis.
+    #[arg(
+        long = "x-ticks",
+        value_name = "N",
+        default_value_t = 10,
+        help_heading = "X-Axis"
+    )]
+    x_ticks: u32,
+
+    /// Draw a small tick ruler along the bottom of every Nth path row, in that path's own coordinates.
+    #[arg(
+        long = "row-axes",
+        value_name = "every=N",
+        value_parser = parse_row_axes,
+        conflicts_with_all = ["pack_paths", "compressed_mode"],
+        help_heading = "X-Axis"
+    )]
+    row_axes: Option<u32>,
+
+    /// Show absolute coordinates by adding the subpath start position (from name:start-end format). Cannot be used with "pangenomic".
+    #[arg(long = "x-axis-absolute", requires = "x_axis", help_heading = "X-Axis")]
+    x_axis_absolute: bool,
+
+    // === Edges ===
+    /// Edges drawn in the link panel: every edge, only those traversed by the displayed paths, or only those declared by L lines.
+    #[arg(
+        long = "edges",
+        value_name = "SET",
+        value_enum,
+        default_value_t = EdgeSelection::All,
+        help_heading = "Edges"
+    )]
+    edges: EdgeSelection,
+
+    /// Color each edge by the blended colors of the displayed paths traversing it.
+    #[arg(long = "color-edges", help_heading = "Edges")]
+    color_edges: bool,
+
+    /// Also write the edge panel alone to FILE, with the same x-coordinates as the main image.
+    #[arg(long = "edges-out", value_name = "FILE", help_heading = "Edges")]
+    edges_out: Option<PathBuf>,
+
+    /// Also write the displayed segments and edges to FILE as GraphViz DOT, or Cytoscape JSON for .json files, sized by length and colored as in the image.
+    #[arg(long = "export-dot", value_name = "FILE", help_heading = "Edges")]
+    export_dot: Option<PathBuf>,
+
+    // === Annotation ===
+    /// Load path annotations from TSV file (columns: prefix, annotation, optional color). Prefix matches path names.
+    #[arg(
+        short = 'E',
+        long = "annotation-file",
+        value_name = "FILE",
+        help_heading = "Annotation"
+    )]
+    annotation_file: Option<PathBuf>,
+
+    /// Width of annotation bar in pixels.
+    #[arg(
+        long = "annotation-bar-width",
+        value_name = "N",
+        default_value = "10",
+        requires = "annotation_file",
+        help_heading = "Annotation"
+    )]
+    annotation_bar_width: u32,
+
+    /// Height of legend area in pixels.
+    #[arg(
+        long = "legend-height",
+        value_name = "N",
+        default_value = "30",
+        requires = "annotation_file",
+        help_heading = "Annotation"
+    )]
+    legend_height: u32,
+
+    // === Performance ===
+    /// Store clustering results in DIR and reuse them when only rendering options change.
+    #[arg(long = "cache", value_name = "DIR", help_heading = "Performance")]
+    cache: Option<PathBuf>,
+
+    /// Number of threads for the parallel stages (distance matrices, binning, clustering); default: all cores.
+    #[arg(
+        short = 't',
+        long = "threads",
+        value_name = "N",
+        help_heading = "Performance"
+    )]
+    threads: Option<usize>,
+
+    /// Verbosity level (0 = error, 1 = info, 2 = debug).
+    #[arg(
+        short = 'v',
+        long = "verbose",
+        value_name = "N",
+        default_value_t = 1,
+        help_heading = "Performance"
+    )]
+    verbose: u8,
+
+    /// Write log messages, warnings and phase timings to stderr as text or JSON lines.
+    #[arg(
+        long = "log-format",
+        value_name = "FORMAT",
+        value_enum,
+        default_value_t = LogFormat::Text,
+        help_heading = "Performance"
+    )]
+    log_format: LogFormat,
+}
+
+/// Which edges --edges draws in the link panel
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum EdgeSelection {
+    /// Every edge from L lines or consecutive path steps
+    All,
+    /// Only edges between consecutive steps of a displayed path
+    DisplayedPaths,
+    /// Only edges declared by L lines
+    LLinesOnly,
+}
+
+/// Mean color of the displayed paths traversing each edge, for --color-edges
+fn edge_path_colors(
+    display_paths: &[&GfaPath],
+    path_color: impl Fn(&GfaPath) -> (u8, u8, u8),
+) -> FxHashMap<EdgeKey, (u8, u8, u8)> {
+    let mut sums: FxHashMap<EdgeKey, [u64; 4]> = FxHashMap::default();
+    for path in display_paths {
+        let (r, g, b) = path_color(path);
+        let traversed: FxHashSet<_> = path
+            .steps
+            .windows(2)
+            .map(|w| {
+                edge_key(
+                    w[0].segment_id,
+                    w[0].is_reverse,
+                    w[1].segment_id,
+                    w[1].is_reverse,
+                )
+            })
+            .collect();
+        for key in traversed {
+            let sum = sums.entry(key).or_default();
+            sum[0] += r as u64;
+            sum[1] += g as u64;
+            sum[2] += b as u64;
+            sum[3] += 1;
+        }
+    }
+    sums.into_iter()
+        .map(|(key, [r, g, b, n])| (key, ((r / n) as u8, (g / n) as u8, (b / n) as u8)))
+        .collect()
+}
+
+/// Mean color of the displayed paths traversing each segment, for --export-dot
+fn segment_path_colors(
+    graph: &Graph,
+    display_paths: &[&GfaPath],
+    path_color: impl Fn(&GfaPath) -> (u8, u8, u8),
+) -> Vec<Option<(u8, u8, u8)>> {
+    let mut sums = vec![[0u64; 4]; graph.segments.len()];
+    for path in display_paths {
+        let (r, g, b) = path_color(path);
+        let traversed: FxHashSet<u64> = path.steps.iter().map(|s| s.segment_id).collect();
+        for id in traversed {
+            let sum = &mut sums[id as usize];
+            sum[0] += r as u64;
+            sum[1] += g as u64;
+            sum[2] += b as u64;
+            sum[3] += 1;
+        }
+    }
+    sums.into_iter()
+        .map(|[r, g, b, n]| (n > 0).then(|| ((r / n) as u8, (g / n) as u8, (b / n) as u8)))
+        .collect()
+}
+
+/// The graph edges selected by --edges for the given displayed paths
+fn selected_edges<'a>(
+    selection: EdgeSelection,
+    graph: &'a Graph,
+    display_paths: &[&GfaPath],
+) -> Vec<&'a Edge> {
+    match selection {
+        EdgeSelection::All => graph.edges.iter().collect(),
+        EdgeSelection::LLinesOnly => graph.edges.iter().filter(|e| e.from_link).collect(),
+        EdgeSelection::DisplayedPaths => {
+            let traversed: FxHashSet<EdgeKey> = display_paths
+                .iter()
+                .flat_map(|path| path.steps.windows(2))
+                .map(|w| {
+                    edge_key(
+                        w[0].segment_id,
+                        w[0].is_reverse,
+                        w[1].segment_id,
+                        w[1].is_reverse,
+                    )
+                })
+                .collect();
+            graph
+                .edges
+                .iter()
+                .filter(|e| traversed.contains(&e.key()))
+                .collect()
+        }
+    }
+}
+
+/// 5x8 bitmap font (matching odgi's font5x8.h)
+const FONT_5X8: [[u8; 8]; 128] = {
+    let mut font = [[0u8; 8]; 128];
+    font[b' ' as usize] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    font[b'!' as usize] = [0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x20, 0x00];
+    font[b'"' as usize] = [0x50, 0x50, 0x50, 0x00, 0x00, 0x00, 0x00, 0x00];
+    font[b'#' as usize] = [0x50, 0x50, 0xF8, 0x50, 0xF8, 0x50, 0x50, 0x00];
+    font[b'$' as usize] = [0x20, 0x78, 0xA0, 0x70, 0x28, 0xF0, 0x20, 0x00];
+    font[b'%' as usize] = [0xC0, 0xC8, 0x10, 0x20, 0x40, 0x98, 0x18, 0x00];
+    font[b'&' as usize] = [0x40, 0xA0, 0xA0, 0x40, 0xA8, 0x90, 0x68, 0x00];
+    font[b'\'' as usize] = [0x20, 0x20, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00];
+    font[b'(' as usize] = [0x10, 0x20, 0x40, 0x40, 0x40, 0x20, 0x10, 0x00];
+    font[b')' as usize] = [0x40, 0x20, 0x10, 0x10, 0x10, 0x20, 0x40, 0x00];
+    font[b'*' as usize] = [0x00, 0x20, 0xA8, 0x70, 0xA8, 0x20, 0x00, 0x00];
+    font[b'+' as usize] = [0x00, 0x20, 0x20, 0xF8, 0x20, 0x20, 0x00, 0x00];
+    font[b',' as usize] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x20, 0x40];
+    font[b'-' as usize] = [0x00, 0x00, 0x00, 0xF8, 0x00, 0x00, 0x00, 0x00];
+    font[b'.' as usize] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x20, 0x00];
+    font[b'/' as usize] = [0x00, 0x08, 0x10, 0x20, 0x40, 0x80, 0x00, 0x00];
+    font[b'0' as usize] = [0x70, 0x88, 0x98, 0xA8, 0xC8, 0x88, 0x70, 0x00];
+    font[b'1' as usize] = [0x20, 0x60, 0x20, 0x20, 0x20, 0x20, 0x70, 0x00];
+    font[b'2' as usize] = [0x70, 0x88, 0x08, 0x30, 0x40, 0x80, 0xF8, 0x00];
+    font[b'3' as usize] = [0xF8, 0x10, 0x20, 0x10, 0x08, 0x88, 0x70, 0x00];
+    font[b'4' as usize] = [0x10, 0x30, 0x50, 0x90, 0xF8, 0x10, 0x10, 0x00];
+    font[b'5' as usize] = [0xF8, 0x80, 0xF0, 0x08, 0x08, 0x88, 0x70, 0x00];
+    font[b'6' as usize] = [0x30, 0x40, 0x80, 0xF0, 0x88, 0x88, 0x70, 0x00];
+    font[b'7' as usize] = [0xF8, 0x08, 0x10, 0x20, 0x40, 0x40, 0x40, 0x00];
+    font[b'8' as usize] = [0x70, 0x88, 0x88, 0x70, 0x88, 0x88, 0x70, 0x00];
+    font[b'9' as usize] = [0x70, 0x88, 0x88, 0x78, 0x08, 0x10, 0x60, 0x00];
+    font[b':' as usize] = [0x00, 0x00, 0x20, 0x00, 0x00, 0x20, 0x00, 0x00];
+    font[b';' as usize] = [0x00, 0x00, 0x20, 0x00, 0x00, 0x20, 0x20, 0x40];
+    font[b'<' as usize] = [0x08, 0x10, 0x20, 0x40, 0x20, 0x10, 0x08, 0x00];
+    font[b'=' as usize] = [0x00, 0x00, 0xF8, 0x00, 0xF8, 0x00, 0x00, 0x00];
+    font[b'>' as usize] = [0x80, 0x40, 0x20, 0x10, 0x20, 0x40, 0x80, 0x00];
+    font[b'?' as usize] = [0x70, 0x88, 0x08, 0x10, 0x20, 0x00, 0x20, 0x00];
+    font[b'@' as usize] = [0x70, 0x88, 0xB8, 0xA8, 0xB8, 0x80, 0x70, 0x00];
+    font[b'A' as usize] = [0x70, 0x88, 0x88, 0xF8, 0x88, 0x88, 0x88, 0x00];
+    font[b'B' as usize] = [0xF0, 0x88, 0x88, 0xF0, 0x88, 0x88, 0xF0, 0x00];
+    font[b'C' as usize] = [0x70, 0x88, 0x80, 0x80, 0x80, 0x88, 0x70, 0x00];
+    font[b'D' as usize] = [0xE0, 0x90, 0x88, 0x88, 0x88, 0x90, 0xE0, 0x00];
+    font[b'E' as usize] = [0xF8, 0x80, 0x80, 0xF0, 0x80, 0x80, 0xF8, 0x00];
+    font[b'F' as usize] = [0xF8, 0x80, 0x80, 0xF0, 0x80, 0x80, 0x80, 0x00];
+    font[b'G' as usize] = [0x70, 0x88, 0x80, 0xB8, 0x88, 0x88, 0x70, 0x00];
+    font[b'H' as usize] = [0x88, 0x88, 0x88, 0xF8, 0x88, 0x88, 0x88, 0x00];
+    font[b'I' as usize] = [0x70, 0x20, 0x20, 0x20, 0x20, 0x20, 0x70, 0x00];
+    font[b'J' as usize] = [0x38, 0x10, 0x10, 0x10, 0x10, 0x90, 0x60, 0x00];
+    font[b'K' as usize] = [0x88, 0x90, 0xA0, 0xC0, 0xA0, 0x90, 0x88, 0x00];
+    font[b'L' as usize] = [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0xF8, 0x00];
+    font[b'M' as usize] = [0x88, 0xD8, 0xA8, 0xA8, 0x88, 0x88, 0x88, 0x00];
+    font[b'N' as usize] = [0x88, 0xC8, 0xA8, 0x98, 0x88, 0x88, 0x88, 0x00];
+    font[b'O' as usize] = [0x70, 0x88, 0x88, 0x88, 0x88, 0x88, 0x70, 0x00];
+    font[b'P' as usize] = [0xF0, 0x88, 0x88, 0xF0, 0x80, 0x80, 0x80, 0x00];
+    font[b'Q' as usize] = [0x70, 0x88, 0x88, 0x88, 0xA8, 0x90, 0x68, 0x00];
+    font[b'R' as usize] = [0xF0, 0x88, 0x88, 0xF0, 0xA0, 0x90, 0x88, 0x00];
+    font[b'S' as usize] = [0x70, 0x88, 0x80, 0x70, 0x08, 0x88, 0x70, 0x00];
+    font[b'T' as usize] = [0xF8, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00];
+    font[b'U' as usize] = [0x88, 0x88, 0x88, 0x88, 0x88, 0x88, 0x70, 0x00];
+    font[b'V' as usize] = [0x88, 0x88, 0x88, 0x88, 0x88, 0x50, 0x20, 0x00];
+    font[b'W' as usize] = [0x88, 0x88, 0x88, 0xA8, 0xA8, 0xD8, 0x88, 0x00];
+    font[b'X' as usize] = [0x88, 0x88, 0x50, 0x20, 0x50, 0x88, 0x88, 0x00];
+    font[b'Y' as usize] = [0x88, 0x88, 0x50, 0x20, 0x20, 0x20, 0x20, 0x00];
+    font[b'Z' as usize] = [0xF8, 0x08, 0x10, 0x20, 0x40, 0x80, 0xF8, 0x00];
+    font[b'[' as usize] = [0x70, 0x40, 0x40, 0x40, 0x40, 0x40, 0x70, 0x00];
+    font[b'\\' as usize] = [0x00, 0x80, 0x40, 0x20, 0x10, 0x08, 0x00, 0x00];
+    font[b']' as usize] = [0x70, 0x10, 0x10, 0x10, 0x10, 0x10, 0x70, 0x00];
+    font[b'^' as usize] = [0x20, 0x50, 0x88, 0x00, 0x00, 0x00, 0x00, 0x00];
+    font[b'_' as usize] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF8, 0x00];
+    font[b'`' as usize] = [0x40, 0x20, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00];
+    font[b'a' as usize] = [0x00, 0x00, 0x70, 0x08, 0x78, 0x88, 0x78, 0x00];
+    font[b'b' as usize] = [0x80, 0x80, 0xB0, 0xC8, 0x88, 0x88, 0xF0, 0x00];
+    font[b'c' as usize] = [0x00, 0x00, 0x70, 0x80, 0x80, 0x88, 0x70, 0x00];
+    font[b'd' as usize] = [0x08, 0x08, 0x68, 0x98, 0x88, 0x88, 0x78, 0x00];
+    font[b'e' as usize] = [0x00, 0x00, 0x70, 0x88, 0xF8, 0x80, 0x70, 0x00];
+    font[b'f' as usize] = [0x30, 0x48, 0x40, 0xE0, 0x40, 0x40, 0x40, 0x00];
+    font[b'g' as usize] = [0x00, 0x00, 0x78, 0x88, 0x78, 0x08, 0x70, 0x00];
+    font[b'h' as usize] = [0x80, 0x80, 0xB0, 0xC8, 0x88, 0x88, 0x88, 0x00];
+    font[b'i' as usize] = [0x20, 0x00, 0x60, 0x20, 0x20, 0x20, 0x70, 0x00];
+    font[b'j' as usize] = [0x10, 0x00, 0x30, 0x10, 0x10, 0x90, 0x60, 0x00];
+    font[b'k' as usize] = [0x80, 0x80, 0x90, 0xA0, 0xC0, 0xA0, 0x90, 0x00];
+    font[b'l' as usize] = [0x60, 0x20, 0x20, 0x20, 0x20, 0x20, 0x70, 0x00];
+    font[b'm' as usize] = [0x00, 0x00, 0xD0, 0xA8, 0xA8, 0xA8, 0xA8, 0x00];
+    font[b'n' as usize] = [0x00, 0x00, 0xB0, 0xC8, 0x88, 0x88, 0x88, 0x00];
+    font[b'o' as usize] = [0x00, 0x00, 0x70, 0x88, 0x88, 0x88, 0x70, 0x00];
+    font[b'p' as usize] = [0x00, 0x00, 0xF0, 0x88, 0xF0, 0x80, 0x80, 0x00];
+    font[b'q' as usize] = [0x00, 0x00, 0x78, 0x88, 0x78, 0x08, 0x08, 0x00];
+    font[b'r' as usize] = [0x00, 0x00, 0xB0, 0xC8, 0x80, 0x80, 0x80, 0x00];
+    font[b's' as usize] = [0x00, 0x00, 0x70, 0x80, 0x70, 0x08, 0xF0, 0x00];
+    font[b't' as usize] = [0x40, 0x40, 0xE0, 0x40, 0x40, 0x48, 0x30, 0x00];
+    font[b'u' as usize] = [0x00, 0x00, 0x88, 0x88, 0x88, 0x98, 0x68, 0x00];
+    font[b'v' as usize] = [0x00, 0x00, 0x88, 0x88, 0x88, 0x50, 0x20, 0x00];
+    font[b'w' as usize] = [0x00, 0x00, 0x88, 0x88, 0xA8, 0xA8, 0x50, 0x00];
+    font[b'x' as usize] = [0x00, 0x00, 0x88, 0x50, 0x20, 0x50, 0x88, 0x00];
+    font[b'y' as usize] = [0x00, 0x00, 0x88, 0x88, 0x78, 0x08, 0x70, 0x00];
+    font[b'z' as usize] = [0x00, 0x00, 0xF8, 0x10, 0x20, 0x40, 0xF8, 0x00];
+    font[b'{' as usize] = [0x10, 0x20, 0x20, 0x40, 0x20, 0x20, 0x10, 0x00];
+    font[b'|' as usize] = [0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00];
+    font[b'}' as usize] = [0x40, 0x20, 0x20, 0x10, 0x20, 0x20, 0x40, 0x00];
+    font[b'~' as usize] = [0x00, 0x00, 0x40, 0xA8, 0x10, 0x00, 0x00, 0x00];
+    font
+};
+
+const TRAILING_DOTS: [u8; 8] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xA8, 0x00];
+
+/// Glyph for '←', which marks flipped paths
+const LEFT_ARROW: [u8; 8] = [0x00, 0x20, 0x40, 0xF8, 0x40, 0x20, 0x00, 0x00];
+
+/// Hollow box drawn for characters the bitmap font cannot represent
+const MISSING_GLYPH: [u8; 8] = [0x00, 0xF8, 0x88, 0x88, 0x88, 0x88, 0xF8, 0x00];
+
+/// Fold accented Latin characters to their unaccented ASCII base letter.
+/// Returns None for characters without an obvious ASCII counterpart.
+fn fold_to_ascii(c: char) -> Option<char> {
+    let folded = match c {
+        'À'..='Å' | 'Ā' | 'Ă' | 'Ą' => 'A',
+        'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+        'Ç' | 'Ć' | 'Č' => 'C',
+        'ç' | 'ć' | 'č' => 'c',
+        'Ð' | 'Ď' | 'Đ' => 'D',
+        'ð' | 'ď' | 'đ' => 'd',
+        'È'..='Ë' | 'Ē' | 'Ę' | 'Ě' => 'E',
+        'è'..='ë' | 'ē' | 'ę' | 'ě' => 'e',
+        'Ğ' => 'G',
+        'ğ' => 'g',
+        'Ì'..='Ï' | 'Ī' | 'İ' => 'I',
+        'ì'..='ï' | 'ī' | 'ı' => 'i',
+        'Ł' | 'Ľ' => 'L',
+        'ł' | 'ľ' => 'l',
+        'Ñ' | 'Ń' | 'Ň' => 'N',
+        'ñ' | 'ń' | 'ň' => 'n',
+        'Ò'..='Ö' | 'Ø' | 'Ő' => 'O',
+        'ò'..='ö' | 'ø' | 'ő' => 'o',
+        'Ř' => 'R',
+        'ř' => 'r',
+        'Ś' | 'Ş' | 'Š' => 'S',
+        'ś' | 'ş' | 'š' | 'ß' => 's',
+        'Ť' | 'Ţ' => 'T',
+        'ť' | 'ţ' => 't',
+        'Ù'..='Ü' | 'Ů' | 'Ű' => 'U',
+        'ù'..='ü' | 'ů' | 'ű' => 'u',
+        'Ý' | 'Ÿ' => 'Y',
+        'ý' | 'ÿ' => 'y',
+        'Ź' | 'Ż' | 'Ž' => 'Z',
+        'ź' | 'ż' | 'ž' => 'z',
+        '\u{2010}'..='\u{2015}' => '-',
+        '\u{2018}' | '\u{2019}' => '\'',
+        '\u{201C}' | '\u{201D}' => '"',
+        '\u{00A0}' => ' ',
+        _ => return None,
+    };
+    Some(folded)
+}
+
+/// Look up the bitmap glyph for any character, folding accented Latin letters
+/// to ASCII and drawing a box for everything else
+fn glyph_for_char(c: char) -> &'static [u8; 8] {
+    if c == '\u{2026}' {
+        &TRAILING_DOTS
+    } else if c == '\u{2190}' {
+        &LEFT_ARROW
+    } else if c.is_ascii() {
+        &FONT_5X8[c as usize]
+    } else if let Some(folded) = fold_to_ascii(c) {
+        &FONT_5X8[folded as usize]
+    } else {
+        &MISSING_GLYPH
+    }
+}
+
+/// Where to cut path names that exceed the maximum number of characters
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum TruncateMode {
+    /// Keep the beginning of the name: `HG002#1#chr6_…`
+    End,
+    /// Keep both ends of the name: `HG002#1#…#chr6_42`
+    Middle,
+    /// Keep the end of the name: `…#1#chr6_42`
+    Start,
+}
+
+/// Shorten a name to at most `max_chars` characters, marking the cut with an ellipsis
+fn truncate_name(name: &str, max_chars: usize, mode: TruncateMode) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    if chars.len() <= max_chars {
+        return name.to_string();
+    }
+    if max_chars == 0 {
+        return String::new();
+    }
+    let keep = max_chars - 1;
+    let ellipsis = '\u{2026}';
+    match mode {
+        TruncateMode::End => chars[..keep].iter().chain([&ellipsis]).collect(),
+        TruncateMode::Start => [&ellipsis]
+            .into_iter()
+            .chain(chars[chars.len() - keep..].iter())
+            .collect(),
+        TruncateMode::Middle => {
+            let head = keep.div_ceil(2);
+            let tail = keep - head;
+            chars[..head]
+                .iter()
+                .chain([&ellipsis])
+                .chain(chars[chars.len() - tail..].iter())
+                .collect()
+        }
+    }
+}
+
+/// Estimated number of monospace columns a string occupies in SVG text.
+/// East Asian wide characters and emoji take two columns.
+fn text_columns(s: &str) -> usize {
+    s.chars()
+        .map(|c| {
+            let cp = c as u32;
+            let wide = (0x1100..=0x115F).contains(&cp)
+                || (0x2E80..=0xA4CF).contains(&cp)
+                || (0xAC00..=0xD7A3).contains(&cp)
+                || (0xF900..=0xFAFF).contains(&cp)
+                || (0xFE30..=0xFE4F).contains(&cp)
+                || (0xFF00..=0xFF60).contains(&cp)
+                || (0xFFE0..=0xFFE6).contains(&cp)
+                || (0x1F300..=0x1FAFF).contains(&cp)
+                || (0x20000..=0x3FFFD).contains(&cp);
+            if wide {
+                2
+            } else {
+                1
+            }
+        })
+        .sum()
+}
+
+/// ColorBrewer Spectral 11-class diverging palette (default for -m)
+/// With two grey colors prepended for low coverage (matching odgi)
+const COLORBREWER_SPECTRAL_13: [(u8, u8, u8); 13] = [
+    (196, 196, 196), // < 0.5x coverage (very low)
+    (128, 128, 128), // < 1.5x coverage (low)
+    // Spectral 11 (reversed from standard order)
+    (158, 1, 66),
+    (213, 62, 79),
+    (244, 109, 67),
+    (253, 174, 97),
+    (254, 224, 139),
+    (255, 255, 191),
+    (230, 245, 152),
+    (171, 221, 164),
+    (102, 194, 165),
+    (50, 136, 189),
+    (94, 79, 162),
+];
+
+/// ColorBrewer RdBu 11-class diverging palette (used for compressed mode by default)
+const COLORBREWER_RDBU_11: [(u8, u8, u8); 11] = [
+    (103, 0, 31),
+    (178, 24, 43),
+    (214, 96, 77),
+    (244, 165, 130),
+    (253, 219, 199),
+    (247, 247, 247),
+    (209, 229, 240),
+    (146, 197, 222),
+    (67, 147, 195),
+    (33, 102, 172),
+    (5, 48, 97),
+];
+
+/// Get a ColorBrewer palette by name and number of classes.
+/// Supports all 35 ColorBrewer schemes (matching odgi): diverging, qualitative, and sequential.
+/// If the requested N doesn't match any variant, returns the largest available variant.
+fn get_colorbrewer_palette(name: &str, n: usize) -> Option<&'static [(u8, u8, u8)]> {
+    /*** Diverging ***/
+    if name.eq_ignore_ascii_case("spectral") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(252,141,89), (255,255,191), (153,213,148)],
+            &[(215,25,28), (253,174,97), (171,221,164), (43,131,186)],
+            &[(215,25,28), (253,174,97), (255,255,191), (171,221,164), (43,131,186)],
+            &[(213,62,79), (252,141,89), (254,224,139), (230,245,152), (153,213,148), (50,136,189)],
+            &[(213,62,79), (252,141,89), (254,224,139), (255,255,191), (230,245,152), (153,213,148), (50,136,189)],
+            &[(213,62,79), (244,109,67), (253,174,97), (254,224,139), (230,245,152), (171,221,164), (102,194,165), (50,136,189)],
+            &[(213,62,79), (244,109,67), (253,174,97), (254,224,139), (255,255,191), (230,245,152), (171,221,164), (102,194,165), (50,136,189)],
+            &[(158,1,66), (213,62,79), (244,109,67), (253,174,97), (254,224,139), (230,245,152), (171,221,164), (102,194,165), (50,136,189), (94,79,162)],
+            &[(158,1,66), (213,62,79), (244,109,67), (253,174,97), (254,224,139), (255,255,191), (230,245,152), (171,221,164), (102,194,165), (50,136,189), (94,79,162)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("rdylgn") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(252,141,89), (255,255,191), (145,207,96)],
+            &[(215,25,28), (253,174,97), (166,217,106), (26,150,65)],
+            &[(215,25,28), (253,174,97), (255,255,191), (166,217,106), (26,150,65)],
+            &[(215,48,39), (252,141,89), (254,224,139), (217,239,139), (145,207,96), (26,152,80)],
+            &[(215,48,39), (252,141,89), (254,224,139), (255,255,191), (217,239,139), (145,207,96), (26,152,80)],
+            &[(215,48,39), (244,109,67), (253,174,97), (254,224,139), (217,239,139), (166,217,106), (102,189,99), (26,152,80)],
+            &[(215,48,39), (244,109,67), (253,174,97), (254,224,139), (255,255,191), (217,239,139), (166,217,106), (102,189,99), (26,152,80)],
+            &[(165,0,38), (215,48,39), (244,109,67), (253,174,97), (254,224,139), (217,239,139), (166,217,106), (102,189,99), (26,152,80), (0,104,55)],
+            &[(165,0,38), (215,48,39), (244,109,67), (253,174,97), (254,224,139), (255,255,191), (217,239,139), (166,217,106), (102,189,99), (26,152,80), (0,104,55)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("rdbu") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(239,138,98), (247,247,247), (103,169,207)],
+            &[(202,0,32), (244,165,130), (146,197,222), (5,113,176)],
+            &[(202,0,32), (244,165,130), (247,247,247), (146,197,222), (5,113,176)],
+            &[(178,24,43), (239,138,98), (253,219,199), (209,229,240), (103,169,207), (33,102,172)],
+            &[(178,24,43), (239,138,98), (253,219,199), (247,247,247), (209,229,240), (103,169,207), (33,102,172)],
+            &[(178,24,43), (214,96,77), (244,165,130), (253,219,199), (209,229,240), (146,197,222), (67,147,195), (33,102,172)],
+            &[(178,24,43), (214,96,77), (244,165,130), (253,219,199), (247,247,247), (209,229,240), (146,197,222), (67,147,195), (33,102,172)],
+            &[(103,0,31), (178,24,43), (214,96,77), (244,165,130), (253,219,199), (209,229,240), (146,197,222), (67,147,195), (33,102,172), (5,48,97)],
+            &[(103,0,31), (178,24,43), (214,96,77), (244,165,130), (253,219,199), (247,247,247), (209,229,240), (146,197,222), (67,147,195), (33,102,172), (5,48,97)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("piyg") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(233,163,201), (247,247,247), (161,215,106)],
+            &[(208,28,139), (241,182,218), (184,225,134), (77,172,38)],
+            &[(208,28,139), (241,182,218), (247,247,247), (184,225,134), (77,172,38)],
+            &[(197,27,125), (233,163,201), (253,224,239), (230,245,208), (161,215,106), (77,146,33)],
+            &[(197,27,125), (233,163,201), (253,224,239), (247,247,247), (230,245,208), (161,215,106), (77,146,33)],
+            &[(197,27,125), (222,119,174), (241,182,218), (253,224,239), (230,245,208), (184,225,134), (127,188,65), (77,146,33)],
+            &[(197,27,125), (222,119,174), (241,182,218), (253,224,239), (247,247,247), (230,245,208), (184,225,134), (127,188,65), (77,146,33)],
+            &[(142,1,82), (197,27,125), (222,119,174), (241,182,218), (253,224,239), (230,245,208), (184,225,134), (127,188,65), (77,146,33), (39,100,25)],
+            &[(142,1,82), (197,27,125), (222,119,174), (241,182,218), (253,224,239), (247,247,247), (230,245,208), (184,225,134), (127,188,65), (77,146,33), (39,100,25)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("prgn") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(175,141,195), (247,247,247), (127,191,123)],
+            &[(123,50,148), (194,165,207), (166,219,160), (0,136,55)],
+            &[(123,50,148), (194,165,207), (247,247,247), (166,219,160), (0,136,55)],
+            &[(118,42,131), (175,141,195), (231,212,232), (217,240,211), (127,191,123), (27,120,55)],
+            &[(118,42,131), (175,141,195), (231,212,232), (247,247,247), (217,240,211), (127,191,123), (27,120,55)],
+            &[(118,42,131), (153,112,171), (194,165,207), (231,212,232), (217,240,211), (166,219,160), (90,174,97), (27,120,55)],
+            &[(118,42,131), (153,112,171), (194,165,207), (231,212,232), (247,247,247), (217,240,211), (166,219,160), (90,174,97), (27,120,55)],
+            &[(64,0,75), (118,42,131), (153,112,171), (194,165,207), (231,212,232), (217,240,211), (166,219,160), (90,174,97), (27,120,55), (0,68,27)],
+            &[(64,0,75), (118,42,131), (153,112,171), (194,165,207), (231,212,232), (247,247,247), (217,240,211), (166,219,160), (90,174,97), (27,120,55), (0,68,27)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("rdylbu") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(252,141,89), (255,255,191), (145,191,219)],
+            &[(215,25,28), (253,174,97), (171,217,233), (44,123,182)],
+            &[(215,25,28), (253,174,97), (255,255,191), (171,217,233), (44,123,182)],
+            &[(215,48,39), (252,141,89), (254,224,144), (224,243,248), (145,191,219), (69,117,180)],
+            &[(215,48,39), (252,141,89), (254,224,144), (255,255,191), (224,243,248), (145,191,219), (69,117,180)],
+            &[(215,48,39), (244,109,67), (253,174,97), (254,224,144), (224,243,248), (171,217,233), (116,173,209), (69,117,180)],
+            &[(215,48,39), (244,109,67), (253,174,97), (254,224,144), (255,255,191), (224,243,248), (171,217,233), (116,173,209), (69,117,180)],
+            &[(165,0,38), (215,48,39), (244,109,67), (253,174,97), (254,224,144), (224,243,248), (171,217,233), (116,173,209), (69,117,180), (49,54,149)],
+            &[(165,0,38), (215,48,39), (244,109,67), (253,174,97), (254,224,144), (255,255,191), (224,243,248), (171,217,233), (116,173,209), (69,117,180), (49,54,149)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("brbg") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(216,179,101), (245,245,245), (90,180,172)],
+            &[(166,97,26), (223,194,125), (128,205,193), (1,133,113)],
+            &[(166,97,26), (223,194,125), (245,245,245), (128,205,193), (1,133,113)],
+            &[(140,81,10), (216,179,101), (246,232,195), (199,234,229), (90,180,172), (1,102,94)],
+            &[(140,81,10), (216,179,101), (246,232,195), (245,245,245), (199,234,229), (90,180,172), (1,102,94)],
+            &[(140,81,10), (191,129,45), (223,194,125), (246,232,195), (199,234,229), (128,205,193), (53,151,143), (1,102,94)],
+            &[(140,81,10), (191,129,45), (223,194,125), (246,232,195), (245,245,245), (199,234,229), (128,205,193), (53,151,143), (1,102,94)],
+            &[(84,48,5), (140,81,10), (191,129,45), (223,194,125), (246,232,195), (199,234,229), (128,205,193), (53,151,143), (1,102,94), (0,60,48)],
+            &[(84,48,5), (140,81,10), (191,129,45), (223,194,125), (246,232,195), (245,245,245), (199,234,229), (128,205,193), (53,151,143), (1,102,94), (0,60,48)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("rdgy") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(239,138,98), (255,255,255), (153,153,153)],
+            &[(202,0,32), (244,165,130), (186,186,186), (64,64,64)],
+            &[(202,0,32), (244,165,130), (255,255,255), (186,186,186), (64,64,64)],
+            &[(178,24,43), (239,138,98), (253,219,199), (224,224,224), (153,153,153), (77,77,77)],
+            &[(178,24,43), (239,138,98), (253,219,199), (255,255,255), (224,224,224), (153,153,153), (77,77,77)],
+            &[(178,24,43), (214,96,77), (244,165,130), (253,219,199), (224,224,224), (186,186,186), (135,135,135), (77,77,77)],
+            &[(178,24,43), (214,96,77), (244,165,130), (253,219,199), (255,255,255), (224,224,224), (186,186,186), (135,135,135), (77,77,77)],
+            &[(103,0,31), (178,24,43), (214,96,77), (244,165,130), (253,219,199), (224,224,224), (186,186,186), (135,135,135), (77,77,77), (26,26,26)],
+            &[(103,0,31), (178,24,43), (214,96,77), (244,165,130), (253,219,199), (255,255,255), (224,224,224), (186,186,186), (135,135,135), (77,77,77), (26,26,26)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("puor") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(241,163,64), (247,247,247), (153,142,195)],
+            &[(230,97,1), (253,184,99), (178,171,210), (94,60,153)],
+            &[(230,97,1), (253,184,99), (247,247,247), (178,171,210), (94,60,153)],
+            &[(179,88,6), (241,163,64), (254,224,182), (216,218,235), (153,142,195), (84,39,136)],
+            &[(179,88,6), (241,163,64), (254,224,182), (247,247,247), (216,218,235), (153,142,195), (84,39,136)],
+            &[(179,88,6), (224,130,20), (253,184,99), (254,224,182), (216,218,235), (178,171,210), (128,115,172), (84,39,136)],
+            &[(179,88,6), (224,130,20), (253,184,99), (254,224,182), (247,247,247), (216,218,235), (178,171,210), (128,115,172), (84,39,136)],
+            &[(127,59,8), (179,88,6), (224,130,20), (253,184,99), (254,224,182), (216,218,235), (178,171,210), (128,115,172), (84,39,136), (45,0,75)],
+            &[(127,59,8), (179,88,6), (224,130,20), (253,184,99), (254,224,182), (247,247,247), (216,218,235), (178,171,210), (128,115,172), (84,39,136), (45,0,75)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    /*** Qualitative ***/
+    if name.eq_ignore_ascii_case("set2") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(102,194,165), (252,141,98), (141,160,203)],
+            &[(102,194,165), (252,141,98), (141,160,203), (231,138,195)],
+            &[(102,194,165), (252,141,98), (141,160,203), (231,138,195), (166,216,84)],
+            &[(102,194,165), (252,141,98), (141,160,203), (231,138,195), (166,216,84), (255,217,47)],
+            &[(102,194,165), (252,141,98), (141,160,203), (231,138,195), (166,216,84), (255,217,47), (229,196,148)],
+            &[(102,194,165), (252,141,98), (141,160,203), (231,138,195), (166,216,84), (255,217,47), (229,196,148), (179,179,179)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("accent") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(127,201,127), (190,174,212), (253,192,134)],
+            &[(127,201,127), (190,174,212), (253,192,134), (255,255,153)],
+            &[(127,201,127), (190,174,212), (253,192,134), (255,255,153), (56,108,176)],
+            &[(127,201,127), (190,174,212), (253,192,134), (255,255,153), (56,108,176), (240,2,127)],
+            &[(127,201,127), (190,174,212), (253,192,134), (255,255,153), (56,108,176), (240,2,127), (191,91,23)],
+            &[(127,201,127), (190,174,212), (253,192,134), (255,255,153), (56,108,176), (240,2,127), (191,91,23), (102,102,102)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("set1") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(228,26,28), (55,126,184), (77,175,74)],
+            &[(228,26,28), (55,126,184), (77,175,74), (152,78,163)],
+            &[(228,26,28), (55,126,184), (77,175,74), (152,78,163), (255,127,0)],
+            &[(228,26,28), (55,126,184), (77,175,74), (152,78,163), (255,127,0), (255,255,51)],
+            &[(228,26,28), (55,126,184), (77,175,74), (152,78,163), (255,127,0), (255,255,51), (166,86,40)],
+            &[(228,26,28), (55,126,184), (77,175,74), (152,78,163), (255,127,0), (255,255,51), (166,86,40), (247,129,191)],
+            &[(228,26,28), (55,126,184), (77,175,74), (152,78,163), (255,127,0), (255,255,51), (166,86,40), (247,129,191), (153,153,153)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("set3") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(141,211,199), (255,255,179), (190,186,218)],
+            &[(141,211,199), (255,255,179), (190,186,218), (251,128,114)],
+            &[(141,211,199), (255,255,179), (190,186,218), (251,128,114), (128,177,211)],
+            &[(141,211,199), (255,255,179), (190,186,218), (251,128,114), (128,177,211), (253,180,98)],
+            &[(141,211,199), (255,255,179), (190,186,218), (251,128,114), (128,177,211), (253,180,98), (179,222,105)],
+            &[(141,211,199), (255,255,179), (190,186,218), (251,128,114), (128,177,211), (253,180,98), (179,222,105), (252,205,229)],
+            &[(141,211,199), (255,255,179), (190,186,218), (251,128,114), (128,177,211), (253,180,98), (179,222,105), (252,205,229), (217,217,217)],
+            &[(141,211,199), (255,255,179), (190,186,218), (251,128,114), (128,177,211), (253,180,98), (179,222,105), (252,205,229), (217,217,217), (188,128,189)],
+            &[(141,211,199), (255,255,179), (190,186,218), (251,128,114), (128,177,211), (253,180,98), (179,222,105), (252,205,229), (217,217,217), (188,128,189), (204,235,197)],
+            &[(141,211,199), (255,255,179), (190,186,218), (251,128,114), (128,177,211), (253,180,98), (179,222,105), (252,205,229), (217,217,217), (188,128,189), (204,235,197), (255,237,111)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("dark2") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(27,158,119), (217,95,2), (117,112,179)],
+            &[(27,158,119), (217,95,2), (117,112,179), (231,41,138)],
+            &[(27,158,119), (217,95,2), (117,112,179), (231,41,138), (102,166,30)],
+            &[(27,158,119), (217,95,2), (117,112,179), (231,41,138), (102,166,30), (230,171,2)],
+            &[(27,158,119), (217,95,2), (117,112,179), (231,41,138), (102,166,30), (230,171,2), (166,118,29)],
+            &[(27,158,119), (217,95,2), (117,112,179), (231,41,138), (102,166,30), (230,171,2), (166,118,29), (102,102,102)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("paired") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(166,206,227), (31,120,180), (178,223,138)],
+            &[(166,206,227), (31,120,180), (178,223,138), (51,160,44)],
+            &[(166,206,227), (31,120,180), (178,223,138), (51,160,44), (251,154,153)],
+            &[(166,206,227), (31,120,180), (178,223,138), (51,160,44), (251,154,153), (227,26,28)],
+            &[(166,206,227), (31,120,180), (178,223,138), (51,160,44), (251,154,153), (227,26,28), (253,191,111)],
+            &[(166,206,227), (31,120,180), (178,223,138), (51,160,44), (251,154,153), (227,26,28), (253,191,111), (255,127,0)],
+            &[(166,206,227), (31,120,180), (178,223,138), (51,160,44), (251,154,153), (227,26,28), (253,191,111), (255,127,0), (202,178,214)],
+            &[(166,206,227), (31,120,180), (178,223,138), (51,160,44), (251,154,153), (227,26,28), (253,191,111), (255,127,0), (202,178,214), (106,61,154)],
+            &[(166,206,227), (31,120,180), (178,223,138), (51,160,44), (251,154,153), (227,26,28), (253,191,111), (255,127,0), (202,178,214), (106,61,154), (255,255,153)],
+            &[(166,206,227), (31,120,180), (178,223,138), (51,160,44), (251,154,153), (227,26,28), (253,191,111), (255,127,0), (202,178,214), (106,61,154), (255,255,153), (177,89,40)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("pastel2") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(179,226,205), (253,205,172), (203,213,232)],
+            &[(179,226,205), (253,205,172), (203,213,232), (244,202,228)],
+            &[(179,226,205), (253,205,172), (203,213,232), (244,202,228), (230,245,201)],
+            &[(179,226,205), (253,205,172), (203,213,232), (244,202,228), (230,245,201), (255,242,174)],
+            &[(179,226,205), (253,205,172), (203,213,232), (244,202,228), (230,245,201), (255,242,174), (241,226,204)],
+            &[(179,226,205), (253,205,172), (203,213,232), (244,202,228), (230,245,201), (255,242,174), (241,226,204), (204,204,204)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("pastel1") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(251,180,174), (179,205,227), (204,235,197)],
+            &[(251,180,174), (179,205,227), (204,235,197), (222,203,228)],
+            &[(251,180,174), (179,205,227), (204,235,197), (222,203,228), (254,217,166)],
+            &[(251,180,174), (179,205,227), (204,235,197), (222,203,228), (254,217,166), (255,255,204)],
+            &[(251,180,174), (179,205,227), (204,235,197), (222,203,228), (254,217,166), (255,255,204), (229,216,189)],
+            &[(251,180,174), (179,205,227), (204,235,197), (222,203,228), (254,217,166), (255,255,204), (229,216,189), (253,218,236)],
+            &[(251,180,174), (179,205,227), (204,235,197), (222,203,228), (254,217,166), (255,255,204), (229,216,189), (253,218,236), (242,242,242)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    /*** Sequential ***/
+    if name.eq_ignore_ascii_case("orrd") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(254,232,200), (253,187,132), (227,74,51)],
+            &[(254,240,217), (253,204,138), (252,141,89), (215,48,31)],
+            &[(254,240,217), (253,204,138), (252,141,89), (227,74,51), (179,0,0)],
+            &[(254,240,217), (253,212,158), (253,187,132), (252,141,89), (227,74,51), (179,0,0)],
+            &[(254,240,217), (253,212,158), (253,187,132), (252,141,89), (239,101,72), (215,48,31), (153,0,0)],
+            &[(255,247,236), (254,232,200), (253,212,158), (253,187,132), (252,141,89), (239,101,72), (215,48,31), (153,0,0)],
+            &[(255,247,236), (254,232,200), (253,212,158), (253,187,132), (252,141,89), (239,101,72), (215,48,31), (179,0,0), (127,0,0)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("pubu") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(236,231,242), (166,189,219), (43,140,190)],
+            &[(241,238,246), (189,201,225), (116,169,207), (5,112,176)],
+            &[(241,238,246), (189,201,225), (116,169,207), (43,140,190), (4,90,141)],
+            &[(241,238,246), (208,209,230), (166,189,219), (116,169,207), (43,140,190), (4,90,141)],
+            &[(241,238,246), (208,209,230), (166,189,219), (116,169,207), (54,144,192), (5,112,176), (3,78,123)],
+            &[(255,247,251), (236,231,242), (208,209,230), (166,189,219), (116,169,207), (54,144,192), (5,112,176), (3,78,123)],
+            &[(255,247,251), (236,231,242), (208,209,230), (166,189,219), (116,169,207), (54,144,192), (5,112,176), (4,90,141), (2,56,88)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("bupu") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(224,236,244), (158,188,218), (136,86,167)],
+            &[(237,248,251), (179,205,227), (140,150,198), (136,65,157)],
+            &[(237,248,251), (179,205,227), (140,150,198), (136,86,167), (129,15,124)],
+            &[(237,248,251), (191,211,230), (158,188,218), (140,150,198), (136,86,167), (129,15,124)],
+            &[(237,248,251), (191,211,230), (158,188,218), (140,150,198), (140,107,177), (136,65,157), (110,1,107)],
+            &[(247,252,253), (224,236,244), (191,211,230), (158,188,218), (140,150,198), (140,107,177), (136,65,157), (110,1,107)],
+            &[(247,252,253), (224,236,244), (191,211,230), (158,188,218), (140,150,198), (140,107,177), (136,65,157), (129,15,124), (77,0,75)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("oranges") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(254,230,206), (253,174,107), (230,85,13)],
+            &[(254,237,222), (253,190,133), (253,141,60), (217,71,1)],
+            &[(254,237,222), (253,190,133), (253,141,60), (230,85,13), (166,54,3)],
+            &[(254,237,222), (253,208,162), (253,174,107), (253,141,60), (230,85,13), (166,54,3)],
+            &[(254,237,222), (253,208,162), (253,174,107), (253,141,60), (241,105,19), (217,72,1), (140,45,4)],
+            &[(255,245,235), (254,230,206), (253,208,162), (253,174,107), (253,141,60), (241,105,19), (217,72,1), (140,45,4)],
+            &[(255,245,235), (254,230,206), (253,208,162), (253,174,107), (253,141,60), (241,105,19), (217,72,1), (166,54,3), (127,39,4)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("bugn") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(229,245,249), (153,216,201), (44,162,95)],
+            &[(237,248,251), (178,226,226), (102,194,164), (35,139,69)],
+            &[(237,248,251), (178,226,226), (102,194,164), (44,162,95), (0,109,44)],
+            &[(237,248,251), (204,236,230), (153,216,201), (102,194,164), (44,162,95), (0,109,44)],
+            &[(237,248,251), (204,236,230), (153,216,201), (102,194,164), (65,174,118), (35,139,69), (0,88,36)],
+            &[(247,252,253), (229,245,249), (204,236,230), (153,216,201), (102,194,164), (65,174,118), (35,139,69), (0,88,36)],
+            &[(247,252,253), (229,245,249), (204,236,230), (153,216,201), (102,194,164), (65,174,118), (35,139,69), (0,109,44), (0,68,27)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("ylorbr") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(255,247,188), (254,196,79), (217,95,14)],
+            &[(255,255,212), (254,217,142), (254,153,41), (204,76,2)],
+            &[(255,255,212), (254,217,142), (254,153,41), (217,95,14), (153,52,4)],
+            &[(255,255,212), (254,227,145), (254,196,79), (254,153,41), (217,95,14), (153,52,4)],
+            &[(255,255,212), (254,227,145), (254,196,79), (254,153,41), (236,112,20), (204,76,2), (140,45,4)],
+            &[(255,255,229), (255,247,188), (254,227,145), (254,196,79), (254,153,41), (236,112,20), (204,76,2), (140,45,4)],
+            &[(255,255,229), (255,247,188), (254,227,145), (254,196,79), (254,153,41), (236,112,20), (204,76,2), (153,52,4), (102,37,6)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("ylgn") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(247,252,185), (173,221,142), (49,163,84)],
+            &[(255,255,204), (194,230,153), (120,198,121), (35,132,67)],
+            &[(255,255,204), (194,230,153), (120,198,121), (49,163,84), (0,104,55)],
+            &[(255,255,204), (217,240,163), (173,221,142), (120,198,121), (49,163,84), (0,104,55)],
+            &[(255,255,204), (217,240,163), (173,221,142), (120,198,121), (65,171,93), (35,132,67), (0,90,50)],
+            &[(255,255,229), (247,252,185), (217,240,163), (173,221,142), (120,198,121), (65,171,93), (35,132,67), (0,90,50)],
+            &[(255,255,229), (247,252,185), (217,240,163), (173,221,142), (120,198,121), (65,171,93), (35,132,67), (0,104,55), (0,69,41)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("reds") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(254,224,210), (252,146,114), (222,45,38)],
+            &[(254,229,217), (252,174,145), (251,106,74), (203,24,29)],
+            &[(254,229,217), (252,174,145), (251,106,74), (222,45,38), (165,15,21)],
+            &[(254,229,217), (252,187,161), (252,146,114), (251,106,74), (222,45,38), (165,15,21)],
+            &[(254,229,217), (252,187,161), (252,146,114), (251,106,74), (239,59,44), (203,24,29), (153,0,13)],
+            &[(255,245,240), (254,224,210), (252,187,161), (252,146,114), (251,106,74), (239,59,44), (203,24,29), (153,0,13)],
+            &[(255,245,240), (254,224,210), (252,187,161), (252,146,114), (251,106,74), (239,59,44), (203,24,29), (165,15,21), (103,0,13)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("rdpu") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(253,224,221), (250,159,181), (197,27,138)],
+            &[(254,235,226), (251,180,185), (247,104,161), (174,1,126)],
+            &[(254,235,226), (251,180,185), (247,104,161), (197,27,138), (122,1,119)],
+            &[(254,235,226), (252,197,192), (250,159,181), (247,104,161), (197,27,138), (122,1,119)],
+            &[(254,235,226), (252,197,192), (250,159,181), (247,104,161), (221,52,151), (174,1,126), (122,1,119)],
+            &[(255,247,243), (253,224,221), (252,197,192), (250,159,181), (247,104,161), (221,52,151), (174,1,126), (122,1,119)],
+            &[(255,247,243), (253,224,221), (252,197,192), (250,159,181), (247,104,161), (221,52,151), (174,1,126), (122,1,119), (73,0,106)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("greens") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(229,245,224), (161,217,155), (49,163,84)],
+            &[(237,248,233), (186,228,179), (116,196,118), (35,139,69)],
+            &[(237,248,233), (186,228,179), (116,196,118), (49,163,84), (0,109,44)],
+            &[(237,248,233), (199,233,192), (161,217,155), (116,196,118), (49,163,84), (0,109,44)],
+            &[(237,248,233), (199,233,192), (161,217,155), (116,196,118), (65,171,93), (35,139,69), (0,90,50)],
+            &[(247,252,245), (229,245,224), (199,233,192), (161,217,155), (116,196,118), (65,171,93), (35,139,69), (0,90,50)],
+            &[(247,252,245), (229,245,224), (199,233,192), (161,217,155), (116,196,118), (65,171,93), (35,139,69), (0,109,44), (0,68,27)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("ylgnbu") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(237,248,177), (127,205,187), (44,127,184)],
+            &[(255,255,204), (161,218,180), (65,182,196), (34,94,168)],
+            &[(255,255,204), (161,218,180), (65,182,196), (44,127,184), (37,52,148)],
+            &[(255,255,204), (199,233,180), (127,205,187), (65,182,196), (44,127,184), (37,52,148)],
+            &[(255,255,204), (199,233,180), (127,205,187), (65,182,196), (29,145,192), (34,94,168), (12,44,132)],
+            &[(255,255,217), (237,248,177), (199,233,180), (127,205,187), (65,182,196), (29,145,192), (34,94,168), (12,44,132)],
+            &[(255,255,217), (237,248,177), (199,233,180), (127,205,187), (65,182,196), (29,145,192), (34,94,168), (37,52,148), (8,29,88)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("purples") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(239,237,245), (188,189,220), (117,107,177)],
+            &[(242,240,247), (203,201,226), (158,154,200), (106,81,163)],
+            &[(242,240,247), (203,201,226), (158,154,200), (117,107,177), (84,39,143)],
+            &[(242,240,247), (218,218,235), (188,189,220), (158,154,200), (117,107,177), (84,39,143)],
+            &[(242,240,247), (218,218,235), (188,189,220), (158,154,200), (128,125,186), (106,81,163), (74,20,134)],
+            &[(252,251,253), (239,237,245), (218,218,235), (188,189,220), (158,154,200), (128,125,186), (106,81,163), (74,20,134)],
+            &[(252,251,253), (239,237,245), (218,218,235), (188,189,220), (158,154,200), (128,125,186), (106,81,163), (84,39,143), (63,0,125)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("gnbu") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(224,243,219), (168,221,181), (67,162,202)],
+            &[(240,249,232), (186,228,188), (123,204,196), (43,140,190)],
+            &[(240,249,232), (186,228,188), (123,204,196), (67,162,202), (8,104,172)],
+            &[(240,249,232), (204,235,197), (168,221,181), (123,204,196), (67,162,202), (8,104,172)],
+            &[(240,249,232), (204,235,197), (168,221,181), (123,204,196), (78,179,211), (43,140,190), (8,88,158)],
+            &[(247,252,240), (224,243,219), (204,235,197), (168,221,181), (123,204,196), (78,179,211), (43,140,190), (8,88,158)],
+            &[(247,252,240), (224,243,219), (204,235,197), (168,221,181), (123,204,196), (78,179,211), (43,140,190), (8,104,172), (8,64,129)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("greys") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(240,240,240), (189,189,189), (99,99,99)],
+            &[(247,247,247), (204,204,204), (150,150,150), (82,82,82)],
+            &[(247,247,247), (204,204,204), (150,150,150), (99,99,99), (37,37,37)],
+            &[(247,247,247), (217,217,217), (189,189,189), (150,150,150), (99,99,99), (37,37,37)],
+            &[(247,247,247), (217,217,217), (189,189,189), (150,150,150), (115,115,115), (82,82,82), (37,37,37)],
+            &[(255,255,255), (240,240,240), (217,217,217), (189,189,189), (150,150,150), (115,115,115), (82,82,82), (37,37,37)],
+            &[(255,255,255), (240,240,240), (217,217,217), (189,189,189), (150,150,150), (115,115,115), (82,82,82), (37,37,37), (0,0,0)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("ylorrd") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(255,237,160), (254,178,76), (240,59,32)],
+            &[(255,255,178), (254,204,92), (253,141,60), (227,26,28)],
+            &[(255,255,178), (254,204,92), (253,141,60), (240,59,32), (189,0,38)],
+            &[(255,255,178), (254,217,118), (254,178,76), (253,141,60), (240,59,32), (189,0,38)],
+            &[(255,255,178), (254,217,118), (254,178,76), (253,141,60), (252,78,42), (227,26,28), (177,0,38)],
+            &[(255,255,204), (255,237,160), (254,217,118), (254,178,76), (253,141,60), (252,78,42), (227,26,28), (177,0,38)],
+            &[(255,255,204), (255,237,160), (254,217,118), (254,178,76), (253,141,60), (252,78,42), (227,26,28), (189,0,38), (128,0,38)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("purd") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(231,225,239), (201,148,199), (221,28,119)],
+            &[(241,238,246), (215,181,216), (223,101,176), (206,18,86)],
+            &[(241,238,246), (215,181,216), (223,101,176), (221,28,119), (152,0,67)],
+            &[(241,238,246), (212,185,218), (201,148,199), (223,101,176), (221,28,119), (152,0,67)],
+            &[(241,238,246), (212,185,218), (201,148,199), (223,101,176), (231,41,138), (206,18,86), (145,0,63)],
+            &[(247,244,249), (231,225,239), (212,185,218), (201,148,199), (223,101,176), (231,41,138), (206,18,86), (145,0,63)],
+            &[(247,244,249), (231,225,239), (212,185,218), (201,148,199), (223,101,176), (231,41,138), (206,18,86), (152,0,67), (103,0,31)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("blues") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(222,235,247), (158,202,225), (49,130,189)],
+            &[(239,243,255), (189,215,231), (107,174,214), (33,113,181)],
+            &[(239,243,255), (189,215,231), (107,174,214), (49,130,189), (8,81,156)],
+            &[(239,243,255), (198,219,239), (158,202,225), (107,174,214), (49,130,189), (8,81,156)],
+            &[(239,243,255), (198,219,239), (158,202,225), (107,174,214), (66,146,198), (33,113,181), (8,69,148)],
+            &[(247,251,255), (222,235,247), (198,219,239), (158,202,225), (107,174,214), (66,146,198), (33,113,181), (8,69,148)],
+            &[(247,251,255), (222,235,247), (198,219,239), (158,202,225), (107,174,214), (66,146,198), (33,113,181), (8,81,156), (8,48,107)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    if name.eq_ignore_ascii_case("pubugn") {
+        let variants: &[&[(u8, u8, u8)]] = &[
+            &[(236,226,240), (166,189,219), (28,144,153)],
+            &[(246,239,247), (189,201,225), (103,169,207), (2,129,138)],
+            &[(246,239,247), (189,201,225), (103,169,207), (28,144,153), (1,108,89)],
+            &[(246,239,247), (208,209,230), (166,189,219), (103,169,207), (28,144,153), (1,108,89)],
+            &[(246,239,247), (208,209,230), (166,189,219), (103,169,207), (54,144,192), (2,129,138), (1,100,80)],
+            &[(255,247,251), (236,226,240), (208,209,230), (166,189,219), (103,169,207), (54,144,192), (2,129,138), (1,100,80)],
+            &[(255,247,251), (236,226,240), (208,209,230), (166,189,219), (103,169,207), (54,144,192), (2,129,138), (1,108,89), (1,70,54)],
+        ];
+        return variants.iter().find(|p| p.len() == n).copied()
+            .or_else(|| variants.last().copied());
+    }
+    None
+}
+
+/// Parse colorbrewer palette argument "SCHEME:N" and return (scheme_name, n)
+fn parse_colorbrewer_arg(arg: &str) -> Option<(String, usize)> {
+    let parts: Vec<&str> = arg.split(':').collect();
+    if parts.len() == 2 {
+        if let Ok(n) = parts[1].parse::<usize>() {
+            return Some((parts[0].to_string(), n));
+        }
+    }
+    // Just scheme name without :N
+    if parts.len() == 1 {
+        return Some((parts[0].to_string(), 11));
+    }
+    None
+}
+
+/// ColorBrewer Set1 qualitative palette for cluster indicators
+/// 9 distinct colors that are easy to distinguish
+const CLUSTER_COLORS: [(u8, u8, u8); 9] = [
+    (228, 26, 28),   // red
+    (55, 126, 184),  // blue
+    (77, 175, 74),   // green
+    (152, 78, 163),  // purple
+    (255, 127, 0),   // orange
+    (255, 255, 51),  // yellow
+    (166, 86, 40),   // brown
+    (247, 129, 191), // pink
+    (153, 153, 153), // grey
+];
+
+/// Get color for a cluster ID: the Set1 colors first, then golden-angle hues so that
+/// clusters beyond the palette still get colors of their own
+fn get_cluster_color(cluster_id: usize) -> (u8, u8, u8) {
+    if let Some(&color) = CLUSTER_COLORS.get(cluster_id) {
+        return color;
+    }
+    let extra = cluster_id - CLUSTER_COLORS.len();
+    // Stepping the hue by the golden angle (137.5°) keeps successive hues far apart;
+    // alternating brightness separates hues that come close after many steps
+    let hue = 0.05 + extra as f64 * 0.381_966_011_250_105;
+    let value = if extra.is_multiple_of(2) { 0.85 } else { 0.6 };
+    hsv_to_rgb(hue, 0.75, value)
+}
+
+/// ColorBrewer Set2 qualitative palette for annotations (8 pastel colors)
+/// Distinct from CLUSTER_COLORS (Set1) to avoid confusion when both are displayed
+const ANNOTATION_COLORS: [(u8, u8, u8); 8] = [
+    (102, 194, 165), // teal
+    (252, 141, 98),  // coral
+    (141, 160, 203), // periwinkle
+    (231, 138, 195), // pink
+    (166, 216, 84),  // lime
+    (255, 217, 47),  // yellow
+    (229, 196, 148), // tan
+    (179, 179, 179), // grey
+];
+
+/// ColorBrewer Paired palette for annotations (12 colors for more categories)
+const ANNOTATION_COLORS_EXTENDED: [(u8, u8, u8); 12] = [
+    (166, 206, 227), // light blue
+    (31, 120, 180),  // blue
+    (178, 223, 138), // light green
+    (51, 160, 44),   // green
+    (251, 154, 153), // light red
+    (227, 26, 28),   // red
+    (253, 191, 111), // light orange
+    (255, 127, 0),   // orange
+    (202, 178, 214), // light purple
+    (106, 61, 154),  // purple
+    (255, 255, 153), // light yellow
+    (177, 89, 40),   // brown
+];
+
+/// Get color for an annotation category
+fn get_annotation_color(category_index: usize, total_categories: usize) -> (u8, u8, u8) {
+    if total_categories <= 8 {
+        ANNOTATION_COLORS[category_index % ANNOTATION_COLORS.len()]
+    } else {
+        ANNOTATION_COLORS_EXTENDED[category_index % ANNOTATION_COLORS_EXTENDED.len()]
+    }
+}
+
+/// Compute SHA256-based path color (matching odgi algorithm exactly)
+fn compute_path_color(path_name: &str, color_by_prefix: Option<char>) -> (u8, u8, u8) {
+    let hash_input = if let Some(sep) = color_by_prefix {
+        path_name.split(sep).next().unwrap_or(path_name)
+    } else {
+        path_name
+    };
+
+    let mut hasher = Sha256::new();
+    hasher.update(hash_input.as_bytes());
+    let result = hasher.finalize();
+
+    let path_r = result[24];
+    let path_g = result[8];
+    let path_b = result[16];
+
+    // Normalize to 0-1 range (divide by 255)
+    let mut path_r_f = path_r as f32 / 255.0;
+    let mut path_g_f = path_g as f32 / 255.0;
+    let mut path_b_f = path_b as f32 / 255.0;
+
+    // Normalize by sum
+    let sum = path_r_f + path_g_f + path_b_f;
+    if sum > 0.0 {
+        path_r_f /= sum;
+        path_g_f /= sum;
+        path_b_f /= sum;
+    }
+
+    // Brighten the color (matching odgi's exact formula)
+    let max_component = path_r_f.max(path_g_f).max(path_b_f);
+    let f = if max_component > 0.0 {
+        1.5f32.min(1.0 / max_component)
+    } else {
+        1.0
+    };
+
+    let r_out = (255.0 * (path_r_f * f).min(1.0)).round() as u8;
+    let g_out = (255.0 * (path_g_f * f).min(1.0)).round() as u8;
+    let b_out = (255.0 * (path_b_f * f).min(1.0)).round() as u8;
+
+    (r_out, g_out, b_out)
+}
+
+/// Pick black or white text, whichever contrasts more with the given background.
+/// Uses WCAG relative luminance so dark path colors get white labels.
+fn contrast_text_color(background: (u8, u8, u8)) -> (u8, u8, u8) {
+    let linearize = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let luminance = 0.2126 * linearize(background.0)
+        + 0.7152 * linearize(background.1)
+        + 0.0722 * linearize(background.2);
+
+    // Contrast ratio against black is (L + 0.05) / 0.05, against white 1.05 / (L + 0.05)
+    if (luminance + 0.05) / 0.05 >= 1.05 / (luminance + 0.05) {
+        (0, 0, 0)
+    } else {
+        (255, 255, 255)
+    }
+}
+
+/// Background of the `--dark` theme
+const DARK_BACKGROUND: (u8, u8, u8) = (0, 0, 0);
+
+/// Colors for everything that is not path data: background, text, axis and edges
+#[derive(Clone, Copy)]
+struct Theme {
+    background: (u8, u8, u8),
+    foreground: (u8, u8, u8),
+    /// Mix colors in linear light (`--linear-blend`) rather than on sRGB values
+    linear: bool,
+}
+
+impl Theme {
+    fn from_args(args: &Args) -> Self {
+        let background = args
+            .background
+            .as_deref()
+            .and_then(parse_color)
+            .unwrap_or(if args.dark {
+                DARK_BACKGROUND
+            } else {
+                (255, 255, 255)
+            });
+        Theme {
+            background,
+            foreground: contrast_text_color(background),
+            linear: args.linear_blend,
+        }
+    }
+
+    fn is_dark(&self) -> bool {
+        self.foreground == (255, 255, 255)
+    }
+
+    /// Neutral grey for unlisted or dimmed paths, mirrored on dark backgrounds so it
+    /// stays as far from the background as on white
+    fn muted(&self, level: u8) -> (u8, u8, u8) {
+        if self.is_dark() {
+            (255 - level, 255 - level, 255 - level)
+        } else {
+            (level, level, level)
+        }
+    }
+
+    /// Faded grey version of a color, used for rows outside `--highlight-paths`
+    fn dim(&self, color: (u8, u8, u8)) -> (u8, u8, u8) {
+        let luma = 0.299 * color.0 as f64 + 0.587 * color.1 as f64 + 0.114 * color.2 as f64;
+        (
+            self.mix(luma, self.background.0, 0.25),
+            self.mix(luma, self.background.1, 0.25),
+            self.mix(luma, self.background.2, 0.25),
+        )
+    }
+
+    /// Mix a color with the background; `opacity` 1.0 keeps the color, 0.0 gives the background
+    fn blend(&self, color: (u8, u8, u8), opacity: f64) -> (u8, u8, u8) {
+        (
+            self.mix(color.0 as f64, self.background.0, opacity),
+            self.mix(color.1 as f64, self.background.1, opacity),
+            self.mix(color.2 as f64, self.background.2, opacity),
+        )
+    }
+
+    /// One channel `t` of the way from `bg` to `c`
+    fn mix(&self, c: f64, bg: u8, t: f64) -> u8 {
+        if self.linear {
+            let bg = srgb_to_linear(bg as f64);
+            linear_to_srgb(bg + (srgb_to_linear(c) - bg) * t)
+        } else {
+            (bg as f64 + (c - bg as f64) * t).round() as u8
+        }
+    }
+
+    /// RGBA buffer of the given size filled with the background color
+    fn canvas(&self, width: u32, height: u32) -> Vec<u8> {
+        let (r, g, b) = self.background;
+        [r, g, b, 255].repeat((width * height) as usize)
+    }
+}
+
+/// Path color at a `-d` gradient position: a grey from white to black with `-b`, otherwise
+/// the color darkened by up to 80%
+fn darkness_shade(
+    (r, g, b): (u8, u8, u8),
+    darkness: f64,
+    white_to_black: bool,
+    linear: bool,
+) -> (u8, u8, u8) {
+    if white_to_black {
+        let gray = if linear {
+            linear_to_srgb(1.0 - darkness)
+        } else {
+            (255.0 * (1.0 - darkness)).round() as u8
+        };
+        return (gray, gray, gray);
+    }
+    let factor = 1.0 - (darkness * 0.8);
+    let darken = |c: u8| {
+        if linear {
+            linear_to_srgb(srgb_to_linear(c as f64) * factor)
+        } else {
+            (c as f64 * factor).round() as u8
+        }
+    };
+    (darken(r), darken(g), darken(b))
+}
+
+/// Format a color as an SVG `rgb()` value
+fn svg_rgb(color: (u8, u8, u8)) -> String {
+    format!("rgb({},{},{})", color.0, color.1, color.2)
+}
+
+/// Stdin, read once and shared by every list option given as `-`
+static STDIN_LIST: OnceLock<String> = OnceLock::new();
+
+/// Lines of a list option: `-` reads stdin, an existing FILE is read, and any other value
+/// is an inline list with one entry per comma-separated item
+fn read_list(path: &Path) -> std::io::Result<Vec<String>> {
+    let value = path.to_string_lossy();
+    let text = if value == "-" {
+        STDIN_LIST
+            .get_or_init(|| std::io::read_to_string(std::io::stdin()).unwrap_or_default())
+            .clone()
+    } else if !path.exists() {
+        value.split(',').collect::<Vec<_>>().join("\n")
+    } else {
+        std::fs::read_to_string(path)?
+    };
+    Ok(text.lines().map(str::to_string).collect())
+}
+
+/// Name and color of a path colors line: tab-separated in files, NAME=COLOR inline
+fn path_color_entry(line: &str) -> Option<(&str, &str)> {
+    if line.contains('\t') {
+        let mut fields = line.split('\t');
+        Some((fields.next()?, fields.next()?))
+    } else {
+        line.rsplit_once('=')
+    }
+}
+
+fn load_path_colors(path: &Path) -> std::io::Result<FxHashMap<String, (u8, u8, u8)>> {
+    let mut colors = FxHashMap::default();
+
+    for line in read_list(path)? {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some((path_name, color_str)) = path_color_entry(line) {
+            let path_name = path_name.to_string();
+
+            let rgb = parse_color(color_str).unwrap_or_else(|| {
+                warning!(
+                    "invalid color '{}' for path '{}', using grey",
+                    color_str,
+                    path_name
+                );
+                (128, 128, 128)
+            });
+
+            colors.insert(path_name, rgb);
+        }
+    }
+
+    Ok(colors)
+}
+
+/// Load a --color-by-contig FILE: a path name and its chromosome per tab-separated line
+fn load_contig_map(path: &PathBuf) -> std::io::Result<FxHashMap<String, String>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut contigs = FxHashMap::default();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((path_name, contig)) = line.split_once('\t') {
+            contigs.insert(path_name.to_string(), contig.trim().to_string());
+        }
+    }
+    Ok(contigs)
+}
+
+/// The contig field of a PanSN name (`sample#haplotype#contig`), without a range suffix
+fn pansn_contig(path_name: &str) -> &str {
+    let base = strip_subpath_range(path_name);
+    base.rsplit('#').next().unwrap_or(base)
+}
+
+/// --color-by-contig: the chromosome color of each displayed path that has one, from the
+/// mapping file when given and the PanSN contig otherwise
+fn contig_colors(
+    display_paths: &[&GfaPath],
+    mapping: Option<&FxHashMap<String, String>>,
+) -> FxHashMap<String, (u8, u8, u8)> {
+    let mut colors = FxHashMap::default();
+    let mut unassigned = 0;
+    for path in display_paths {
+        let contig = mapping
+            .and_then(|m| {
+                m.get(&path.name)
+                    .or_else(|| m.get(strip_subpath_range(&path.name)))
+            })
+            .map_or_else(|| pansn_contig(&path.name), String::as_str);
+        match chromosome_color(contig) {
+            Some(color) => {
+                colors.insert(path.name.clone(), color);
+            }
+            None => unassigned += 1,
+        }
+    }
+    if unassigned > 0 {
+        warning!(
+            "--color-by-contig: {} of {} paths have no chromosome assignment and are drawn grey",
+            unassigned,
+            display_paths.len()
+        );
+    }
+    colors
+}
+
+/// Levenshtein distance between two names, for "did you mean" suggestions
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+/// Graph path closest to `name`, if it is near enough to be a plausible typo
+fn closest_path_name<'a>(name: &str, graph: &'a Graph) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 4).max(2);
+    graph
+        .paths
+        .par_iter()
+        .map(|p| (edit_distance(name, &p.name), p.name.as_str()))
+        .filter(|&(d, _)| d <= max_distance)
+        .min()
+        .map(|(_, n)| n)
+}
+
+/// Widest image gfalook renders; wider requests are usage errors
+const MAX_IMAGE_WIDTH: u32 = 1 << 16;
+
+/// Tallest stack of path rows gfalook renders; taller requests are usage errors
+const MAX_IMAGE_HEIGHT: u32 = 1 << 18;
+
+/// Check that `rows` path rows of `row_height` px, plus --path-spacing between them, fit
+/// within MAX_IMAGE_HEIGHT
+fn check_rows_height(args: &Args, rows: u32, row_height: u32) -> Result<(), GfalookError> {
+    let height = rows as u64 * (row_height as u64 + args.path_spacing as u64);
+    if height > MAX_IMAGE_HEIGHT as u64 {
+        return Err(GfalookError::Usage(format!(
+            "{} path rows need a {} px tall image, over the {} px limit; lower --path-height \
+             or draw fewer rows, e.g. with -p or a larger --split-discontiguous",
+            rows, height, MAX_IMAGE_HEIGHT
+        )));
+    }
+    Ok(())
+}
+
+/// Image width --show-all-nodes needs for the smallest segment to get --node-width pixels
+fn show_all_nodes_width(args: &Args, graph: &Graph) -> u64 {
+    let min_seg_len = graph
+        .segments
+        .iter()
+        .map(|s| s.sequence_len)
+        .filter(|&len| len > 0)
+        .min()
+        .unwrap_or(1);
+    // bin_width = total_length / viz_width, we want bin_width <= min_seg_len / node_width
+    let min_width = graph.total_length * args.node_width as u64 / min_seg_len;
+    debug!(
+        "show_all_nodes: min_seg={}bp, need {}px width for {}px/node",
+        min_seg_len, min_width, args.node_width
+    );
+    min_width.max(args.width as u64)
+}
+
+/// The --show-all-nodes image width, or an error when it exceeds MAX_IMAGE_WIDTH
+fn show_all_nodes_viz_width(args: &Args, graph: &Graph) -> Result<u32, GfalookError> {
+    let width = show_all_nodes_width(args, graph);
+    if width > MAX_IMAGE_WIDTH as u64 {
+        return Err(GfalookError::Usage(format!(
+            "--show-all-nodes needs a {} px wide image, over the {} px limit; add --tile to \
+             write it as {} tiles, or lower --node-width",
+            width,
+            MAX_IMAGE_WIDTH,
+            width.div_ceil(MAX_IMAGE_WIDTH as u64)
+        )));
+    }
+    Ok(width as u32)
+}
+
+/// The graph restricted to the segments in `ids`, renumbered from 0; every path keeps
+/// its row (possibly with no steps) so that rows line up across tiles
+fn subgraph(graph: &Graph, ids: std::ops::Range<usize>) -> Graph {
+    let first = ids.start as u64;
+    let in_range = |id: u64| ids.contains(&(id as usize));
+    let mut sub = Graph::new();
+    sub.segments = graph.segments[ids.clone()].to_vec();
+    let base = graph.segment_offsets[ids.start];
+    sub.segment_offsets = graph.segment_offsets[ids.clone()]
+        .iter()
+        .map(|offset| offset - base)
+        .collect();
+    sub.total_length = sub.segments.iter().map(|s| s.sequence_len).sum();
+    sub.segment_name_to_id = graph
+        .segment_name_to_id
+        .iter()
+        .filter(|&(_, &id)| in_range(id))
+        .map(|(name, &id)| (name.clone(), id - first))
+        .collect();
+    sub.paths = graph
+        .paths
+        .iter()
+        .map(|path| GfaPath {
+            name: path.name.clone(),
+            steps: path
+                .steps
+                .iter()
+                .filter(|step| in_range(step.segment_id))
+                .map(|step| PathStep {
+                    segment_id: step.segment_id - first,
+                    is_reverse: step.is_reverse,
+                })
+                .collect(),
+            piece: path.piece,
+        })
+        .collect();
+    sub.origin = RangeOrigin {
+        first_segment: graph.origin.first_segment + first,
+        ..RangeOrigin::default()
+    };
+    sub.header = graph.header.clone();
+    sub.file_segments = graph.file_segments.clone();
+    sub.edges = graph
+        .edges
+        .iter()
+        .filter(|edge| in_range(edge.from_id) && in_range(edge.to_id))
+        .map(|edge| Edge {
+            from_id: edge.from_id - first,
+            to_id: edge.to_id - first,
+            ..edge.clone()
+        })
+        .collect();
+    sub
+}
+
+/// A parsed --path-range: the path it is measured on (pangenomic without one) and the
+/// 0-based, end-exclusive bounds, None for an open `*` end
+struct PathRange<'a> {
+    path: Option<&'a str>,
+    start: Option<u64>,
+    end: Option<u64>,
+}
+
+fn parse_path_range(spec: &str) -> Result<PathRange<'_>, GfalookError> {
+    let invalid = |why: &str| {
+        GfalookError::Usage(format!(
+            "invalid --path-range '{}': {}; expected [PATH:]start-end, e.g. chr1:1000-2000 or 5000-*",
+            spec, why
+        ))
+    };
+    // Path names may hold ':' themselves (name:start-end subpaths), so split at the last one
+    let (path, range) = match spec.rsplit_once(':') {
+        Some((path, range)) if !path.is_empty() => (Some(path), range),
+        Some(_) => return Err(invalid("the path name is empty")),
+        None => (None, spec),
+    };
+    let (start, end) = range
+        .split_once('-')
+        .ok_or_else(|| invalid("no '-' between start and end"))?;
+    let bound = |value: &str| -> Result<Option<u64>, GfalookError> {
+        match value.trim() {
+            "*" => Ok(None),
+            value => value
+                .parse()
+                .map(Some)
+                .map_err(|_| invalid(&format!("'{}' is not a position", value))),
+        }
+    };
+    let range = PathRange {
+        path,
+        start: bound(start)?,
+        end: bound(end)?,
+    };
+    if let (Some(start), Some(end)) = (range.start, range.end) {
+        if start >= end {
+            return Err(invalid("start must be below end"));
+        }
+    }
+    Ok(range)
+}
+
+/// Pangenomic window `start..end` covering positions `start..end` of `path`: from the
+/// leftmost to the rightmost graph position the path steps through there
+fn path_range_window(graph: &Graph, path: &GfaPath, start: u64, end: u64) -> Option<(u64, u64)> {
+    let mut window: Option<(u64, u64)> = None;
+    let mut pos = 0;
+    for step in &path.steps {
+        let Some(segment) = graph.segments.get(step.segment_id as usize) else {
+            continue;
+        };
+        let len = segment.sequence_len;
+        let (lo, hi) = (start.max(pos), end.min(pos + len));
+        if lo < hi {
+            // Bounds within the segment, in the segment's own orientation
+            let (lo, hi) = if step.is_reverse {
+                (pos + len - hi, pos + len - lo)
+            } else {
+                (lo - pos, hi - pos)
+            };
+            let offset = graph.segment_offsets[step.segment_id as usize];
+            let (w_start, w_end) = window.get_or_insert((offset + lo, offset + hi));
+            *w_start = (*w_start).min(offset + lo);
+            *w_end = (*w_end).max(offset + hi);
+        }
+        pos += len;
+        if pos >= end {
+            break;
+        }
+    }
+    window
+}
+
+/// Position within `path` of its first bp inside the pangenomic window `start..end`
+fn path_window_start(graph: &Graph, path: &GfaPath, start: u64, end: u64) -> u64 {
+    let mut pos = 0;
+    for step in &path.steps {
+        let Some(segment) = graph.segments.get(step.segment_id as usize) else {
+            continue;
+        };
+        let offset = graph.segment_offsets[step.segment_id as usize];
+        let seg_end = offset + segment.sequence_len;
+        if offset < end && seg_end > start {
+            let clipped = if step.is_reverse {
+                seg_end.saturating_sub(end)
+            } else {
+                start.saturating_sub(offset)
+            };
+            return pos + clipped;
+        }
+        pos += segment.sequence_len;
+    }
+    0
+}
+
+/// --path-range: the graph cut down to the pangenomic window the range covers. Segments
+/// straddling the window edges are shortened to the part inside it, and edges leaving the
+/// window are dropped
+fn path_range_graph(graph: &Graph, spec: &str) -> Result<Graph, GfalookError> {
+    let range = parse_path_range(spec)?;
+    let (start, end) = match range.path {
+        None => {
+            let start = range.start.unwrap_or(0);
+            let end = range
+                .end
+                .unwrap_or(graph.total_length)
+                .min(graph.total_length);
+            if start >= end {
+                return Err(GfalookError::Usage(format!(
+                    "--path-range {} starts past the end of the graph ({} bp)",
+                    spec, graph.total_length
+                )));
+            }
+            (start, end)
+        }
+        Some(name) => {
+            let resolver = PathNameResolver::new(graph.paths.iter().map(|p| p.name.as_str()));
+            let name = match resolver.resolve(name) {
+                Resolution::Exact => name,
+                Resolution::Resolved(path) => {
+                    info!("--path-range: resolved path '{}' to '{}'", name, path);
+                    path
+                }
+                Resolution::Ambiguous(candidates) => {
+                    return Err(GfalookError::Usage(format!(
+                        "--path-range: path '{}' matches {} graph paths ({})",
+                        name,
+                        candidates.len(),
+                        candidates.join(", ")
+                    )));
+                }
+                Resolution::Missing => {
+                    return Err(GfalookError::Usage(match closest_path_name(name, graph) {
+                        Some(suggestion) => format!(
+                            "--path-range: path '{}' not found in the graph (did you mean '{}'?)",
+                            name, suggestion
+                        ),
+                        None => format!("--path-range: path '{}' not found in the graph", name),
+                    }));
+                }
+            };
+            let path = graph
+                .paths
+                .iter()
+                .find(|p| p.name == name)
+                .expect("resolved path");
+            let path_len: u64 = path
+                .steps
+                .iter()
+                .filter_map(|step| graph.segments.get(step.segment_id as usize))
+                .map(|segment| segment.sequence_len)
+                .sum();
+            let path_start = range.start.unwrap_or(0);
+            let path_end = range.end.unwrap_or(path_len).min(path_len);
+            if path_start >= path_end {
+                return Err(GfalookError::Usage(format!(
+                    "--path-range {} starts past the end of '{}' ({} bp)",
+                    spec, name, path_len
+                )));
+            }
+            let (start, end) = path_range_window(graph, path, path_start, path_end)
+                .expect("a non-empty path range covers some segment");
+            info!(
+                "--path-range: {}:{}-{} spans pangenomic positions {}-{}",
+                name, path_start, path_end, start, end
+            );
+            (start, end)
+        }
+    };
+
+    let first = graph
+        .segment_offsets
+        .partition_point(|&offset| offset < start)
+        .saturating_sub(1);
+    let last = graph
+        .segment_offsets
+        .partition_point(|&offset| offset < end);
+    let first = (first..last)
+        .find(|&id| graph.segment_offsets[id] + graph.segments[id].sequence_len > start)
+        .unwrap_or(first);
+    let mut window = subgraph(graph, first..last);
+    let head = start - graph.segment_offsets[first];
+    let tail = (graph.segment_offsets[last - 1] + graph.segments[last - 1].sequence_len)
+        .saturating_sub(end);
+    let n = window.segments.len();
+    window.segments[0].sequence_len -= head;
+    window.segments[n - 1].sequence_len -= tail;
+    let mut offset = 0;
+    for (segment, segment_offset) in window.segments.iter_mut().zip(&mut window.segment_offsets) {
+        segment.n_count = segment.n_count.min(segment.sequence_len);
+        *segment_offset = offset;
+        offset += segment.sequence_len;
+    }
+    window.total_length = offset;
+    window.origin = RangeOrigin {
+        first_segment: graph.origin.first_segment + first as u64,
+        pangenomic: graph.origin.pangenomic + start,
+        paths: graph
+            .paths
+            .iter()
+            .map(|path| {
+                (
+                    path.name.clone(),
+                    path_window_start(graph, path, start, end),
+                )
+            })
+            .collect(),
+    };
+    let visiting = window.paths.iter().filter(|p| !p.steps.is_empty()).count();
+    info!(
+        "--path-range: drawing {} bp over {} segments, visited by {} of {} paths",
+        window.total_length,
+        n,
+        visiting,
+        window.paths.len()
+    );
+    Ok(window)
+}
+
+/// Write the side files that describe the whole graph (--export-matrix, --export-pav,
+/// --write-colored-gfa, --export-dot, --node-frequency-out, --write-distances) once, before
+/// --tile or --per-component draws it piece by piece. A --show-all-nodes graph too wide for
+/// one image is binned at --width instead
+fn write_whole_graph_products(args: &Args, graph: &Graph) -> Result<(), GfalookError> {
+    let requested = args.export_matrix.is_some()
+        || args.export_pav.is_some()
+        || args.write_colored_gfa.is_some()
+        || args.export_dot.is_some()
+        || args.node_frequency_out.is_some()
+        || args.write_distances.is_some();
+    if !requested {
+        return Ok(());
+    }
+    info!("Writing the graph-wide data products from the whole graph...");
+    let products = Args {
+        no_image: true,
+        tile: false,
+        per_component: false,
+        show_all_nodes: args.show_all_nodes
+            && show_all_nodes_width(args, graph) <= MAX_IMAGE_WIDTH as u64,
+        edges_out: None,
+        names_out: None,
+        per_cluster_images: None,
+        ..args.clone()
+    };
+    render_to_file(&products, graph)
+}
+
+/// Options for one piece of a --tile or --per-component run: the image and the --edges-out
+/// and --names-out panels get `label` before their extension, and the graph-wide side files
+/// are left to [`write_whole_graph_products`]
+fn piece_args(args: &Args, label: &str) -> Args {
+    let numbered = |file: &Path| {
+        let ext = file
+            .extension()
+            .map_or_else(String::new, |ext| ext.to_string_lossy().into_owned());
+        file.with_extension(format!("{}.{}", label, ext))
+    };
+    Args {
+        out: numbered(&args.out),
+        edges_out: args.edges_out.as_deref().map(numbered),
+        names_out: args.names_out.as_deref().map(numbered),
+        export_matrix: None,
+        export_pav: None,
+        write_colored_gfa: None,
+        export_dot: None,
+        node_frequency_out: None,
+        write_distances: None,
+        ..args.clone()
+    }
+}
+
+/// Render a --show-all-nodes image too wide for one file as OUT.1.EXT, OUT.2.EXT, ...,
+/// cutting at segment boundaries so each tile fits within MAX_IMAGE_WIDTH
+fn render_tiles(args: &Args, graph: &Graph) -> Result<(), GfalookError> {
+    let min_seg_len = graph
+        .segments
+        .iter()
+        .map(|s| s.sequence_len)
+        .filter(|&len| len > 0)
+        .min()
+        .unwrap_or(1);
+    let max_tile_len = MAX_IMAGE_WIDTH as u64 * min_seg_len / args.node_width.max(1) as u64;
+    let mut ranges = Vec::new();
+    let (mut start, mut len) = (0, 0);
+    for (id, segment) in graph.segments.iter().enumerate() {
+        if len > 0 && len + segment.sequence_len > max_tile_len {
+            ranges.push(start..id);
+            (start, len) = (id, 0);
+        }
+        len += segment.sequence_len;
+    }
+    ranges.push(start..graph.segments.len());
+
+    if args.cluster_paths {
+        warning!("--tile: rows are clustered separately in each tile");
+    }
+    write_whole_graph_products(args, graph)?;
+    for (i, ids) in ranges.into_iter().enumerate() {
+        let tile_args = piece_args(args, &(i + 1).to_string());
+        info!(
+            "Tile {}: segments {}-{} to {:?}",
+            i + 1,
+            ids.start + 1,
+            ids.end,
+            tile_args.out
+        );
+        render_to_file(&tile_args, &subgraph(graph, ids))?;
+        interrupt::check("rendering tiles")?;
+    }
+    Ok(())
+}
+
+/// --per-cluster-images: draw the members of each cluster, in the main image's row order,
+/// as DIR/OUT.cluster-N.EXT over the same graph, so coordinates and path colors match.
+/// Clustering, path filters and the data products of the main run are turned off
+fn render_per_cluster_images(
+    args: &Args,
+    dir: &Path,
+    graph: &Graph,
+    paths: &[&GfaPath],
+    result: &ClusteringResult,
+) -> Result<(), GfalookError> {
+    std::fs::create_dir_all(dir).map_err(|e| {
+        GfalookError::from_io("failed to create per-cluster image directory", dir, e)
+    })?;
+    let stem = args
+        .out
+        .file_stem()
+        .map_or("gfalook".into(), |s| s.to_string_lossy());
+    let ext = args
+        .out
+        .extension()
+        .map_or("png".into(), |e| e.to_string_lossy());
+    let cluster_args = Args {
+        per_cluster_images: None,
+        cluster_paths: false,
+        cluster_representatives: false,
+        cluster_legend: false,
+        cluster_bar_labels: false,
+        mark_medoids: false,
+        dendrogram: false,
+        use_upgma: false,
+        shade_cluster_stability: false,
+        write_distances: None,
+        min_cluster_fraction: None,
+        cluster_bootstrap: 0,
+        cluster_within: None,
+        cluster_bed: None,
+        paths_to_display: None,
+        ignore_prefix: None,
+        write_dropped: false,
+        flip_paths: None,
+        orient_paths_by: None,
+        join_subranges: false,
+        split_discontiguous: None,
+        show_orphans: false,
+        export_matrix: None,
+        export_pav: None,
+        node_frequency_out: None,
+        write_colored_gfa: None,
+        edges_out: None,
+        names_out: None,
+        export_dot: None,
+        watch: false,
+        ..args.clone()
+    };
+    // The paths are already flipped, joined or split as in the main image
+    let mut cluster_graph = Graph {
+        segments: graph.segments.clone(),
+        segment_name_to_id: graph.segment_name_to_id.clone(),
+        segment_offsets: graph.segment_offsets.clone(),
+        total_length: graph.total_length,
+        paths: Vec::new(),
+        edges: graph.edges.clone(),
+        origin: graph.origin.clone(),
+        header: graph.header.clone(),
+        file_segments: graph.file_segments.clone(),
+    };
+    for cluster_id in 0..result.num_clusters {
+        cluster_graph.paths = result
+            .ordering
+            .iter()
+            .zip(&result.cluster_ids)
+            .filter(|&(_, &c)| c == cluster_id)
+            .map(|(&i, _)| paths[i].clone())
+            .collect();
+        if cluster_graph.paths.is_empty() {
+            continue;
+        }
+        let label = if result.noise_cluster == Some(cluster_id) {
+            "unclustered".to_string()
+        } else {
+            cluster_id.to_string()
+        };
+        let out = dir.join(format!("{}.cluster-{}.{}", stem, label, ext));
+        info!(
+            "Cluster {}: {} paths to {:?}",
+            label,
+            cluster_graph.paths.len(),
+            out
+        );
+        render_to_file(
+            &Args {
+                out,
+                ..cluster_args.clone()
+            },
+            &cluster_graph,
+        )?;
+        interrupt::check("rendering per-cluster images")?;
+    }
+    Ok(())
+}
+
+/// Segments that no path visits and no edge joins, usually left behind by graph trimming
+fn orphan_segments(graph: &Graph) -> Vec<usize> {
+    let mut used = vec![false; graph.segments.len()];
+    for step in graph.paths.iter().flat_map(|p| p.steps.iter()) {
+        used[step.segment_id as usize] = true;
+    }
+    for edge in &graph.edges {
+        used[edge.from_id as usize] = true;
+        used[edge.to_id as usize] = true;
+    }
+    (0..used.len()).filter(|&id| !used[id]).collect()
+}
+
+/// The graph with its orphan segments laid out after all others, and the offset where
+/// they start; None when there are none
+fn orphans_last(graph: &Graph) -> Option<(Graph, u64)> {
+    let orphans = orphan_segments(graph);
+    if orphans.is_empty() {
+        return None;
+    }
+    let mut is_orphan = vec![false; graph.segments.len()];
+    orphans.iter().for_each(|&id| is_orphan[id] = true);
+    let mut segment_offsets = vec![0; graph.segments.len()];
+    let mut offset = 0;
+    for pass in [false, true] {
+        for (id, segment) in graph.segments.iter().enumerate() {
+            if is_orphan[id] == pass {
+                segment_offsets[id] = offset;
+                offset += segment.sequence_len;
+            }
+        }
+    }
+    let orphan_start = segment_offsets[orphans[0]];
+    let mut reordered = Graph::new();
+    reordered.segments = graph.segments.clone();
+    reordered.segment_name_to_id = graph.segment_name_to_id.clone();
+    reordered.segment_offsets = segment_offsets;
+    reordered.total_length = graph.total_length;
+    reordered.paths = graph.paths.clone();
+    reordered.edges = graph.edges.clone();
+    reordered.origin = graph.origin.clone();
+    reordered.file_segments = graph.file_segments.clone();
+    Some((reordered, orphan_start))
+}
+
+/// Warn about orphan segments, which otherwise show up as unexplained blank columns
+fn report_orphans(args: &Args, graph: &Graph) {
+    let orphans = orphan_segments(graph);
+    if orphans.is_empty() {
+        return;
+    }
+    let bp: u64 = orphans
+        .iter()
+        .map(|&id| graph.segments[id].sequence_len)
+        .sum();
+    let mut names = vec![""; graph.segments.len()];
+    for (name, &id) in &graph.segment_name_to_id {
+        names[id as usize] = name;
+    }
+    let examples: Vec<&str> = orphans.iter().take(5).map(|&id| names[id]).collect();
+    warning!(
+        "{} segments ({} bp) are visited by no path and joined by no edge (e.g. {}){}",
+        orphans.len(),
+        bp,
+        examples.join(", "),
+        if args.show_orphans {
+            "; drawn hatched at the right end"
+        } else {
+            "; --show-orphans moves them to a hatched block at the right end"
+        }
+    );
+}
+
+/// Height of the --separate-components label strip
+const COMPONENT_TRACK_HEIGHT: u32 = 10;
+
+/// Weakly connected component of every segment, joining segments by edges and by
+/// consecutive path steps, numbered in order of their first segment
+fn segment_components(graph: &Graph) -> Vec<usize> {
+    let n = graph.segments.len();
+    let mut uf = UnionFind::new(n);
+    for edge in &graph.edges {
+        if (edge.from_id as usize) < n && (edge.to_id as usize) < n {
+            uf.union(edge.from_id as usize, edge.to_id as usize);
+        }
+    }
+    for path in &graph.paths {
+        for pair in path.steps.windows(2) {
+            uf.union(pair[0].segment_id as usize, pair[1].segment_id as usize);
+        }
+    }
+    let mut root_to_component: FxHashMap<usize, usize> = FxHashMap::default();
+    (0..n)
+        .map(|id| {
+            let next = root_to_component.len();
+            *root_to_component.entry(uf.find(id)).or_insert(next)
+        })
+        .collect()
+}
+
+/// Number of components that some path visits
+fn path_component_count(graph: &Graph, component: &[usize]) -> usize {
+    let visited: FxHashSet<usize> = graph
+        .paths
+        .iter()
+        .filter_map(|path| path.steps.first())
+        .map(|step| component[step.segment_id as usize])
+        .collect();
+    visited.len()
+}
+
+/// Point at the component options when the graph is several graphs concatenated
+fn report_components(args: &Args, graph: &Graph) {
+    if args.separate_components || args.per_component {
+        return;
+    }
+    let count = path_component_count(graph, &segment_components(graph));
+    if count > 1 {
+        warning!(
+            "the graph has {} weakly connected components, drawn end to end on one axis; \
+             --separate-components marks their boundaries, --per-component draws each on its own",
+            count
+        );
+    }
+}
+
+/// A weakly connected component laid out as one block of the pangenomic axis, labeled by
+/// the first path that visits it
+struct ComponentSpan {
+    start: u64,
+    end: u64,
+    label: String,
+}
+
+/// The graph with the segments of each component laid out together, in order of their
+/// first segment, and the span of every component; None when the graph is connected
+fn components_contiguous(graph: &Graph) -> Option<(Graph, Vec<ComponentSpan>)> {
+    let component = segment_components(graph);
+    let count = component.iter().max().map_or(0, |&c| c + 1);
+    if count < 2 {
+        return None;
+    }
+    let mut labels: Vec<Option<&str>> = vec![None; count];
+    for path in &graph.paths {
+        if let Some(step) = path.steps.first() {
+            labels[component[step.segment_id as usize]].get_or_insert(&path.name);
+        }
+    }
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for (id, &c) in component.iter().enumerate() {
+        members[c].push(id);
+    }
+    let mut segment_offsets = vec![0; graph.segments.len()];
+    let mut spans = Vec::with_capacity(count);
+    let mut offset = 0;
+    for (c, ids) in members.iter().enumerate() {
+        let start = offset;
+        for &id in ids {
+            segment_offsets[id] = offset;
+            offset += graph.segments[id].sequence_len;
+        }
+        spans.push(ComponentSpan {
+            start,
+            end: offset,
+            label: labels[c].map_or_else(|| format!("component {}", c + 1), str::to_string),
+        });
+    }
+    let grouped = Graph {
+        segments: graph.segments.clone(),
+        segment_name_to_id: graph.segment_name_to_id.clone(),
+        segment_offsets,
+        total_length: graph.total_length,
+        paths: graph.paths.clone(),
+        edges: graph.edges.clone(),
+        origin: graph.origin.clone(),
+        header: graph.header.clone(),
+        file_segments: graph.file_segments.clone(),
+    };
+    Some((grouped, spans))
+}
+
+/// Pixel columns `start..end` of each component span
+fn component_columns(spans: &[ComponentSpan], bin_width: f64, viz_width: u32) -> Vec<(u32, u32)> {
+    spans
+        .iter()
+        .map(|span| {
+            let start = ((span.start as f64 / bin_width) as u32).min(viz_width);
+            let end = ((span.end as f64 / bin_width) as u32).clamp(start, viz_width);
+            (start, end)
+        })
+        .collect()
+}
+
+/// Height of the --separate-components strip and the panel gap above it, or 0 without it
+fn component_track_panel(args: &Args) -> u32 {
+    if args.separate_components {
+        COMPONENT_TRACK_HEIGHT + args.panel_gap
+    } else {
+        0
+    }
+}
+
+/// The segments `ids` of `graph` on their own, renumbered in order, with the paths and
+/// edges among them
+fn component_graph(graph: &Graph, ids: &[usize]) -> Graph {
+    let mut new_id = vec![None; graph.segments.len()];
+    for (i, &id) in ids.iter().enumerate() {
+        new_id[id] = Some(i as u64);
+    }
+    let mut sub = Graph::new();
+    sub.segments = ids.iter().map(|&id| graph.segments[id].clone()).collect();
+    let mut offset = 0;
+    sub.segment_offsets = sub
+        .segments
+        .iter()
+        .map(|segment| {
+            let start = offset;
+            offset += segment.sequence_len;
+            start
+        })
+        .collect();
+    sub.total_length = offset;
+    sub.segment_name_to_id = graph
+        .segment_name_to_id
+        .iter()
+        .filter_map(|(name, &id)| Some((name.clone(), new_id[id as usize]?)))
+        .collect();
+    sub.paths = graph
+        .paths
+        .iter()
+        .filter(|path| {
+            path.steps
+                .first()
+                .is_some_and(|step| new_id[step.segment_id as usize].is_some())
+        })
+        .map(|path| GfaPath {
+            name: path.name.clone(),
+            steps: path
+                .steps
+                .iter()
+                .filter_map(|step| {
+                    Some(PathStep {
+                        segment_id: new_id[step.segment_id as usize]?,
+                        is_reverse: step.is_reverse,
+                    })
+                })
+                .collect(),
+            piece: path.piece,
+        })
+        .collect();
+    sub.header = graph.header.clone();
+    // Each S line maps to its new id, and lines outside the component past the last one
+    let file_lines = if graph.file_segments.is_empty() {
+        graph.origin.first_segment + graph.segments.len() as u64
+    } else {
+        graph.file_segments.len() as u64
+    };
+    sub.file_segments = (0..file_lines)
+        .map(|index| {
+            graph
+                .segment_at_file_index(index)
+                .and_then(|id| new_id[id as usize])
+                .unwrap_or(u64::MAX)
+        })
+        .collect();
+    sub.edges = graph
+        .edges
+        .iter()
+        .filter_map(|edge| {
+            Some(Edge {
+                from_id: *new_id.get(edge.from_id as usize)?.as_ref()?,
+                to_id: *new_id.get(edge.to_id as usize)?.as_ref()?,
+                ..edge.clone()
+            })
+        })
+        .collect();
+    sub
+}
+
+/// --per-component: render each component that a path visits as OUT.component-N.EXT
+fn render_components(args: &Args, graph: &Graph) -> Result<(), GfalookError> {
+    let component = segment_components(graph);
+    let count = component.iter().max().map_or(0, |&c| c + 1);
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for (id, &c) in component.iter().enumerate() {
+        members[c].push(id);
+    }
+    write_whole_graph_products(args, graph)?;
+    let mut drawn = 0;
+    for ids in &members {
+        let sub = component_graph(graph, ids);
+        if sub.paths.is_empty() {
+            continue;
+        }
+        drawn += 1;
+        let component_args = Args {
+            per_component: false,
+            ..piece_args(args, &format!("component-{}", drawn))
+        };
+        info!(
+            "Component {}: {} segments, {} paths to {:?}",
+            drawn,
+            sub.segments.len(),
+            sub.paths.len(),
+            component_args.out
+        );
+        render_to_file(&component_args, &sub)?;
+        interrupt::check("rendering components")?;
+    }
+    if drawn < count {
+        info!("Skipped {} components that no path visits", count - drawn);
+    }
+    Ok(())
+}
+
+/// Image width for --auto-width: the graph length at --bp-per-pixel, within MAX_IMAGE_WIDTH
+fn auto_width(args: &Args, graph: &Graph) -> Result<u32, GfalookError> {
+    if !(args.bp_per_pixel.is_finite() && args.bp_per_pixel > 0.0) {
+        return Err(GfalookError::Usage(format!(
+            "--bp-per-pixel must be a positive number, got {}",
+            args.bp_per_pixel
+        )));
+    }
+    let width = (graph.total_length as f64 / args.bp_per_pixel).ceil().max(1.0);
+    if width > MAX_IMAGE_WIDTH as f64 {
+        return Err(GfalookError::Usage(format!(
+            "--auto-width: {} bp at {} bp per pixel needs {} px, more than the {} px limit; \
+             use --bp-per-pixel {} or more",
+            graph.total_length,
+            args.bp_per_pixel,
+            width,
+            MAX_IMAGE_WIDTH,
+            graph.total_length.div_ceil(MAX_IMAGE_WIDTH as u64)
+        )));
+    }
+    Ok(width as u32)
+}
+
+/// --downsample: keep the segments starting in every `n`th bin of `bin_width` bp and zero
+/// the length of the others, so that only the sampled columns remain. Edges touching a
+/// dropped segment are left out.
+fn downsample(graph: &Graph, bin_width: f64, n: u32) -> Graph {
+    let kept: Vec<bool> = graph
+        .segment_offsets
+        .iter()
+        .map(|&offset| ((offset as f64 / bin_width) as u64).is_multiple_of(n as u64))
+        .collect();
+    let mut order: Vec<usize> = (0..graph.segments.len()).collect();
+    order.sort_by_key(|&id| graph.segment_offsets[id]);
+    let mut sampled = Graph::new();
+    sampled.segments = graph
+        .segments
+        .iter()
+        .zip(&kept)
+        .map(|(segment, &keep)| {
+            if keep {
+                segment.clone()
+            } else {
+                Segment {
+                    sequence_len: 0,
+                    n_count: 0,
+                    rank: segment.rank,
+                }
+            }
+        })
+        .collect();
+    sampled.segment_offsets = vec![0; graph.segments.len()];
+    let mut offset = 0;
+    for id in order {
+        sampled.segment_offsets[id] = offset;
+        offset += sampled.segments[id].sequence_len;
+    }
+    sampled.total_length = offset;
+    sampled.segment_name_to_id = graph.segment_name_to_id.clone();
+    sampled.paths = graph.paths.clone();
+    sampled.origin = graph.origin.clone();
+    sampled.header = graph.header.clone();
+    sampled.file_segments = graph.file_segments.clone();
+    sampled.edges = graph
+        .edges
+        .iter()
+        .filter(|e| kept[e.from_id as usize] && kept[e.to_id as usize])
+        .cloned()
+        .collect();
+    sampled
+}
+
+/// Segments of at least `min_len` bp whose set of visiting paths differs from that of both
+/// neighbors in the layout, i.e. the variant nodes that bins shared with their flanks hide
+fn distinct_features(graph: &Graph, min_len: u64) -> Vec<bool> {
+    let mut visitors: Vec<Vec<u32>> = vec![Vec::new(); graph.segments.len()];
+    for (path_idx, path) in graph.paths.iter().enumerate() {
+        for step in &path.steps {
+            if let Some(ids) = visitors.get_mut(step.segment_id as usize) {
+                if ids.last() != Some(&(path_idx as u32)) {
+                    ids.push(path_idx as u32);
+                }
+            }
+        }
+    }
+    let signature: Vec<u64> = visitors
+        .iter_mut()
+        .map(|ids| {
+            ids.sort_unstable();
+            ids.dedup();
+            let mut hasher = FxHasher::default();
+            ids.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..graph.segments.len()).collect();
+    order.sort_by_key(|&id| graph.segment_offsets[id]);
+    let mut distinct = vec![false; graph.segments.len()];
+    for (i, &id) in order.iter().enumerate() {
+        let differs = |neighbor: Option<&usize>| {
+            neighbor.is_none_or(|&other| signature[other] != signature[id])
+        };
+        distinct[id] = graph.segments[id].sequence_len >= min_len
+            && !visitors[id].is_empty()
+            && differs(i.checked_sub(1).and_then(|j| order.get(j)))
+            && differs(order.get(i + 1));
+    }
+    distinct
+}
+
+/// --guarantee-min-feature: lengthen the distinct features shorter than two bins to two
+/// bins, which always covers one whole pixel column, re-solving the bin width as the
+/// graph grows. None, with a warning, when the features need more columns than there are
+fn guarantee_min_feature(args: &Args, graph: &Graph, min_len: u64) -> Option<Graph> {
+    let distinct = distinct_features(graph, min_len);
+    let viz_width = args.width.min(graph.total_length as u32).max(1) as f64;
+    // A feature of 2 bins + 1 bp covers a whole column even after rounding; solve for the
+    // bin width that the graph widened this way gets, growing it until no new feature falls
+    // below two bins
+    let mut bin_width = args
+        .bin_width
+        .unwrap_or(graph.total_length as f64 / viz_width);
+    let feature_len = |len: u64, bin_width: f64| len.max((2.0 * bin_width).ceil() as u64 + 1);
+    let widened =
+        loop {
+            let (widened, rest) = graph.segments.iter().zip(&distinct).fold(
+                (0u64, 0u64),
+                |(widened, rest), (s, &d)| {
+                    if d && (s.sequence_len as f64) < 2.0 * bin_width + 1.0 {
+                        (widened + 1, rest)
+                    } else {
+                        (widened, rest + s.sequence_len)
+                    }
+                },
+            );
+            if args.bin_width.is_some() {
+                break widened;
+            }
+            if 2 * widened >= viz_width as u64 {
+                warning!(
+                    "--guarantee-min-feature: {} features of {}+ bp need more than the {} px \
+                 width; drawing the graph linearly. Raise --width or N",
+                    widened,
+                    min_len,
+                    viz_width
+                );
+                return None;
+            }
+            let next = (rest + 2 * widened) as f64 / (viz_width - 2.0 * widened as f64);
+            if next <= bin_width + 0.5 {
+                bin_width = bin_width.max(next);
+                break widened;
+            }
+            bin_width = next;
+        };
+    let mut widened_graph = Graph::new();
+    widened_graph.segments = graph
+        .segments
+        .iter()
+        .zip(&distinct)
+        .map(|(segment, &d)| {
+            let len = if d {
+                feature_len(segment.sequence_len, bin_width)
+            } else {
+                segment.sequence_len
+            };
+            Segment {
+                sequence_len: len,
+                // Keep the share of uncalled bases
+                n_count: (segment.n_count as f64 * len as f64 / segment.sequence_len.max(1) as f64)
+                    .round() as u64,
+                rank: segment.rank,
+            }
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..graph.segments.len()).collect();
+    order.sort_by_key(|&id| graph.segment_offsets[id]);
+    widened_graph.segment_offsets = vec![0; graph.segments.len()];
+    let mut offset = 0;
+    for id in order {
+        widened_graph.segment_offsets[id] = offset;
+        offset += widened_graph.segments[id].sequence_len;
+    }
+    widened_graph.total_length = offset;
+    widened_graph.segment_name_to_id = graph.segment_name_to_id.clone();
+    widened_graph.paths = graph.paths.clone();
+    widened_graph.edges = graph.edges.clone();
+    widened_graph.origin = graph.origin.clone();
+    widened_graph.header = graph.header.clone();
+    widened_graph.file_segments = graph.file_segments.clone();
+    info!(
+        "--guarantee-min-feature: widened {} of {} distinct features to {} bp, {} of {} bp in all",
+        widened,
+        distinct.iter().filter(|&&d| d).count(),
+        feature_len(0, bin_width),
+        widened_graph.total_length,
+        graph.total_length
+    );
+    Some(widened_graph)
+}
+
+/// Segments that every displayed path steps through the same number of times, in the
+/// same orientation
+fn invariant_segments(graph: &Graph, display_paths: &[&GfaPath]) -> Vec<bool> {
+    let n = graph.segments.len();
+    // Visits of the first path, and how many paths in a row have matched them so far
+    let mut first: Vec<Option<(u32, u32)>> = vec![None; n];
+    let mut matched = vec![0usize; n];
+    for (path_idx, path) in display_paths.iter().enumerate() {
+        let mut visits: FxHashMap<usize, (u32, u32)> = FxHashMap::default();
+        for step in &path.steps {
+            if (step.segment_id as usize) < n {
+                let entry = visits.entry(step.segment_id as usize).or_default();
+                entry.0 += 1;
+                entry.1 += step.is_reverse as u32;
+            }
+        }
+        for (id, counts) in visits {
+            if path_idx == 0 {
+                first[id] = Some(counts);
+            }
+            if matched[id] == path_idx && first[id] == Some(counts) {
+                matched[id] += 1;
+            }
+        }
+    }
+    matched.iter().map(|&m| m == display_paths.len()).collect()
+}
+
+/// --compress-invariant: the graph with every run of invariant segments at least `factor`
+/// bins long shrunk by `factor`, and the midpoint of each shrunk run. None when no run
+/// qualifies or fewer than two paths are displayed
+fn compress_invariant(
+    args: &Args,
+    graph: &Graph,
+    display_paths: &[&GfaPath],
+    factor: f64,
+) -> Option<(Graph, Vec<u64>)> {
+    if display_paths.len() < 2 {
+        info!("--compress-invariant: fewer than two paths displayed, nothing to compare");
+        return None;
+    }
+    let invariant = invariant_segments(graph, display_paths);
+    let viz_width = args.width.min(graph.total_length as u32).max(1);
+    let bin_width = args
+        .bin_width
+        .unwrap_or(graph.total_length as f64 / viz_width as f64);
+    let min_run = factor * bin_width;
+
+    let mut order: Vec<usize> = (0..graph.segments.len()).collect();
+    order.sort_by_key(|&id| graph.segment_offsets[id]);
+    let mut lengths: Vec<u64> = graph.segments.iter().map(|s| s.sequence_len).collect();
+    let mut breaks = Vec::new();
+    let mut offset = 0;
+    let mut i = 0;
+    while i < order.len() {
+        let mut end = i;
+        let mut run_len = 0;
+        while end < order.len() && invariant[order[end]] {
+            run_len += graph.segments[order[end]].sequence_len;
+            end += 1;
+        }
+        if end > i && run_len as f64 >= min_run {
+            // Round the running total so the run shrinks by exactly `factor` overall
+            let mut before = 0;
+            let mut cumulative = 0;
+            for &id in &order[i..end] {
+                cumulative += graph.segments[id].sequence_len;
+                let scaled = (cumulative as f64 / factor).round() as u64;
+                lengths[id] = scaled - before;
+                before = scaled;
+            }
+            breaks.push(offset + before / 2);
+            offset += before;
+            i = end;
+        } else {
+            offset += lengths[order[i]];
+            i += 1;
+        }
+    }
+    if breaks.is_empty() {
+        info!(
+            "--compress-invariant: no invariant run spans {} bins, drawing the graph linearly",
+            factor
+        );
+        return None;
+    }
+
+    let mut compressed = Graph::new();
+    compressed.segments = graph
+        .segments
+        .iter()
+        .zip(&lengths)
+        .map(|(segment, &len)| Segment {
+            sequence_len: len,
+            n_count: segment.n_count.min(len),
+            rank: segment.rank,
+        })
+        .collect();
+    compressed.segment_offsets = vec![0; graph.segments.len()];
+    let mut offset = 0;
+    for id in order {
+        compressed.segment_offsets[id] = offset;
+        offset += lengths[id];
+    }
+    compressed.total_length = offset;
+    compressed.segment_name_to_id = graph.segment_name_to_id.clone();
+    compressed.paths = graph.paths.clone();
+    compressed.edges = graph.edges.clone();
+    compressed.origin = graph.origin.clone();
+    compressed.header = graph.header.clone();
+    compressed.file_segments = graph.file_segments.clone();
+    info!(
+        "--compress-invariant: shrank {} invariant runs by {}x, {} of {} bp left",
+        breaks.len(),
+        factor,
+        compressed.total_length,
+        graph.total_length
+    );
+    Some((compressed, breaks))
+}
+
+/// Check that the image width stays within MAX_IMAGE_WIDTH and that --bin-width does not
+/// split the graph into more bins than there are pixels (which piles the excess into the
+/// last column), suggesting a bin width that fits
+fn check_bin_width(args: &Args, graph: &Graph) -> Result<(), GfalookError> {
+    if args.width > MAX_IMAGE_WIDTH {
+        return Err(GfalookError::Usage(format!(
+            "--width {} exceeds the {} px limit",
+            args.width, MAX_IMAGE_WIDTH
+        )));
+    }
+    let Some(bin_width) = args.bin_width else {
+        return Ok(());
+    };
+    if !(bin_width.is_finite() && bin_width > 0.0) {
+        return Err(GfalookError::Usage(format!(
+            "--bin-width must be a positive number of bp, got {}",
+            bin_width
+        )));
+    }
+    if args.show_all_nodes || graph.total_length == 0 {
+        return Ok(());
+    }
+    let viz_width = args.width.min(graph.total_length as u32).max(1);
+    let bins = (graph.total_length as f64 / bin_width).ceil();
+    if bins <= viz_width as f64 {
+        return Ok(());
+    }
+    let suggested = (graph.total_length as f64 / viz_width as f64).ceil() as u64;
+    let alternative = if bins <= MAX_IMAGE_WIDTH as f64 {
+        format!(", or --width {}", bins as u64)
+    } else {
+        format!(
+            " (a {} px wide image would exceed the {} px limit)",
+            bins as u64, MAX_IMAGE_WIDTH
+        )
+    };
+    Err(GfalookError::Usage(format!(
+        "--bin-width {} splits the {} bp graph into {} bins, more than the {} px image width; \
+         use --bin-width {} or more{}",
+        bin_width, graph.total_length, bins as u64, viz_width, suggested, alternative
+    )))
+}
+
+/// Report how each name given via --paths-to-display, --path-colors, --highlight-paths or
+/// --x-axis maps onto graph paths, warning about names that match none (or several);
+/// under --strict, fail instead
+fn check_requested_paths(args: &Args, graph: &Graph) -> Result<(), GfalookError> {
+    let mut requested: Vec<(&str, Vec<String>)> = Vec::new();
+    if let Some(ref file) = args.paths_to_display {
+        let names = load_paths_to_display(file)
+            .map_err(|e| GfalookError::from_io("failed to read paths to display", file, e))?;
+        requested.push(("--paths-to-display", names));
+    }
+    if let Some(ref file) = args.path_colors {
+        // Read only the name column so invalid colors are reported once, by the renderer
+        let lines = read_list(file)
+            .map_err(|e| GfalookError::from_io("failed to read path colors", file, e))?;
+        let names: Vec<String> = lines
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| path_color_entry(line).map(|(name, _)| name.to_string()))
+            .collect();
+        requested.push(("--path-colors", names));
+    }
+    if let Some(ref file) = args.highlight_paths {
+        let names = load_paths_to_display(file)
+            .map_err(|e| GfalookError::from_io("failed to read highlighted paths", file, e))?;
+        requested.push(("--highlight-paths", names));
+    }
+    if let Some(ref axis) = args.x_axis {
+        if !axis.eq_ignore_ascii_case("pangenomic") {
+            requested.push(("--x-axis", vec![axis.clone()]));
+        }
+    }
+    if let Some(ref reference) = args.orient_paths_by {
+        requested.push(("--orient-paths-by", vec![reference.clone()]));
+    }
+    if let Some(ref file) = args.flip_paths {
+        let names = load_paths_to_display(file)
+            .map_err(|e| GfalookError::from_io("failed to read paths to flip", file, e))?;
+        requested.push(("--flip-paths", names));
+    }
+
+    let resolver = PathNameResolver::new(graph.paths.iter().map(|p| p.name.as_str()));
+    let mut missing = 0;
+    for (flag, names) in &requested {
+        for name in names {
+            match resolver.resolve(name) {
+                Resolution::Exact => {}
+                Resolution::Resolved(path) => {
+                    info!("{}: resolved path '{}' to '{}'", flag, name, path);
+                }
+                Resolution::Ambiguous(candidates) => {
+                    missing += 1;
+                    warning!(
+                        "{}: path '{}' matches {} graph paths ({}), ignoring it",
+                        flag,
+                        name,
+                        candidates.len(),
+                        candidates.join(", ")
+                    );
+                }
+                Resolution::Missing => {
+                    missing += 1;
+                    match closest_path_name(name, graph) {
+                        Some(suggestion) => warning!(
+                            "{}: path '{}' not found in the graph (did you mean '{}'?)",
+                            flag,
+                            name,
+                            suggestion
+                        ),
+                        None => warning!("{}: path '{}' not found in the graph", flag, name),
+                    }
+                }
+            }
+        }
+    }
+
+    if missing > 0 && args.strict {
+        return Err(GfalookError::Usage(format!(
+            "{} requested path name(s) not found in the graph (--strict)",
+            missing
+        )));
+    }
+    Ok(())
+}
+
+fn load_paths_to_display(path: &Path) -> std::io::Result<Vec<String>> {
+    let mut paths = Vec::new();
+
+    for line in read_list(path)? {
+        let line = line.trim();
+        if !line.is_empty() {
+            paths.push(line.to_string());
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Load node IDs to highlight from a file (one ID per line)
+fn load_highlight_node_ids(path: &Path) -> std::io::Result<FxHashSet<u64>> {
+    let mut node_ids = FxHashSet::default();
+
+    for line in read_list(path)? {
+        let line = line.trim();
+        if !line.is_empty() {
+            if let Ok(id) = line.parse::<u64>() {
+                node_ids.insert(id);
+            }
+        }
+    }
+
+    Ok(node_ids)
+}
+
+/// Result of path grouping by prefix
+struct PathGrouping {
+    /// For each original path index, the group index (-1 if not grouped)
+    path_to_group: Vec<i64>,
+    /// List of valid prefixes (group names)
+    prefixes: Vec<String>,
+    /// Number of groups
+    num_groups: usize,
+}
+
+impl PathGrouping {
+    /// The path indices in each group, in path order
+    fn members(&self) -> Vec<Vec<usize>> {
+        let mut members = vec![Vec::new(); self.num_groups];
+        for (path_idx, &group_idx) in self.path_to_group.iter().enumerate() {
+            if group_idx >= 0 {
+                members[group_idx as usize].push(path_idx);
+            }
+        }
+        members
+    }
+}
+
+/// Read a prefix file (one prefix per line), dropping blank lines and duplicates
+fn read_prefixes(path: &Path) -> std::io::Result<Vec<String>> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let mut prefixes_tmp: Vec<String> = Vec::new();
+    let mut seen: FxHashSet<String> = FxHashSet::default();
+
+    // Read all prefixes from file
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim().to_string();
+        if !line.is_empty() {
+            if seen.contains(&line) {
+                warning!("duplicate prefix found: {}", line);
+            } else {
+                prefixes_tmp.push(line.clone());
+                seen.insert(line);
+            }
+        }
+    }
+    Ok(prefixes_tmp)
+}
+
+/// Load prefixes and create path groupings
+fn load_prefix_merges(path: &Path, paths: &[GfaPath]) -> std::io::Result<PathGrouping> {
+    let prefixes_tmp = read_prefixes(path)?;
+    let mut path_to_group: Vec<i64> = vec![-1; paths.len()];
+    let mut prefixes: Vec<String> = Vec::new();
+
+    // Assign each path to a group based on matching prefix
+    for (path_idx, gfa_path) in paths.iter().enumerate() {
+        let mut found = false;
+
+        // First search in already validated prefixes
+        for (group_idx, prefix) in prefixes.iter().enumerate() {
+            if gfa_path.name.starts_with(prefix) {
+                path_to_group[path_idx] = group_idx as i64;
+                found = true;
+                break;
+            }
+        }
+
+        // If not found, search in all read prefixes
+        if !found {
+            for prefix in &prefixes_tmp {
+                if gfa_path.name.starts_with(prefix) {
+                    let group_idx = prefixes.len();
+                    prefixes.push(prefix.clone());
+                    path_to_group[path_idx] = group_idx as i64;
+                    break;
+                }
+            }
+        }
+    }
+
+    let num_groups = prefixes.len();
+    Ok(PathGrouping {
+        path_to_group,
+        prefixes,
+        num_groups,
+    })
+}
+
+/// A single BED region (0-based, half-open coordinates)
+#[derive(Debug, Clone)]
+struct BedRegion {
+    start: u64, // 0-based inclusive
+    end: u64,   // 0-based exclusive (half-open)
+}
+
+/// BED regions for clustering, organized by path name
+pub(crate) struct ClusteringBedRegions {
+    /// Map from path name to sorted, merged list of regions
+    path_regions: FxHashMap<String, Vec<BedRegion>>,
+}
+
+impl ClusteringBedRegions {
+    /// Check if a path has any BED regions defined
+    fn has_regions(&self, path_name: &str) -> bool {
+        self.path_regions.contains_key(path_name)
+    }
+
+    /// Compute the bp overlap between a segment at [seg_start, seg_end) in path coordinates
+    /// and the BED regions for that path. Returns 0 if path has no regions.
+    fn compute_overlap(&self, path_name: &str, seg_start: u64, seg_end: u64) -> u64 {
+        let regions = match self.path_regions.get(path_name) {
+            Some(r) => r,
+            None => return 0,
+        };
+
+        let mut total_overlap: u64 = 0;
+        for region in regions {
+            // Check if segment overlaps this region
+            if seg_start < region.end && seg_end > region.start {
+                // Compute intersection
+                let overlap_start = seg_start.max(region.start);
+                let overlap_end = seg_end.min(region.end);
+                total_overlap += overlap_end - overlap_start;
+            }
+        }
+        total_overlap
+    }
+}
+
+/// Load BED file for clustering subsetting
+/// Expected format: BED3 (path_name, start, end) - 0-based, half-open
+/// Multiple regions per path are allowed and will be merged/unionized
+fn load_clustering_bed(path: &PathBuf) -> std::io::Result<ClusteringBedRegions> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+
+    // Collect all regions per path
+    let mut raw_regions: FxHashMap<String, Vec<BedRegion>> = FxHashMap::default();
+
+    for (line_num, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+
+        // Skip empty lines and comments
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // Parse BED3 format: path_name \t start \t end
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 3 {
+            warning!(
+                "skipping malformed BED line {} (expected 3+ fields, got {})",
+                line_num + 1,
+                fields.len()
+            );
+            continue;
+        }
+
+        let path_name = fields[0].to_string();
+        let start: u64 = match fields[1].parse() {
+            Ok(v) => v,
+            Err(_) => {
+                warning!(
+                    "skipping BED line {}: invalid start '{}'",
+                    line_num + 1,
+                    fields[1]
+                );
+                continue;
+            }
+        };
+        let end: u64 = match fields[2].parse() {
+            Ok(v) => v,
+            Err(_) => {
+                warning!(
+                    "skipping BED line {}: invalid end '{}'",
+                    line_num + 1,
+                    fields[2]
+                );
+                continue;
+            }
+        };
+
+        // Validate start < end
+        if start >= end {
+            warning!(
+                "skipping BED line {}: start ({}) >= end ({})",
+                line_num + 1,
+                start,
+                end
+            );
+            continue;
+        }
+
+        raw_regions
+            .entry(path_name)
+            .or_default()
+            .push(BedRegion { start, end });
+    }
+
+    // Merge overlapping regions for each path
+    let mut path_regions: FxHashMap<String, Vec<BedRegion>> = FxHashMap::default();
+    for (path_name, mut regions) in raw_regions {
+        // Sort by start position
+        regions.sort_by_key(|r| r.start);
+
+        // Merge overlapping/adjacent regions
+        let mut merged: Vec<BedRegion> = Vec::new();
+        for region in regions {
+            if let Some(last) = merged.last_mut() {
+                if region.start <= last.end {
+                    // Overlapping or adjacent - extend
+                    last.end = last.end.max(region.end);
+                } else {
+                    merged.push(region);
+                }
+            } else {
+                merged.push(region);
+            }
+        }
+        path_regions.insert(path_name, merged);
+    }
+
+    info!("Loaded BED regions for {} paths", path_regions.len());
+
+    Ok(ClusteringBedRegions { path_regions })
+}
+
+/// Annotation data loaded from TSV file
+#[derive(Clone)]
+struct AnnotationData {
+    /// Map from prefix to annotation category
+    prefix_to_annotation: FxHashMap<String, String>,
+    /// Ordered list of prefixes (sorted by length descending for longest-match-first)
+    prefixes: Vec<String>,
+    /// Ordered list of unique categories (sorted alphabetically)
+    categories: Vec<String>,
+    /// Map from category name to assigned color
+    category_colors: FxHashMap<String, (u8, u8, u8)>,
+}
+/// Parse a CSV line handling quoted fields that may contain commas
+fn parse_csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '"' {
+            if in_quotes && i + 1 < chars.len() && chars[i + 1] == '"' {
+                // Escaped quote
+                current.push('"');
+                i += 1;
+            } else {
+                in_quotes = !in_quotes;
+            }
+        } else if c == ',' && !in_quotes {
+            fields.push(current.trim().to_string());
+            current = String::new();
+        } else {
+            current.push(c);
+        }
+        i += 1;
+    }
+    fields.push(current.trim().to_string());
+    fields
+}
+
+/// Fixed grey color for NA (unmatched) paths
+const NA_COLOR: (u8, u8, u8) = (180, 180, 180);
+
+impl AnnotationData {
+    /// Find annotation for a path by matching against prefixes (longest match wins)
+    /// Returns "NA" for paths that don't match any prefix
+    fn get_annotation(&self, path_name: &str) -> &str {
+        for prefix in &self.prefixes {
+            if path_name.starts_with(prefix) {
+                if let Some(ann) = self.prefix_to_annotation.get(prefix) {
+                    return ann.as_str();
+                }
+            }
+        }
+        "NA"
+    }
+
+    /// Get color for a category (grey for NA, palette color for others)
+    fn get_color(&self, category: &str) -> (u8, u8, u8) {
+        if category == "NA" {
+            NA_COLOR
+        } else {
+            self.category_colors
+                .get(category)
+                .copied()
+                .unwrap_or(NA_COLOR)
+        }
+    }
+}
+
+/// Load path annotations from a TSV/CSV file
+/// Expected format: prefix,annotation[,color] (first line is header)
+/// The prefix column matches path names that start with that prefix
+/// The optional color column (hex, R,G,B or a color name) sets the category's color
+/// Supports both TSV (tab-separated) and CSV (comma-separated) based on file extension
+fn load_annotations(path: &PathBuf) -> std::io::Result<AnnotationData> {
+    // Read file as bytes and convert lossy to handle non-UTF8 characters
+    let bytes = std::fs::read(path)?;
+    let content = String::from_utf8_lossy(&bytes);
+
+    // Detect delimiter based on file extension
+    let is_csv = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase() == "csv")
+        .unwrap_or(false);
+
+    let mut prefix_to_annotation: FxHashMap<String, String> = FxHashMap::default();
+    let mut categories_set: FxHashSet<String> = FxHashSet::default();
+    let mut palette: FxHashMap<String, (u8, u8, u8)> = FxHashMap::default();
+    let mut is_first_line = true;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        // Skip empty lines
+        if line.is_empty() {
+            continue;
+        }
+
+        // Skip header line (first non-empty line)
+        if is_first_line {
+            is_first_line = false;
+            continue;
+        }
+
+        // Parse fields based on delimiter
+        let fields: Vec<String> = if is_csv {
+            parse_csv_fields(line)
+        } else {
+            line.split('\t').map(|s| s.to_string()).collect()
+        };
+
+        // Get prefix (column 0) and annotation (column 1)
+        if fields.len() >= 2 {
+            let prefix = fields[0].clone();
+            let annotation = fields[1].clone();
+
+            if !annotation.is_empty() && !prefix.is_empty() {
+                if let Some(color_str) = fields.get(2).filter(|c| !c.trim().is_empty()) {
+                    match parse_color(color_str) {
+                        Some(rgb) => {
+                            palette.insert(annotation.clone(), rgb);
+                        }
+                        None => warning!(
+                            "invalid color '{}' for annotation '{}'",
+                            color_str,
+                            annotation
+                        ),
+                    }
+                }
+                categories_set.insert(annotation.clone());
+                prefix_to_annotation.insert(prefix, annotation);
+            }
+        }
+    }
+
+    // Sort prefixes by length descending (longest match first)
+    let mut prefixes: Vec<String> = prefix_to_annotation.keys().cloned().collect();
+    prefixes.sort_by_key(|b| std::cmp::Reverse(b.len()));
+
+    // Sort categories alphabetically for consistent ordering, but put "NA" last
+    let mut categories: Vec<String> = categories_set.into_iter().collect();
+    categories.sort_by(|a, b| match (a.as_str(), b.as_str()) {
+        ("NA", "NA") => std::cmp::Ordering::Equal,
+        ("NA", _) => std::cmp::Ordering::Greater,
+        (_, "NA") => std::cmp::Ordering::Less,
+        _ => a.cmp(b),
+    });
+
+    // Assign colors to categories, preferring colors given in the file
+    let total = categories.len();
+    let category_colors: FxHashMap<String, (u8, u8, u8)> = categories
+        .iter()
+        .enumerate()
+        .map(|(i, cat)| {
+            let rgb = palette
+                .get(cat)
+                .copied()
+                .unwrap_or_else(|| get_annotation_color(i, total));
+            (cat.clone(), rgb)
+        })
+        .collect();
+
+    Ok(AnnotationData {
+        prefix_to_annotation,
+        prefixes,
+        categories,
+        category_colors,
+    })
+}
+
+/// Annotation data for --cluster-within: each prefix is its own category, in file order
+fn prefix_group_annotations(group_prefixes: Vec<String>) -> AnnotationData {
+    let total = group_prefixes.len();
+    let category_colors = group_prefixes
+        .iter()
+        .enumerate()
+        .map(|(i, prefix)| (prefix.clone(), get_annotation_color(i, total)))
+        .collect();
+    let prefix_to_annotation = group_prefixes
+        .iter()
+        .map(|prefix| (prefix.clone(), prefix.clone()))
+        .collect();
+    let mut prefixes = group_prefixes.clone();
+    prefixes.sort_by_key(|p| std::cmp::Reverse(p.len()));
+    AnnotationData {
+        prefix_to_annotation,
+        prefixes,
+        categories: group_prefixes,
+        category_colors,
+    }
+}
+
+/// Result of path clustering
+#[derive(Clone)]
+pub(crate) struct ClusteringResult {
+    pub(crate) ordering: Vec<usize>,
+    pub(crate) cluster_ids: Vec<usize>,
+    num_clusters: usize,
+    representatives: Vec<usize>, // medoid index (into original paths array) per cluster
+    cluster_sizes: Vec<usize>,   // member count per cluster
+    dendrogram: Option<Dendrogram>, // hierarchical clustering tree
+    noise_cluster: Option<usize>, // ID of the "unclustered" group of small clusters and noise
+    stability: Option<Vec<f64>>, // bootstrap co-clustering frequency per cluster
+}
+
+impl ClusteringResult {
+    /// Cluster bar color (grey for the unclustered group), faded by instability when
+    /// --shade-cluster-stability is set
+    fn cluster_color(&self, cluster_id: usize, args: &Args, theme: &Theme) -> (u8, u8, u8) {
+        let color = self.base_color(cluster_id);
+        match self.stability.as_ref().and_then(|s| s.get(cluster_id)) {
+            Some(&stability) if args.shade_cluster_stability => {
+                theme.blend(color, 0.2 + 0.8 * stability)
+            }
+            _ => color,
+        }
+    }
+
+    /// Cluster color before stability shading, as listed in the medoids TSV
+    fn base_color(&self, cluster_id: usize) -> (u8, u8, u8) {
+        if self.noise_cluster == Some(cluster_id) {
+            NA_COLOR
+        } else {
+            get_cluster_color(cluster_id)
+        }
+    }
+
+    /// Cluster label for TSV output
+    fn cluster_label(&self, cluster_id: usize) -> String {
+        if self.noise_cluster == Some(cluster_id) {
+            "unclustered".to_string()
+        } else {
+            cluster_id.to_string()
+        }
+    }
+}
+
+/// A node in the dendrogram tree
+#[derive(Clone, Debug)]
+struct DendrogramNode {
+    left: usize,  // index of left child (< n means leaf, >= n means internal node)
+    right: usize, // index of right child
+    height: f64,  // merge height (distance at which clusters merged)
+    #[allow(dead_code)]
+    size: usize, // number of leaves in this subtree
+}
+
+/// Dendrogram structure for hierarchical clustering visualization
+#[derive(Clone, Debug)]
+struct Dendrogram {
+    nodes: Vec<DendrogramNode>, // internal nodes (n-1 nodes for n leaves)
+    leaf_order: Vec<usize>,     // optimal leaf ordering for visualization
+    max_height: f64,            // maximum merge height
+}
+
+/// Build a dendrogram using UPGMA (Unweighted Pair Group Method with Arithmetic Mean)
+/// cluster_assignments: DBSCAN cluster IDs for each path (used to constrain merging order)
+/// If provided, merging happens within clusters first, then between clusters
+fn build_dendrogram(dist_matrix: &[Vec<f64>], cluster_assignments: Option<&[usize]>) -> Dendrogram {
+    let n = dist_matrix.len();
+    if n == 0 {
+        return Dendrogram {
+            nodes: Vec::new(),
+            leaf_order: Vec::new(),
+            max_height: 0.0,
+        };
+    }
+    if n == 1 {
+        return Dendrogram {
+            nodes: Vec::new(),
+            leaf_order: vec![0],
+            max_height: 0.0,
+        };
+    }
+
+    // Working distance matrix (will be modified during clustering)
+    let mut dists: Vec<Vec<f64>> = dist_matrix.to_vec();
+
+    // Track which cluster each index belongs to (-1 means merged)
+    // Cluster IDs: 0..n are leaves, n..2n-1 are internal nodes
+    let mut cluster_id: Vec<isize> = (0..n as isize).collect();
+    let mut cluster_sizes: Vec<usize> = vec![1; n];
+
+    // Track DBSCAN cluster for each active node (for constrained merging)
+    let dbscan_cluster: Vec<Option<usize>> = if let Some(assignments) = cluster_assignments {
+        assignments.iter().map(|&c| Some(c)).collect()
+    } else {
+        vec![None; n]
+    };
+
+    let mut nodes: Vec<DendrogramNode> = Vec::with_capacity(n - 1);
+    let mut max_height: f64 = 0.0;
+
+    // Track children (leaf indices) for each cluster ID
+    // Key: cluster_id (0..n for leaves, n..2n-1 for internal nodes)
+    // Value: list of original leaf indices in this cluster
+    let mut children: FxHashMap<usize, Vec<usize>> = FxHashMap::default();
+    for i in 0..n {
+        children.insert(i, vec![i]);
+    }
+
+    for merge_idx in 0..(n - 1) {
+        // Find minimum distance pair
+        // If cluster_assignments provided, prefer merging within same DBSCAN cluster first
+        let mut min_dist = f64::MAX;
+        let mut min_i = 0;
+        let mut min_j = 0;
+        let mut found_same_cluster = false;
+
+        // First pass: look for merges within same DBSCAN cluster
+        if cluster_assignments.is_some() {
+            for i in 0..n {
+                if cluster_id[i] < 0 {
+                    continue;
+                }
+                for j in (i + 1)..n {
+                    if cluster_id[j] < 0 {
+                        continue;
+                    }
+                    // Only consider if both are in the same DBSCAN cluster
+                    if dbscan_cluster[i] == dbscan_cluster[j] && dists[i][j] < min_dist {
+                        min_dist = dists[i][j];
+                        min_i = i;
+                        min_j = j;
+                        found_same_cluster = true;
+                    }
+                }
+            }
+        }
+
+        // Second pass: if no same-cluster merge found, allow any merge
+        if !found_same_cluster {
+            min_dist = f64::MAX;
+            for i in 0..n {
+                if cluster_id[i] < 0 {
+                    continue;
+                }
+                for j in (i + 1)..n {
+                    if cluster_id[j] < 0 {
+                        continue;
+                    }
+                    if dists[i][j] < min_dist {
+                        min_dist = dists[i][j];
+                        min_i = i;
+                        min_j = j;
+                    }
+                }
+            }
+        }
+
+        let new_cluster_id = (n + merge_idx) as isize;
+        let left_id = cluster_id[min_i] as usize;
+        let right_id = cluster_id[min_j] as usize;
+        let left_size = cluster_sizes[min_i];
+        let right_size = cluster_sizes[min_j];
+        let new_size = left_size + right_size;
+
+        // Record the merge
+        nodes.push(DendrogramNode {
+            left: left_id,
+            right: right_id,
+            height: min_dist / 2.0, // UPGMA uses half the distance as height
+            size: new_size,
+        });
+        max_height = max_height.max(min_dist / 2.0);
+
+        // Merge children lists for leaf ordering
+        // Get children of left and right clusters by their cluster IDs
+        let left_children = children.get(&left_id).cloned().unwrap_or_default();
+        let right_children = children.get(&right_id).cloned().unwrap_or_default();
+        let mut new_children = left_children;
+        new_children.extend(right_children);
+        children.insert(new_cluster_id as usize, new_children);
+
+        // Update distances using UPGMA formula
+        for k in 0..n {
+            if k == min_i || k == min_j || cluster_id[k] < 0 {
+                continue;
+            }
+            let new_dist = (dists[min_i][k] * left_size as f64
+                + dists[min_j][k] * right_size as f64)
+                / new_size as f64;
+            dists[min_i][k] = new_dist;
+            dists[k][min_i] = new_dist;
+        }
+
+        // Mark min_j as merged into min_i
+        cluster_id[min_j] = -1;
+        cluster_id[min_i] = new_cluster_id;
+        cluster_sizes[min_i] = new_size;
+    }
+
+    // Get leaf order from the root (the last cluster ID created)
+    let root_cluster_id = 2 * n - 2;
+    let leaf_order = children.get(&root_cluster_id).cloned().unwrap_or_default();
+
+    Dendrogram {
+        nodes,
+        leaf_order,
+        max_height,
+    }
+}
+
+/// Cut the dendrogram tree at a given height threshold and return cluster assignments.
+/// Returns a vector where cluster_ids[i] is the cluster ID for leaf i.
+fn cut_dendrogram_at_height(dendrogram: &Dendrogram, threshold: f64) -> Vec<usize> {
+    let n_leaves = dendrogram.leaf_order.len();
+    if n_leaves == 0 {
+        return Vec::new();
+    }
+    if dendrogram.nodes.is_empty() {
+        // Single leaf - one cluster
+        return vec![0];
+    }
+
+    // Use Union-Find to track which leaves belong to which cluster
+    let mut uf = UnionFind::new(n_leaves);
+
+    // Process merges in order (they're already sorted by height due to UPGMA)
+    for node in &dendrogram.nodes {
+        if node.height <= threshold {
+            // This merge happens below the threshold, so merge the clusters
+            // Need to find representative leaves from left and right subtrees
+            let left_leaf = find_leftmost_leaf(dendrogram, node.left, n_leaves);
+            let right_leaf = find_leftmost_leaf(dendrogram, node.right, n_leaves);
+            uf.union(left_leaf, right_leaf);
+        }
+        // If height > threshold, don't merge - these become separate clusters
+    }
+
+    // Assign consecutive cluster IDs
+    let mut root_to_cluster: FxHashMap<usize, usize> = FxHashMap::default();
+    let mut cluster_ids = Vec::with_capacity(n_leaves);
+    let mut next_cluster = 0;
+
+    for i in 0..n_leaves {
+        let root = uf.find(i);
+        let cluster = *root_to_cluster.entry(root).or_insert_with(|| {
+            let c = next_cluster;
+            next_cluster += 1;
+            c
+        });
+        cluster_ids.push(cluster);
+    }
+
+    cluster_ids
+}
+
+/// Whether every cluster occupies a single contiguous block of `order`
+fn clusters_are_contiguous(order: &[usize], assignments: &[usize]) -> bool {
+    let mut finished: FxHashSet<usize> = FxHashSet::default();
+    let mut current: Option<usize> = None;
+    for &idx in order {
+        let cluster = assignments[idx];
+        if current != Some(cluster) {
+            if let Some(prev) = current {
+                finished.insert(prev);
+            }
+            if finished.contains(&cluster) {
+                return false;
+            }
+            current = Some(cluster);
+        }
+    }
+    true
+}
+
+/// Find the leftmost (smallest index) leaf in a subtree
+fn find_leftmost_leaf(dendrogram: &Dendrogram, node_idx: usize, n_leaves: usize) -> usize {
+    if node_idx < n_leaves {
+        return node_idx;
+    }
+    let internal_idx = node_idx - n_leaves;
+    if internal_idx >= dendrogram.nodes.len() {
+        return 0;
+    }
+    // Recursively find leftmost leaf in left subtree
+    find_leftmost_leaf(dendrogram, dendrogram.nodes[internal_idx].left, n_leaves)
+}
+
+/// Collapse a dendrogram onto a subset of its leaves: `new_leaf[i]` is the new index of
+/// leaf `i`, or None to drop it. Merges left with a single surviving child disappear, so
+/// keeping one leaf per cluster leaves the tree of merges between clusters.
+fn prune_dendrogram(dendrogram: &Dendrogram, new_leaf: &[Option<usize>]) -> Dendrogram {
+    let n_leaves = dendrogram.leaf_order.len();
+    let n_kept = new_leaf.iter().flatten().count();
+    let mut new_id: Vec<Option<usize>> = new_leaf.to_vec();
+    new_id.resize(n_leaves, None);
+    let mut nodes: Vec<DendrogramNode> = Vec::new();
+    let mut sizes: Vec<usize> = vec![1; n_kept];
+    for node in &dendrogram.nodes {
+        let id = match (new_id[node.left], new_id[node.right]) {
+            (Some(left), Some(right)) => {
+                let size = sizes[left] + sizes[right];
+                nodes.push(DendrogramNode {
+                    left,
+                    right,
+                    height: node.height,
+                    size,
+                });
+                sizes.push(size);
+                Some(n_kept + nodes.len() - 1)
+            }
+            (left, right) => left.or(right),
+        };
+        new_id.push(id);
+    }
+    Dendrogram {
+        leaf_order: dendrogram
+            .leaf_order
+            .iter()
+            .filter_map(|&leaf| new_leaf.get(leaf).copied().flatten())
+            .collect(),
+        max_height: nodes.iter().map(|n| n.height).fold(0.0, f64::max),
+        nodes,
+    }
+}
+
+/// Find optimal threshold for UPGMA tree cutting using the "elbow" method.
+/// Looks for the largest gap in merge heights.
+fn find_optimal_upgma_threshold(dendrogram: &Dendrogram, max_clusters: Option<usize>) -> f64 {
+    if dendrogram.nodes.is_empty() {
+        return 0.5;
+    }
+
+    let n_leaves = dendrogram.leaf_order.len();
+    let max_clusters = max_clusters.unwrap_or_else(|| n_leaves.div_ceil(9)); // ~11% like DBSCAN
+
+    // Collect all merge heights
+    let mut heights: Vec<f64> = dendrogram.nodes.iter().map(|n| n.height).collect();
+    heights.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    // Find threshold that gives approximately max_clusters clusters
+    // Start from highest threshold (fewer clusters) and work down
+    for i in (0..heights.len()).rev() {
+        let threshold = heights[i];
+        let clusters = cut_dendrogram_at_height(dendrogram, threshold);
+        let num_clusters = clusters.iter().max().map(|&m| m + 1).unwrap_or(1);
+
+        if num_clusters >= max_clusters {
+            // Found a good threshold
+            debug!(
+                "UPGMA auto-threshold: {:.4} gives {} clusters (target: {})",
+                threshold, num_clusters, max_clusters
+            );
+            return threshold;
+        }
+    }
+
+    // If we couldn't find a good threshold, use the smallest height
+    let threshold = heights.first().copied().unwrap_or(0.0);
+    debug!("UPGMA using minimum threshold: {:.4}", threshold);
+    threshold
+}
+
+/// Union-Find data structure for DBSCAN clustering
+struct UnionFind {
+    parent: Vec<usize>,
+    rank: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        UnionFind {
+            parent: (0..n).collect(),
+            rank: vec![0; n],
+        }
+    }
+
+    fn find(&mut self, x: usize) -> usize {
+        if self.parent[x] != x {
+            self.parent[x] = self.find(self.parent[x]); // Path compression
+        }
+        self.parent[x]
+    }
+
+    fn union(&mut self, x: usize, y: usize) {
+        let px = self.find(x);
+        let py = self.find(y);
+        if px != py {
+            // Union by rank
+            if self.rank[px] < self.rank[py] {
+                self.parent[px] = py;
+            } else if self.rank[px] > self.rank[py] {
+                self.parent[py] = px;
+            } else {
+                self.parent[py] = px;
+                self.rank[px] += 1;
+            }
+        }
+    }
+}
+
+/// Run DBSCAN on distance matrix, return number of clusters, counting noise as one
+/// (like R's `table(dbscan(...)$cluster)`)
+fn dbscan_count_clusters(dist_matrix: &[Vec<f64>], eps: f64, min_pts: usize) -> usize {
+    let clusters: FxHashSet<Option<usize>> = dbscan_cluster(dist_matrix, eps, min_pts)
+        .into_iter()
+        .collect();
+    clusters.len()
+}
+
+/// Run DBSCAN, return cluster assignments (None for noise).
+/// Paths with at least `min_pts` paths within eps (themselves included) are core points;
+/// core points within eps of each other share a cluster, border paths join the cluster of
+/// their nearest core point and the rest are noise. With minPts=1 every path is a core
+/// point, so clusters are the connected components where edges exist for distance <= eps
+fn dbscan_cluster(dist_matrix: &[Vec<f64>], eps: f64, min_pts: usize) -> Vec<Option<usize>> {
+    let n = dist_matrix.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let is_core: Vec<bool> = dist_matrix
+        .iter()
+        .map(|row| row.iter().filter(|&&d| d <= eps).count() >= min_pts)
+        .collect();
+    let mut uf = UnionFind::new(n);
+
+    // Connect core points within eps distance
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if is_core[i] && is_core[j] && dist_matrix[i][j] <= eps {
+                uf.union(i, j);
+            }
+        }
+    }
+
+    // Assign cluster IDs (0-indexed, consecutive) through each path's nearest core point
+    let mut root_to_cluster: FxHashMap<usize, usize> = FxHashMap::default();
+    let mut cluster_ids = Vec::with_capacity(n);
+    let mut next_cluster = 0;
+
+    for i in 0..n {
+        let core = if is_core[i] {
+            Some(i)
+        } else {
+            (0..n)
+                .filter(|&j| is_core[j] && dist_matrix[i][j] <= eps)
+                .min_by(|&a, &b| dist_matrix[i][a].total_cmp(&dist_matrix[i][b]))
+        };
+        let cluster = core.map(|core| {
+            let root = uf.find(core);
+            *root_to_cluster.entry(root).or_insert_with(|| {
+                let c = next_cluster;
+                next_cluster += 1;
+                c
+            })
+        });
+        cluster_ids.push(cluster);
+    }
+
+    cluster_ids
+}
+
+/// DBSCAN assignments with each noise path as a cluster of its own
+fn noise_as_singletons(clusters: Vec<Option<usize>>) -> Vec<usize> {
+    let mut next = clusters.len();
+    clusters
+        .into_iter()
+        .map(|c| {
+            c.unwrap_or_else(|| {
+                next += 1;
+                next
+            })
+        })
+        .collect()
+}
+
+/// Find optimal eps using cosigt's stabilization detection
+/// Tests eps from 0.001 to 0.300, finds where cluster count stabilizes
+fn find_optimal_eps(
+    dist_matrix: &[Vec<f64>],
+    n_paths: usize,
+    max_clusters_override: Option<usize>,
+    min_pts: usize,
+) -> f64 {
+    if dist_matrix.is_empty() {
+        return 0.30;
+    }
+
+    // Determine max_clusters: use override if provided, otherwise ~11% of paths
+    let max_clusters = max_clusters_override.unwrap_or_else(|| n_paths.div_ceil(9));
+    debug!(
+        "DBSCAN max_clusters: {} ({})",
+        max_clusters,
+        if max_clusters_override.is_some() {
+            "user override"
+        } else {
+            "automatic"
+        }
+    );
+
+    // cosigt: pclust <- length(table(dbscan(distanceMatrix, eps = 0, minPts = 1)$cluster))
+    let mut prev_clusters = dbscan_count_clusters(dist_matrix, 0.0, min_pts);
+    debug!("DBSCAN eps scan: eps=0.00 -> {} clusters", prev_clusters);
+
+    // eps from 0.005 to 0.300 in steps of 0.005
+    for eps_int in 1..=60 {
+        let eps = eps_int as f64 * 0.005;
+        let curr_clusters = dbscan_count_clusters(dist_matrix, eps, min_pts);
+
+        // cosigt: if (abs(pclust - cclust) <= 1)
+        let change = (prev_clusters as i64 - curr_clusters as i64).abs();
+        debug!(
+            "DBSCAN eps scan: eps={:.3} -> {} clusters (change={} from prev={}, max_allowed={})",
+            eps, curr_clusters, change, prev_clusters, max_clusters
+        );
+
+        // Modified stabilization: also accept when we first reach <= max_clusters
+        // This captures cases where cluster count jumps directly to the target
+        let first_hit_max = prev_clusters > max_clusters && curr_clusters <= max_clusters;
+
+        if (change <= 1 || first_hit_max) && curr_clusters <= max_clusters {
+            if first_hit_max && change > 1 {
+                debug!(
+                    "DBSCAN: first hit max_clusters at eps {:.3} with {} clusters (jumped from {})",
+                    eps, curr_clusters, prev_clusters
+                );
+            } else {
+                debug!(
+                    "DBSCAN: stabilized at eps {:.3} with {} clusters (max allowed: {})",
+                    eps, curr_clusters, max_clusters
+                );
+            }
+            return eps;
+        }
+        prev_clusters = curr_clusters;
+    }
+
+    // cosigt: return(ifelse(eps < 0.3, optimal_eps, 0.3))
+    debug!("DBSCAN: no stabilization found, using fallback eps 0.30");
+    0.30
+}
+
+/// Small deterministic PRNG (SplitMix64) so bootstrap results are reproducible
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Per-cluster stability over bootstrap replicates: each replicate resamples `nodes` with
+/// replacement, rebuilds the normalized EDR matrix and reclusters it with `recluster`.
+/// A cluster's score is the mean fraction of its member pairs that end up together
+/// (for singletons, the fraction of replicates in which the path stays alone).
+fn bootstrap_cluster_stability<F>(
+    bp_counts: &[NodeCounts],
+    nodes: &[u64],
+    cluster_members: &[Vec<usize>],
+    replicates: usize,
+    recluster: F,
+) -> Vec<f64>
+where
+    F: Fn(&[Vec<f64>]) -> Vec<usize> + Sync,
+{
+    let n = bp_counts.len();
+    if nodes.is_empty() || n == 0 {
+        return vec![1.0; cluster_members.len()];
+    }
+
+    let scores: Vec<Vec<f64>> = (0..replicates)
+        .into_par_iter()
+        .map(|rep| {
+            let mut rng = SplitMix64(0x6766_616c_6f6f_6b00 ^ rep as u64);
+            let mut weights: FxHashMap<u64, u64> = FxHashMap::default();
+            for _ in 0..nodes.len() {
+                *weights.entry(nodes[rng.below(nodes.len())]).or_insert(0) += 1;
+            }
+
+            let counts: Vec<NodeCounts> = bp_counts
+                .iter()
+                .map(|c| {
+                    c.iter()
+                        .filter_map(|&(node, bp)| weights.get(&node).map(|&w| (node, bp * w)))
+                        .collect()
+                })
+                .collect();
+            let assignment = recluster(&normalized_edr_matrix(&counts));
+            cluster_members
+                .iter()
+                .map(|members| {
+                    if members.len() == 1 {
+                        let alone = !(0..n)
+                            .any(|k| k != members[0] && assignment[k] == assignment[members[0]]);
+                        return if alone { 1.0 } else { 0.0 };
+                    }
+                    let mut together = 0usize;
+                    let mut pairs = 0usize;
+                    for (a, &i) in members.iter().enumerate() {
+                        for &j in &members[a + 1..] {
+                            pairs += 1;
+                            if assignment[i] == assignment[j] {
+                                together += 1;
+                            }
+                        }
+                    }
+                    together as f64 / pairs as f64
+                })
+                .collect()
+        })
+        .collect();
+
+    (0..cluster_members.len())
+        .map(|c| scores.iter().map(|s| s[c]).sum::<f64>() / replicates as f64)
+        .collect()
+}
+
+/// Pairwise EDR matrix scaled to [0, 1] by its maximum, with each path's total bp taken
+/// from its own counts
+fn normalized_edr_matrix(counts: &[NodeCounts]) -> Vec<Vec<f64>> {
+    let n = counts.len();
+    let totals: Vec<u64> = counts
+        .iter()
+        .map(|c| c.iter().map(|&(_, bp)| bp).sum())
+        .collect();
+    let mut dists = vec![vec![0.0; n]; n];
+    let mut max_edr = 0.0f64;
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let jaccard = weighted_jaccard_similarity(&counts[i], &counts[j], totals[i], totals[j]);
+            let edr = jaccard_to_edr(jaccard);
+            dists[i][j] = edr;
+            dists[j][i] = edr;
+            max_edr = max_edr.max(edr);
+        }
+    }
+    if max_edr > 0.0 {
+        for row in dists.iter_mut() {
+            for d in row.iter_mut() {
+                *d /= max_edr;
+            }
+        }
+    }
+    dists
+}
+
+/// Cluster paths independently in consecutive pangenomic windows of `window_size` bp.
+/// `result[w][p]` is the size-ranked DBSCAN cluster of display path `p` in window `w`,
+/// or None when the path does not cover that window.
+fn cluster_windows(
+    paths: &[&GfaPath],
+    graph: &Graph,
+    window_size: u64,
+    threshold: Option<f64>,
+    max_clusters: Option<usize>,
+    min_pts: usize,
+) -> Vec<Vec<Option<usize>>> {
+    let window_size = window_size.max(1);
+    let num_windows = graph.total_length.div_ceil(window_size) as usize;
+
+    // bp per node for every path, split by window
+    let per_path: Vec<Vec<FxHashMap<u64, u64>>> = paths
+        .par_iter()
+        .map(|path| {
+            let mut windows: Vec<FxHashMap<u64, u64>> = vec![FxHashMap::default(); num_windows];
+            for step in &path.steps {
+                let seg_id = step.segment_id as usize;
+                if seg_id >= graph.segments.len() {
+                    continue;
+                }
+                let start = graph.segment_offsets[seg_id];
+                let end = start + graph.segments[seg_id].sequence_len;
+                let mut pos = start;
+                while pos < end {
+                    let w = (pos / window_size) as usize;
+                    let w_end = ((w as u64 + 1) * window_size).min(end);
+                    if w < num_windows {
+                        *windows[w].entry(step.segment_id).or_insert(0) += w_end - pos;
+                    }
+                    pos = w_end;
+                }
+            }
+            windows
+        })
+        .collect();
+
+    (0..num_windows)
+        .into_par_iter()
+        .map(|w| {
+            let present: Vec<usize> = (0..paths.len())
+                .filter(|&p| !per_path[p][w].is_empty())
+                .collect();
+            let mut assignment = vec![None; paths.len()];
+            if present.is_empty() {
+                return assignment;
+            }
+            let counts: Vec<NodeCounts> = present
+                .iter()
+                .map(|&p| sorted_node_counts(&per_path[p][w]))
+                .collect();
+            let dists = normalized_edr_matrix(&counts);
+            let eps = match threshold {
+                Some(t) => 1.0 - t,
+                None => find_optimal_eps(&dists, present.len(), max_clusters, min_pts),
+            };
+            let clusters = dbscan_cluster(&dists, eps, min_pts);
+
+            // Rank clusters by size so the largest group gets the first color in every window
+            let num_clusters = clusters.iter().flatten().max().map_or(0, |&m| m + 1);
+            let mut sizes = vec![0usize; num_clusters];
+            for &c in clusters.iter().flatten() {
+                sizes[c] += 1;
+            }
+            let mut by_size: Vec<usize> = (0..num_clusters).collect();
+            by_size.sort_by_key(|&c| (std::cmp::Reverse(sizes[c]), c));
+            let mut rank = vec![0usize; num_clusters];
+            for (r, &c) in by_size.iter().enumerate() {
+                rank[c] = r;
+            }
+
+            // DBSCAN noise gets no band, like a path that does not cover the window
+            for (k, &p) in present.iter().enumerate() {
+                assignment[p] = clusters[k].map(|c| rank[c]);
+            }
+            assignment
+        })
+        .collect()
+}
+
+/// Per-path (node_id, bp on that node) pairs sorted by node id, so pairwise intersections
+/// are a linear merge scan instead of hash lookups
+type NodeCounts = Vec<(u64, u64)>;
+
+/// Convert a node -> bp map into sorted `NodeCounts`
+fn sorted_node_counts(counts: &FxHashMap<u64, u64>) -> NodeCounts {
+    let mut sorted: NodeCounts = counts.iter().map(|(&node, &bp)| (node, bp)).collect();
+    sorted.sort_unstable_by_key(|&(node, _)| node);
+    sorted
+}
+
+/// Compute base-pair weighted Jaccard similarity (matching odgi similarity)
+/// For each node: add min(bp_a_on_node, bp_b_on_node) to intersection
+/// jaccard = intersection / (bp_a + bp_b - intersection)
+fn weighted_jaccard_similarity(
+    counts_a: &[(u64, u64)], // sorted (node_id, total bp on that node) for path a
+    counts_b: &[(u64, u64)], // sorted (node_id, total bp on that node) for path b
+    bp_a: u64,               // total bp in path a
+    bp_b: u64,               // total bp in path b
+) -> f64 {
+    if bp_a == 0 && bp_b == 0 {
+        return 1.0;
+    }
+
+    let intersection = weighted_intersection(counts_a, counts_b);
+    let union = bp_a + bp_b - intersection;
+    if union == 0 {
+        1.0
+    } else {
+        intersection as f64 / union as f64
+    }
+}
+
+/// Sum of min(bp_a_on_node, bp_b_on_node) over the nodes two sorted counts share
+fn weighted_intersection(counts_a: &[(u64, u64)], counts_b: &[(u64, u64)]) -> u64 {
+    let mut intersection: u64 = 0;
+    let (mut i, mut j) = (0, 0);
+    while i < counts_a.len() && j < counts_b.len() {
+        let (node_a, bp_a_on_node) = counts_a[i];
+        let (node_b, bp_b_on_node) = counts_b[j];
+        match node_a.cmp(&node_b) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                intersection += bp_a_on_node.min(bp_b_on_node);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    intersection
+}
+
+/// Base pairs each path spends on each node, counting only BED-covered bases when
+/// regions are given
+fn path_node_bp_counts(
+    paths: &[&GfaPath],
+    segment_lengths: &[u64],
+    bed_regions: Option<&ClusteringBedRegions>,
+) -> Vec<FxHashMap<u64, u64>> {
+    paths
+        .par_iter()
+        .map(|path| {
+            let mut counts: FxHashMap<u64, u64> = FxHashMap::default();
+            let mut path_pos: u64 = 0; // Track cumulative position in path coordinates
+
+            for step in &path.steps {
+                let seg_len = segment_lengths
+                    .get(step.segment_id as usize)
+                    .copied()
+                    .unwrap_or(0);
+
+                // Compute bp to count for this segment
+                let bp_to_count = match bed_regions {
+                    Some(bed) if bed.has_regions(&path.name) => {
+                        // Path has BED regions: compute overlap
+                        bed.compute_overlap(&path.name, path_pos, path_pos + seg_len)
+                    }
+                    Some(_) => {
+                        // BED file provided but path has no regions: count 0 bp (excluded)
+                        0
+                    }
+                    None => {
+                        // No BED file: use full segment length (original behavior)
+                        seg_len
+                    }
+                };
+
+                if bp_to_count > 0 {
+                    *counts.entry(step.segment_id).or_insert(0) += bp_to_count;
+                }
+
+                path_pos += seg_len;
+            }
+            counts
+        })
+        .collect()
+}
+
+/// Nodes whose bp count differs between at least two paths
+fn variable_nodes(path_bp_counts: &[FxHashMap<u64, u64>], nodes: FxHashSet<u64>) -> FxHashSet<u64> {
+    nodes
+        .into_iter()
+        .filter(|&node| {
+            let first_bp = path_bp_counts[0].get(&node).copied().unwrap_or(0);
+            path_bp_counts
+                .iter()
+                .skip(1)
+                .any(|counts| counts.get(&node).copied().unwrap_or(0) != first_bp)
+        })
+        .collect()
+}
+
+/// What a similarity compares, from a single path up to a whole PanSN assembly
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum SimilarityLevel {
+    /// Each path on its own
+    Path,
+    /// All contigs of a `sample#haplotype`
+    Haplotype,
+    /// All contigs of a `sample`, across haplotypes
+    Sample,
+}
+
+impl SimilarityLevel {
+    /// The group a path name belongs to; names without PanSN fields form their own group
+    fn group_key<'a>(&self, name: &'a str) -> &'a str {
+        let cut = match self {
+            SimilarityLevel::Path => None,
+            SimilarityLevel::Haplotype => name.match_indices('#').nth(1).map(|(i, _)| i),
+            SimilarityLevel::Sample => name.find('#'),
+        };
+        cut.map_or(name, |i| &name[..i])
+    }
+}
+
+/// Sum the node coverage of paths in the same group, giving every path its group's counts
+fn aggregate_node_bp_counts(
+    paths: &[&GfaPath],
+    path_bp_counts: Vec<FxHashMap<u64, u64>>,
+    level: SimilarityLevel,
+) -> Vec<FxHashMap<u64, u64>> {
+    if level == SimilarityLevel::Path {
+        return path_bp_counts;
+    }
+    let mut group_counts: FxHashMap<&str, FxHashMap<u64, u64>> = FxHashMap::default();
+    for (path, counts) in paths.iter().zip(path_bp_counts) {
+        let group = group_counts.entry(level.group_key(&path.name)).or_default();
+        for (node, bp) in counts {
+            *group.entry(node).or_insert(0) += bp;
+        }
+    }
+    debug!(
+        "Similarity level {:?}: {} paths in {} groups",
+        level,
+        paths.len(),
+        group_counts.len()
+    );
+    paths
+        .iter()
+        .map(|path| group_counts[level.group_key(&path.name)].clone())
+        .collect()
+}
+
+/// Per-path node coverage prepared for pairwise similarity
+struct SimilarityCounts {
+    /// Sorted bp per node, restricted to the compared nodes
+    counts: Vec<NodeCounts>,
+    /// Similarity denominator per path: full length with all nodes, compared bp otherwise
+    total_bp: Vec<u64>,
+    /// Nodes the similarity is computed over
+    nodes: FxHashSet<u64>,
+}
+
+/// Node coverage for similarity, over all nodes or only the variable ones
+fn similarity_counts(
+    paths: &[&GfaPath],
+    segment_lengths: &[u64],
+    bed_regions: Option<&ClusteringBedRegions>,
+    use_all_nodes: bool,
+    level: SimilarityLevel,
+) -> SimilarityCounts {
+    // Build bp-weighted node counts for each path (node_id -> total bp on that node)
+    // This matches odgi similarity: for each step, add segment length to that node's count
+    // If bed_regions is provided, only count bp that fall within BED regions
+    let path_bp_counts = aggregate_node_bp_counts(
+        paths,
+        path_node_bp_counts(paths, segment_lengths, bed_regions),
+        level,
+    );
+
+    // Collect all unique nodes
+    let mut all_nodes: FxHashSet<u64> = FxHashSet::default();
+    for counts in &path_bp_counts {
+        for &node in counts.keys() {
+            all_nodes.insert(node);
+        }
+    }
+    let total_unique_nodes = all_nodes.len();
+
+    // Determine which nodes to use for clustering (based on bp variation, not just count)
+    let nodes_to_use: FxHashSet<u64> = if use_all_nodes {
+        debug!(
+            "Clustering mode: --cluster-all-nodes (using all {} nodes)",
+            total_unique_nodes
+        );
+        all_nodes
+    } else {
+        // Find variable nodes: nodes where bp count varies across paths
+        let variable_nodes = variable_nodes(&path_bp_counts, all_nodes);
+
+        let invariant_nodes = total_unique_nodes - variable_nodes.len();
+        debug!("Clustering mode: variable nodes only (using {} of {} nodes, {} invariant nodes excluded)",
+               variable_nodes.len(), total_unique_nodes, invariant_nodes);
+        variable_nodes
+    };
+
+    // Build filtered bp counts (only include nodes_to_use), sorted for merge-scan intersections
+    let filtered_bp_counts: Vec<NodeCounts> = path_bp_counts
+        .par_iter()
+        .map(|counts| {
+            let mut sorted: NodeCounts = counts
+                .iter()
+                .filter(|(node, _)| nodes_to_use.contains(node))
+                .map(|(&node, &bp)| (node, bp))
+                .collect();
+            sorted.sort_unstable_by_key(|&(node, _)| node);
+            sorted
+        })
+        .collect();
+
+    // Compute total bp for each path
+    // When using all nodes, use full path lengths (matching odgi)
+    // When using variable nodes only, use filtered lengths (consistent intersection/denominator)
+    let total_bp: Vec<u64> = if use_all_nodes {
+        path_bp_counts
+            .iter()
+            .map(|counts| counts.values().sum())
+            .collect()
+    } else {
+        filtered_bp_counts
+            .iter()
+            .map(|counts| counts.iter().map(|&(_, bp)| bp).sum())
+            .collect()
+    };
+
+    SimilarityCounts {
+        counts: filtered_bp_counts,
+        total_bp,
+        nodes: nodes_to_use,
+    }
+}
+
+/// Compute EDR (estimated difference rate) from Jaccard similarity
+/// EDR = (1 - jaccard) / (1 + jaccard)
+/// This matches odgi similarity's estimated.difference.rate
+fn jaccard_to_edr(jaccard: f64) -> f64 {
+    (1.0 - jaccard) / (1.0 + jaccard)
+}
+
+/// Cluster paths by EDR (estimated difference rate)
+/// Uses base-pair weighted Jaccard similarity like odgi similarity
+/// If use_upgma is true, uses pure UPGMA hierarchical clustering with tree cutting
+/// Otherwise uses DBSCAN (matching cosigt exactly)
+pub(crate) fn cluster_paths_by_similarity(
+    paths: &[&GfaPath],
+    segment_lengths: &[u64], // segment_id -> length (0-indexed by segment_id - 1)
+    threshold: Option<f64>,
+    use_all_nodes: bool,
+    max_clusters: Option<usize>,
+    compute_dendrogram: bool,
+    use_upgma: bool,
+    upgma_threshold: Option<f64>,
+    bed_regions: Option<&ClusteringBedRegions>,
+    min_cluster_size: usize,
+    min_pts: usize,
+    bootstrap_replicates: usize,
+    level: SimilarityLevel,
+) -> ClusteringResult {
+    if paths.is_empty() {
+        return ClusteringResult {
+            ordering: Vec::new(),
+            cluster_ids: Vec::new(),
+            num_clusters: 0,
+            representatives: Vec::new(),
+            cluster_sizes: Vec::new(),
+            dendrogram: None,
+            noise_cluster: None,
+            stability: None,
+        };
+    }
+
+    let n = paths.len();
+
+    let SimilarityCounts {
+        counts: filtered_bp_counts,
+        total_bp,
+        nodes: nodes_to_use,
+    } = similarity_counts(paths, segment_lengths, bed_regions, use_all_nodes, level);
+
+    // Build full pairwise EDR matrix (matching cosigt: uses normalized EDR)
+    debug!("Computing {}x{} pairwise EDR matrix", n, n);
+
+    // Compute upper triangle in parallel: EDR for each pair
+    let filtered_bp_counts_ref = &filtered_bp_counts;
+    let total_bp_ref = &total_bp;
+    let pairs: Vec<(usize, usize, f64)> = (0..n)
+        .into_par_iter()
+        .flat_map(|i| {
+            (i + 1..n)
+                .map(move |j| {
+                    let jaccard = weighted_jaccard_similarity(
+                        &filtered_bp_counts_ref[i],
+                        &filtered_bp_counts_ref[j],
+                        total_bp_ref[i],
+                        total_bp_ref[j],
+                    );
+                    let edr = jaccard_to_edr(jaccard);
+                    (i, j, edr)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    // Find max EDR for normalization (like cosigt: maxD <- max(regularMatrix))
+    let max_edr = pairs.iter().map(|(_, _, edr)| *edr).fold(0.0f64, f64::max);
+    debug!("Max EDR: {:.6}", max_edr);
+
+    // Debug: print first few EDR values for comparison with odgi
+    for (i, j, edr) in pairs.iter().take(5) {
+        let jaccard = weighted_jaccard_similarity(
+            &filtered_bp_counts[*i],
+            &filtered_bp_counts[*j],
+            total_bp[*i],
+            total_bp[*j],
+        );
+        debug!(
+            "EDR: {} vs {} = {:.6} (jaccard={:.6}, bp_a={}, bp_b={})",
+            paths[*i].name, paths[*j].name, edr, jaccard, total_bp[*i], total_bp[*j]
+        );
+    }
+
+    // Build normalized distance matrix (like cosigt: normRegularMatrix <- regularMatrix / maxD)
+    let mut dist_matrix: Vec<Vec<f64>> = vec![vec![0.0; n]; n];
+    for (i, j, edr) in pairs {
+        let norm_edr = if max_edr > 0.0 { edr / max_edr } else { 0.0 };
+        dist_matrix[i][j] = norm_edr;
+        dist_matrix[j][i] = norm_edr;
+    }
+
+    // Log distance distribution
+    let mut all_dists: Vec<f64> = Vec::with_capacity(n * (n - 1) / 2);
+    for i in 0..n {
+        for j in (i + 1)..n {
+            all_dists.push(dist_matrix[i][j]);
+        }
+    }
+    all_dists.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    if !all_dists.is_empty() {
+        debug!(
+            "Distance range: {:.3} - {:.3}",
+            all_dists[0],
+            all_dists[all_dists.len() - 1]
+        );
+        if all_dists.len() >= 4 {
+            let q1 = all_dists[all_dists.len() / 4];
+            let median = all_dists[all_dists.len() / 2];
+            let q3 = all_dists[3 * all_dists.len() / 4];
+            debug!(
+                "Distance quartiles: Q1={:.3}, median={:.3}, Q3={:.3}",
+                q1, median, q3
+            );
+        }
+    }
+
+    // Get cluster assignments using either UPGMA or DBSCAN
+    // cut_height is the UPGMA cut height or the DBSCAN eps, reused for bootstrap replicates
+    let (cluster_assignments, dendrogram_for_upgma, cut_height): (
+        Vec<Option<usize>>,
+        Option<Dendrogram>,
+        f64,
+    ) = if use_upgma {
+        // Pure UPGMA mode: build dendrogram first, then cut at threshold
+        debug!("Using UPGMA hierarchical clustering");
+        let dg = build_dendrogram(&dist_matrix, None); // No DBSCAN constraint for pure UPGMA
+
+        // Determine cut threshold
+        let cut_threshold = match upgma_threshold {
+            Some(t) => {
+                debug!("Using user-specified UPGMA threshold: {:.4}", t);
+                t * dg.max_height // Scale to actual height range
+            }
+            None => find_optimal_upgma_threshold(&dg, max_clusters),
+        };
+
+        let clusters = cut_dendrogram_at_height(&dg, cut_threshold);
+        let num_clusters = clusters.iter().max().map(|&m| m + 1).unwrap_or(1);
+        debug!(
+            "UPGMA cut at height {:.4} gives {} clusters",
+            cut_threshold, num_clusters
+        );
+
+        (
+            clusters.into_iter().map(Some).collect(),
+            Some(dg),
+            cut_threshold,
+        )
+    } else {
+        // DBSCAN mode (original behavior)
+        // Find optimal eps (or convert user threshold to eps)
+        let eps = match threshold {
+            Some(t) => {
+                let e = 1.0 - t; // Convert similarity threshold to distance eps
+                debug!("Using user-specified threshold {:.2} (eps = {:.2})", t, e);
+                e
+            }
+            None => find_optimal_eps(&dist_matrix, n, max_clusters, min_pts),
+        };
+        debug!("DBSCAN eps: {:.2}, minPts: {}", eps, min_pts);
+
+        // Run DBSCAN to get cluster assignments
+        let clusters = dbscan_cluster(&dist_matrix, eps, min_pts);
+        let num_clusters = clusters.iter().flatten().max().map_or(0, |&m| m + 1);
+        let noise = clusters.iter().filter(|c| c.is_none()).count();
+        debug!(
+            "DBSCAN detected {} clusters and {} noise paths",
+            num_clusters, noise
+        );
+
+        (clusters, None, eps)
+    };
+
+    let num_clusters = cluster_assignments
+        .iter()
+        .flatten()
+        .max()
+        .map_or(0, |&m| m + 1);
+
+    // Group paths by cluster, setting DBSCAN noise aside
+    let mut cluster_members: Vec<Vec<usize>> = vec![Vec::new(); num_clusters];
+    let mut noise: Vec<usize> = Vec::new();
+    for (i, &cluster) in cluster_assignments.iter().enumerate() {
+        match cluster {
+            Some(cluster) => cluster_members[cluster].push(i),
+            None => noise.push(i),
+        }
+    }
+
+    // Sort clusters by size (largest first) for consistent ordering
+    cluster_members.sort_by_key(|v| std::cmp::Reverse(v.len()));
+
+    // Pool clusters below --min-cluster-size and DBSCAN noise into one "unclustered" group
+    // placed last
+    let mut noise_cluster = None;
+    if min_cluster_size > 1 {
+        let (kept, small): (Vec<Vec<usize>>, Vec<Vec<usize>>) = cluster_members
+            .into_iter()
+            .partition(|members| members.len() >= min_cluster_size);
+        cluster_members = kept;
+        let small: Vec<usize> = small.into_iter().flatten().collect();
+        if !small.is_empty() {
+            debug!(
+                "{} paths in clusters smaller than {} moved to the unclustered group",
+                small.len(),
+                min_cluster_size
+            );
+        }
+        noise.extend(small);
+    }
+    if !noise.is_empty() {
+        noise.sort_unstable();
+        noise_cluster = Some(cluster_members.len());
+        cluster_members.push(noise);
+    }
+    let num_clusters = cluster_members.len();
+
+    // Final (size-ranked) cluster ID of every path
+    let mut cluster_of = vec![0usize; n];
+    for (rank, members) in cluster_members.iter().enumerate() {
+        for &m in members {
+            cluster_of[m] = rank;
+        }
+    }
+
+    // Bootstrap the clustering nodes to score how reproducible each cluster is
+    let stability = (bootstrap_replicates > 0).then(|| {
+        let mut nodes: Vec<u64> = nodes_to_use.iter().copied().collect();
+        nodes.sort_unstable();
+        debug!(
+            "Bootstrapping clusters: {} replicates over {} nodes",
+            bootstrap_replicates,
+            nodes.len()
+        );
+        bootstrap_cluster_stability(
+            &filtered_bp_counts,
+            &nodes,
+            &cluster_members,
+            bootstrap_replicates,
+            |dists| {
+                if use_upgma {
+                    cut_dendrogram_at_height(&build_dendrogram(dists, None), cut_height)
+                } else {
+                    noise_as_singletons(dbscan_cluster(dists, cut_height, min_pts))
+                }
+            },
+        )
+    });
+
+    // Compute medoid for each cluster (path with minimum average distance to others)
+    let mut representatives: Vec<usize> = Vec::with_capacity(num_clusters);
+    let mut cluster_sizes: Vec<usize> = Vec::with_capacity(num_clusters);
+
+    for members in &cluster_members {
+        cluster_sizes.push(members.len());
+
+        if members.len() == 1 {
+            // Singleton: the single member is the representative
+            representatives.push(members[0]);
+        } else {
+            // Find medoid: member with minimum average distance to all others
+            let mut best_medoid = members[0];
+            let mut best_avg_dist = f64::MAX;
+
+            for &candidate in members {
+                let sum_dist: f64 = members
+                    .iter()
+                    .filter(|&&m| m != candidate)
+                    .map(|&m| dist_matrix[candidate][m])
+                    .sum();
+                let avg_dist = sum_dist / (members.len() - 1) as f64;
+
+                if avg_dist < best_avg_dist {
+                    best_avg_dist = avg_dist;
+                    best_medoid = candidate;
+                }
+            }
+            representatives.push(best_medoid);
+        }
+    }
+
+    // Build final ordering: within each cluster, order by greedy nearest-neighbor
+    let mut ordering = Vec::with_capacity(n);
+    let mut final_cluster_ids = Vec::with_capacity(n);
+
+    for (cluster_id, members) in cluster_members.iter().enumerate() {
+        if members.is_empty() {
+            continue;
+        }
+
+        if members.len() == 1 {
+            ordering.push(members[0]);
+            final_cluster_ids.push(cluster_id);
+        } else {
+            // Greedy nearest-neighbor within cluster
+            let mut placed = vec![false; members.len()];
+
+            // Start with the member that has the most base pairs
+            let start = members
+                .iter()
+                .enumerate()
+                .max_by_key(|&(_, &idx)| total_bp[idx])
+                .map(|(local_idx, _)| local_idx)
+                .unwrap();
+
+            placed[start] = true;
+            ordering.push(members[start]);
+            final_cluster_ids.push(cluster_id);
+            let mut current = start;
+
+            while ordering.len() < ordering.capacity() && placed.iter().filter(|&&p| !p).count() > 0
+            {
+                // Find nearest unplaced member within this cluster
+                let current_global = members[current];
+                let mut best_local = None;
+                let mut best_dist = f64::MAX;
+
+                for (local_idx, &global_idx) in members.iter().enumerate() {
+                    if !placed[local_idx] {
+                        let dist = dist_matrix[current_global][global_idx];
+                        if dist < best_dist {
+                            best_dist = dist;
+                            best_local = Some(local_idx);
+                        }
+                    }
+                }
+
+                if let Some(local_idx) = best_local {
+                    placed[local_idx] = true;
+                    ordering.push(members[local_idx]);
+                    final_cluster_ids.push(cluster_id);
+                    current = local_idx;
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+
+    debug!(
+        "Final ordering: {} paths in {} clusters",
+        ordering.len(),
+        num_clusters
+    );
+
+    // Paths that take part in the dendrogram: everything except the unclustered group,
+    // which is listed below the tree
+    let tree_paths: Vec<usize> = (0..n)
+        .filter(|&i| noise_cluster != Some(cluster_of[i]))
+        .collect();
+
+    // Build or reuse dendrogram
+    let dendrogram = if noise_cluster.is_some() && (use_upgma || compute_dendrogram) {
+        let sub_matrix: Vec<Vec<f64>> = tree_paths
+            .iter()
+            .map(|&i| tree_paths.iter().map(|&j| dist_matrix[i][j]).collect())
+            .collect();
+        let sub_clusters: Vec<usize> = tree_paths.iter().map(|&i| cluster_of[i]).collect();
+        Some(build_dendrogram(&sub_matrix, Some(&sub_clusters)))
+    } else if use_upgma {
+        // For UPGMA mode, we already have the dendrogram; rebuild it constrained by the
+        // clusters if its leaf order would interleave them (gaps and bars need contiguous blocks)
+        dendrogram_for_upgma.map(|dg| {
+            if clusters_are_contiguous(&dg.leaf_order, &cluster_of) {
+                dg
+            } else {
+                debug!("UPGMA leaf order interleaves clusters, rebuilding constrained dendrogram");
+                build_dendrogram(&dist_matrix, Some(&cluster_of))
+            }
+        })
+    } else if compute_dendrogram {
+        // For DBSCAN mode, build dendrogram constrained by clusters
+        Some(build_dendrogram(&dist_matrix, Some(&cluster_of)))
+    } else {
+        None
+    };
+
+    // If dendrogram is available, use its leaf order for visualization
+    let (final_ordering, final_cluster_ids) = if let Some(ref dg) = dendrogram {
+        // Map dendrogram leaf order (over tree_paths) to path indices and cluster IDs
+        let mut dg_ordering = Vec::with_capacity(n);
+        let mut dg_cluster_ids = Vec::with_capacity(n);
+
+        for &leaf in &dg.leaf_order {
+            let orig_idx = tree_paths[leaf];
+            dg_ordering.push(orig_idx);
+            dg_cluster_ids.push(cluster_of[orig_idx]);
+        }
+
+        // Unclustered paths follow the tree in their nearest-neighbor order
+        for (&orig_idx, &cluster_id) in ordering.iter().zip(final_cluster_ids.iter()) {
+            if noise_cluster == Some(cluster_id) {
+                dg_ordering.push(orig_idx);
+                dg_cluster_ids.push(cluster_id);
+            }
+        }
+        (dg_ordering, dg_cluster_ids)
+    } else {
+        (ordering, final_cluster_ids)
+    };
+
+    ClusteringResult {
+        ordering: final_ordering,
+        cluster_ids: final_cluster_ids,
+        num_clusters,
+        representatives,
+        cluster_sizes,
+        dendrogram,
+        noise_cluster,
+        stability,
+    }
+}
+
+#[derive(Default, Clone)]
+pub(crate) struct BinInfo {
+    pub(crate) mean_depth: f64,
+    mean_inv: f64,
+    mean_pos: f64,      // mean position within path (for darkness gradient)
+    mean_uncalled: f64, // proportion of uncalled bases (N's) in bin
+    max_uncalled: f64,  // highest uncalled proportion of any base in bin
+    highlighted: bool,  // whether this bin contains highlighted nodes
+    mixed_strand: bool, // whether both forward and reverse steps fall in this bin
+    frequency: f64,     // mean share of displayed paths containing the bin's nodes
+    rank: Option<u32>,  // highest rGFA rank among the bin's nodes
+}
+
+/// Bin the bases of one path's steps: per-bin depth, inversion rate, mean position within
+/// the path and uncalled proportion, before --bin-aggregate, --smooth and --normalize-depth
+pub(crate) fn path_bins(
+    path: &GfaPath,
+    graph: &Graph,
+    bin_width: f64,
+    highlight_nodes: Option<&FxHashSet<u64>>,
+) -> FxHashMap<usize, BinInfo> {
+    let mut bins: FxHashMap<usize, BinInfo> = FxHashMap::default();
+    let mut path_pos: u64 = 0; // Track position within path
+    for step in &path.steps {
+        let seg_id = step.segment_id as usize;
+        if seg_id < graph.segments.len() {
+            let offset = graph.segment_offsets[seg_id];
+            let seg_len = graph.segments[seg_id].sequence_len;
+            let n_count = graph.segments[seg_id].n_count;
+            // Proportion of N's in this segment (for uncalled base coloring)
+            let n_proportion = if seg_len > 0 {
+                n_count as f64 / seg_len as f64
+            } else {
+                0.0
+            };
+            let is_highlighted = highlight_nodes.is_some_and(|hn| hn.contains(&step.segment_id));
+
+            for k in 0..seg_len {
+                let pos = offset + k;
+                let curr_bin = (pos as f64 / bin_width) as usize;
+                let entry = bins.entry(curr_bin).or_default();
+                entry.mean_depth += 1.0;
+                if step.is_reverse {
+                    entry.mean_inv += 1.0;
+                }
+                entry.mean_pos += path_pos as f64;
+                entry.mean_uncalled += n_proportion;
+                entry.max_uncalled = entry.max_uncalled.max(n_proportion);
+                if is_highlighted {
+                    entry.highlighted = true;
+                }
+                path_pos += 1;
+            }
+        }
+    }
+
+    for (_, v) in bins.iter_mut() {
+        if v.mean_depth > 0.0 {
+            v.mean_pos /= v.mean_depth;
+            v.mean_uncalled /= v.mean_depth;
+        }
+        v.mean_inv /= if v.mean_depth > 0.0 {
+            v.mean_depth
+        } else {
+            1.0
+        };
+        v.mixed_strand = v.mean_inv > 0.0 && v.mean_inv < 1.0;
+        v.mean_depth /= bin_width;
+    }
+    bins
+}
+
+/// Draw a line on the buffer (Bresenham's algorithm)
+fn draw_line(
+    buffer: &mut [u8],
+    width: u32,
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+    r: u8,
+    g: u8,
+    b: u8,
+) {
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let mut x = x0;
+    let mut y = y0;
+
+    loop {
+        if x >= 0 && y >= 0 {
+            let idx = ((y as u32 * width + x as u32) * 4) as usize;
+            if idx + 3 < buffer.len() {
+                buffer[idx] = r;
+                buffer[idx + 1] = g;
+                buffer[idx + 2] = b;
+                buffer[idx + 3] = 255;
+            }
+        }
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+/// Render dendrogram recursively, returning the Y coordinate of the node's connection point
+fn render_dendrogram_node(
+    buffer: &mut [u8],
+    width: u32,
+    dendrogram: &Dendrogram,
+    node_idx: usize,
+    n_leaves: usize,
+    x_offset: u32,
+    dendro_width: u32,
+    pix_per_path: u32,
+    leaf_y_positions: &[u32],
+) -> (u32, f64) {
+    // Returns (y_position, height)
+    if node_idx < n_leaves {
+        // Leaf node - return its Y position (center of the path row)
+        let y = leaf_y_positions[node_idx] + pix_per_path / 2;
+        return (y, 0.0);
+    }
+
+    let internal_idx = node_idx - n_leaves;
+    if internal_idx >= dendrogram.nodes.len() {
+        // Safety check: invalid node index
+        return (0, 0.0);
+    }
+    let node = &dendrogram.nodes[internal_idx];
+
+    // Recursively get positions of children
+    let (left_y, _left_h) = render_dendrogram_node(
+        buffer,
+        width,
+        dendrogram,
+        node.left,
+        n_leaves,
+        x_offset,
+        dendro_width,
+        pix_per_path,
+        leaf_y_positions,
+    );
+    let (right_y, _right_h) = render_dendrogram_node(
+        buffer,
+        width,
+        dendrogram,
+        node.right,
+        n_leaves,
+        x_offset,
+        dendro_width,
+        pix_per_path,
+        leaf_y_positions,
+    );
+
+    // Calculate X position based on merge height
+    // X goes from right (leaves at dendro_width) to left (root at 0)
+    let x = if dendrogram.max_height > 0.0 {
+        x_offset + ((1.0 - node.height / dendrogram.max_height) * (dendro_width - 5) as f64) as u32
+    } else {
+        x_offset + dendro_width / 2
+    };
+
+    // Draw horizontal lines from children to this node's X
+    let left_x = if node.left < n_leaves {
+        x_offset + dendro_width - 2 // Leaves are at the right edge
+    } else {
+        let left_internal = node.left - n_leaves;
+        if left_internal < dendrogram.nodes.len() {
+            let left_node = &dendrogram.nodes[left_internal];
+            x_offset
+                + ((1.0 - left_node.height / dendrogram.max_height) * (dendro_width - 5) as f64)
+                    as u32
+        } else {
+            x_offset + dendro_width / 2
+        }
+    };
+    let right_x = if node.right < n_leaves {
+        x_offset + dendro_width - 2
+    } else {
+        let right_internal = node.right - n_leaves;
+        if right_internal < dendrogram.nodes.len() {
+            let right_node = &dendrogram.nodes[right_internal];
+            x_offset
+                + ((1.0 - right_node.height / dendrogram.max_height) * (dendro_width - 5) as f64)
+                    as u32
+        } else {
+            x_offset + dendro_width / 2
+        }
+    };
+
+    // Draw lines (dark grey color)
+    let line_color = (80u8, 80u8, 80u8);
+
+    // Horizontal line from left child to this X
+    draw_line(
+        buffer,
+        width,
+        left_x as i32,
+        left_y as i32,
+        x as i32,
+        left_y as i32,
+        line_color.0,
+        line_color.1,
+        line_color.2,
+    );
+    // Horizontal line from right child to this X
+    draw_line(
+        buffer,
+        width,
+        right_x as i32,
+        right_y as i32,
+        x as i32,
+        right_y as i32,
+        line_color.0,
+        line_color.1,
+        line_color.2,
+    );
+    // Vertical line connecting the two horizontal lines
+    draw_line(
+        buffer,
+        width,
+        x as i32,
+        left_y as i32,
+        x as i32,
+        right_y as i32,
+        line_color.0,
+        line_color.1,
+        line_color.2,
+    );
+
+    // Return the midpoint Y
+    let mid_y = (left_y + right_y) / 2;
+    (mid_y, node.height)
+}
+
+/// Render the full dendrogram for PNG output
+/// leaf_y_positions: pre-computed Y positions for each leaf (indexed by original path index)
+fn render_dendrogram_png(
+    buffer: &mut [u8],
+    width: u32,
+    dendrogram: &Dendrogram,
+    dendro_width: u32,
+    pix_per_path: u32,
+    leaf_y_positions: &[u32],
+) {
+    if dendrogram.nodes.is_empty() || dendrogram.leaf_order.len() <= 1 {
+        return;
+    }
+
+    // Number of leaves from the dendrogram
+    let n_leaves = dendrogram.leaf_order.len();
+
+    // Root is the last internal node
+    let root_idx = n_leaves + dendrogram.nodes.len() - 1;
+
+    render_dendrogram_node(
+        buffer,
+        width,
+        dendrogram,
+        root_idx,
+        n_leaves,
+        0,
+        dendro_width,
+        pix_per_path,
+        leaf_y_positions,
+    );
+}
+
+/// Render dendrogram node recursively for SVG, returning Y position and collecting path elements
+fn render_dendrogram_node_svg(
+    dendrogram: &Dendrogram,
+    node_idx: usize,
+    n_leaves: usize,
+    x_offset: f64,
+    dendro_width: f64,
+    pix_per_path: f64,
+    leaf_y_positions: &[f64],
+    paths: &mut Vec<String>,
+) -> (f64, f64) {
+    // Returns (y_position, height)
+    if node_idx < n_leaves {
+        // Leaf node - return its Y position (center of the path row)
+        let y = leaf_y_positions[node_idx] + pix_per_path / 2.0;
+        return (y, 0.0);
+    }
+
+    let internal_idx = node_idx - n_leaves;
+    if internal_idx >= dendrogram.nodes.len() {
+        return (0.0, 0.0);
+    }
+    let node = &dendrogram.nodes[internal_idx];
+
+    // Recursively get positions of children
+    let (left_y, _) = render_dendrogram_node_svg(
+        dendrogram,
+        node.left,
+        n_leaves,
+        x_offset,
+        dendro_width,
+        pix_per_path,
+        leaf_y_positions,
+        paths,
+    );
+    let (right_y, _) = render_dendrogram_node_svg(
+        dendrogram,
+        node.right,
+        n_leaves,
+        x_offset,
+        dendro_width,
+        pix_per_path,
+        leaf_y_positions,
+        paths,
+    );
+
+    // Calculate X position based on merge height
+    let x = if dendrogram.max_height > 0.0 {
+        x_offset + (1.0 - node.height / dendrogram.max_height) * (dendro_width - 5.0)
+    } else {
+        x_offset + dendro_width / 2.0
+    };
+
+    // Calculate child X positions
+    let left_x = if node.left < n_leaves {
+        x_offset + dendro_width - 2.0
+    } else {
+        let left_internal = node.left - n_leaves;
+        if left_internal < dendrogram.nodes.len() {
+            let left_node = &dendrogram.nodes[left_internal];
+            x_offset + (1.0 - left_node.height / dendrogram.max_height) * (dendro_width - 5.0)
+        } else {
+            x_offset + dendro_width / 2.0
+        }
+    };
+    let right_x = if node.right < n_leaves {
+        x_offset + dendro_width - 2.0
+    } else {
+        let right_internal = node.right - n_leaves;
+        if right_internal < dendrogram.nodes.len() {
+            let right_node = &dendrogram.nodes[right_internal];
+            x_offset + (1.0 - right_node.height / dendrogram.max_height) * (dendro_width - 5.0)
+        } else {
+            x_offset + dendro_width / 2.0
+        }
+    };
+
+    // Add SVG path elements for the lines
+    let line_color = "#505050";
+    // Horizontal line from left child to this X
+    paths.push(format!(
+        r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="1"/>"#,
+        left_x, left_y, x, left_y, line_color
+    ));
+    // Horizontal line from right child to this X
+    paths.push(format!(
+        r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="1"/>"#,
+        right_x, right_y, x, right_y, line_color
+    ));
+    // Vertical line connecting the two horizontal lines
+    paths.push(format!(
+        r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="1"/>"#,
+        x, left_y, x, right_y, line_color
+    ));
+
+    let mid_y = (left_y + right_y) / 2.0;
+    (mid_y, node.height)
+}
+
+/// Render the full dendrogram for SVG output, returns SVG elements as string
+/// leaf_y_positions: pre-computed Y positions for each leaf (indexed by original path index)
+fn render_dendrogram_svg(
+    dendrogram: &Dendrogram,
+    dendro_width: f64,
+    pix_per_path: f64,
+    leaf_y_positions: &[f64],
+) -> String {
+    if dendrogram.nodes.is_empty() || dendrogram.leaf_order.len() <= 1 {
+        return String::new();
+    }
+
+    let n_leaves = dendrogram.leaf_order.len();
+    let root_idx = n_leaves + dendrogram.nodes.len() - 1;
+    let mut paths = Vec::new();
+
+    render_dendrogram_node_svg(
+        dendrogram,
+        root_idx,
+        n_leaves,
+        0.0,
+        dendro_width,
+        pix_per_path,
+        leaf_y_positions,
+        &mut paths,
+    );
+
+    paths.join("\n")
+}
+
+/// Render annotation legend at the top of the image (PNG)
+fn render_annotation_legend_png(
+    buffer: &mut [u8],
+    width: u32,
+    _left_margin: u32,
+    categories: &[String],
+    category_colors: &FxHashMap<String, (u8, u8, u8)>,
+    legend_height: u32,
+    char_size: u32,
+    text_color: (u8, u8, u8),
+) {
+    let swatch_size = 12u32;
+    let swatch_padding = 8u32;
+    let text_padding = 4u32;
+    let item_spacing = 12u32;
+
+    // Calculate available width (reserve space for "+N" indicator)
+    let available_width = width.saturating_sub(swatch_padding * 2 + 50);
+
+    // Calculate width needed for each category
+    let category_widths: Vec<u32> = categories
+        .iter()
+        .map(|cat| {
+            swatch_size + text_padding + (cat.chars().count() as u32 * char_size) + item_spacing
+        })
+        .collect();
+
+    // Determine how many categories fit
+    let mut total_items_width = 0u32;
+    let mut visible_count = 0usize;
+    for w in &category_widths {
+        if total_items_width + w <= available_width {
+            total_items_width += w;
+            visible_count += 1;
+        } else {
+            break;
+        }
+    }
+
+    // If none fit, show at least one truncated
+    if visible_count == 0 && !categories.is_empty() {
+        visible_count = 1;
+        total_items_width = category_widths.first().copied().unwrap_or(0);
+    }
+
+    // Calculate starting x position to center the legend
+    let hidden_count = categories.len().saturating_sub(visible_count);
+    let indicator_width = if hidden_count > 0 {
+        let indicator = format!("+{}", hidden_count);
+        indicator.len() as u32 * char_size + item_spacing
+    } else {
+        0
+    };
+    let total_legend_width = total_items_width + indicator_width;
+    let x_start = (width.saturating_sub(total_legend_width)) / 2;
+
+    let mut x_pos = x_start;
+    let y_center = legend_height / 2;
+    let swatch_y = y_center.saturating_sub(swatch_size / 2);
+
+    for category in categories.iter().take(visible_count) {
+        // Use grey for NA, otherwise look up in category_colors
+        let (r, g, b) = if category == "NA" {
+            NA_COLOR
+        } else {
+            category_colors.get(category).copied().unwrap_or(NA_COLOR)
+        };
+
+        // Draw color swatch
+        for sx in 0..swatch_size {
+            for sy in 0..swatch_size {
+                let px = x_pos + sx;
+                let py = swatch_y + sy;
+                if px < width {
+                    let idx = ((py * width + px) * 4) as usize;
+                    if idx + 3 < buffer.len() {
+                        buffer[idx] = r;
+                        buffer[idx + 1] = g;
+                        buffer[idx + 2] = b;
+                        buffer[idx + 3] = 255;
+                    }
+                }
+            }
+        }
+
+        // Draw category label
+        let text_x = x_pos + swatch_size + text_padding;
+        let text_y = y_center.saturating_sub(char_size / 2);
+        for (i, c) in category.chars().enumerate() {
+            let char_x = text_x + (i as u32) * char_size;
+            if char_x + char_size > width {
+                break;
+            }
+            let char_data = glyph_for_char(c);
+            write_char(
+                buffer,
+                width,
+                char_x,
+                text_y,
+                char_data,
+                char_size,
+                text_color.0,
+                text_color.1,
+                text_color.2,
+            );
+        }
+
+        // Move to next item
+        x_pos += swatch_size
+            + text_padding
+            + (category.chars().count() as u32 * char_size)
+            + item_spacing;
+    }
+
+    // Draw "+N" indicator if there are hidden categories
+    let hidden_count = categories.len().saturating_sub(visible_count);
+    if hidden_count > 0 {
+        let indicator = format!("+{}", hidden_count);
+        let text_y = y_center.saturating_sub(char_size / 2);
+        for (i, c) in indicator.chars().enumerate() {
+            let char_x = x_pos + (i as u32) * char_size;
+            if char_x + char_size <= width {
+                let char_data = glyph_for_char(c);
+                write_char(
+                    buffer, width, char_x, text_y, char_data, char_size, 128, 128, 128,
+                );
+            }
+        }
+    }
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Opening tags wrapping one SVG path row: an --link-template link and an
+/// --svg-interactive group, with ids `{group}-{path_idx}`
+fn svg_row_open(args: &Args, group: &str, path_idx: usize, path_name: &str) -> String {
+    let mut open = String::new();
+    if let Some(ref template) = args.link_template {
+        let url = template.replace("{path}", &percent_encode(path_name));
+        open.push_str(&format!("<a href=\"{}\">\n", escape_xml(&url)));
+    }
+    if args.svg_interactive {
+        open.push_str(&format!("<g id=\"{}-{}\">\n", group, path_idx));
+    }
+    open
+}
+
+/// Wrap everything written to `svg` since `start` in a named top-level group, marked as a
+/// layer for Inkscape and Illustrator; nothing is added when the section drew nothing
+fn wrap_svg_layer(svg: &mut String, start: usize, id: &str) {
+    if svg.len() > start {
+        svg.insert_str(
+            start,
+            &format!(
+                "<g id=\"{}\" inkscape:groupmode=\"layer\" inkscape:label=\"{}\">\n",
+                id, id
+            ),
+        );
+        svg.push_str("</g>\n");
+    }
+}
+
+/// Closing tags matching [`svg_row_open`]
+fn svg_row_close(args: &Args) -> String {
+    let mut close = String::new();
+    if args.svg_interactive {
+        close.push_str("</g>\n");
+    }
+    if args.link_template.is_some() {
+        close.push_str("</a>\n");
+    }
+    close
+}
+
+/// `data-*` attributes of an SVG bin rectangle for --svg-data-attrs, or nothing
+fn svg_bin_attrs(args: &Args, path_name: &str, bin: usize, info: Option<&BinInfo>) -> String {
+    if !args.svg_data_attrs {
+        return String::new();
+    }
+    format!(
+        r#" data-path="{}" data-bin="{}" data-depth="{:.3}""#,
+        escape_xml(path_name),
+        bin,
+        info.map_or(0.0, |info| info.mean_depth)
+    )
+}
+
+/// Escape XML special characters for SVG text
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Render annotation legend at the top of the image (SVG)
+fn render_annotation_legend_svg(
+    categories: &[String],
+    category_colors: &FxHashMap<String, (u8, u8, u8)>,
+    image_width: f64,
+    legend_height: f64,
+    font_size: f64,
+    text_color: (u8, u8, u8),
+) -> String {
+    let mut svg = String::new();
+
+    let swatch_size = 12.0;
+    let text_padding = 4.0;
+    let item_spacing = 16.0;
+
+    // Calculate total legend width for centering
+    let total_legend_width: f64 = categories
+        .iter()
+        .map(|cat| {
+            let text_width = text_columns(cat) as f64 * font_size * 0.6;
+            swatch_size + text_padding + text_width + item_spacing
+        })
+        .sum();
+
+    // Center the legend
+    let x_start = (image_width - total_legend_width).max(0.0) / 2.0;
+
+    let mut x_pos = x_start;
+    let y_center = legend_height / 2.0;
+    let swatch_y = y_center - swatch_size / 2.0;
+
+    for category in categories {
+        // Use grey for NA, otherwise look up in category_colors
+        let (r, g, b) = if category == "NA" {
+            NA_COLOR
+        } else {
+            category_colors.get(category).copied().unwrap_or(NA_COLOR)
+        };
+
+        // Draw color swatch
+        svg.push_str(&format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"/>"#,
+            x_pos, swatch_y, swatch_size, swatch_size, r, g, b
+        ));
+        svg.push('\n');
+
+        // Draw category label
+        let text_x = x_pos + swatch_size + text_padding;
+        let text_y = y_center + font_size / 3.0;
+        svg.push_str(&format!(
+            r#"<text x="{}" y="{}" font-family="'DejaVu Sans Mono', 'Courier New', monospace" font-size="{}" fill="{}">{}</text>"#,
+            text_x, text_y, font_size, svg_rgb(text_color), escape_xml(category)
+        ));
+        svg.push('\n');
+
+        // Estimate text width (approximate: 0.6 * font_size per character)
+        let text_width = text_columns(category) as f64 * font_size * 0.6;
+        x_pos += swatch_size + text_padding + text_width + item_spacing;
+    }
+
+    svg
+}
+
+fn write_char(
+    buffer: &mut [u8],
+    width: u32,
+    base_x: u32,
+    base_y: u32,
+    char_data: &[u8; 8],
+    char_size: u32,
+    r: u8,
+    g: u8,
+    b: u8,
+) {
+    let ratio = char_size / 8;
+    for j in 0..8u32 {
+        let row = char_data[j as usize];
+        let y = base_y + j * ratio;
+        for z in (0..8i32).rev() {
+            if (row >> z) & 1 == 1 {
+                let x = base_x + (7 - z as u32) * ratio;
+                for rx in 0..ratio {
+                    for ry in 0..ratio {
+                        let px = x + rx;
+                        let py = y + ry;
+                        let idx = ((py * width + px) * 4) as usize;
+                        if idx + 3 < buffer.len() {
+                            buffer[idx] = r;
+                            buffer[idx + 1] = g;
+                            buffer[idx + 2] = b;
+                            buffer[idx + 3] = 255;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Split an indicator bar `width` px wide between the colors of the paths sharing its row,
+/// in proportion to how many paths have each color: (x offset, width, color) per stripe
+fn bar_stripes(colors: &[(u8, u8, u8)], width: u32) -> Vec<(u32, u32, (u8, u8, u8))> {
+    let mut counts: Vec<((u8, u8, u8), usize)> = Vec::new();
+    for &color in colors {
+        match counts.iter_mut().find(|(c, _)| *c == color) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((color, 1)),
+        }
+    }
+    let total = colors.len().max(1);
+    let mut stripes = Vec::with_capacity(counts.len());
+    let mut before = 0;
+    for (color, n) in counts {
+        let x0 = (before * width as usize / total) as u32;
+        before += n;
+        let x1 = (before * width as usize / total) as u32;
+        if x1 > x0 {
+            stripes.push((x0, x1 - x0, color));
+        }
+    }
+    stripes
+}
+
+/// Pixel height of one digit of a --cluster-bar-labels label
+const BAR_LABEL_CHAR: u32 = 8;
+
+/// The cluster id to print in the cluster bar of row `path_idx` for --cluster-bar-labels:
+/// only on a cluster's first row, and only when the row fits the digits stacked vertically
+fn cluster_bar_label(cr: &ClusteringResult, path_idx: usize, row_height: u32) -> Option<String> {
+    let cluster_id = cr.cluster_ids[path_idx];
+    let first = path_idx == 0 || cr.cluster_ids[path_idx - 1] != cluster_id;
+    let label = cluster_id.to_string();
+    (first
+        && cr.noise_cluster != Some(cluster_id)
+        && label.len() as u32 * BAR_LABEL_CHAR + 2 <= row_height)
+        .then_some(label)
+}
+
+/// A path row queued for rendering: display index, y offset, whether it draws the group's
+/// name and bars, and the label to draw
+type RowJob = (usize, u32, bool, String);
+
+fn add_path_step(
+    buffer: &mut [u8],
+    width: u32,
+    x: u32,
+    y_start: u32,
+    pix_per_path: u32,
+    r: u8,
+    g: u8,
+    b: u8,
+    no_path_borders: bool,
+    black_border: bool,
+) {
+    let t = y_start;
+    if no_path_borders || pix_per_path < 3 {
+        let s = t + pix_per_path;
+        for y in t..s {
+            let idx = ((y * width + x) * 4) as usize;
+            if idx + 3 < buffer.len() {
+                buffer[idx] = r;
+                buffer[idx + 1] = g;
+                buffer[idx + 2] = b;
+                buffer[idx + 3] = 255;
+            }
+        }
+    } else {
+        let s = t + pix_per_path - 1;
+        for y in t..s {
+            let idx = ((y * width + x) * 4) as usize;
+            if idx + 3 < buffer.len() {
+                buffer[idx] = r;
+                buffer[idx + 1] = g;
+                buffer[idx + 2] = b;
+                buffer[idx + 3] = 255;
+            }
+        }
+        if black_border {
+            let idx = ((s * width + x) * 4) as usize;
+            if idx + 3 < buffer.len() {
+                buffer[idx] = 0;
+                buffer[idx + 1] = 0;
+                buffer[idx + 2] = 0;
+                buffer[idx + 3] = 255;
+            }
+        }
+    }
+}
+
+/// Add a point to the edge visualization area
+fn add_edge_point(
+    buffer: &mut [u8],
+    width: u32,
+    x: u32,
+    y: u32,
+    path_space: u32,
+    rgb: (u8, u8, u8),
+) {
+    let actual_y = y + path_space;
+    let idx = ((actual_y * width + x) * 4) as usize;
+    if idx + 3 < buffer.len() {
+        buffer[idx] = rgb.0;
+        buffer[idx + 1] = rgb.1;
+        buffer[idx + 2] = rgb.2;
+        buffer[idx + 3] = 255;
+    }
+}
+
+/// Limits applied by --preview
+const PREVIEW_MAX_PATHS: usize = 100;
+const PREVIEW_MAX_WIDTH: u32 = 800;
+const PREVIEW_MAX_PATH_HEIGHT: u32 = 10;
+
+/// Options with the image size capped for --preview
+fn preview_args(args: &Args) -> Args {
+    let mut preview = args.clone();
+    preview.width = args.width.min(PREVIEW_MAX_WIDTH);
+    preview.path_height = args.path_height.min(PREVIEW_MAX_PATH_HEIGHT);
+    preview.fit_height = args
+        .fit_height
+        .map(|h| h.min(PREVIEW_MAX_PATHS as u32 * PREVIEW_MAX_PATH_HEIGHT));
+    preview
+}
+
+/// Evenly spaced subset of at most `max` paths, keeping their order
+fn sample_paths(paths: Vec<&GfaPath>, max: usize) -> Vec<&GfaPath> {
+    if paths.len() <= max {
+        return paths;
+    }
+    (0..max).map(|i| paths[i * paths.len() / max]).collect()
+}
+
+/// Suffix appended to the names of paths drawn reverse-complemented
+const FLIP_MARKER: &str = " \u{2190}";
+
+/// The path walked backwards on the opposite strand
+fn reverse_complement(path: &GfaPath) -> GfaPath {
+    GfaPath {
+        name: path.name.clone(),
+        steps: path
+            .steps
+            .iter()
+            .rev()
+            .map(|step| PathStep {
+                segment_id: step.segment_id,
+                is_reverse: !step.is_reverse,
+            })
+            .collect(),
+        piece: path.piece,
+    }
+}
+
+/// Per-bin diversity of the displayed paths: Shannon entropy of the node sets they
+/// traverse in the bin, with "absent" as one more set, divided by its maximum log2(paths)
+/// so 0 means all paths agree and 1 means every path differs
+fn bin_diversity(paths: &[&GfaPath], graph: &Graph, bin_width: f64, num_bins: usize) -> Vec<f64> {
+    if paths.len() < 2 || num_bins == 0 {
+        return vec![0.0; num_bins];
+    }
+    // Hash of each path's sorted node set per covered bin
+    let signatures: Vec<FxHashMap<usize, u64>> = paths
+        .par_iter()
+        .map(|path| {
+            let mut nodes: FxHashMap<usize, Vec<u64>> = FxHashMap::default();
+            for step in &path.steps {
+                let id = step.segment_id as usize;
+                let offset = graph.segment_offsets[id];
+                let len = graph.segments[id].sequence_len.max(1);
+                let first = (offset as f64 / bin_width) as usize;
+                let last = ((offset + len - 1) as f64 / bin_width) as usize;
+                for bin in first.min(num_bins - 1)..=last.min(num_bins - 1) {
+                    nodes.entry(bin).or_default().push(step.segment_id);
+                }
+            }
+            nodes
+                .into_iter()
+                .map(|(bin, mut ids)| {
+                    ids.sort_unstable();
+                    ids.dedup();
+                    let mut hasher = FxHasher::default();
+                    ids.hash(&mut hasher);
+                    (bin, hasher.finish())
+                })
+                .collect()
+        })
+        .collect();
+    let n = paths.len() as f64;
+    (0..num_bins)
+        .into_par_iter()
+        .map(|bin| {
+            let mut counts: FxHashMap<Option<u64>, usize> = FxHashMap::default();
+            for signature in &signatures {
+                *counts.entry(signature.get(&bin).copied()).or_default() += 1;
+            }
+            let entropy: f64 = counts
+                .values()
+                .map(|&c| {
+                    let p = c as f64 / n;
+                    -p * p.log2()
+                })
+                .sum();
+            (entropy / n.log2()).max(0.0)
+        })
+        .collect()
+}
+
+/// Height in pixels of the --diversity-track strip
+const DIVERSITY_TRACK_HEIGHT: u32 = 12;
+
+/// Height in pixels of the --core-track bar
+const CORE_TRACK_HEIGHT: u32 = 4;
+
+/// Bins covered by every one of `paths`
+fn core_bins(paths: &[&GfaPath], graph: &Graph, bin_width: f64, num_bins: usize) -> Vec<bool> {
+    if paths.is_empty() || num_bins == 0 {
+        return vec![false; num_bins];
+    }
+    let mut coverage = vec![0usize; num_bins];
+    let covered: Vec<FxHashSet<usize>> = paths
+        .par_iter()
+        .map(|path| {
+            let mut bins = FxHashSet::default();
+            for step in &path.steps {
+                let id = step.segment_id as usize;
+                let offset = graph.segment_offsets[id];
+                let len = graph.segments[id].sequence_len;
+                if len == 0 {
+                    continue;
+                }
+                let first = (offset as f64 / bin_width) as usize;
+                let last = ((offset + len - 1) as f64 / bin_width) as usize;
+                bins.extend(first.min(num_bins - 1)..=last.min(num_bins - 1));
+            }
+            bins
+        })
+        .collect();
+    for bin in covered.iter().flatten() {
+        coverage[*bin] += 1;
+    }
+    coverage.into_iter().map(|c| c == paths.len()).collect()
+}
+
+/// Base pairs of the displayed paths' segments by how many paths traverse them
+struct CoreStats {
+    paths: usize,
+    /// Segments and bp traversed by every path
+    core: (usize, u64),
+    /// Traversed by more than one path but not all
+    shell: (usize, u64),
+    /// Traversed by a single path
+    cloud: (usize, u64),
+    /// Longest run of consecutive core bins, as a pangenomic `[start, end)` range
+    longest_core: (u64, u64),
+}
+
+/// Number of `paths` traversing each segment, counting each path once
+fn node_frequencies(paths: &[&GfaPath], graph: &Graph) -> Vec<usize> {
+    let mut coverage = vec![0usize; graph.segments.len()];
+    let mut last_path = vec![usize::MAX; graph.segments.len()];
+    for (i, path) in paths.iter().enumerate() {
+        for step in &path.steps {
+            let id = step.segment_id as usize;
+            if last_path[id] != i {
+                last_path[id] = i;
+                coverage[id] += 1;
+            }
+        }
+    }
+    coverage
+}
+
+fn core_stats(paths: &[&GfaPath], graph: &Graph, core: &[bool], bin_width: f64) -> CoreStats {
+    let coverage = node_frequencies(paths, graph);
+    let mut stats = CoreStats {
+        paths: paths.len(),
+        core: (0, 0),
+        shell: (0, 0),
+        cloud: (0, 0),
+        longest_core: (0, 0),
+    };
+    for (id, &count) in coverage.iter().enumerate() {
+        let len = graph.segments[id].sequence_len;
+        if len == 0 {
+            continue;
+        }
+        let class = match count {
+            0 => None,
+            c if c == paths.len() => Some(&mut stats.core),
+            1 => Some(&mut stats.cloud),
+            _ => Some(&mut stats.shell),
+        };
+        if let Some(class) = class {
+            class.0 += 1;
+            class.1 += len;
+        }
+    }
+    let mut best = (0, 0);
+    let mut start = 0;
+    for (bin, &is_core) in core.iter().enumerate() {
+        if !is_core {
+            start = bin + 1;
+        } else if bin + 1 - start > best.1 - best.0 {
+            best = (start, bin + 1);
+        }
+    }
+    let end = (graph.total_length as f64).min(best.1 as f64 * bin_width);
+    stats.longest_core = ((best.0 as f64 * bin_width) as u64, end as u64);
+    stats
+}
+
+/// Log the --core-track statistics and write them next to the image: foo.png -> foo.core.tsv
+fn write_core_tsv(output_path: &Path, stats: &CoreStats) {
+    let total = stats.core.1 + stats.shell.1 + stats.cloud.1;
+    let percent = |bp: u64| 100.0 * bp as f64 / total.max(1) as f64;
+    info!(
+        "Core: {} bp ({:.1}%) shared by all {} paths, accessory: {} bp ({:.1}%), \
+         longest core run: {} bp",
+        stats.core.1,
+        percent(stats.core.1),
+        stats.paths,
+        stats.shell.1 + stats.cloud.1,
+        percent(stats.shell.1 + stats.cloud.1),
+        stats.longest_core.1 - stats.longest_core.0
+    );
+    let tsv_path = output_path.with_extension("core.tsv");
+    let mut content = String::from("class\tsegments\tbp\tpercent\n");
+    for (class, (segments, bp)) in [
+        ("core", stats.core),
+        ("shell", stats.shell),
+        ("cloud", stats.cloud),
+    ] {
+        content.push_str(&format!(
+            "{}\t{}\t{}\t{:.2}\n",
+            class,
+            segments,
+            bp,
+            percent(bp)
+        ));
+    }
+    content.push_str(&format!(
+        "longest_core\t{}-{}\t{}\t{:.2}\n",
+        stats.longest_core.0,
+        stats.longest_core.1,
+        stats.longest_core.1 - stats.longest_core.0,
+        percent(stats.longest_core.1 - stats.longest_core.0)
+    ));
+    match std::fs::write(&tsv_path, content) {
+        Ok(_) => info!("Core statistics saved to {:?}", tsv_path),
+        Err(e) => warning!("could not write core TSV: {}", e),
+    }
+}
+
+/// Height in pixels of the --frequency-spectrum histogram
+const FREQUENCY_SPECTRUM_HEIGHT: u32 = 40;
+
+/// Number of nodes traversed by each number of paths, from 1 to `path_count`
+fn frequency_spectrum(frequencies: &[usize], path_count: usize) -> Vec<usize> {
+    let mut spectrum = vec![0usize; path_count];
+    for &f in frequencies {
+        if f > 0 {
+            spectrum[f.min(path_count) - 1] += 1;
+        }
+    }
+    spectrum
+}
+
+/// Spectrum bars as `(x_start, x_end, height)` across `width` pixels, scaled so the
+/// tallest reaches `height`
+fn spectrum_bars(spectrum: &[usize], width: u32, height: u32) -> Vec<(u32, u32, u32)> {
+    let max = spectrum.iter().copied().max().unwrap_or(0).max(1);
+    let n = spectrum.len().max(1) as u64;
+    spectrum
+        .iter()
+        .enumerate()
+        .map(|(k, &count)| {
+            let x_start = (k as u64 * width as u64 / n) as u32;
+            let x_end = (((k + 1) as u64 * width as u64 / n) as u32).max(x_start + 1);
+            let bar = (count as f64 / max as f64 * height as f64).round() as u32;
+            (x_start, x_end.min(width), bar.max((count > 0) as u32))
+        })
+        .collect()
+}
+
+/// Height of the --frequency-spectrum panel and the panel gap above it, or 0 without it
+fn frequency_spectrum_panel(args: &Args) -> u32 {
+    if args.frequency_spectrum {
+        FREQUENCY_SPECTRUM_HEIGHT + args.panel_gap
+    } else {
+        0
+    }
+}
+
+/// Write every node's name, length and number of traversing `paths` for --node-frequency-out
+fn write_node_frequencies(
+    out: &Path,
+    paths: &[&GfaPath],
+    graph: &Graph,
+) -> Result<(), GfalookError> {
+    let frequencies = node_frequencies(paths, graph);
+    let mut names = vec![""; graph.segments.len()];
+    for (name, &id) in &graph.segment_name_to_id {
+        names[id as usize] = name;
+    }
+    let mut content = String::from("node\tlength\tpaths\n");
+    for (id, &count) in frequencies.iter().enumerate() {
+        content.push_str(&format!(
+            "{}\t{}\t{}\n",
+            names[id], graph.segments[id].sequence_len, count
+        ));
+    }
+    std::fs::write(out, content)
+        .map_err(|e| GfalookError::from_io("failed to write node frequencies", out, e))?;
+    info!(
+        "Node frequencies of {} paths saved to {:?}",
+        paths.len(),
+        out
+    );
+    Ok(())
+}
+
+/// Height of the --core-track bar and the panel gap above it, or 0 without it
+fn core_track_panel(args: &Args) -> u32 {
+    if args.core_track {
+        CORE_TRACK_HEIGHT + args.panel_gap
+    } else {
+        0
+    }
+}
+
+/// X-axis layout shared by PNG and SVG: tick length, label height (the 5x8 font) and the
+/// padding above and below
+const AXIS_TICK_HEIGHT: u32 = 4;
+const AXIS_LABEL_HEIGHT: u32 = 8;
+const AXIS_PADDING: u32 = 2;
+
+/// Height of the x-axis, or 0 without --x-axis
+fn axis_height(args: &Args) -> u32 {
+    if args.x_axis.is_some() {
+        AXIS_TICK_HEIGHT + AXIS_LABEL_HEIGHT + 2 * AXIS_PADDING
+    } else {
+        0
+    }
+}
+
+/// Height of the --diversity-track strip and the panel gap above it, or 0 without it
+fn diversity_track_panel(args: &Args) -> u32 {
+    if args.diversity_track {
+        DIVERSITY_TRACK_HEIGHT + args.panel_gap
+    } else {
+        0
+    }
+}
+
+/// Diversity scaled so the most variable bin is 1, for --shade-variable-regions
+fn relative_diversity(mut diversity: Vec<f64>) -> Vec<f64> {
+    let max = diversity.iter().copied().fold(0.0, f64::max);
+    if max > 0.0 {
+        diversity.iter_mut().for_each(|d| *d /= max);
+    }
+    diversity
+}
+
+/// Background shade of a column with the given relative diversity
+fn diversity_shade(diversity: f64, theme: &Theme) -> (u8, u8, u8) {
+    theme.blend(theme.foreground, 0.2 * diversity)
+}
+
+/// Shade the background of variable columns over the rows `y_start..y_start + height`
+fn shade_columns(
+    buffer: &mut [u8],
+    width: u32,
+    x_offset: u32,
+    y_start: u32,
+    height: u32,
+    diversity: &[f64],
+    theme: &Theme,
+) {
+    for (x, &d) in diversity.iter().enumerate() {
+        if d <= 0.0 {
+            continue;
+        }
+        let color = diversity_shade(d, theme);
+        for y in y_start..y_start + height {
+            add_edge_point(buffer, width, x as u32 + x_offset, y, 0, color);
+        }
+    }
+}
+
+/// SVG rectangles shading variable columns, merging neighbours of the same shade
+fn svg_shade_columns(
+    x_offset: f64,
+    y_start: f64,
+    height: f64,
+    diversity: &[f64],
+    theme: &Theme,
+) -> String {
+    let mut svg = String::new();
+    let mut x = 0;
+    while x < diversity.len() {
+        let color = diversity_shade(diversity[x], theme);
+        let mut end = x + 1;
+        while end < diversity.len() && diversity_shade(diversity[end], theme) == color {
+            end += 1;
+        }
+        if color != theme.background {
+            svg.push_str(&format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                x_offset + x as f64,
+                y_start,
+                end - x,
+                height,
+                svg_rgb(color)
+            ));
+            svg.push('\n');
+        }
+        x = end;
+    }
+    svg
+}
+
+/// Height in pixels of the --jump-arcs band above the path rows
+const JUMP_ARC_HEIGHT: u32 = 40;
+
+/// Height of the --cluster-legend row, or 0 without it
+fn cluster_legend_band(args: &Args) -> u32 {
+    if args.cluster_legend {
+        args.legend_height
+    } else {
+        0
+    }
+}
+
+/// Height of the --downsample label row, above the --jump-arcs band
+fn downsample_band(args: &Args) -> u32 {
+    if args.downsample.is_some_and(|n| n > 1) {
+        args.legend_height
+    } else {
+        0
+    }
+}
+
+/// Text of the --downsample label row
+fn downsample_label(n: u32) -> String {
+    format!("DOWNSAMPLED: 1 of every {} bins shown", n)
+}
+
+/// Legend items in order, with the color of each
+type LegendEntries = (Vec<String>, FxHashMap<String, (u8, u8, u8)>);
+
+/// Entries of the --cluster-legend row: an "id (n=size)" label per cluster and its color
+fn cluster_legend_entries(cr: &ClusteringResult, args: &Args, theme: &Theme) -> LegendEntries {
+    let mut labels = Vec::with_capacity(cr.num_clusters);
+    let mut colors = FxHashMap::default();
+    for (cluster_id, size) in cr.cluster_sizes.iter().enumerate() {
+        let label = format!("{} (n={})", cr.cluster_label(cluster_id), size);
+        colors.insert(label.clone(), cr.cluster_color(cluster_id, args, theme));
+        labels.push(label);
+    }
+    (labels, colors)
+}
+
+/// Height of the --jump-arcs band, or 0 without it
+fn jump_arc_band(args: &Args) -> u32 {
+    if args.jump_arcs.is_some() {
+        JUMP_ARC_HEIGHT
+    } else {
+        0
+    }
+}
+
+/// Arcs for --jump-arcs as (path index, start x, end x, peak height) in pixels from the
+/// band's left edge and baseline; wider jumps rise higher
+fn jump_arcs(
+    display_paths: &[&GfaPath],
+    graph: &Graph,
+    max_jump: u64,
+    bin_width: f64,
+    viz_width: u32,
+) -> Vec<(usize, f64, f64, f64)> {
+    let mut arcs = Vec::new();
+    for (path_idx, path) in display_paths.iter().enumerate() {
+        for (_, from, to) in layout_jumps(path, graph, max_jump) {
+            let (a, b) = (
+                from.min(to) as f64 / bin_width,
+                from.max(to) as f64 / bin_width,
+            );
+            let rise = ((b - a) / viz_width as f64).sqrt().clamp(0.1, 1.0);
+            arcs.push((path_idx, a, b, rise * (JUMP_ARC_HEIGHT - 2) as f64));
+        }
+    }
+    arcs
+}
+
+/// Suffix labeling a piece of a path split by --split-discontiguous
+fn piece_label((index, count): (usize, usize)) -> String {
+    format!(" [{}/{}]", index + 1, count)
+}
+
+/// Jumps of more than `max_jump` bp in the linear layout between consecutive steps of
+/// `path`: the index of the step after the jump and the layout positions left and entered
+fn layout_jumps(path: &GfaPath, graph: &Graph, max_jump: u64) -> Vec<(usize, u64, u64)> {
+    // Layout position where a step is left, and where it is entered
+    let exit = |step: &PathStep| {
+        let offset = graph.segment_offsets[step.segment_id as usize];
+        if step.is_reverse {
+            offset
+        } else {
+            offset + graph.segments[step.segment_id as usize].sequence_len
+        }
+    };
+    let entry = |step: &PathStep| {
+        let offset = graph.segment_offsets[step.segment_id as usize];
+        if step.is_reverse {
+            offset + graph.segments[step.segment_id as usize].sequence_len
+        } else {
+            offset
+        }
+    };
+    path.steps
+        .windows(2)
+        .enumerate()
+        .map(|(i, w)| (i + 1, exit(&w[0]), entry(&w[1])))
+        .filter(|&(_, from, to)| from.abs_diff(to) > max_jump)
+        .collect()
+}
+
+/// The pieces of `path` between steps more than `max_jump` bp apart in the linear
+/// layout, or nothing when the path is contiguous
+fn split_at_jumps(path: &GfaPath, graph: &Graph, max_jump: u64) -> Vec<GfaPath> {
+    let jumps = layout_jumps(path, graph, max_jump);
+    if jumps.is_empty() {
+        return Vec::new();
+    }
+    let mut pieces: Vec<Vec<PathStep>> = Vec::new();
+    let mut start = 0;
+    for end in jumps.iter().map(|&(i, _, _)| i).chain([path.steps.len()]) {
+        pieces.push(path.steps[start..end].to_vec());
+        start = end;
+    }
+    let count = pieces.len();
+    pieces
+        .into_iter()
+        .enumerate()
+        .map(|(index, steps)| GfaPath {
+            name: path.name.clone(),
+            steps,
+            piece: Some((index, count)),
+        })
+        .collect()
+}
+
+/// The displayed paths, each replaced by its pieces from `pieces` if it was split
+fn apply_splits<'a>(display_paths: &[&'a GfaPath], pieces: &'a [GfaPath]) -> Vec<&'a GfaPath> {
+    let mut by_name: FxHashMap<&str, Vec<&GfaPath>> = FxHashMap::default();
+    for piece in pieces {
+        by_name.entry(piece.name.as_str()).or_default().push(piece);
+    }
+    display_paths
+        .iter()
+        .flat_map(|path| match by_name.get(path.name.as_str()) {
+            Some(split) => split.clone(),
+            None => vec![*path],
+        })
+        .collect()
+}
+
+/// One path per contig whose displayed subrange paths (`name:start-end`) number two or
+/// more, with their steps concatenated in start order and named after the joint range
+fn join_subranges(display_paths: &[&GfaPath]) -> Vec<(GfaPath, Vec<String>)> {
+    let mut by_base: FxHashMap<&str, Vec<&GfaPath>> = FxHashMap::default();
+    for path in display_paths {
+        let base = strip_subpath_range(&path.name);
+        if base.len() < path.name.len() {
+            by_base.entry(base).or_default().push(path);
+        }
+    }
+    let mut joined: Vec<(GfaPath, Vec<String>)> = by_base
+        .into_iter()
+        .filter(|(_, parts)| parts.len() > 1)
+        .map(|(base, mut parts)| {
+            parts.sort_by_key(|p| parse_subpath_start(&p.name));
+            let end = parts
+                .iter()
+                .filter_map(|p| p.name.rsplit('-').next()?.parse::<u64>().ok())
+                .max()
+                .unwrap_or(0);
+            let path = GfaPath {
+                name: format!("{}:{}-{}", base, parse_subpath_start(&parts[0].name), end),
+                steps: parts.iter().flat_map(|p| p.steps.iter().cloned()).collect(),
+                piece: None,
+            };
+            (path, parts.iter().map(|p| p.name.clone()).collect())
+        })
+        .collect();
+    joined.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+    joined
+}
+
+/// The displayed paths with each group of joined subranges replaced, at the position of its
+/// first displayed part, by the joined path
+fn apply_joins<'a>(
+    display_paths: &[&'a GfaPath],
+    joined: &'a [(GfaPath, Vec<String>)],
+) -> Vec<&'a GfaPath> {
+    let mut part_of: FxHashMap<&str, usize> = FxHashMap::default();
+    for (i, (_, parts)) in joined.iter().enumerate() {
+        part_of.extend(parts.iter().map(|name| (name.as_str(), i)));
+    }
+    let mut placed = vec![false; joined.len()];
+    display_paths
+        .iter()
+        .filter_map(|path| match part_of.get(path.name.as_str()) {
+            Some(&i) if placed[i] => None,
+            Some(&i) => {
+                placed[i] = true;
+                Some(&joined[i].0)
+            }
+            None => Some(*path),
+        })
+        .collect()
+}
+
+/// Names of the paths to draw reverse-complemented: those listed with --flip-paths and
+/// the displayed paths that traverse most (by bp) of the nodes they share with the
+/// --orient-paths-by reference in the opposite orientation
+fn paths_to_flip(
+    args: &Args,
+    graph: &Graph,
+    display_paths: &[&GfaPath],
+    path_resolver: &PathNameResolver,
+) -> Result<FxHashSet<String>, GfalookError> {
+    let mut flip: FxHashSet<String> = FxHashSet::default();
+    if let Some(ref file) = args.flip_paths {
+        let names = load_paths_to_display(file)
+            .map_err(|e| GfalookError::from_io("failed to read paths to flip", file, e))?;
+        flip.extend(names.iter().map(|name| path_resolver.canonical(name)));
+    }
+    let Some(reference) = args.orient_paths_by.as_ref().and_then(|name| {
+        let name = path_resolver.canonical(name);
+        graph.paths.iter().find(|p| p.name == name)
+    }) else {
+        return Ok(flip);
+    };
+    // Majority orientation of each reference node; nodes it visits equally often both
+    // ways say nothing about the orientation
+    let mut balance: FxHashMap<u64, i64> = FxHashMap::default();
+    for step in &reference.steps {
+        *balance.entry(step.segment_id).or_default() += if step.is_reverse { -1 } else { 1 };
+    }
+    let oriented = display_paths
+        .par_iter()
+        .filter(|path| path.name != reference.name)
+        .filter(|path| {
+            let (mut same, mut opposite) = (0u64, 0u64);
+            for step in &path.steps {
+                let Some(&b) = balance.get(&step.segment_id) else {
+                    continue;
+                };
+                if b == 0 {
+                    continue;
+                }
+                let len = graph.segments[step.segment_id as usize].sequence_len;
+                if step.is_reverse == (b < 0) {
+                    same += len;
+                } else {
+                    opposite += len;
+                }
+            }
+            opposite > same
+        })
+        .map(|path| path.name.clone())
+        .collect::<Vec<_>>();
+    flip.extend(oriented);
+    Ok(flip)
+}
+
+/// The displayed paths, each replaced by its reverse complement from `flipped` if there is one
+fn apply_flips<'a>(display_paths: &[&'a GfaPath], flipped: &'a [GfaPath]) -> Vec<&'a GfaPath> {
+    let by_name: FxHashMap<&str, &GfaPath> = flipped.iter().map(|p| (p.name.as_str(), p)).collect();
+    display_paths
+        .iter()
+        .map(|path| by_name.get(path.name.as_str()).copied().unwrap_or(path))
+        .collect()
+}
+
+/// Row height that makes `rows` path rows plus `reserved` pixels of other panels
+/// come out at roughly `target_height` pixels (never less than 1 pixel per row)
+fn fit_path_height(target_height: u32, rows: u32, reserved: u32) -> u32 {
+    (target_height.saturating_sub(reserved) / rows.max(1)).max(1)
+}
+
+/// How --normalize-depth rescales mean depth before the palette lookup
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum DepthNormalization {
+    /// Absolute depth: copies of the bin's bases in the path
+    None,
+    /// Divide by the path's mean depth over the bins it covers
+    PerPath,
+    /// Divide by the mean depth of all displayed paths covering the bin
+    PerBin,
+    /// The path's z-score, shifted so its mean sits at depth 1 with one palette step per standard deviation
+    Zscore,
+}
+
+/// Apply --bin-aggregate, --smooth and --normalize-depth to a path's bins from [`path_bins`]
+fn adjust_bins(
+    args: &Args,
+    bins: &mut FxHashMap<usize, BinInfo>,
+    bin_means: Option<&FxHashMap<usize, f64>>,
+) {
+    aggregate_bins(args.bin_aggregate, bins);
+    if let Some(window) = args.smooth {
+        smooth_bins(bins, window, args.smooth_median);
+    }
+    normalize_depths(args.normalize_depth, bins, bin_means);
+}
+
+/// Mean depth per bin over the paths that cover it, for --normalize-depth per-bin; each
+/// path's bins come from `raw_bins`, before any adjustment
+fn mean_bin_depths(
+    paths: &[&GfaPath],
+    raw_bins: impl Fn(&GfaPath) -> FxHashMap<usize, BinInfo>,
+) -> FxHashMap<usize, f64> {
+    let mut totals: FxHashMap<usize, (f64, u32)> = FxHashMap::default();
+    for path in paths {
+        for (bin, info) in raw_bins(path) {
+            let total = totals.entry(bin).or_default();
+            total.0 += info.mean_depth;
+            total.1 += 1;
+        }
+    }
+    totals
+        .into_iter()
+        .map(|(bin, (sum, count))| (bin, sum / count as f64))
+        .collect()
+}
+
+/// What a row of --compressed-mode shows per bin
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum CompressedStat {
+    /// Step depth: copies of the bin's bases summed over all displayed path traversals
+    Depth,
+    /// Fraction of the displayed paths that cover the bin at least once
+    Coverage,
+    /// Fraction of the bin's step bases traversed in reverse
+    Inversion,
+    /// Fraction of the bin's step bases that are uncalled (N)
+    Uncalled,
+}
+
+impl CompressedStat {
+    /// Row label of an extra --compressed-rows row
+    fn label(self) -> &'static str {
+        match self {
+            CompressedStat::Depth => "DEPTH",
+            CompressedStat::Coverage => "COVERAGE",
+            CompressedStat::Inversion => "INVERSION",
+            CompressedStat::Uncalled => "UNCALLED",
+        }
+    }
+}
+
+/// Per-bin values of the compressed row; bins no path covers are absent
+fn compressed_bins(
+    paths: &[&GfaPath],
+    graph: &Graph,
+    bin_width: f64,
+    stat: CompressedStat,
+) -> FxHashMap<usize, f64> {
+    let mut depth: FxHashMap<usize, f64> = FxHashMap::default();
+    let mut covering: FxHashMap<usize, u32> = FxHashMap::default();
+    let mut reverse: FxHashMap<usize, f64> = FxHashMap::default();
+    let mut uncalled: FxHashMap<usize, f64> = FxHashMap::default();
+    for path in paths {
+        let mut bins: FxHashSet<usize> = FxHashSet::default();
+        for step in &path.steps {
+            let seg_id = step.segment_id as usize;
+            if seg_id >= graph.segments.len() {
+                continue;
+            }
+            let offset = graph.segment_offsets[seg_id];
+            let segment = &graph.segments[seg_id];
+            let n_proportion = if segment.sequence_len > 0 {
+                segment.n_count as f64 / segment.sequence_len as f64
+            } else {
+                0.0
+            };
+            for pos in offset..offset + segment.sequence_len {
+                let bin = (pos as f64 / bin_width) as usize;
+                *depth.entry(bin).or_default() += 1.0;
+                if step.is_reverse {
+                    *reverse.entry(bin).or_default() += 1.0;
+                }
+                *uncalled.entry(bin).or_default() += n_proportion;
+                bins.insert(bin);
+            }
+        }
+        for bin in bins {
+            *covering.entry(bin).or_default() += 1;
+        }
+    }
+    match stat {
+        CompressedStat::Depth => depth
+            .into_iter()
+            .map(|(bin, bp)| (bin, bp / bin_width))
+            .collect(),
+        CompressedStat::Coverage => {
+            let num_paths = paths.len().max(1) as f64;
+            covering
+                .into_iter()
+                .map(|(bin, count)| (bin, count as f64 / num_paths))
+                .collect()
+        }
+        CompressedStat::Inversion => depth
+            .into_iter()
+            .map(|(bin, bp)| (bin, reverse.get(&bin).copied().unwrap_or(0.0) / bp))
+            .collect(),
+        CompressedStat::Uncalled => depth
+            .into_iter()
+            .map(|(bin, bp)| (bin, uncalled.get(&bin).copied().unwrap_or(0.0) / bp))
+            .collect(),
+    }
+}
+
+/// Color of a compressed-row bin: depth uses the depth cuts, coverage spreads the
+/// fraction evenly over the palette, and inversion and uncalled fractions use the
+/// black-to-red and black-to-green gradients of -z and -N
+fn compressed_bin_color(
+    value: f64,
+    stat: CompressedStat,
+    no_grey_depth: bool,
+    palette: &[(u8, u8, u8)],
+) -> (u8, u8, u8) {
+    match stat {
+        CompressedStat::Depth => get_depth_color(value, no_grey_depth, Some(palette)),
+        CompressedStat::Coverage if palette.is_empty() => (128, 128, 128),
+        CompressedStat::Coverage => {
+            let n = palette.len();
+            palette[((value * n as f64).ceil() as usize).clamp(1, n) - 1]
+        }
+        CompressedStat::Inversion => ((value * 255.0).min(255.0) as u8, 0, 0),
+        CompressedStat::Uncalled => (0, (value * 255.0).min(255.0) as u8, 0),
+    }
+}
+
+/// What --fade-by weighs rows by
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum FadeBy {
+    /// Members in the row's cluster
+    ClusterSize,
+    /// Displayed paths in the row's annotation category
+    AnnotationCount,
+}
+
+/// Opacity of the rarest rows under --fade-by; the most common are drawn opaque
+const MIN_FADE_OPACITY: f64 = 0.25;
+
+/// Opacity per path name for --fade-by, in proportion to the size of the path's cluster
+/// or annotation category relative to the largest one
+fn row_fades(
+    args: &Args,
+    display_paths: &[&GfaPath],
+    cluster_result: Option<&ClusteringResult>,
+    annotations: Option<&AnnotationData>,
+) -> FxHashMap<String, f64> {
+    let counts: Vec<usize> = match (args.fade_by, cluster_result, annotations) {
+        (Some(FadeBy::ClusterSize), Some(cr), _) => display_paths
+            .iter()
+            .enumerate()
+            .map(|(i, _)| {
+                cr.cluster_ids
+                    .get(i)
+                    .and_then(|&c| cr.cluster_sizes.get(c))
+                    .copied()
+                    .unwrap_or(1)
+            })
+            .collect(),
+        (Some(FadeBy::AnnotationCount), _, Some(ann)) => {
+            let mut per_category: FxHashMap<&str, usize> = FxHashMap::default();
+            for path in display_paths {
+                *per_category
+                    .entry(ann.get_annotation(&path.name))
+                    .or_default() += 1;
+            }
+            display_paths
+                .iter()
+                .map(|p| per_category[ann.get_annotation(&p.name)])
+                .collect()
+        }
+        _ => return FxHashMap::default(),
+    };
+    let largest = counts.iter().copied().max().unwrap_or(1).max(1) as f64;
+    display_paths
+        .iter()
+        .zip(counts)
+        .map(|(path, count)| {
+            let opacity = MIN_FADE_OPACITY + (1.0 - MIN_FADE_OPACITY) * count as f64 / largest;
+            (path.name.clone(), opacity)
+        })
+        .collect()
+}
+
+/// How --gap-style marks the columns a path skips inside its own extent
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum GapStyle {
+    /// Leave them as background
+    None,
+    /// Fill them with light grey
+    Grey,
+    /// Fill them with a faint diagonal hatch
+    Hatch,
+}
+
+/// SVG pattern referenced by `--gap-style hatch` rectangles
+const SVG_GAP_HATCH_ID: &str = "gap-hatch";
+
+/// Column runs `[start, end)` between a path's first and last bin that it does not cover
+fn gap_runs<'a>(bins: impl Iterator<Item = &'a usize>, viz_width: u32) -> Vec<(u32, u32)> {
+    let mut covered = vec![false; viz_width as usize];
+    for &bin in bins {
+        covered[bin.min(viz_width as usize - 1)] = true;
+    }
+    let (Some(first), Some(last)) = (
+        covered.iter().position(|&c| c),
+        covered.iter().rposition(|&c| c),
+    ) else {
+        return Vec::new();
+    };
+    let mut runs = Vec::new();
+    let mut x = first;
+    while x < last {
+        if covered[x] {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while !covered[x] {
+            x += 1;
+        }
+        runs.push((start as u32, x as u32));
+    }
+    runs
+}
+
+/// Paint --gap-style runs into one row of the PNG buffer
+fn draw_gap_runs(
+    buffer: &mut [u8],
+    width: u32,
+    x_offset: u32,
+    y_start: u32,
+    height: u32,
+    runs: &[(u32, u32)],
+    style: GapStyle,
+    theme: &Theme,
+) {
+    let (r, g, b) = match style {
+        GapStyle::None => return,
+        GapStyle::Grey => theme.muted(235),
+        GapStyle::Hatch => theme.muted(190),
+    };
+    for &(start, end) in runs {
+        for y in y_start..y_start + height {
+            for x in start..end {
+                if style == GapStyle::Hatch && (x + y) % 4 != 0 {
+                    continue;
+                }
+                let idx = ((y * width + x + x_offset) * 4) as usize;
+                if idx + 3 < buffer.len() {
+                    buffer[idx..idx + 3].copy_from_slice(&[r, g, b]);
+                }
+            }
+        }
+    }
+}
+
+/// SVG rectangles for --gap-style runs of one row
+fn svg_gap_runs(
+    x_offset: f64,
+    y_start: f64,
+    height: f64,
+    runs: &[(u32, u32)],
+    style: GapStyle,
+    theme: &Theme,
+) -> String {
+    let fill = match style {
+        GapStyle::None => return String::new(),
+        GapStyle::Grey => svg_rgb(theme.muted(235)),
+        GapStyle::Hatch => format!("url(#{})", SVG_GAP_HATCH_ID),
+    };
+    runs.iter()
+        .map(|&(start, end)| {
+            format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                x_offset + start as f64,
+                y_start,
+                end - start,
+                height,
+                fill
+            )
+        })
+        .collect()
+}
+
+/// Outline color of --flag-mixed-bins runs, distinct from the red/blue/black strand colors
+const MIXED_BIN_COLOR: (u8, u8, u8) = (255, 176, 0);
+
+/// Runs of consecutive bins whose steps mix orientations, as `[start, end)` bin ranges
+fn mixed_runs(bins: &FxHashMap<usize, BinInfo>, viz_width: u32) -> Vec<(u32, u32)> {
+    let mut mixed: Vec<u32> = bins
+        .iter()
+        .filter(|(_, b)| b.mixed_strand)
+        .map(|(&bin, _)| (bin as u32).min(viz_width - 1))
+        .collect();
+    mixed.sort_unstable();
+    mixed.dedup();
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for bin in mixed {
+        match runs.last_mut() {
+            Some(run) if run.1 == bin => run.1 = bin + 1,
+            _ => runs.push((bin, bin + 1)),
+        }
+    }
+    runs
+}
+
+/// Outline --flag-mixed-bins runs in one row of the PNG buffer
+fn draw_mixed_runs(
+    buffer: &mut [u8],
+    width: u32,
+    x_offset: u32,
+    y_start: u32,
+    height: u32,
+    runs: &[(u32, u32)],
+) {
+    let (r, g, b) = MIXED_BIN_COLOR;
+    let y_end = y_start + height.max(1) - 1;
+    let mut paint = |x: u32, y: u32| {
+        let idx = ((y * width + x + x_offset) * 4) as usize;
+        if idx + 3 < buffer.len() {
+            buffer[idx..idx + 3].copy_from_slice(&[r, g, b]);
+        }
+    };
+    for &(start, end) in runs {
+        for x in start..end {
+            paint(x, y_start);
+            paint(x, y_end);
+        }
+        for y in y_start..=y_end {
+            paint(start, y);
+            paint(end - 1, y);
+        }
+    }
+}
+
+/// Parse `--row-axes every=N` (or just `N`)
+fn parse_row_axes(value: &str) -> Result<u32, String> {
+    let n = value.strip_prefix("every=").unwrap_or(value);
+    match n.parse::<u32>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("expected every=N with N >= 1, got '{}'", value)),
+    }
+}
+
+/// Base pairs between --row-axes ticks: a 1, 2 or 5 times power of ten giving about
+/// `ticks` intervals along the longest path, so every ruler shares one scale
+fn row_axis_step(max_path_length: u64, ticks: u32) -> u64 {
+    let target = (max_path_length / ticks.max(1) as u64).max(1);
+    let mut magnitude = 1u64;
+    while magnitude * 10 <= target {
+        magnitude *= 10;
+    }
+    [1, 2, 5, 10]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|&step| step >= target)
+        .unwrap_or(10 * magnitude)
+}
+
+/// A path's ruler: its first and last bin, and the bins where its position first reaches
+/// each multiple of `step`
+fn row_axis(bins: &FxHashMap<usize, BinInfo>, step: u64, viz_width: u32) -> (u32, u32, Vec<u32>) {
+    let mut sorted: Vec<(u32, u64)> = bins
+        .iter()
+        .map(|(&bin, b)| ((bin as u32).min(viz_width - 1), b.mean_pos as u64 / step))
+        .collect();
+    sorted.sort_unstable();
+    let first = sorted.first().map_or(0, |&(bin, _)| bin);
+    let last = sorted.last().map_or(0, |&(bin, _)| bin);
+    // One tick per multiple, at its first bin, so loops and noisy means don't double it
+    let mut seen: FxHashSet<u64> = FxHashSet::default();
+    let ticks = sorted
+        .iter()
+        .filter(|&&(_, multiple)| seen.insert(multiple))
+        .map(|&(bin, _)| bin)
+        .collect();
+    (first, last, ticks)
+}
+
+/// Paint a --row-axes ruler along the bottom of one row of the PNG buffer
+fn draw_row_axis(
+    buffer: &mut [u8],
+    width: u32,
+    x_offset: u32,
+    y_start: u32,
+    height: u32,
+    (first, last, ticks): &(u32, u32, Vec<u32>),
+    color: (u8, u8, u8),
+) {
+    let y_end = y_start + height.max(1) - 1;
+    let tick_height = (height / 3).clamp(1, 4);
+    let mut paint = |x: u32, y: u32| {
+        let idx = ((y * width + x + x_offset) * 4) as usize;
+        if idx + 3 < buffer.len() {
+            buffer[idx..idx + 3].copy_from_slice(&[color.0, color.1, color.2]);
+        }
+    };
+    for x in *first..=*last {
+        paint(x, y_end);
+    }
+    for &x in ticks {
+        for y in y_end + 1 - tick_height..=y_end {
+            paint(x, y);
+        }
+    }
+}
+
+/// SVG for a --row-axes ruler along the bottom of one row
+fn svg_row_axis(
+    x_offset: f64,
+    y_start: f64,
+    height: f64,
+    (first, last, ticks): &(u32, u32, Vec<u32>),
+    color: &str,
+) -> String {
+    let y = y_start + height - 0.5;
+    let tick_height = (height / 3.0).clamp(1.0, 4.0);
+    let mut d = format!(
+        "M{} {}H{}",
+        x_offset + *first as f64,
+        y,
+        x_offset + *last as f64 + 1.0
+    );
+    for &x in ticks {
+        d.push_str(&format!(
+            "M{} {}v{}",
+            x_offset + x as f64 + 0.5,
+            y,
+            -tick_height
+        ));
+    }
+    format!(
+        "<path class=\"row-axis\" d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1\"/>\n",
+        d, color
+    )
+}
+
+/// Whether a row's bins are colored by strand, so mixed bins show an average
+fn strand_colored(args: &Args, name: &str) -> bool {
+    args.color_by_mean_inversion_rate
+        || (args.show_strand
+            && args
+                .alignment_prefix
+                .as_ref()
+                .is_none_or(|prefix| name.starts_with(prefix)))
+}
+
+/// Point at --flag-mixed-bins when strand colors averaged away mixed orientations
+fn warn_mixed_bins(args: &Args, count: usize) {
+    if count > 0 && !args.flag_mixed_bins {
+        warning!(
+            "{} bins mix forward and reverse steps and show their average; \
+             --flag-mixed-bins outlines them, a smaller --bin-width resolves them",
+            count
+        );
+    }
+}
+
+/// SVG outlines for --flag-mixed-bins runs of one row
+fn svg_mixed_runs(x_offset: f64, y_start: f64, height: f64, runs: &[(u32, u32)]) -> String {
+    runs.iter()
+        .map(|&(start, end)| {
+            format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1\"/>\n",
+                x_offset + start as f64,
+                y_start,
+                end - start,
+                height,
+                svg_rgb(MIXED_BIN_COLOR)
+            )
+        })
+        .collect()
+}
+
+/// How --bin-aggregate summarizes the inversion and uncalled statistics of a bin
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum BinAggregate {
+    /// Fraction of the bin's bases
+    Mean,
+    /// Value of the most affected base
+    Max,
+    /// 1 when any base in the bin is affected, else 0
+    Any,
+}
+
+/// Apply --bin-aggregate to one path's normalized bins; inversion is per base 0 or 1,
+/// so its maximum and `any` coincide
+fn aggregate_bins(mode: BinAggregate, bins: &mut FxHashMap<usize, BinInfo>) {
+    if mode == BinAggregate::Mean {
+        return;
+    }
+    for b in bins.values_mut() {
+        b.mean_inv = if b.mean_inv > 0.0 { 1.0 } else { 0.0 };
+        b.mean_uncalled = match mode {
+            BinAggregate::Max => b.max_uncalled,
+            _ if b.max_uncalled > 0.0 => 1.0,
+            _ => 0.0,
+        };
+    }
+}
+
+/// Replace each covered bin's depth, inversion and uncalled statistics with their mean
+/// (or median) over the covered bins within a window of `window` bins centered on it
+fn smooth_bins(bins: &mut FxHashMap<usize, BinInfo>, window: usize, median: bool) {
+    if window <= 1 {
+        return;
+    }
+    let mut keys: Vec<usize> = bins.keys().copied().collect();
+    keys.sort_unstable();
+    let values: Vec<[f64; 3]> = keys
+        .iter()
+        .map(|k| {
+            let b = &bins[k];
+            [b.mean_depth, b.mean_inv, b.mean_uncalled]
+        })
+        .collect();
+    let half = window / 2;
+    let (mut lo, mut hi) = (0, 0);
+    for (i, &key) in keys.iter().enumerate() {
+        while keys[lo] + half < key {
+            lo += 1;
+        }
+        while hi < keys.len() && keys[hi] <= key + (window - 1 - half) {
+            hi += 1;
+        }
+        let neighbors = &values[lo..hi];
+        let stat = |field: usize| {
+            if median {
+                let mut v: Vec<f64> = neighbors.iter().map(|n| n[field]).collect();
+                v.sort_by(|a, b| a.total_cmp(b));
+                v[v.len() / 2]
+            } else {
+                neighbors.iter().map(|n| n[field]).sum::<f64>() / neighbors.len() as f64
+            }
+        };
+        let b = bins.get_mut(&keys[i]).unwrap();
+        b.mean_depth = stat(0);
+        b.mean_inv = stat(1);
+        b.mean_uncalled = stat(2);
+    }
+}
+
+/// Core, shell and unique colors for --color-by-frequency-class (ColorBrewer Dark2)
+const FREQUENCY_CLASS_COLORS: [(u8, u8, u8); 3] = [(27, 158, 119), (217, 95, 2), (117, 112, 179)];
+
+/// Set each bin's `frequency` to the bp-weighted mean share of the displayed paths that
+/// traverse the nodes this path has there; `node_frequency` holds those shares per segment
+fn assign_frequencies(
+    bins: &mut FxHashMap<usize, BinInfo>,
+    path: &GfaPath,
+    graph: &Graph,
+    bin_width: f64,
+    node_frequency: Option<&[f64]>,
+) {
+    let Some(node_frequency) = node_frequency else {
+        return;
+    };
+    let mut sums: FxHashMap<usize, (f64, f64)> = FxHashMap::default();
+    for step in &path.steps {
+        let id = step.segment_id as usize;
+        let (offset, len) = (graph.segment_offsets[id], graph.segments[id].sequence_len);
+        if len == 0 {
+            continue;
+        }
+        let first = (offset as f64 / bin_width) as usize;
+        let last = ((offset + len - 1) as f64 / bin_width) as usize;
+        for bin in first..=last {
+            let bin_start = (bin as f64 * bin_width).max(offset as f64);
+            let bin_end = ((bin + 1) as f64 * bin_width).min((offset + len) as f64);
+            let bp = (bin_end - bin_start).max(0.0);
+            let entry = sums.entry(bin).or_default();
+            entry.0 += bp * node_frequency[id];
+            entry.1 += bp;
+        }
+    }
+    for (bin, info) in bins.iter_mut() {
+        if let Some(&(sum, bp)) = sums.get(bin) {
+            info.frequency = if bp > 0.0 { sum / bp } else { 0.0 };
+        }
+    }
+}
+
+/// Color of a bin's frequency class under --color-by-frequency-class
+fn frequency_class_color(frequency: f64, path_count: usize, args: &Args) -> (u8, u8, u8) {
+    let [core, shell, unique] = FREQUENCY_CLASS_COLORS;
+    // Small tolerance so a bin of only core nodes reaches a fraction of exactly 1
+    if frequency >= args.core_min_fraction - 1e-9 {
+        core
+    } else if frequency * path_count as f64 <= args.unique_max_paths as f64 + 1e-9 {
+        unique
+    } else {
+        shell
+    }
+}
+
+/// Rank 1, 2, 3 and 4+ colors for --color-by-rank (ColorBrewer Set1)
+const RANK_COLORS: [(u8, u8, u8); 4] =
+    [(228, 26, 28), (55, 126, 184), (77, 175, 74), (152, 78, 163)];
+
+/// Set each bin's `rank` to the highest rGFA rank among the nodes this path has there
+fn assign_ranks(
+    bins: &mut FxHashMap<usize, BinInfo>,
+    path: &GfaPath,
+    graph: &Graph,
+    bin_width: f64,
+) {
+    for step in &path.steps {
+        let id = step.segment_id as usize;
+        let (offset, len) = (graph.segment_offsets[id], graph.segments[id].sequence_len);
+        let Some(rank) = graph.segments[id].rank else {
+            continue;
+        };
+        if len == 0 {
+            continue;
+        }
+        let first = (offset as f64 / bin_width) as usize;
+        let last = ((offset + len - 1) as f64 / bin_width) as usize;
+        for bin in first..=last {
+            if let Some(info) = bins.get_mut(&bin) {
+                info.rank = info.rank.max(Some(rank));
+            }
+        }
+    }
+}
+
+/// Color of a bin under --color-by-rank: light grey for the reference backbone, darker
+/// grey when none of its segments has an SR tag
+fn rank_color(rank: Option<u32>, theme: &Theme) -> (u8, u8, u8) {
+    match rank {
+        Some(0) => theme.muted(200),
+        Some(rank) => RANK_COLORS[(rank as usize - 1).min(RANK_COLORS.len() - 1)],
+        None => theme.muted(120),
+    }
+}
+
+/// Rescale one path's binned depths for --normalize-depth so that typical coverage
+/// lands on depth 1, the single-copy color
+fn normalize_depths(
+    mode: DepthNormalization,
+    bins: &mut FxHashMap<usize, BinInfo>,
+    bin_means: Option<&FxHashMap<usize, f64>>,
+) {
+    let covered = bins.values().filter(|b| b.mean_depth > 0.0).count();
+    if covered == 0 {
+        return;
+    }
+    let mean = bins.values().map(|b| b.mean_depth).sum::<f64>() / covered as f64;
+    match mode {
+        DepthNormalization::None => {}
+        DepthNormalization::PerPath => {
+            for b in bins.values_mut() {
+                b.mean_depth /= mean;
+            }
+        }
+        DepthNormalization::PerBin => {
+            for (bin, b) in bins.iter_mut() {
+                match bin_means.and_then(|m| m.get(bin)) {
+                    Some(&bin_mean) if bin_mean > 0.0 => b.mean_depth /= bin_mean,
+                    _ => {}
+                }
+            }
+        }
+        DepthNormalization::Zscore => {
+            let variance = bins
+                .values()
+                .filter(|b| b.mean_depth > 0.0)
+                .map(|b| (b.mean_depth - mean).powi(2))
+                .sum::<f64>()
+                / covered as f64;
+            let sd = variance.sqrt();
+            for b in bins.values_mut().filter(|b| b.mean_depth > 0.0) {
+                let z = if sd > 0.0 {
+                    (b.mean_depth - mean) / sd
+                } else {
+                    0.0
+                };
+                b.mean_depth = (1.0 + z).max(0.0);
+            }
+        }
+    }
+}
+
+/// Get color for depth using colorbrewer palette (with optional grey for low coverage).
+/// Matches odgi's behavior: each palette color covers exactly 1 depth unit.
+/// Cuts are at 0.5, 1.5, 2.5, ... with grey prepended for low coverage unless no_grey_depth.
+fn get_depth_color(
+    mean_depth: f64,
+    no_grey_depth: bool,
+    palette: Option<&[(u8, u8, u8)]>,
+) -> (u8, u8, u8) {
+    // Use custom palette if provided, otherwise use Spectral
+    if let Some(pal) = palette {
+        let n = pal.len();
+        if n == 0 {
+            return (128, 128, 128);
+        }
+        if no_grey_depth {
+            // No grey: palette colors at cuts 0.5, 1.5, 2.5, ...
+            for i in 0..n {
+                if mean_depth <= 0.5 + i as f64 {
+                    return pal[i];
+                }
+            }
+            pal[n - 1]
+        } else {
+            // Grey prepended: cuts 0.5 (grey1), 1.5 (grey2), then palette colors at 2.5, 3.5, ...
+            if mean_depth <= 0.5 {
+                return (196, 196, 196);
+            }
+            if mean_depth <= 1.5 {
+                return (128, 128, 128);
+            }
+            for i in 0..n {
+                if mean_depth <= 2.5 + i as f64 {
+                    return pal[i];
+                }
+            }
+            pal[n - 1]
+        }
+    } else if no_grey_depth {
+        // Use full Spectral 11 range for all depths (skip grey colors at indices 0-1)
+        let cuts = [1.5, 2.5, 3.5, 4.5, 5.5, 6.5, 7.5, 8.5, 9.5, 10.5, 11.5];
+        for (i, &cut) in cuts.iter().enumerate() {
+            if mean_depth <= cut {
+                return COLORBREWER_SPECTRAL_13[i + 2];
+            }
+        }
+        COLORBREWER_SPECTRAL_13[12]
+    } else {
+        // Default: use grey for low coverage (indices 0-1), Spectral for rest
+        let cuts = [
+            0.5, 1.5, 2.5, 3.5, 4.5, 5.5, 6.5, 7.5, 8.5, 9.5, 10.5, 11.5, 12.5,
+        ];
+        for (i, &cut) in cuts.iter().enumerate() {
+            if mean_depth <= cut {
+                return COLORBREWER_SPECTRAL_13[i];
+            }
+        }
+        COLORBREWER_SPECTRAL_13[12]
+    }
+}
+
+pub(crate) fn render(args: &Args, graph: &Graph) -> Result<Vec<u8>, GfalookError> {
+    let preview;
+    let args = if args.preview {
+        preview = preview_args(args);
+        &preview
+    } else {
+        args
+    };
+    let reordered;
+    let (graph, orphan_start) = match args.show_orphans.then(|| orphans_last(graph)).flatten() {
+        Some((orphans_last, start)) => {
+            reordered = orphans_last;
+            (&reordered, Some(start))
+        }
+        None => (graph, None),
+    };
+    let grouped;
+    let (graph, components) = match args
+        .separate_components
+        .then(|| components_contiguous(graph))
+        .flatten()
+    {
+        Some((components_grouped, spans)) => {
+            grouped = components_grouped;
+            (&grouped, spans)
+        }
+        None => (graph, Vec::new()),
+    };
+    // Note: mode conflicts (compressed, pack, clustering with prefix merges) are handled by clap
+
+    let theme = Theme::from_args(args);
+    let (fg_r, fg_g, fg_b) = theme.foreground;
+
+    let mut display_paths: Vec<&GfaPath> = graph.paths.iter().collect();
+    let path_resolver = PathNameResolver::new(graph.paths.iter().map(|p| p.name.as_str()));
+
+    // Paths left out by the filters below, with the reason, for --write-dropped
+    let mut dropped: Vec<(String, &'static str)> = Vec::new();
+    if let Some(ref prefix) = args.ignore_prefix {
+        display_paths.retain(|p| {
+            let keep = !p.name.starts_with(prefix);
+            if !keep {
+                dropped.push((p.name.clone(), "ignore-prefix"));
+            }
+            keep
+        });
+    }
+
+    if let Some(ref ptd_file) = args.paths_to_display {
+        let ptd: Vec<String> = load_paths_to_display(ptd_file)
+            .map_err(|e| GfalookError::from_io("failed to read paths to display", ptd_file, e))?
+            .iter()
+            .map(|name| path_resolver.canonical(name))
+            .collect();
+        let ptd_set: std::collections::HashSet<_> = ptd.iter().collect();
+        display_paths.retain(|p| {
+            let keep = ptd_set.contains(&p.name);
+            if !keep {
+                dropped.push((p.name.clone(), "not in paths-to-display"));
+            }
+            keep
+        });
+        let path_map: FxHashMap<&String, &GfaPath> =
+            display_paths.iter().map(|p| (&p.name, *p)).collect();
+        display_paths = ptd
+            .iter()
+            .filter_map(|name| path_map.get(name).copied())
+            .collect();
+    }
+
+    if display_paths.is_empty() && !graph.paths.is_empty() {
+        return Err(GfalookError::EmptySelection(
+            "no paths left to display after --ignore-prefix/--paths-to-display filtering"
+                .to_string(),
+        ));
+    }
+
+    if args.preview && display_paths.len() > PREVIEW_MAX_PATHS {
+        info!(
+            "Preview: drawing {} of {} paths",
+            PREVIEW_MAX_PATHS,
+            display_paths.len()
+        );
+        let sampled = sample_paths(display_paths.clone(), PREVIEW_MAX_PATHS);
+        note_dropped(
+            &mut dropped,
+            &display_paths,
+            &sampled,
+            "not sampled by preview",
+        );
+        display_paths = sampled;
+    }
+
+    let flip_names = paths_to_flip(args, graph, &display_paths, &path_resolver)?;
+    let flipped_paths: Vec<GfaPath> = display_paths
+        .iter()
+        .filter(|p| flip_names.contains(&p.name))
+        .map(|p| reverse_complement(p))
+        .collect();
+    if !flipped_paths.is_empty() {
+        info!("Drawing {} paths reverse-complemented", flipped_paths.len());
+        display_paths = apply_flips(&display_paths, &flipped_paths);
+    }
+    let joined_paths = if args.join_subranges {
+        join_subranges(&display_paths)
+    } else {
+        Vec::new()
+    };
+    if !joined_paths.is_empty() {
+        let before = display_paths.len();
+        display_paths = apply_joins(&display_paths, &joined_paths);
+        info!(
+            "Joined subrange paths: {} rows from {} paths",
+            display_paths.len(),
+            before
+        );
+    }
+    let split_pieces: Vec<GfaPath> = match args.split_discontiguous {
+        Some(max_jump) => display_paths
+            .iter()
+            .flat_map(|p| split_at_jumps(p, graph, max_jump))
+            .collect(),
+        None => Vec::new(),
+    };
+    if !split_pieces.is_empty() {
+        let before = display_paths.len();
+        display_paths = apply_splits(&display_paths, &split_pieces);
+        info!(
+            "Split discontiguous paths: {} rows from {} paths",
+            display_paths.len(),
+            before
+        );
+    }
+
+    let bottom_padding = 5u32;
+
+    let compressed;
+    let (graph, invariant_breaks) = match args
+        .compress_invariant
+        .and_then(|factor| compress_invariant(args, graph, &display_paths, factor))
+    {
+        Some((compressed_graph, breaks)) => {
+            compressed = compressed_graph;
+            (&compressed, breaks)
+        }
+        None => (graph, Vec::new()),
+    };
+
+    let len_to_visualize = graph.total_length;
+    let viz_width = if args.show_all_nodes {
+        show_all_nodes_viz_width(args, graph)?
+    } else {
+        args.width.min(len_to_visualize as u32)
+    };
+
+    let bin_width = args
+        .bin_width
+        .unwrap_or_else(|| len_to_visualize as f64 / viz_width as f64);
+    let _scale_x = 1.0; // In binned mode
+    let _scale_y = viz_width as f64 / len_to_visualize as f64;
+
+    // Load BED regions for clustering if specified
+    let bed_regions: Option<ClusteringBedRegions> = match args.cluster_bed {
+        Some(ref p) => {
+            let regions = load_clustering_bed(p)
+                .map_err(|e| GfalookError::from_io("failed to load BED file", p, e))?;
+            if regions.path_regions.is_empty() {
+                warning!("BED file is empty or has no valid regions, clustering all paths");
+                None
+            } else {
+                Some(regions)
+            }
+        }
+        None => None,
+    };
+
+    // Names of the cluster medoids, filled in when --mark-medoids is set
+    let mut medoid_names: FxHashSet<String> = FxHashSet::default();
+    // Cluster medoids with their cluster colors, for --write-colored-gfa
+    let mut medoid_colors: Vec<(&GfaPath, (u8, u8, u8))> = Vec::new();
+
+    // Cluster paths by similarity if requested (PNG rendering)
+    // Prefix groups to cluster within for --cluster-within
+    let within_groups = match args.cluster_within {
+        Some(ref p) => {
+            Some(prefix_group_annotations(read_prefixes(p).map_err(|e| {
+                GfalookError::from_io("failed to load prefix file", p, e)
+            })?))
+        }
+        None => None,
+    };
+
+    let cluster_result = if args.cluster_paths {
+        debug!(
+            "Clustering {} paths by EDR (estimated difference rate)",
+            display_paths.len()
+        );
+        // Build segment lengths vector for EDR computation
+        let segment_lengths: Vec<u64> = graph.segments.iter().map(|s| s.sequence_len).collect();
+
+        // If BED regions provided, partition paths into those to cluster vs. those excluded
+        let (paths_to_cluster, unclustered_paths): (Vec<&GfaPath>, Vec<&GfaPath>) =
+            if let Some(ref bed) = bed_regions {
+                let (to_cluster, unclustered): (Vec<_>, Vec<_>) =
+                    display_paths.iter().partition(|p| bed.has_regions(&p.name));
+                if to_cluster.is_empty() {
+                    return Err(GfalookError::EmptySelection(
+                        "no paths match BED regions, cannot cluster".to_string(),
+                    ));
+                }
+                debug!(
+                    "BED subsetting: {} paths to cluster, {} paths excluded",
+                    to_cluster.len(),
+                    unclustered.len()
+                );
+                (to_cluster, unclustered)
+            } else {
+                (display_paths.clone(), Vec::new())
+            };
+
+        let original_paths = paths_to_cluster.clone(); // Save for medoids TSV
+        let cluster_start = Instant::now();
+        let result = match within_groups {
+            Some(ref groups) => {
+                cluster_within_groups(args, &paths_to_cluster, groups, &segment_lengths)
+            }
+            None => cluster_with_cache(
+                args,
+                &paths_to_cluster,
+                &segment_lengths,
+                bed_regions.as_ref(),
+            ),
+        };
+        logging::phase(
+            "cluster",
+            cluster_start,
+            &[
+                ("paths", paths_to_cluster.len() as u64),
+                ("clusters", result.num_clusters as u64),
+            ],
+        );
+
+        // Rebuild display_paths: clustered paths in order, then unclustered
+        display_paths = result
+            .ordering
+            .iter()
+            .map(|&i| paths_to_cluster[i])
+            .collect();
+        display_paths.extend(unclustered_paths.clone());
+
+        // Extend cluster_ids for unclustered paths (use num_clusters as special "unclustered" ID)
+        let mut extended_cluster_ids = result.cluster_ids.clone();
+        extended_cluster_ids.extend(std::iter::repeat_n(
+            result.num_clusters,
+            unclustered_paths.len(),
+        ));
+
+        // Build extended result
+        let extended_result = ClusteringResult {
+            ordering: (0..display_paths.len()).collect(),
+            cluster_ids: extended_cluster_ids,
+            num_clusters: if unclustered_paths.is_empty() {
+                result.num_clusters
+            } else {
+                result.num_clusters + 1 // +1 for "unclustered" group
+            },
+            representatives: result.representatives.clone(),
+            cluster_sizes: {
+                let mut sizes = result.cluster_sizes.clone();
+                if !unclustered_paths.is_empty() {
+                    sizes.push(unclustered_paths.len());
+                }
+                sizes
+            },
+            dendrogram: result.dendrogram.clone(),
+            noise_cluster: result.noise_cluster,
+            stability: result.stability.clone(),
+        };
+
+        // Write cluster assignments to TSV (using original result for clustered paths only)
+        write_cluster_tsv(
+            args,
+            &original_paths,
+            &result,
+            &segment_lengths,
+            bed_regions.as_ref(),
+        );
+        // Write medoids TSV
+        write_medoids_tsv(&args.out, &original_paths, &result);
+        if let Some(ref dir) = args.per_cluster_images {
+            render_per_cluster_images(args, dir, graph, &original_paths, &result)?;
+        }
+        if let Some(ref distances_path) = args.write_distances {
+            write_distances_tsv(
+                distances_path,
+                &original_paths,
+                &segment_lengths,
+                args.cluster_all_nodes,
+                bed_regions.as_ref(),
+                args.similarity_level,
+            )?;
+        }
+        if args.write_colored_gfa.is_some() || args.export_dot.is_some() {
+            medoid_colors = result
+                .representatives
+                .iter()
+                .enumerate()
+                .filter(|&(cluster_id, _)| result.noise_cluster != Some(cluster_id))
+                .map(|(cluster_id, &i)| {
+                    (
+                        original_paths[i],
+                        result.cluster_color(cluster_id, args, &theme),
+                    )
+                })
+                .collect();
+        }
+        if args.mark_medoids {
+            medoid_names = result
+                .representatives
+                .iter()
+                .enumerate()
+                .filter(|&(cluster_id, _)| result.noise_cluster != Some(cluster_id))
+                .map(|(_, &i)| original_paths[i].name.clone())
+                .collect();
+        }
+
+        // Filter to representatives only if requested (PNG)
+        // Note: only applies to clustered paths, unclustered paths are not included
+        let final_result = if args.cluster_representatives {
+            let rep_set: FxHashSet<usize> =
+                pick_representatives(args, &paths_to_cluster, &result, &segment_lengths)
+                    .into_iter()
+                    .collect();
+            let mut filtered_paths = Vec::new();
+            let mut filtered_cluster_ids = Vec::new();
+            // The tree's leaves are the rows in display order; keep the representatives'
+            let mut kept_leaves =
+                vec![None; result.dendrogram.as_ref().map_or(0, |dg| dg.leaf_order.len())];
+            for (pos, &orig_idx) in result.ordering.iter().enumerate() {
+                if rep_set.contains(&orig_idx)
+                    && result.noise_cluster != Some(result.cluster_ids[pos])
+                {
+                    if let Some(dg) = &result.dendrogram {
+                        if let Some(&leaf) = dg.leaf_order.get(pos) {
+                            kept_leaves[leaf] = Some(filtered_paths.len());
+                        }
+                    }
+                    filtered_paths.push(paths_to_cluster[orig_idx]);
+                    filtered_cluster_ids.push(result.cluster_ids[pos]);
+                }
+            }
+            note_dropped(
+                &mut dropped,
+                &display_paths,
+                &filtered_paths,
+                "not a cluster representative",
+            );
+            display_paths = filtered_paths;
+            // Don't include unclustered paths when showing representatives only
+            ClusteringResult {
+                ordering: (0..display_paths.len()).collect(),
+                cluster_ids: filtered_cluster_ids,
+                num_clusters: result.num_clusters,
+                representatives: result.representatives,
+                cluster_sizes: result.cluster_sizes,
+                dendrogram: result
+                    .dendrogram
+                    .map(|dg| prune_dendrogram(&dg, &kept_leaves)),
+                noise_cluster: result.noise_cluster,
+                stability: result.stability,
+            }
+        } else {
+            extended_result
+        };
+        let pool = (!args.cluster_representatives && !unclustered_paths.is_empty())
+            .then_some(result.num_clusters);
+        Some(hide_minor_clusters(
+            args,
+            &mut display_paths,
+            final_result,
+            pool,
+            &mut dropped,
+        )?)
+    } else {
+        None
+    };
+    if args.write_dropped {
+        write_dropped_tsv(&args.out, &dropped);
+    }
+
+    // Recalculate path_count after potential filtering by cluster_representatives (PNG)
+    let path_count = display_paths.len() as u32;
+
+    if let Some(ref pav_path) = args.export_pav {
+        write_pav_matrix(args, pav_path, &display_paths, graph)?;
+    }
+    if let Some(ref frequency_path) = args.node_frequency_out {
+        write_node_frequencies(frequency_path, &display_paths, graph)?;
+    }
+    // --core-track: the core bins under the figure, and their statistics
+    let core = args.core_track.then(|| {
+        let core = core_bins(&display_paths, graph, bin_width, viz_width as usize);
+        write_core_tsv(
+            &args.out,
+            &core_stats(&display_paths, graph, &core, bin_width),
+        );
+        core
+    });
+
+    // Load colorbrewer palette if specified
+    let depth_palette: Option<&[(u8, u8, u8)]> =
+        args.colorbrewer_palette.as_ref().and_then(|arg| {
+            if let Some((scheme, n)) = parse_colorbrewer_arg(arg) {
+                get_colorbrewer_palette(&scheme, n).or_else(|| {
+                    warning!(
+                        "unknown colorbrewer palette '{}', using default Spectral",
+                        scheme
+                    );
+                    None
+                })
+            } else {
+                warning!(
+                    "invalid colorbrewer palette format '{}', expected SCHEME:N",
+                    arg
+                );
+                None
+            }
+        });
+
+    let custom_colors: Option<FxHashMap<String, (u8, u8, u8)>> = match args.color_by_contig {
+        Some(ref mapping_file) => {
+            let mapping = match mapping_file {
+                Some(p) => Some(
+                    load_contig_map(p)
+                        .map_err(|e| GfalookError::from_io("failed to load contig map", p, e))?
+                        .into_iter()
+                        .map(|(name, contig)| (path_resolver.canonical(&name), contig))
+                        .collect(),
+                ),
+                None => None,
+            };
+            Some(contig_colors(&display_paths, mapping.as_ref()))
+        }
+        None => args
+            .path_colors
+            .as_ref()
+            .and_then(|p| load_path_colors(p).ok())
+            .map(|colors| {
+                colors
+                    .into_iter()
+                    .map(|(name, color)| (path_resolver.canonical(&name), color))
+                    .collect()
+            }),
+    };
+
+    // Load highlight node IDs if specified
+    let highlight_nodes: Option<FxHashSet<u64>> = args
+        .highlight_node_ids
+        .as_ref()
+        .and_then(|p| load_highlight_node_ids(p).ok())
+        .map(|ids| {
+            ids.into_iter()
+                .filter_map(|index| graph.segment_at_file_index(index))
+                .collect()
+        });
+
+    // --watch re-renders reuse the bins of paths whose binning inputs are unchanged
+    let binning = args
+        .watch
+        .then(|| bin_cache::binning_key(graph, bin_width, highlight_nodes.as_ref()));
+    let raw_bins = |path: &GfaPath| {
+        bin_cache::bins(binning, path, || {
+            path_bins(path, graph, bin_width, highlight_nodes.as_ref())
+        })
+    };
+    // Mean depth of every bin over the displayed paths, for --normalize-depth per-bin
+    let bin_depth_means = (args.normalize_depth == DepthNormalization::PerBin)
+        .then(|| mean_bin_depths(&display_paths, raw_bins));
+    // A path's bins as the rows are drawn
+    let drawn_bins = |path: &GfaPath| {
+        let mut bins = raw_bins(path);
+        adjust_bins(args, &mut bins, bin_depth_means.as_ref());
+        bins
+    };
+    if let Some(ref matrix_path) = args.export_matrix {
+        write_depth_matrix(
+            matrix_path,
+            &display_paths,
+            graph,
+            bin_width,
+            viz_width,
+            &drawn_bins,
+        )?;
+    }
+
+    if let Some(ref gfa_path) = args.write_colored_gfa {
+        let colors = segment_colors(
+            graph,
+            &display_paths,
+            &medoid_colors,
+            highlight_nodes.as_ref(),
+            args.no_grey_depth,
+            depth_palette,
+            &theme,
+        );
+        write_colored_gfa(&args.idx, gfa_path, graph, &colors)?;
+    }
+
+    let path_color = |path: &GfaPath| match custom_colors {
+        Some(ref colors) => colors.get(&path.name).copied().unwrap_or(theme.muted(200)),
+        None => compute_path_color(&path.name, args.color_by_prefix),
+    };
+    let edge_colors = args
+        .color_edges
+        .then(|| edge_path_colors(&display_paths, path_color));
+    if let Some(ref dot_path) = args.export_dot {
+        let node_colors =
+            if !medoid_colors.is_empty() || highlight_nodes.is_some() || args.color_by_mean_depth {
+                segment_colors(
+                    graph,
+                    &display_paths,
+                    &medoid_colors,
+                    highlight_nodes.as_ref(),
+                    args.no_grey_depth,
+                    depth_palette,
+                    &theme,
+                )
+            } else {
+                segment_path_colors(graph, &display_paths, path_color)
+            };
+        let colors = graph_export::ExportColors {
+            nodes: &node_colors,
+            edges: edge_colors.as_ref(),
+            default: theme.foreground,
+        };
+        let edges = selected_edges(args.edges, graph, &display_paths);
+        graph_export::write(dot_path, graph, &display_paths, &edges, &colors)?;
+    }
+    // --no-image: the data products above are all that was asked for
+    if args.no_image {
+        return Ok(Vec::new());
+    }
+
+    // Per-window cluster assignments for the recombination mosaic band (PNG)
+    let window_clusters: Option<Vec<Vec<Option<usize>>>> = args.cluster_windows.map(|size| {
+        let wc = cluster_windows(
+            &display_paths,
+            graph,
+            size,
+            args.cluster_threshold,
+            args.max_clusters,
+            args.dbscan_min_pts,
+        );
+        info!(
+            "Clustered {} paths in {} windows of {}bp",
+            display_paths.len(),
+            wc.len(),
+            size
+        );
+        wc
+    });
+
+    let node_frequency: Option<Vec<f64>> = args.color_by_frequency_class.then(|| {
+        let n = display_paths.len().max(1) as f64;
+        node_frequencies(&display_paths, graph)
+            .into_iter()
+            .map(|count| count as f64 / n)
+            .collect()
+    });
+
+    // Clustering and the exports above are written; stop here on Ctrl-C
+    interrupt::check("clustering")?;
+
+    // Calculate total gap space needed for cluster separators
+    let total_gap = if let Some(ref cr) = cluster_result {
+        (cr.num_clusters.saturating_sub(1) as u32) * args.cluster_gap
+    } else {
+        0
+    };
+
+    // Load prefix grouping if specified (PNG) - must be after clustering check
+    let path_grouping: Option<PathGrouping> = args.prefix_merges.as_ref().and_then(|p| {
+        let paths_vec: Vec<GfaPath> = display_paths.iter().map(|&p| p.clone()).collect();
+        match load_prefix_merges(p, &paths_vec) {
+            Ok(grouping) => {
+                info!(
+                    "Read {} valid prefixes for {} groups",
+                    grouping.prefixes.len(),
+                    grouping.num_groups
+                );
+                Some(grouping)
+            }
+            Err(e) => {
+                warning!("failed to load prefix merges: {}", e);
+                None
+            }
+        }
+    });
+
+    // Load annotations if specified
+    let annotations: Option<AnnotationData> = args
+        .annotation_file
+        .as_ref()
+        .and_then(|p| match load_annotations(p) {
+            Ok(ann) => {
+                info!(
+                    "Loaded {} prefixes across {} categories",
+                    ann.prefixes.len(),
+                    ann.categories.len()
+                );
+                Some(ann)
+            }
+            Err(e) => {
+                warning!("failed to load annotations: {}", e);
+                None
+            }
+        })
+        .or(within_groups);
+
+    // Effective row count: use num_groups if grouping is enabled, the summary rows if
+    // compressed mode
+    let effective_row_count = if args.compressed_mode {
+        1 + args.compressed_rows.len() as u32
+    } else if let Some(ref pg) = path_grouping {
+        pg.num_groups as u32
+    } else {
+        path_count
+    };
+
+    // Row height: fixed by -a, or derived from --fit-height once the row count is known
+    let pix_per_path = match args.fit_height {
+        Some(target) => {
+            let legend = if annotations.is_some() {
+                args.legend_height
+            } else {
+                0
+            } + cluster_legend_band(args)
+                + downsample_band(args)
+                + jump_arc_band(args);
+            let axis = if args.x_axis.is_some() {
+                axis_height(args) + args.panel_gap
+            } else {
+                0
+            } + diversity_track_panel(args)
+                + core_track_panel(args)
+                + frequency_spectrum_panel(args)
+                + component_track_panel(args);
+            let edges = (len_to_visualize.min((args.height + bottom_padding) as u64)) as u32;
+            let spacing = effective_row_count.saturating_sub(1) * args.path_spacing;
+            let reserved =
+                legend + total_gap + spacing + axis + args.panel_gap + edges + 2 * args.margin;
+            let fitted = fit_path_height(target, effective_row_count, reserved);
+            info!(
+                "Fitting {} rows into {}px: path height {}px",
+                effective_row_count, target, fitted
+            );
+            fitted
+        }
+        None => args.path_height,
+    };
+    check_rows_height(args, effective_row_count, pix_per_path)?;
+
+    debug!("Binned mode");
+    debug!("bin width: {:.2e}", bin_width);
+    debug!("image width: {}", viz_width);
+
+    // Use prefix names for max_name_len when grouping, "COMPRESSED_MODE" for compressed mode
+    let max_name_len = if args.compressed_mode {
+        "COMPRESSED_MODE".len()
+    } else if let Some(ref pg) = path_grouping {
+        pg.prefixes
+            .iter()
+            .map(|p| p.chars().count())
+            .max()
+            .unwrap_or(10)
+    } else if args.cluster_representatives {
+        // Account for " (n=X)" suffix: max cluster size determines suffix length
+        let max_size = cluster_result
+            .as_ref()
+            .map(|cr| cr.cluster_sizes.iter().max().copied().unwrap_or(1))
+            .unwrap_or(1);
+        let suffix_len = format!(" (n={})", max_size).len();
+        display_paths
+            .iter()
+            .map(|p| p.name.chars().count() + suffix_len)
+            .max()
+            .unwrap_or(10)
+    } else {
+        display_paths
+            .iter()
+            .map(|p| p.name.chars().count())
+            .max()
+            .unwrap_or(10)
+    };
+    let max_num_of_chars = args.max_num_of_characters.unwrap_or(max_name_len.min(128));
+    let char_size = ((pix_per_path / 8) * 8).clamp(8, 64);
+
+    // Cluster bar width (only if clustering is enabled)
+    let cluster_bar_width: u32 = if cluster_result.is_some() { 10 } else { 0 };
+
+    // Annotation bar width (only if annotations are loaded)
+    let annotation_bar_width: u32 = if annotations.is_some() {
+        args.annotation_bar_width
+    } else {
+        0
+    };
+
+    // Gap between cluster bar and annotation bar when both are present
+    let bar_gap: u32 = if cluster_result.is_some() && annotations.is_some() {
+        4
+    } else {
+        0
+    };
+
+    // Legend height (only if annotations are loaded)
+    // Everything above the path rows: the annotation legend, the --cluster-legend row, the
+    // --downsample label and the --jump-arcs band
+    let legend_height: u32 = if annotations.is_some() {
+        args.legend_height
+    } else {
+        0
+    } + cluster_legend_band(args)
+        + downsample_band(args)
+        + jump_arc_band(args);
+
+    // Dendrogram width (only if dendrogram is enabled and we have a dendrogram)
+    let dendrogram_width: u32 = if args.dendrogram
+        && cluster_result
+            .as_ref()
+            .map(|cr| cr.dendrogram.is_some())
+            .unwrap_or(false)
+    {
+        args.dendrogram_width
+    } else {
+        0
+    };
+
+    // Disable path names when pack_paths is enabled (they wouldn't make sense)
+    let text_only_width = if args.hide_path_names || args.pack_paths {
+        0u32
+    } else if pix_per_path >= 8 {
+        (max_num_of_chars as u32 * char_size) + char_size / 2
+    } else {
+        0
+    };
+
+    // Total left panel width includes dendrogram + cluster bar + gap + annotation bar + path names
+    let path_names_width =
+        dendrogram_width + cluster_bar_width + bar_gap + annotation_bar_width + text_only_width;
+
+    // Rows are laid out on a fixed pitch so --path-spacing leaves a gap between them
+    let row_pitch = pix_per_path + args.path_spacing;
+    let path_space = effective_row_count * pix_per_path
+        + effective_row_count.saturating_sub(1) * args.path_spacing
+        + total_gap;
+
+    // Height for edge visualization area - matches odgi's calculation
+    // height = min(len_to_visualize, args.height + bottom_padding)
+    // scale_y = height / len_to_visualize
+    let height_param = (args.height + bottom_padding) as u64;
+    let edge_height = (len_to_visualize.min(height_param)) as u32;
+    let scale_y_edges = edge_height as f64 / len_to_visualize as f64;
+
+    let total_width = viz_width + path_names_width;
+    // Calculate max axis height for buffer allocation, plus the panel gap separating it
+    // from the rows, and the --diversity-track strip below it
+    let max_axis_height: u32 = if args.x_axis.is_some() {
+        axis_height(args) + args.panel_gap
+    } else {
+        0
+    } + diversity_track_panel(args)
+        + core_track_panel(args)
+        + frequency_spectrum_panel(args)
+        + component_track_panel(args);
+    // Initial height - will be cropped later based on actual edge rendering (includes legend at top)
+    let max_possible_height =
+        legend_height + path_space + max_axis_height + args.panel_gap + edge_height;
+
+    let mut buffer = theme.canvas(total_width, max_possible_height);
+    let mut path_names_buffer = if path_names_width > 0 {
+        theme.canvas(path_names_width, max_possible_height)
+    } else {
+        Vec::new()
+    };
+
+    // Pre-compute leaf Y positions for dendrogram (accounting for cluster gaps and legend)
+    let dendrogram_leaf_y_positions: Vec<u32> = if dendrogram_width > 0 {
+        if let Some(ref cr) = cluster_result {
+            if let Some(ref dg) = cr.dendrogram {
+                let n_leaves = dg.leaf_order.len();
+                let mut positions = vec![0u32; n_leaves];
+                let mut cumulative_gap: u32 = 0;
+                let mut prev_cluster_id: Option<usize> = None;
+
+                for (display_pos, &orig_idx) in dg.leaf_order.iter().enumerate() {
+                    if orig_idx < n_leaves && display_pos < cr.cluster_ids.len() {
+                        // cluster_ids is indexed by display position, not original index
+                        let cluster_id = cr.cluster_ids[display_pos];
+                        if prev_cluster_id.is_some_and(|prev| prev != cluster_id) {
+                            cumulative_gap += args.cluster_gap;
+                        }
+                        prev_cluster_id = Some(cluster_id);
+                        positions[orig_idx] =
+                            legend_height + display_pos as u32 * row_pitch + cumulative_gap;
+                    }
+                }
+                positions
+            } else {
+                Vec::new()
+            }
+        } else {
+            Vec::new()
+        }
+    } else {
+        Vec::new()
+    };
+
+    // Filter annotation categories to only those used by paths in the graph (for legend)
+    // NA is added at the end if any path doesn't match a prefix
+    let filtered_categories: Vec<String> = if let Some(ref ann) = annotations {
+        let used_categories: std::collections::HashSet<&str> = display_paths
+            .iter()
+            .map(|p| ann.get_annotation(&p.name))
+            .collect();
+        let mut cats: Vec<String> = ann
+            .categories
+            .iter()
+            .filter(|c| used_categories.contains(c.as_str()))
+            .cloned()
+            .collect();
+        // Add NA at the end if any path has it
+        if used_categories.contains("NA") && !cats.iter().any(|c| c == "NA") {
+            cats.push("NA".to_string());
+        }
+        cats
+    } else {
+        Vec::new()
+    };
+
+    // Render dendrogram if enabled (PNG)
+    if dendrogram_width > 0 && !dendrogram_leaf_y_positions.is_empty() {
+        if let Some(ref cr) = cluster_result {
+            if let Some(ref dg) = cr.dendrogram {
+                render_dendrogram_png(
+                    &mut path_names_buffer,
+                    path_names_width,
+                    dg,
+                    dendrogram_width,
+                    pix_per_path,
+                    &dendrogram_leaf_y_positions,
+                );
+            }
+        }
+    }
+
+    // Track maximum y coordinate used (for cropping)
+    let mut max_y: u32 = legend_height + path_space + max_axis_height;
+
+
+    // Load highlighted path names if specified; all other rows are faded
+    let highlight_paths: Option<FxHashSet<String>> = args
+        .highlight_paths
+        .as_ref()
+        .and_then(|p| load_paths_to_display(p).ok())
+        .map(|names| names.iter().map(|n| path_resolver.canonical(n)).collect());
+    // Reference paths get a black border and a bold name
+    let reference_names: FxHashSet<&str> = if args.no_reference_style {
+        FxHashSet::default()
+    } else {
+        display_paths
+            .iter()
+            .filter(|path| is_reference_path(graph, path))
+            .map(|path| path.name.as_str())
+            .collect()
+    };
+    let row_fade = row_fades(
+        args,
+        &display_paths,
+        cluster_result.as_ref(),
+        annotations.as_ref(),
+    );
+
+    // Track which groups have already been rendered (for path names)
+    let mut rendered_groups: FxHashSet<i64> = FxHashSet::default();
+
+    // Calculate max path length for longest-path option
+    let needs_length = args.longest_path || args.change_darkness || args.row_axes.is_some();
+    let max_path_length: u64 = if needs_length {
+        display_paths
+            .iter()
+            .map(|path| {
+                path.steps
+                    .iter()
+                    .map(|step| {
+                        let seg_id = step.segment_id as usize;
+                        if seg_id < graph.segments.len() {
+                            graph.segments[seg_id].sequence_len
+                        } else {
+                            0
+                        }
+                    })
+                    .sum::<u64>()
+            })
+            .max()
+            .unwrap_or(1)
+    } else {
+        1
+    };
+    let ruler_step = row_axis_step(max_path_length, args.x_ticks);
+    if args.row_axes.is_some() {
+        info!("Row axes: one tick every {} bp", ruler_step);
+    }
+
+    // Compressed mode: aggregate bins across all paths and render single row (PNG)
+    if args.compressed_mode {
+        // Use RdBu palette by default for compressed mode, or user-specified palette
+        let compressed_palette = depth_palette.unwrap_or(COLORBREWER_RDBU_11.as_slice());
+
+        // One row per statistic: --compressed-stat first, then --compressed-rows
+        let stats =
+            std::iter::once(args.compressed_stat).chain(args.compressed_rows.iter().copied());
+        for (row, stat) in stats.enumerate() {
+            // Aggregate bins across all paths
+            let compressed_bins = compressed_bins(&display_paths, graph, bin_width, stat);
+
+            // Render path name "COMPRESSED_MODE", or the statistic on extra rows
+            let y_start = legend_height + row as u32 * row_pitch;
+            if text_only_width > 0 && pix_per_path >= 8 {
+                let display_name = if row == 0 {
+                    "COMPRESSED_MODE"
+                } else {
+                    stat.label()
+                };
+                let num_of_chars = display_name.chars().count().min(max_num_of_chars);
+                let left_padding = max_num_of_chars - num_of_chars;
+
+                let base_y = y_start + pix_per_path / 2 - char_size / 2;
+                for (i, c) in display_name.chars().take(num_of_chars).enumerate() {
+                    let base_x = (left_padding + i) as u32 * char_size
+                        + 3
+                        + dendrogram_width
+                        + cluster_bar_width
+                        + annotation_bar_width;
+                    let char_data = glyph_for_char(c);
+                    write_char(
+                        &mut path_names_buffer,
+                        path_names_width,
+                        base_x,
+                        base_y,
+                        char_data,
+                        char_size,
+                        fg_r,
+                        fg_g,
+                        fg_b,
+                    );
+                }
+            }
+
+            // Render aggregated bins (PNG compressed mode)
+            for (bin_idx, value) in &compressed_bins {
+                let x = (*bin_idx as u32).min(viz_width - 1);
+                let (r, g, b) =
+                    compressed_bin_color(*value, stat, args.no_grey_depth, compressed_palette);
+                add_path_step(
+                    &mut buffer,
+                    total_width,
+                    x + path_names_width,
+                    y_start,
+                    pix_per_path,
+                    r,
+                    g,
+                    b,
+                    args.no_path_borders,
+                    args.black_path_borders,
+                );
+            }
+        }
+    }
+
+    // Pack-paths mode: use 2D collision detection to pack paths compactly (PNG)
+    let diversity = args.shade_variable_regions.then(|| {
+        relative_diversity(bin_diversity(
+            &display_paths,
+            graph,
+            bin_width,
+            viz_width as usize,
+        ))
+    });
+    // --show-orphans: the hatched columns of the orphan block
+    let orphan_runs: Vec<(u32, u32)> = orphan_start
+        .map(|start| vec![(((start as f64 / bin_width) as u32).min(viz_width), viz_width)])
+        .unwrap_or_default();
+    let mixed_bins = AtomicUsize::new(0);
+    if args.pack_paths && !args.compressed_mode {
+        // Pre-compute bins for all paths to determine their X ranges
+        struct PathBinData {
+            min_bin: usize,
+            max_bin: usize,
+            bins: FxHashMap<usize, BinInfo>,
+            color: (u8, u8, u8),
+        }
+
+        let mut path_data: Vec<PathBinData> = Vec::with_capacity(display_paths.len());
+
+        for path in display_paths.iter() {
+            let mut bins = raw_bins(path);
+            let min_bin = bins.keys().copied().min().unwrap_or(usize::MAX);
+            let max_bin = bins.keys().copied().max().unwrap_or(0);
+            adjust_bins(args, &mut bins, bin_depth_means.as_ref());
+            assign_frequencies(&mut bins, path, graph, bin_width, node_frequency.as_deref());
+            if args.color_by_rank {
+                assign_ranks(&mut bins, path, graph, bin_width);
+            }
+
+            let color = if let Some(ref colors) = custom_colors {
+                colors.get(&path.name).copied().unwrap_or(theme.muted(200)) // Light grey for non-specified paths
+            } else {
+                compute_path_color(&path.name, args.color_by_prefix)
+            };
+
+            path_data.push(PathBinData {
+                min_bin,
+                max_bin,
+                bins,
+                color,
+            });
+        }
+
+        // Layout buffer: for each X position, track the lowest available row
+        // Using a simple greedy approach: for each path, find first row where it fits
+        let mut occupancy: Vec<Vec<bool>> = vec![vec![false; viz_width as usize]; 1]; // Start with 1 row
+
+        // Assign Y row to each path
+        let mut path_rows: Vec<usize> = Vec::with_capacity(path_data.len());
+
+        for pd in &path_data {
+            if pd.min_bin == usize::MAX {
+                // Empty path, assign to row 0
+                path_rows.push(0);
+                continue;
+            }
+
+            // Find first row where this path fits
+            let mut found_row = None;
+            for row in 0..occupancy.len() {
+                let mut fits = true;
+                for bin_idx in pd.min_bin..=pd.max_bin.min(viz_width as usize - 1) {
+                    if occupancy[row][bin_idx] {
+                        fits = false;
+                        break;
+                    }
+                }
+                if fits {
+                    found_row = Some(row);
+                    break;
+                }
+            }
+
+            let row = found_row.unwrap_or_else(|| {
+                // Need a new row
+                occupancy.push(vec![false; viz_width as usize]);
+                occupancy.len() - 1
+            });
+
+            // Mark bins as occupied
+            for bin_idx in pd.min_bin..=pd.max_bin.min(viz_width as usize - 1) {
+                occupancy[row][bin_idx] = true;
+            }
+
+            path_rows.push(row);
+        }
+
+        let packed_rows = occupancy.len() as u32;
+        info!(
+            "Pack-paths: {} paths packed into {} rows (vs {} original)",
+            display_paths.len(),
+            packed_rows,
+            path_count
+        );
+
+        // Resize buffer if packed height is different
+        let packed_path_space =
+            packed_rows * pix_per_path + packed_rows.saturating_sub(1) * args.path_spacing;
+        let packed_total_height =
+            legend_height + packed_path_space + max_axis_height + args.panel_gap + edge_height;
+        if packed_total_height != max_possible_height {
+            buffer = theme.canvas(total_width, packed_total_height);
+            max_y = legend_height + packed_path_space + max_axis_height;
+        }
+
+        if let Some(ref diversity) = diversity {
+            shade_columns(
+                &mut buffer,
+                total_width,
+                path_names_width,
+                legend_height,
+                packed_path_space,
+                diversity,
+                &theme,
+            );
+        }
+        draw_gap_runs(
+            &mut buffer,
+            total_width,
+            path_names_width,
+            legend_height,
+            packed_path_space,
+            &orphan_runs,
+            GapStyle::Hatch,
+            &theme,
+        );
+
+        // Render each path at its packed Y position
+        for (path_idx, (pd, path)) in path_data.iter().zip(display_paths.iter()).enumerate() {
+            let y_start = legend_height + path_rows[path_idx] as u32 * row_pitch;
+            let (path_r, path_g, path_b) = pd.color;
+            let dimmed = highlight_paths
+                .as_ref()
+                .is_some_and(|hp| !hp.contains(&path.name));
+            let fade = row_fade.get(&path.name).copied().unwrap_or(1.0);
+            let dim_if = |c: (u8, u8, u8)| theme.blend(if dimmed { theme.dim(c) } else { c }, fade);
+            let reference = reference_names.contains(path.name.as_str());
+            let path_length: u64 = path
+                .steps
+                .iter()
+                .map(|step| {
+                    let seg_id = step.segment_id as usize;
+                    if seg_id < graph.segments.len() {
+                        graph.segments[seg_id].sequence_len
+                    } else {
+                        0
+                    }
+                })
+                .sum();
+            let darkness_length = if args.longest_path {
+                max_path_length
+            } else {
+                path_length
+            };
+
+            if args.gap_style != GapStyle::None {
+                let bar_height = if args.no_path_borders || pix_per_path < 3 {
+                    pix_per_path
+                } else {
+                    pix_per_path - 1
+                };
+                draw_gap_runs(
+                    &mut buffer,
+                    total_width,
+                    path_names_width,
+                    y_start,
+                    bar_height,
+                    &gap_runs(pd.bins.keys(), viz_width),
+                    args.gap_style,
+                    &theme,
+                );
+            }
+
+            for (bin_idx, bin_info) in &pd.bins {
+                let x = (*bin_idx as u32).min(viz_width - 1);
+
+                // Determine color (same logic as normal rendering)
+                let (r, g, b) = if highlight_nodes.is_some() {
+                    if bin_info.highlighted {
+                        (255, 0, 0)
+                    } else {
+                        theme.muted(180)
+                    }
+                } else if args.color_by_frequency_class {
+                    frequency_class_color(bin_info.frequency, path_count as usize, args)
+                } else if args.color_by_rank {
+                    rank_color(bin_info.rank, &theme)
+                } else if args.color_by_mean_depth {
+                    get_depth_color(bin_info.mean_depth, args.no_grey_depth, depth_palette)
+                } else if args.color_by_mean_inversion_rate {
+                    let inv_r = (bin_info.mean_inv * 255.0).min(255.0) as u8;
+                    (inv_r, 0, 0)
+                } else if args.color_by_uncalled_bases {
+                    let green = (bin_info.mean_uncalled * 255.0).min(255.0) as u8;
+                    (0, green, 0)
+                } else if args.show_strand {
+                    let apply_strand = args
+                        .alignment_prefix
+                        .as_ref()
+                        .is_none_or(|prefix| path.name.starts_with(prefix));
+                    if apply_strand {
+                        if bin_info.mean_inv > 0.5 {
+                            (200, 50, 50)
+                        } else {
+                            (50, 50, 200)
+                        }
+                    } else {
+                        (path_r, path_g, path_b)
+                    }
+                } else {
+                    (path_r, path_g, path_b)
+                };
+
+                // Apply darkness gradient if enabled
+                let (r, g, b) = if args.change_darkness && highlight_nodes.is_none() {
+                    let apply_darkness = args
+                        .alignment_prefix
+                        .as_ref()
+                        .is_none_or(|prefix| path.name.starts_with(prefix));
+                    if apply_darkness && darkness_length > 0 {
+                        let pos_factor = bin_info.mean_pos / darkness_length as f64;
+                        let darkness = if bin_info.mean_inv > 0.5 {
+                            1.0 - pos_factor
+                        } else {
+                            pos_factor
+                        };
+                        darkness_shade((r, g, b), darkness, args.white_to_black, theme.linear)
+                    } else {
+                        (r, g, b)
+                    }
+                } else {
+                    (r, g, b)
+                };
+                let (r, g, b) = dim_if((r, g, b));
+
+                add_path_step(
+                    &mut buffer,
+                    total_width,
+                    x + path_names_width,
+                    y_start,
+                    pix_per_path,
+                    r,
+                    g,
+                    b,
+                    args.no_path_borders,
+                    args.black_path_borders || reference,
+                );
+            }
+
+            // Outline bins whose color averages forward and reverse steps
+            if strand_colored(args, &path.name) {
+                let count = pd.bins.values().filter(|b| b.mixed_strand).count();
+                mixed_bins.fetch_add(count, Ordering::Relaxed);
+                if args.flag_mixed_bins {
+                    draw_mixed_runs(
+                        &mut buffer,
+                        total_width,
+                        path_names_width,
+                        y_start,
+                        pix_per_path,
+                        &mixed_runs(&pd.bins, viz_width),
+                    );
+                }
+            }
+
+            // Draw link lines between discontinuous path pieces
+            if let Some(link_width) = args.link_path_pieces {
+                let (path_r, path_g, path_b) = dim_if((path_r, path_g, path_b));
+                let mut sorted_bins: Vec<usize> = pd.bins.keys().copied().collect();
+                sorted_bins.sort();
+
+                if sorted_bins.len() > 1 {
+                    let link_height = ((pix_per_path as f64 * link_width).round() as u32).max(1);
+                    let link_y = y_start + pix_per_path / 2 - link_height / 2;
+
+                    for i in 1..sorted_bins.len() {
+                        let prev_bin = sorted_bins[i - 1];
+                        let curr_bin = sorted_bins[i];
+
+                        if curr_bin > prev_bin + 1 {
+                            let x_start =
+                                (prev_bin as u32 + 1).min(viz_width - 1) + path_names_width;
+                            let x_end = (curr_bin as u32).min(viz_width - 1) + path_names_width;
+
+                            for lx in x_start..x_end {
+                                for dy in 0..link_height {
+                                    let y = link_y + dy;
+                                    let idx = ((y * total_width + lx) * 4) as usize;
+                                    if idx + 3 < buffer.len() {
+                                        buffer[idx] = path_r;
+                                        buffer[idx + 1] = path_g;
+                                        buffer[idx + 2] = path_b;
+                                        buffer[idx + 3] = 255;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(diversity) = diversity.as_deref().filter(|_| !args.pack_paths) {
+        shade_columns(
+            &mut buffer,
+            total_width,
+            path_names_width,
+            legend_height,
+            path_space,
+            diversity,
+            &theme,
+        );
+    }
+    if !args.pack_paths {
+        draw_gap_runs(
+            &mut buffer,
+            total_width,
+            path_names_width,
+            legend_height,
+            path_space,
+            &orphan_runs,
+            GapStyle::Hatch,
+            &theme,
+        );
+    }
+
+    // Subrange paths sharing a contig are labeled by its name and their interval rank
+    let name_labels = subrange_labels(&display_paths);
+
+    // Render each path (PNG) - skip if compressed mode or pack_paths mode
+    let mut row_jobs: Vec<RowJob> = Vec::new();
+    // Paths sharing each prefix group's row, whose colors split the row's indicator bars
+    let group_members = path_grouping
+        .as_ref()
+        .map(|pg| pg.members())
+        .unwrap_or_default();
+    let row_members = |path_idx: usize| match path_grouping {
+        Some(ref pg) => group_members[pg.path_to_group[path_idx] as usize].clone(),
+        None => vec![path_idx],
+    };
+    let mut prev_cluster_id: Option<usize> = None;
+    let mut cumulative_gap: u32 = 0;
+    let cluster_gap = args.cluster_gap;
+
+    // Lay out rows serially, since prefix groups and cluster gaps depend on path order
+    for (path_idx, path) in display_paths.iter().enumerate() {
+        // Skip normal rendering in compressed mode or pack_paths mode
+        if args.compressed_mode || args.pack_paths {
+            break;
+        }
+        // Check if grouping is enabled and get group index
+        let (row_idx, base_name, is_first_in_group) = if let Some(ref pg) = path_grouping {
+            let group_idx = pg.path_to_group[path_idx];
+            if group_idx < 0 {
+                // Skip paths that don't match any prefix
+                continue;
+            }
+            let first = !rendered_groups.contains(&group_idx);
+            if first {
+                rendered_groups.insert(group_idx);
+            }
+            (
+                group_idx as u32,
+                pg.prefixes[group_idx as usize].clone(),
+                first,
+            )
+        } else {
+            let label = name_labels.get(&path.name).unwrap_or(&path.name);
+            (path_idx as u32, label.clone(), true)
+        };
+
+        // Add abundance suffix for cluster representatives
+        let display_name = if args.cluster_representatives {
+            if let Some(ref cr) = cluster_result {
+                let cluster_id = cr.cluster_ids[path_idx];
+                let size = cr.cluster_sizes[cluster_id];
+                format!("{} (n={})", base_name, size)
+            } else {
+                base_name
+            }
+        } else {
+            base_name
+        };
+        let display_name = if path_grouping.is_none() && flip_names.contains(&path.name) {
+            format!("{}{}", display_name, FLIP_MARKER)
+        } else {
+            display_name
+        };
+        let display_name = match path.piece {
+            Some(piece) if path_grouping.is_none() => display_name + &piece_label(piece),
+            _ => display_name,
+        };
+
+        // Add gap before new cluster (except first)
+        if let Some(ref cr) = cluster_result {
+            let cluster_id = cr.cluster_ids[path_idx];
+            if prev_cluster_id.is_some_and(|prev| prev != cluster_id) {
+                cumulative_gap += cluster_gap;
+            }
+            prev_cluster_id = Some(cluster_id);
+        }
+
+        let y_start = legend_height + row_idx * row_pitch + cumulative_gap;
+        row_jobs.push((path_idx, y_start, is_first_in_group, display_name));
+    }
+
+    // Merge rows that overlap (paths sharing a prefix group row) into bands sorted by y
+    row_jobs.sort_by_key(|job| job.1);
+    let mut bands: Vec<(u32, u32, Vec<RowJob>)> = Vec::new();
+    for job in row_jobs {
+        let y_end = (job.1 + pix_per_path.max(1)).min(max_possible_height);
+        match bands.last_mut() {
+            Some(band) if job.1 < band.1 => {
+                band.1 = band.1.max(y_end);
+                band.2.push(job);
+            }
+            _ => bands.push((job.1, y_end, vec![job])),
+        }
+    }
+
+    // Hand every band its own disjoint slice of the path and name buffers
+    let row_bytes = total_width as usize * 4;
+    let names_row_bytes = path_names_width as usize * 4;
+    let mut rest: &mut [u8] = &mut buffer;
+    let mut names_rest: &mut [u8] = &mut path_names_buffer;
+    let mut consumed = 0u32;
+    let mut band_slices = Vec::with_capacity(bands.len());
+    for (y0, y1, jobs) in bands {
+        let skip = (y0 - consumed) as usize;
+        let take = (y1 - y0) as usize;
+        let tail = std::mem::take(&mut rest);
+        let (_, tail) = tail.split_at_mut((skip * row_bytes).min(tail.len()));
+        let (band, tail) = tail.split_at_mut((take * row_bytes).min(tail.len()));
+        rest = tail;
+        let names_tail = std::mem::take(&mut names_rest);
+        let (_, names_tail) =
+            names_tail.split_at_mut((skip * names_row_bytes).min(names_tail.len()));
+        let (names_band, names_tail) =
+            names_tail.split_at_mut((take * names_row_bytes).min(names_tail.len()));
+        names_rest = names_tail;
+        consumed = y1;
+        band_slices.push((band, names_band, y0, jobs));
+    }
+
+    // Render bands in parallel; y coordinates inside a band are relative to its first row
+    band_slices
+        .into_par_iter()
+        .for_each(|(buffer, path_names_buffer, band_y, jobs)| {
+            for (path_idx, row_y, is_first_in_group, display_name) in jobs {
+                let path = display_paths[path_idx];
+                let y_start = row_y - band_y;
+
+                // Render the row's indicator bars on the left, once per row; a prefix
+                // group's bars are split between the colors of all its paths
+                if is_first_in_group {
+                    let members = row_members(path_idx);
+                    let mut bars = Vec::new();
+                    if let Some(ref cr) = cluster_result {
+                        let colors: Vec<_> = members
+                            .iter()
+                            .map(|&i| cr.cluster_color(cr.cluster_ids[i], args, &theme))
+                            .collect();
+                        bars.push((dendrogram_width, cluster_bar_width, colors));
+                    }
+
+                    // Annotation indicator bar (after cluster bar + gap)
+                    if let Some(ref ann) = annotations {
+                        let colors: Vec<_> = members
+                            .iter()
+                            .map(|&i| ann.get_color(ann.get_annotation(&display_paths[i].name)))
+                            .collect();
+                        let ann_bar_x_start = dendrogram_width + cluster_bar_width + bar_gap;
+                        bars.push((ann_bar_x_start, annotation_bar_width, colors));
+                    }
+
+                    for (bar_x, bar_width, colors) in bars {
+                        for (x0, width, (r, g, b)) in bar_stripes(&colors, bar_width) {
+                            for x in (bar_x + x0)..(bar_x + x0 + width) {
+                                add_path_step(
+                                    path_names_buffer,
+                                    path_names_width,
+                                    x,
+                                    y_start,
+                                    pix_per_path,
+                                    r,
+                                    g,
+                                    b,
+                                    true,
+                                    false,
+                                ); // no border for indicator bars
+                            }
+                        }
+                    }
+
+                    // Cluster id stacked vertically inside the cluster bar
+                    if let Some(cr) = cluster_result.as_ref().filter(|_| args.cluster_bar_labels) {
+                        if let Some(label) = cluster_bar_label(cr, path_idx, pix_per_path) {
+                            let color = cr.cluster_color(cr.cluster_ids[path_idx], args, &theme);
+                            let (r, g, b) = contrast_text_color(color);
+                            let label_height = label.len() as u32 * BAR_LABEL_CHAR;
+                            let x = dendrogram_width
+                                + cluster_bar_width.saturating_sub(BAR_LABEL_CHAR) / 2;
+                            let y = y_start + (pix_per_path - label_height) / 2;
+                            for (i, c) in label.chars().enumerate() {
+                                write_char(
+                                    path_names_buffer,
+                                    path_names_width,
+                                    x,
+                                    y + i as u32 * BAR_LABEL_CHAR,
+                                    glyph_for_char(c),
+                                    BAR_LABEL_CHAR,
+                                    r,
+                                    g,
+                                    b,
+                                );
+                            }
+                        }
+                    }
+                }
+
+                let (path_r, path_g, path_b) = if let Some(ref colors) = custom_colors {
+                    colors.get(&path.name).copied().unwrap_or(theme.muted(200)) // Light grey for non-specified paths
+                } else {
+                    compute_path_color(&path.name, args.color_by_prefix)
+                };
+                let dimmed = highlight_paths
+                    .as_ref()
+                    .is_some_and(|hp| !hp.contains(&path.name));
+                let fade = row_fade.get(&path.name).copied().unwrap_or(1.0);
+                let dim_if =
+                    |c: (u8, u8, u8)| theme.blend(if dimmed { theme.dim(c) } else { c }, fade);
+                let reference = reference_names.contains(path.name.as_str());
+
+                // Render path name (only once per group) - PNG normal paths
+                if is_first_in_group && text_only_width > 0 && pix_per_path >= 8 {
+                    let display_name =
+                        truncate_name(&display_name, max_num_of_chars, args.truncate_names);
+                    let num_of_chars = display_name.chars().count();
+                    let left_padding = max_num_of_chars - num_of_chars;
+
+                    if args.color_path_names_background {
+                        let (path_r, path_g, path_b) = dim_if((path_r, path_g, path_b));
+                        for x in (left_padding as u32 * char_size
+                            + dendrogram_width
+                            + cluster_bar_width
+                            + bar_gap
+                            + annotation_bar_width)
+                            ..path_names_width
+                        {
+                            add_path_step(
+                                path_names_buffer,
+                                path_names_width,
+                                x,
+                                y_start,
+                                pix_per_path,
+                                path_r,
+                                path_g,
+                                path_b,
+                                args.no_path_borders,
+                                args.black_path_borders || reference,
+                            );
+                        }
+                    }
+
+                    // Pick a legible text color when the name sits on the path color
+                    let (text_r, text_g, text_b) = if args.color_path_names_background {
+                        contrast_text_color(dim_if((path_r, path_g, path_b)))
+                    } else {
+                        dim_if(theme.foreground)
+                    };
+
+                    let base_y = y_start + pix_per_path / 2 - char_size / 2;
+                    for (i, c) in display_name.chars().enumerate() {
+                        // +3 offset to match odgi's text positioning, shifted by dendrogram + cluster_bar + annotation_bar
+                        let base_x = (left_padding + i) as u32 * char_size
+                            + 3
+                            + dendrogram_width
+                            + cluster_bar_width
+                            + annotation_bar_width;
+                        let char_data = glyph_for_char(c);
+                        // Reference names are bold: the glyph again, one pixel to the right
+                        for dx in 0..=u32::from(reference) {
+                            write_char(
+                                path_names_buffer,
+                                path_names_width,
+                                base_x + dx,
+                                base_y,
+                                char_data,
+                                char_size,
+                                text_r,
+                                text_g,
+                                text_b,
+                            );
+                        }
+                    }
+                }
+
+                // Calculate current path length for darkness gradient
+                let path_length: u64 = path
+                    .steps
+                    .iter()
+                    .map(|step| {
+                        let seg_id = step.segment_id as usize;
+                        if seg_id < graph.segments.len() {
+                            graph.segments[seg_id].sequence_len
+                        } else {
+                            0
+                        }
+                    })
+                    .sum();
+                let darkness_length = if args.longest_path {
+                    max_path_length
+                } else {
+                    path_length
+                };
+
+                // Compute bins for this path (PNG rendering)
+                let mut bins = drawn_bins(path);
+                assign_frequencies(&mut bins, path, graph, bin_width, node_frequency.as_deref());
+                if args.color_by_rank {
+                    assign_ranks(&mut bins, path, graph, bin_width);
+                }
+
+                if args.gap_style != GapStyle::None {
+                    let bar_height = if args.no_path_borders || pix_per_path < 3 {
+                        pix_per_path
+                    } else {
+                        pix_per_path - 1
+                    };
+                    draw_gap_runs(
+                        buffer,
+                        total_width,
+                        path_names_width,
+                        y_start,
+                        bar_height,
+                        &gap_runs(bins.keys(), viz_width),
+                        args.gap_style,
+                        &theme,
+                    );
+                }
+
+                // Render bins (PNG)
+                for (bin_idx, bin_info) in &bins {
+                    let x = (*bin_idx as u32).min(viz_width - 1);
+
+                    // Determine color for this bin
+                    let (r, g, b) = if highlight_nodes.is_some() {
+                        // Highlighting mode: red for highlighted bins, grey for others
+                        if bin_info.highlighted {
+                            (255, 0, 0)
+                        } else {
+                            theme.muted(180)
+                        }
+                    } else if args.color_by_frequency_class {
+                        frequency_class_color(bin_info.frequency, path_count as usize, args)
+                    } else if args.color_by_rank {
+                        rank_color(bin_info.rank, &theme)
+                    } else if args.color_by_mean_depth {
+                        // Use colorbrewer palette based on depth
+                        get_depth_color(bin_info.mean_depth, args.no_grey_depth, depth_palette)
+                    } else if args.color_by_mean_inversion_rate {
+                        // Black to red gradient based on inversion rate
+                        let inv_r = (bin_info.mean_inv * 255.0).min(255.0) as u8;
+                        (inv_r, 0, 0)
+                    } else if args.color_by_uncalled_bases {
+                        // Black to green gradient based on proportion of uncalled bases (N's)
+                        let green = (bin_info.mean_uncalled * 255.0).min(255.0) as u8;
+                        (0, green, 0)
+                    } else if args.show_strand {
+                        // Check if alignment_prefix applies (if set, only apply to matching paths)
+                        let apply_strand = args
+                            .alignment_prefix
+                            .as_ref()
+                            .is_none_or(|prefix| path.name.starts_with(prefix));
+
+                        if apply_strand {
+                            if bin_info.mean_inv > 0.5 {
+                                (200, 50, 50) // Red for reverse
+                            } else {
+                                (50, 50, 200) // Blue for forward
+                            }
+                        } else {
+                            (path_r, path_g, path_b)
+                        }
+                    } else {
+                        (path_r, path_g, path_b)
+                    };
+
+                    // Apply darkness gradient if enabled
+                    let (r, g, b) = if args.change_darkness && highlight_nodes.is_none() {
+                        // Check if alignment_prefix applies
+                        let apply_darkness = args
+                            .alignment_prefix
+                            .as_ref()
+                            .is_none_or(|prefix| path.name.starts_with(prefix));
+
+                        if apply_darkness && darkness_length > 0 {
+                            // Calculate darkness factor based on position
+                            let pos_factor = bin_info.mean_pos / darkness_length as f64;
+                            // In binned mode: inversion rate determines gradient direction
+                            let darkness = if bin_info.mean_inv > 0.5 {
+                                1.0 - pos_factor // gradient from right for inverted
+                            } else {
+                                pos_factor // gradient from left for forward
+                            };
+
+                            darkness_shade((r, g, b), darkness, args.white_to_black, theme.linear)
+                        } else {
+                            (r, g, b)
+                        }
+                    } else {
+                        (r, g, b)
+                    };
+                    let (r, g, b) = dim_if((r, g, b));
+
+                    add_path_step(
+                        buffer,
+                        total_width,
+                        x + path_names_width,
+                        y_start,
+                        pix_per_path,
+                        r,
+                        g,
+                        b,
+                        args.no_path_borders,
+                        args.black_path_borders || reference,
+                    );
+                }
+
+                // Paint this row's cluster in each window as a band along its top edge
+                if let Some(ref wc) = window_clusters {
+                    let size = args.cluster_windows.unwrap_or(1) as f64;
+                    let band_height = (pix_per_path / 4).max(1);
+                    for (w, assignment) in wc.iter().enumerate() {
+                        let Some(cluster) = assignment[path_idx] else {
+                            continue;
+                        };
+                        let x_start = ((w as f64 * size) / bin_width) as u32;
+                        let x_end =
+                            ((((w + 1) as f64 * size) / bin_width).ceil() as u32).min(viz_width);
+                        let (r, g, b) = dim_if(get_cluster_color(cluster));
+                        for y in y_start..y_start + band_height {
+                            for x in x_start..x_end.max(x_start + 1).min(viz_width) {
+                                let idx = ((y * total_width + x + path_names_width) * 4) as usize;
+                                buffer[idx..idx + 3].copy_from_slice(&[r, g, b]);
+                            }
+                        }
+                    }
+                }
+
+                // Outline the medoid row of each cluster
+                if medoid_names.contains(&path.name) {
+                    let y_end = y_start + pix_per_path.max(1) - 1;
+                    for x in path_names_width..total_width {
+                        for y in [y_start, y_end] {
+                            let idx = ((y * total_width + x) * 4) as usize;
+                            buffer[idx..idx + 3].copy_from_slice(&[fg_r, fg_g, fg_b]);
+                        }
+                    }
+                    for y in y_start..=y_end {
+                        for x in [path_names_width, total_width - 1] {
+                            let idx = ((y * total_width + x) * 4) as usize;
+                            buffer[idx..idx + 3].copy_from_slice(&[fg_r, fg_g, fg_b]);
+                        }
+                    }
+                }
+
+                // Outline bins whose color averages forward and reverse steps
+                if strand_colored(args, &path.name) {
+                    let count = bins.values().filter(|b| b.mixed_strand).count();
+                    mixed_bins.fetch_add(count, Ordering::Relaxed);
+                    if args.flag_mixed_bins {
+                        draw_mixed_runs(
+                            buffer,
+                            total_width,
+                            path_names_width,
+                            y_start,
+                            pix_per_path,
+                            &mixed_runs(&bins, viz_width),
+                        );
+                    }
+                }
+
+                if args
+                    .row_axes
+                    .is_some_and(|every| (path_idx as u32).is_multiple_of(every))
+                {
+                    draw_row_axis(
+                        buffer,
+                        total_width,
+                        path_names_width,
+                        y_start,
+                        pix_per_path,
+                        &row_axis(&bins, ruler_step, viz_width),
+                        (fg_r, fg_g, fg_b),
+                    );
+                }
+
+                // Draw link lines between discontinuous path pieces
+                if let Some(link_width) = args.link_path_pieces {
+                    let (path_r, path_g, path_b) = dim_if((path_r, path_g, path_b));
+                    let mut sorted_bins: Vec<usize> = bins.keys().copied().collect();
+                    sorted_bins.sort();
+
+                    if sorted_bins.len() > 1 {
+                        let link_height =
+                            ((pix_per_path as f64 * link_width).round() as u32).max(1);
+                        let link_y = y_start + pix_per_path / 2 - link_height / 2;
+
+                        for i in 1..sorted_bins.len() {
+                            let prev_bin = sorted_bins[i - 1];
+                            let curr_bin = sorted_bins[i];
+
+                            // If there's a gap between bins, draw a connecting line
+                            if curr_bin > prev_bin + 1 {
+                                let x_start =
+                                    (prev_bin as u32 + 1).min(viz_width - 1) + path_names_width;
+                                let x_end = (curr_bin as u32).min(viz_width - 1) + path_names_width;
+
+                                // Draw thin horizontal line
+                                for x in x_start..x_end {
+                                    for dy in 0..link_height {
+                                        let y = link_y + dy;
+                                        let idx = ((y * total_width + x) * 4) as usize;
+                                        if idx + 3 < buffer.len() {
+                                            buffer[idx] = path_r;
+                                            buffer[idx + 1] = path_g;
+                                            buffer[idx + 2] = path_b;
+                                            buffer[idx + 3] = 255;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    warn_mixed_bins(args, mixed_bins.into_inner());
+
+    // Arcs over large within-path jumps, in the band between the legend and the rows
+    if let Some(max_jump) = args.jump_arcs {
+        let baseline = legend_height - 1;
+        for (path_idx, a, b, h) in jump_arcs(&display_paths, graph, max_jump, bin_width, viz_width)
+        {
+            let path = display_paths[path_idx];
+            let color = match custom_colors {
+                Some(ref colors) => colors.get(&path.name).copied().unwrap_or(theme.muted(200)),
+                None => compute_path_color(&path.name, args.color_by_prefix),
+            };
+            // Quadratic Bezier from (a, 0) over (mid, 2h) to (b, 0), peaking at h
+            let steps = ((b - a) as usize * 2).max(8);
+            for i in 0..=steps {
+                let t = i as f64 / steps as f64;
+                let x = a + (b - a) * t;
+                let y = 2.0 * h * 2.0 * t * (1.0 - t);
+                let x = (x.round() as u32).min(viz_width.saturating_sub(1));
+                add_edge_point(
+                    &mut buffer,
+                    total_width,
+                    x + path_names_width,
+                    baseline - (y.round() as u32).min(baseline),
+                    0,
+                    color,
+                );
+            }
+        }
+    }
+
+    // --compress-invariant: a break cut through the rows at each shrunk run (PNG)
+    for &pos in &invariant_breaks {
+        let x =
+            ((pos as f64 / bin_width) as i32).min(viz_width as i32 - 1) + path_names_width as i32;
+        let (top, bottom) = (
+            legend_height as i32,
+            (legend_height + path_space) as i32 - 1,
+        );
+        let (r, g, b) = theme.background;
+        for dx in [-2, 1] {
+            draw_line(
+                &mut buffer,
+                total_width,
+                x + dx - 1,
+                bottom,
+                x + dx + 1,
+                top,
+                r,
+                g,
+                b,
+            );
+        }
+    }
+
+    // Calculate x-axis dimensions if enabled
+    let axis_char_size = AXIS_LABEL_HEIGHT; // Use native 5x8 font
+    let axis_tick_height = AXIS_TICK_HEIGHT;
+    let axis_padding = AXIS_PADDING;
+    let axis_total_height = axis_height(args);
+
+    // Render x-axis if requested (between paths and edges)
+    let x_axis = args.x_axis.as_ref().map(|a| path_resolver.canonical(a));
+    if let Some(ref coord_system) = x_axis {
+        let axis_y = legend_height + path_space + args.panel_gap + axis_padding;
+
+        // Draw axis label on the left (in path_names_buffer if available)
+        // Strip the :start-end range from the label when showing absolute coordinates
+        let label_text = if coord_system.to_lowercase() == "pangenomic" {
+            "pangenomic".to_string()
+        } else if args.x_axis_absolute {
+            strip_subpath_range(coord_system).to_string()
+        } else {
+            coord_system.clone()
+        };
+
+        // Draw label text in path_names_buffer (aligned like path names, bold effect)
+        if path_names_width > 0 && text_only_width > 0 {
+            let max_label_chars = ((text_only_width) / char_size) as usize;
+            let display_label: String =
+                if label_text.chars().count() > max_label_chars && max_label_chars > 3 {
+                    format!(
+                        "{}...",
+                        &label_text
+                            .chars()
+                            .take(max_label_chars.saturating_sub(3))
+                            .collect::<String>()
+                    )
+                } else {
+                    label_text.chars().take(max_label_chars).collect()
+                };
+
+            // Center vertically in axis area, similar to path name centering
+            let label_y = axis_y + axis_total_height / 2 - char_size / 2;
+            let left_padding = max_label_chars.saturating_sub(display_label.chars().count());
+
+            for (i, c) in display_label.chars().enumerate() {
+                // +3 offset to match path name positioning, shifted by dendrogram + cluster_bar + annotation_bar
+                let char_x = (left_padding + i) as u32 * char_size
+                    + 3
+                    + dendrogram_width
+                    + cluster_bar_width
+                    + annotation_bar_width;
+                let char_data = glyph_for_char(c);
+                // Draw twice with 1-pixel offset for bold effect
+                write_char(
+                    &mut path_names_buffer,
+                    path_names_width,
+                    char_x,
+                    label_y,
+                    char_data,
+                    char_size,
+                    fg_r,
+                    fg_g,
+                    fg_b,
+                );
+                if char_x + 1 < path_names_width {
+                    write_char(
+                        &mut path_names_buffer,
+                        path_names_width,
+                        char_x + 1,
+                        label_y,
+                        char_data,
+                        char_size,
+                        fg_r,
+                        fg_g,
+                        fg_b,
+                    );
+                }
+            }
+        }
+
+        // Calculate tick positions and labels
+        let num_ticks = args.x_ticks.max(2) as usize;
+        let is_pangenomic = coord_system.to_lowercase() == "pangenomic";
+
+        // Warn if --x-axis-absolute is used with pangenomic
+        if args.x_axis_absolute && is_pangenomic {
+            debug!("--x-axis-absolute has no effect with pangenomic coordinates");
+        }
+
+        // For pangenomic coordinates, use total graph length
+        // For path-based coordinates, find the path and use its length
+        // Also calculate pixel range where the path actually appears
+        let (coord_start, coord_end, pixel_start, pixel_end) = if is_pangenomic {
+            (
+                graph.origin.pangenomic,
+                graph.origin.pangenomic + len_to_visualize,
+                0u32,
+                viz_width,
+            )
+        } else if let Some(path) = graph.paths.iter().find(|p| p.name == *coord_system) {
+            // Calculate path length and pangenomic positions from its steps
+            let mut path_len: u64 = 0;
+            let mut pangenomic_start: Option<u64> = None;
+            let mut pangenomic_end: u64 = 0;
+
+            for step in &path.steps {
+                let seg_id = step.segment_id as usize;
+                if seg_id < graph.segments.len() {
+                    let seg_len = graph.segments[seg_id].sequence_len;
+                    let seg_offset = graph.segment_offsets[seg_id];
+
+                    // Track first segment's pangenomic position
+                    if pangenomic_start.is_none() {
+                        pangenomic_start = Some(seg_offset);
+                    }
+                    // Track last segment's end position
+                    pangenomic_end = seg_offset + seg_len;
+                    path_len += seg_len;
+                }
+            }
+
+            let pangenomic_start = pangenomic_start.unwrap_or(0);
+
+            // Convert pangenomic positions to pixel positions
+            let pix_start = ((pangenomic_start as f64 / bin_width) as u32).min(viz_width);
+            let pix_end = ((pangenomic_end as f64 / bin_width) as u32).min(viz_width);
+
+            // Add subpath start offset if --x-axis-absolute is enabled, and the part of the
+            // path before the --path-range window
+            let offset = if args.x_axis_absolute {
+                parse_subpath_start(coord_system)
+            } else {
+                0
+            } + graph.origin.paths.get(&path.name).copied().unwrap_or(0);
+            (offset, offset + path_len, pix_start, pix_end)
+        } else {
+            debug!(
+                "Path '{}' not found, using pangenomic coordinates",
+                coord_system
+            );
+            (0u64, len_to_visualize, 0u32, viz_width)
+        };
+
+        // Calculate the pixel width of the path's range
+        let path_pixel_width = pixel_end.saturating_sub(pixel_start);
+
+        // Draw horizontal axis line only where the path exists (PNG)
+        let axis_line_start = path_names_width + pixel_start;
+        let axis_line_end = path_names_width + pixel_end;
+        for x in axis_line_start..axis_line_end {
+            let idx = ((axis_y * total_width + x) * 4) as usize;
+            if idx + 3 < buffer.len() {
+                buffer[idx] = fg_r;
+                buffer[idx + 1] = fg_g;
+                buffer[idx + 2] = fg_b;
+                buffer[idx + 3] = 255;
+            }
+        }
+
+        // Draw ticks and labels only where the path exists
+        for i in 0..num_ticks {
+            let t = i as f64 / (num_ticks - 1) as f64;
+            // Map tick position to the path's pixel range
+            let x_pos = path_names_width
+                + pixel_start
+                + (t * (path_pixel_width as f64 - 1.0).max(0.0)) as u32;
+            let coord_value = coord_start as f64 + t * (coord_end - coord_start) as f64;
+
+            // Draw tick mark
+            for ty in 0..axis_tick_height {
+                let idx = (((axis_y + ty) * total_width + x_pos) * 4) as usize;
+                if idx + 3 < buffer.len() {
+                    buffer[idx] = fg_r;
+                    buffer[idx + 1] = fg_g;
+                    buffer[idx + 2] = fg_b;
+                    buffer[idx + 3] = 255;
+                }
+            }
+
+            // Format and draw tick label
+            let label = format_coordinate(coord_value as u64);
+            let label_y = axis_y + axis_tick_height;
+
+            // Calculate label x position based on tick position
+            let label_width = (label.len() as u32) * axis_char_size;
+            let label_x = if i == 0 {
+                x_pos // Left-aligned for first tick
+            } else if i == num_ticks - 1 {
+                x_pos.saturating_sub(label_width) // Right-aligned for last tick
+            } else {
+                x_pos.saturating_sub(label_width / 2) // Center-aligned for middle ticks
+            };
+
+            for (j, c) in label.chars().enumerate() {
+                let char_x = label_x + (j as u32) * axis_char_size;
+                if char_x + axis_char_size <= total_width {
+                    let char_data = glyph_for_char(c);
+                    // Draw twice with 1-pixel offset for bold effect
+                    write_char(
+                        &mut buffer,
+                        total_width,
+                        char_x,
+                        label_y,
+                        char_data,
+                        axis_char_size,
+                        fg_r,
+                        fg_g,
+                        fg_b,
+                    );
+                    if char_x + 1 + axis_char_size <= total_width {
+                        write_char(
+                            &mut buffer,
+                            total_width,
+                            char_x + 1,
+                            label_y,
+                            char_data,
+                            axis_char_size,
+                            fg_r,
+                            fg_g,
+                            fg_b,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // Adjust path_space to include legend height, axis height and panel gaps for edge rendering
+    let axis_panel_height = if args.x_axis.is_some() {
+        axis_total_height + args.panel_gap
+    } else {
+        0
+    };
+
+    // Diversity strip below the axis (PNG)
+    if args.diversity_track {
+        let track_y = legend_height + path_space + axis_panel_height + args.panel_gap;
+        let diversity = bin_diversity(&display_paths, graph, bin_width, viz_width as usize);
+        for (x, &d) in diversity.iter().enumerate() {
+            let color = theme.blend(theme.foreground, d);
+            for y in track_y..track_y + DIVERSITY_TRACK_HEIGHT {
+                add_edge_point(
+                    &mut buffer,
+                    total_width,
+                    x as u32 + path_names_width,
+                    y,
+                    0,
+                    color,
+                );
+            }
+        }
+        if path_names_width > 0 && text_only_width > 0 {
+            let label = "diversity";
+            let max_label_chars = (text_only_width / char_size) as usize;
+            let left_padding = max_label_chars.saturating_sub(label.len());
+            let label_y = track_y + DIVERSITY_TRACK_HEIGHT / 2 - char_size / 2;
+            for (i, c) in label.chars().take(max_label_chars).enumerate() {
+                let char_x = (left_padding + i) as u32 * char_size
+                    + 3
+                    + dendrogram_width
+                    + cluster_bar_width
+                    + annotation_bar_width;
+                write_char(
+                    &mut path_names_buffer,
+                    path_names_width,
+                    char_x,
+                    label_y,
+                    glyph_for_char(c),
+                    char_size,
+                    fg_r,
+                    fg_g,
+                    fg_b,
+                );
+            }
+        }
+    }
+    let axis_panel_height = axis_panel_height + diversity_track_panel(args);
+
+    // Core bar below the axis and diversity strip (PNG)
+    if let Some(ref core) = core {
+        let track_y = legend_height + path_space + axis_panel_height + args.panel_gap;
+        for (x, _) in core.iter().enumerate().filter(|&(_, &is_core)| is_core) {
+            for y in track_y..track_y + CORE_TRACK_HEIGHT {
+                add_edge_point(
+                    &mut buffer,
+                    total_width,
+                    x as u32 + path_names_width,
+                    y,
+                    0,
+                    theme.foreground,
+                );
+            }
+        }
+    }
+    let axis_panel_height = axis_panel_height + core_track_panel(args);
+
+    // Node frequency spectrum below the other strips (PNG)
+    if args.frequency_spectrum {
+        let track_y = legend_height + path_space + axis_panel_height + args.panel_gap;
+        let spectrum = frequency_spectrum(
+            &node_frequencies(&display_paths, graph),
+            display_paths.len(),
+        );
+        let bottom = track_y + FREQUENCY_SPECTRUM_HEIGHT;
+        for (x_start, x_end, bar) in spectrum_bars(&spectrum, viz_width, FREQUENCY_SPECTRUM_HEIGHT)
+        {
+            for x in x_start..x_end {
+                for y in bottom - bar..bottom {
+                    add_edge_point(
+                        &mut buffer,
+                        total_width,
+                        x + path_names_width,
+                        y,
+                        0,
+                        theme.foreground,
+                    );
+                }
+            }
+        }
+        if path_names_width > 0 && text_only_width > 0 {
+            let label = "spectrum";
+            let max_label_chars = (text_only_width / char_size) as usize;
+            let left_padding = max_label_chars.saturating_sub(label.len());
+            let label_y = track_y + FREQUENCY_SPECTRUM_HEIGHT / 2 - char_size / 2;
+            for (i, c) in label.chars().take(max_label_chars).enumerate() {
+                let char_x = (left_padding + i) as u32 * char_size
+                    + 3
+                    + dendrogram_width
+                    + cluster_bar_width
+                    + annotation_bar_width;
+                write_char(
+                    &mut path_names_buffer,
+                    path_names_width,
+                    char_x,
+                    label_y,
+                    glyph_for_char(c),
+                    char_size,
+                    fg_r,
+                    fg_g,
+                    fg_b,
+                );
+            }
+        }
+    }
+    let axis_panel_height = axis_panel_height + frequency_spectrum_panel(args);
+
+    // Component bands below the other strips, and a separator through the rows at each
+    // component boundary (PNG)
+    if args.separate_components {
+        let track_y = legend_height + path_space + axis_panel_height + args.panel_gap;
+        let columns = component_columns(&components, bin_width, viz_width);
+        for (i, (span, &(x_start, x_end))) in components.iter().zip(&columns).enumerate() {
+            let band = theme.muted(if i % 2 == 0 { 215 } else { 175 });
+            for x in x_start..x_end {
+                for y in track_y..track_y + COMPONENT_TRACK_HEIGHT {
+                    add_edge_point(&mut buffer, total_width, x + path_names_width, y, 0, band);
+                }
+            }
+            if i > 0 {
+                for y in legend_height..legend_height + path_space {
+                    add_edge_point(
+                        &mut buffer,
+                        total_width,
+                        x_start + path_names_width,
+                        y,
+                        0,
+                        theme.foreground,
+                    );
+                }
+            }
+            let max_chars = ((x_end - x_start).saturating_sub(2) / 8) as usize;
+            let label = truncate_name(&span.label, max_chars, args.truncate_names);
+            for (k, c) in label.chars().enumerate() {
+                write_char(
+                    &mut buffer,
+                    total_width,
+                    x_start + path_names_width + 1 + k as u32 * 8,
+                    track_y + 1,
+                    glyph_for_char(c),
+                    8,
+                    fg_r,
+                    fg_g,
+                    fg_b,
+                );
+            }
+        }
+        if path_names_width > 0 && text_only_width > 0 {
+            let label = "components";
+            let max_label_chars = (text_only_width / char_size) as usize;
+            let left_padding = max_label_chars.saturating_sub(label.len());
+            let label_y = (track_y + COMPONENT_TRACK_HEIGHT / 2).saturating_sub(char_size / 2);
+            for (i, c) in label.chars().take(max_label_chars).enumerate() {
+                let char_x = (left_padding + i) as u32 * char_size
+                    + 3
+                    + dendrogram_width
+                    + cluster_bar_width
+                    + annotation_bar_width;
+                write_char(
+                    &mut path_names_buffer,
+                    path_names_width,
+                    char_x,
+                    label_y,
+                    glyph_for_char(c),
+                    char_size,
+                    fg_r,
+                    fg_g,
+                    fg_b,
+                );
+            }
+        }
+    }
+    let axis_panel_height = axis_panel_height + component_track_panel(args);
+    let path_space_with_axis = legend_height + path_space + axis_panel_height + args.panel_gap;
+
+    // Render edges in the bottom area
+    let mut edge_count = 0;
+    for edge in selected_edges(args.edges, graph, &display_paths) {
+        let from_id = edge.from_id as usize;
+        let to_id = edge.to_id as usize;
+
+        if from_id < graph.segments.len() && to_id < graph.segments.len() {
+            let edge_color = edge_colors
+                .as_ref()
+                .and_then(|colors| colors.get(&edge.key()))
+                .copied()
+                .unwrap_or(theme.foreground);
+            // Get positions of from and to segments
+            let from_offset = graph.segment_offsets[from_id];
+            let from_len = graph.segments[from_id].sequence_len;
+            let to_offset = graph.segment_offsets[to_id];
+
+            // Calculate edge endpoints based on orientation
+            // For forward orientation, edge exits from end of segment
+            // For reverse orientation, edge exits from start of segment
+            let a_pos = if edge.from_rev {
+                from_offset as f64 / bin_width
+            } else {
+                (from_offset + from_len) as f64 / bin_width
+            };
+
+            let b_pos = if edge.to_rev {
+                (to_offset + graph.segments[to_id].sequence_len) as f64 / bin_width
+            } else {
+                to_offset as f64 / bin_width
+            };
+
+            let (a, b) = if a_pos < b_pos {
+                (a_pos, b_pos)
+            } else {
+                (b_pos, a_pos)
+            };
+
+            // dist = (b - a) * bin_width (in bp), used for vertical extent
+            // odgi calculates this as integer
+            let dist = ((b - a) * bin_width) as u64;
+
+            // Use round() for x coordinates to match odgi's std::round()
+            let ax = (a.round() as u32).min(viz_width.saturating_sub(1));
+            let bx = (b.round() as u32).min(viz_width.saturating_sub(1));
+
+            // Draw vertical line at a - iterate in world coords, scale to pixels
+            // odgi: for (; i < dist; i += 1.0 / scale_y) { add_point(a, i, ...) }
+            let mut i = 0.0f64;
+            while i < dist as f64 {
+                let y = (i * scale_y_edges).round() as u32;
+                if y < edge_height {
+                    add_edge_point(
+                        &mut buffer,
+                        total_width,
+                        ax + path_names_width,
+                        y,
+                        path_space_with_axis,
+                        edge_color,
+                    );
+                    max_y = max_y.max(path_space_with_axis + y + 1);
+                }
+                i += 1.0 / scale_y_edges;
+            }
+
+            // Draw horizontal line from a to b at height i (where loop ended)
+            let h_y = (i * scale_y_edges).round() as u32;
+            let h = h_y.min(edge_height.saturating_sub(1));
+            let mut x_f = a;
+            while x_f <= b {
+                let x = (x_f.round() as u32).min(viz_width.saturating_sub(1));
+                if x < viz_width {
+                    add_edge_point(
+                        &mut buffer,
+                        total_width,
+                        x + path_names_width,
+                        h,
+                        path_space_with_axis,
+                        edge_color,
+                    );
+                    max_y = max_y.max(path_space_with_axis + h + 1);
+                }
+                x_f += 1.0; // In binned mode, scale_x is effectively 1
+            }
+
+            // Draw vertical line at b
+            let mut j = 0.0f64;
+            while j < dist as f64 {
+                let y = (j * scale_y_edges).round() as u32;
+                if y < edge_height {
+                    add_edge_point(
+                        &mut buffer,
+                        total_width,
+                        bx + path_names_width,
+                        y,
+                        path_space_with_axis,
+                        edge_color,
+                    );
+                }
+                j += 1.0 / scale_y_edges;
+            }
+
+            edge_count += 1;
+        }
+    }
+
+    debug!("Drew {} edges", edge_count);
+
+    // Apply crop - max_y already includes path_space_with_axis, add padding
+    let total_height = (path_space_with_axis + edge_height).min(max_y + bottom_padding);
+
+    // Combine path names and main image
+    if path_names_width > 0 {
+        for y in 0..total_height {
+            for x in 0..path_names_width {
+                let src_idx = ((y * path_names_width + x) * 4) as usize;
+                let dst_idx = ((y * total_width + x) * 4) as usize;
+                if src_idx + 3 < path_names_buffer.len() && dst_idx + 3 < buffer.len() {
+                    buffer[dst_idx] = path_names_buffer[src_idx];
+                    buffer[dst_idx + 1] = path_names_buffer[src_idx + 1];
+                    buffer[dst_idx + 2] = path_names_buffer[src_idx + 2];
+                    buffer[dst_idx + 3] = path_names_buffer[src_idx + 3];
+                }
+            }
+        }
+    }
+
+    // Render annotation legend at the top using full image width (PNG)
+    if let Some(ref ann) = annotations {
+        render_annotation_legend_png(
+            &mut buffer,
+            total_width,
+            0, // legend starts at left edge
+            &filtered_categories,
+            &ann.category_colors,
+            args.legend_height,
+            char_size,
+            theme.foreground,
+        );
+    }
+
+    // Render the cluster legend below the annotation legend (PNG)
+    if let Some(cr) = cluster_result.as_ref().filter(|_| args.cluster_legend) {
+        let (labels, colors) = cluster_legend_entries(cr, args, &theme);
+        let y0 = if annotations.is_some() {
+            args.legend_height
+        } else {
+            0
+        };
+        let offset = ((y0 * total_width * 4) as usize).min(buffer.len());
+        render_annotation_legend_png(
+            &mut buffer[offset..],
+            total_width,
+            0,
+            &labels,
+            &colors,
+            args.legend_height,
+            char_size,
+            theme.foreground,
+        );
+    }
+
+    // Mark --downsample drafts in the row below the legends (PNG)
+    if let Some(n) = args.downsample.filter(|&n| n > 1) {
+        let y0 = if annotations.is_some() {
+            args.legend_height
+        } else {
+            0
+        } + cluster_legend_band(args);
+        let base_y = y0 + args.legend_height.saturating_sub(char_size) / 2;
+        for (i, c) in downsample_label(n).chars().enumerate() {
+            write_char(
+                &mut buffer,
+                total_width,
+                4 + i as u32 * char_size,
+                base_y,
+                glyph_for_char(c),
+                char_size,
+                fg_r,
+                fg_g,
+                fg_b,
+            );
+        }
+    }
+
+    // --names-out moves the left panel to its own file
+    let columns = match args.names_out {
+        Some(ref names_out) if path_names_width > 0 => {
+            write_panel_png(
+                names_out,
+                &buffer,
+                total_width,
+                0..path_names_width,
+                0..total_height,
+                args.margin,
+                &theme,
+                args.quality,
+            )?;
+            info!("Left panel saved to {:?}", names_out);
+            path_names_width..total_width
+        }
+        Some(_) => {
+            warning!("--names-out: there is no left panel to write");
+            0..total_width
+        }
+        None => 0..total_width,
+    };
+
+    if let Some(ref edges_out) = args.edges_out {
+        write_panel_png(
+            edges_out,
+            &buffer,
+            total_width,
+            columns.clone(),
+            path_space_with_axis..total_height,
+            args.margin,
+            &theme,
+            args.quality,
+        )?;
+        info!("Edge panel saved to {:?}", edges_out);
+    }
+
+    // Return cropped buffer, surrounded by the --margin border
+    let margin = args.margin;
+    let out_width = columns.len() as u32 + 2 * margin;
+    let out_height = total_height + 2 * margin;
+    let row_bytes = (total_width * 4) as usize;
+    let out_row_bytes = (out_width * 4) as usize;
+    let mut result = Vec::with_capacity(8 + out_row_bytes * out_height as usize);
+    result.extend_from_slice(&out_width.to_le_bytes());
+    result.extend_from_slice(&out_height.to_le_bytes());
+    let border_row = theme.canvas(out_width, margin);
+    let border_side = theme.canvas(margin, 1);
+    let (left, right) = (columns.start as usize * 4, columns.end as usize * 4);
+    result.extend_from_slice(&border_row);
+    for y in 0..total_height as usize {
+        result.extend_from_slice(&border_side);
+        result.extend_from_slice(&buffer[y * row_bytes + left..y * row_bytes + right]);
+        result.extend_from_slice(&border_side);
+    }
+    result.extend_from_slice(&border_row);
+    Ok(result)
+}
+
+/// Write the `columns` by `rows` part of the RGBA `buffer` (the edge panel for
+/// --edges-out, the left panel for --names-out) as a standalone image, with the same
+/// --margin border as the main image
+fn write_panel_png(
+    out: &Path,
+    buffer: &[u8],
+    width: u32,
+    columns: std::ops::Range<u32>,
+    rows: std::ops::Range<u32>,
+    margin: u32,
+    theme: &Theme,
+    quality: Option<u8>,
+) -> Result<(), GfalookError> {
+    let height = rows.len().max(1) as u32;
+    let mut image = image::RgbImage::from_pixel(
+        columns.len() as u32 + 2 * margin,
+        height + 2 * margin,
+        image::Rgb([theme.background.0, theme.background.1, theme.background.2]),
+    );
+    for (row, y) in rows.enumerate() {
+        for (column, x) in columns.clone().enumerate() {
+            let idx = ((y * width + x) * 4) as usize;
+            if idx + 3 < buffer.len() {
+                let pixel = image::Rgb([buffer[idx], buffer[idx + 1], buffer[idx + 2]]);
+                image.put_pixel(column as u32 + margin, row as u32 + margin, pixel);
+            }
+        }
+    }
+    save_image(&image, out, quality, &[])
+}
+
+/// How `--representative` picks the path shown for each cluster
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Representative {
+    /// Member with the smallest mean distance to the others
+    Medoid,
+    /// Member covering the most base pairs
+    Longest,
+    /// Member listed first in the input
+    First,
+    /// Member closest to the nodes shared by at least half of the cluster
+    ConsensusNearest,
+}
+
+/// Representative of each cluster of `paths` under `--representative`
+fn pick_representatives(
+    args: &Args,
+    paths: &[&GfaPath],
+    result: &ClusteringResult,
+    segment_lengths: &[u64],
+) -> Vec<usize> {
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); result.representatives.len()];
+    for (pos, &idx) in result.ordering.iter().enumerate() {
+        if let Some(cluster) = members.get_mut(result.cluster_ids[pos]) {
+            cluster.push(idx);
+        }
+    }
+    let seg_len = |id: u64| segment_lengths.get(id as usize).copied().unwrap_or(0);
+    let node_set =
+        |idx: usize| -> FxHashSet<u64> { paths[idx].steps.iter().map(|s| s.segment_id).collect() };
+    members
+        .iter_mut()
+        .zip(&result.representatives)
+        .map(|(cluster, &medoid)| {
+            // Input order breaks ties
+            cluster.sort_unstable();
+            match args.representative {
+                _ if cluster.is_empty() => medoid,
+                Representative::Medoid => medoid,
+                Representative::First => cluster[0],
+                Representative::Longest => *cluster
+                    .iter()
+                    .rev()
+                    .max_by_key(|&&i| {
+                        paths[i]
+                            .steps
+                            .iter()
+                            .map(|s| seg_len(s.segment_id))
+                            .sum::<u64>()
+                    })
+                    .unwrap(),
+                Representative::ConsensusNearest => {
+                    let sets: Vec<FxHashSet<u64>> = cluster.iter().map(|&i| node_set(i)).collect();
+                    let mut support: FxHashMap<u64, usize> = FxHashMap::default();
+                    for &node in sets.iter().flatten() {
+                        *support.entry(node).or_insert(0) += 1;
+                    }
+                    let consensus: FxHashSet<u64> = support
+                        .into_iter()
+                        .filter(|&(_, n)| 2 * n >= cluster.len())
+                        .map(|(node, _)| node)
+                        .collect();
+                    // Base-pair weighted Jaccard distance to the consensus node set
+                    let distance = |set: &FxHashSet<u64>| {
+                        let shared: u64 = set.intersection(&consensus).map(|&n| seg_len(n)).sum();
+                        let union: u64 = set.union(&consensus).map(|&n| seg_len(n)).sum();
+                        if union == 0 {
+                            0.0
+                        } else {
+                            1.0 - shared as f64 / union as f64
+                        }
+                    };
+                    let mut best = (f64::MAX, medoid);
+                    for (&idx, set) in cluster.iter().zip(&sets) {
+                        let d = distance(set);
+                        if d < best.0 {
+                            best = (d, idx);
+                        }
+                    }
+                    best.1
+                }
+            }
+        })
+        .collect()
+}
+
+/// Drop the rows of clusters below `--min-cluster-fraction` of the clustered paths;
+/// `pool` is the id of the "unclustered" group, which is always kept
+fn hide_minor_clusters(
+    args: &Args,
+    display_paths: &mut Vec<&GfaPath>,
+    result: ClusteringResult,
+    pool: Option<usize>,
+    dropped: &mut Vec<(String, &'static str)>,
+) -> Result<ClusteringResult, GfalookError> {
+    let Some(fraction) = args.min_cluster_fraction else {
+        return Ok(result);
+    };
+    let total: usize = result
+        .cluster_sizes
+        .iter()
+        .enumerate()
+        .filter(|&(id, _)| Some(id) != pool)
+        .map(|(_, &size)| size)
+        .sum();
+    let is_minor = |id: usize| {
+        Some(id) != pool
+            && result
+                .cluster_sizes
+                .get(id)
+                .is_some_and(|&size| (size as f64) < fraction * total as f64)
+    };
+    let hidden = (0..result.cluster_sizes.len())
+        .filter(|&id| is_minor(id))
+        .count();
+    if hidden == 0 {
+        return Ok(result);
+    }
+    let mut kept_paths = Vec::new();
+    let mut kept_cluster_ids = Vec::new();
+    let mut kept_leaves = vec![
+        None;
+        result
+            .dendrogram
+            .as_ref()
+            .map_or(0, |dg| dg.leaf_order.len())
+    ];
+    for (pos, &path) in display_paths.iter().enumerate() {
+        let cluster_id = result.cluster_ids[pos];
+        if is_minor(cluster_id) {
+            dropped.push((path.name.clone(), "below min-cluster-fraction"));
+            continue;
+        }
+        if let Some(dg) = &result.dendrogram {
+            if let Some(&leaf) = dg.leaf_order.get(pos) {
+                kept_leaves[leaf] = Some(kept_paths.len());
+            }
+        }
+        kept_paths.push(path);
+        kept_cluster_ids.push(cluster_id);
+    }
+    if kept_paths.is_empty() {
+        return Err(GfalookError::EmptySelection(format!(
+            "every cluster holds less than {} of the paths, nothing left to draw",
+            fraction
+        )));
+    }
+    info!(
+        "Hiding {} cluster(s) below {} of {} paths (--min-cluster-fraction)",
+        hidden, fraction, total
+    );
+    *display_paths = kept_paths;
+    Ok(ClusteringResult {
+        ordering: (0..display_paths.len()).collect(),
+        cluster_ids: kept_cluster_ids,
+        dendrogram: result
+            .dendrogram
+            .map(|dg| prune_dendrogram(&dg, &kept_leaves)),
+        ..result
+    })
+}
+
+/// Cluster `paths`, reusing a cached result for the same paths and options
+fn cluster_with_cache(
+    args: &Args,
+    paths: &[&GfaPath],
+    segment_lengths: &[u64],
+    bed_regions: Option<&ClusteringBedRegions>,
+) -> ClusteringResult {
+    let cache_key = cluster_cache::key(args, paths, segment_lengths, bed_regions);
+    match cluster_cache::get(&cache_key, args.cache.as_deref()) {
+        Some(cached) => {
+            info!("Reusing cached clustering of {} paths", paths.len());
+            cached
+        }
+        None => {
+            let result = cluster_paths_by_similarity(
+                paths,
+                segment_lengths,
+                args.cluster_threshold,
+                args.cluster_all_nodes,
+                args.max_clusters,
+                args.dendrogram || args.use_upgma,
+                args.use_upgma,
+                args.upgma_threshold,
+                bed_regions,
+                args.min_cluster_size,
+                args.dbscan_min_pts,
+                args.cluster_bootstrap,
+                args.similarity_level,
+            );
+            cluster_cache::put(&cache_key, args.cache.as_deref(), &result);
+            result
+        }
+    }
+}
+
+/// --cluster-within: cluster each prefix group's paths on their own and lay the groups out
+/// in file order (paths matching no prefix last), numbering clusters on across groups
+fn cluster_within_groups(
+    args: &Args,
+    paths: &[&GfaPath],
+    groups: &AnnotationData,
+    segment_lengths: &[u64],
+) -> ClusteringResult {
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); groups.categories.len() + 1];
+    for (i, path) in paths.iter().enumerate() {
+        let group = groups.get_annotation(&path.name);
+        let g = groups
+            .categories
+            .iter()
+            .position(|c| c == group)
+            .unwrap_or(groups.categories.len());
+        members[g].push(i);
+    }
+
+    let mut merged = ClusteringResult {
+        ordering: Vec::with_capacity(paths.len()),
+        cluster_ids: Vec::with_capacity(paths.len()),
+        num_clusters: 0,
+        representatives: Vec::new(),
+        cluster_sizes: Vec::new(),
+        dendrogram: None,
+        noise_cluster: None,
+        stability: Some(Vec::new()),
+    };
+    for group in members.iter().filter(|m| !m.is_empty()) {
+        let group_paths: Vec<&GfaPath> = group.iter().map(|&i| paths[i]).collect();
+        let result = cluster_with_cache(args, &group_paths, segment_lengths, None);
+        let offset = merged.num_clusters;
+        merged
+            .ordering
+            .extend(result.ordering.iter().map(|&i| group[i]));
+        merged
+            .cluster_ids
+            .extend(result.cluster_ids.iter().map(|&c| c + offset));
+        merged
+            .representatives
+            .extend(result.representatives.iter().map(|&i| group[i]));
+        merged.cluster_sizes.extend(&result.cluster_sizes);
+        merged.num_clusters += result.num_clusters;
+        merged.stability = merged
+            .stability
+            .zip(result.stability)
+            .map(|(mut all, group)| {
+                all.extend(group);
+                all
+            });
+    }
+    info!(
+        "Clustered {} paths within {} prefix groups into {} clusters",
+        paths.len(),
+        members.iter().filter(|m| !m.is_empty()).count(),
+        merged.num_clusters
+    );
+    merged
+}
+
+/// Write cluster assignments with per-path quality metrics: the path's length and step
+/// count, its EDR to its cluster's medoid (NA for the unclustered group), and its EDR to
+/// the nearest medoid of any other cluster (NA when there is none)
+fn write_cluster_tsv(
+    args: &Args,
+    original_paths: &[&GfaPath],
+    cluster_result: &ClusteringResult,
+    segment_lengths: &[u64],
+    bed_regions: Option<&ClusteringBedRegions>,
+) {
+    // Derive TSV path from output path: foo.png -> foo.clusters.tsv
+    let tsv_path = args.out.with_extension("clusters.tsv");
+
+    let sim = similarity_counts(
+        original_paths,
+        segment_lengths,
+        bed_regions,
+        args.cluster_all_nodes,
+        args.similarity_level,
+    );
+    let edr = |i: usize, j: usize| {
+        jaccard_to_edr(weighted_jaccard_similarity(
+            &sim.counts[i],
+            &sim.counts[j],
+            sim.total_bp[i],
+            sim.total_bp[j],
+        ))
+    };
+    let medoids: Vec<(usize, usize)> = cluster_result
+        .representatives
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|&(cluster_id, _)| cluster_result.noise_cluster != Some(cluster_id))
+        .collect();
+    let format_edr = |d: Option<f64>| d.map_or("NA".to_string(), |d| format!("{:.6}", d));
+
+    let rows: Vec<String> = cluster_result
+        .ordering
+        .par_iter()
+        .zip(cluster_result.cluster_ids.par_iter())
+        .map(|(&path_idx, &cluster_id)| {
+            let path = original_paths[path_idx];
+            let bp: u64 = path
+                .steps
+                .iter()
+                .map(|step| segment_lengths.get(step.segment_id as usize).unwrap_or(&0))
+                .sum();
+            let to_medoid = medoids
+                .iter()
+                .find(|&&(c, _)| c == cluster_id)
+                .map(|&(_, medoid)| edr(path_idx, medoid));
+            let to_nearest_other = medoids
+                .iter()
+                .filter(|&&(c, _)| c != cluster_id)
+                .map(|&(_, medoid)| edr(path_idx, medoid))
+                .min_by(f64::total_cmp);
+            format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\n",
+                path.name,
+                cluster_result.cluster_label(cluster_id),
+                bp,
+                path.steps.len(),
+                format_edr(to_medoid),
+                format_edr(to_nearest_other)
+            )
+        })
+        .collect();
+    let mut content =
+        String::from("path.name\tcluster\tbp\tsteps\tmedoid.edr\tnearest.other.medoid.edr\n");
+    content.extend(rows);
+
+    match std::fs::write(&tsv_path, content) {
+        Ok(_) => info!("Cluster assignments saved to {:?}", tsv_path),
+        Err(e) => warning!("could not write cluster TSV: {}", e),
+    }
+}
+
+/// Mean depth per bin for each path, from the bins `drawn_bins` gives the rows
+fn path_depth_matrix(
+    paths: &[&GfaPath],
+    num_bins: usize,
+    drawn_bins: &(dyn Fn(&GfaPath) -> FxHashMap<usize, BinInfo> + Sync),
+) -> Vec<Vec<f64>> {
+    paths
+        .par_iter()
+        .map(|path| {
+            let last = num_bins - 1;
+            let mut depth = vec![0.0; num_bins];
+            // The last column averages its own bin and any past the edge
+            let (mut edge_sum, mut edge_bins) = (0.0, 0);
+            for (bin, info) in drawn_bins(path) {
+                if bin < last {
+                    depth[bin] = info.mean_depth;
+                } else {
+                    edge_sum += info.mean_depth;
+                    edge_bins += 1;
+                }
+            }
+            if edge_bins > 0 {
+                depth[last] = edge_sum / edge_bins as f64;
+            }
+            depth
+        })
+        .collect()
+}
+
+/// Write the paths × bins depth matrix for --export-matrix: `.npz` gets `depth`, `paths`
+/// and `bin_start` arrays, anything else a TSV with one row per path
+fn write_depth_matrix(
+    matrix_path: &Path,
+    display_paths: &[&GfaPath],
+    graph: &Graph,
+    bin_width: f64,
+    viz_width: u32,
+    drawn_bins: &(dyn Fn(&GfaPath) -> FxHashMap<usize, BinInfo> + Sync),
+) -> Result<(), GfalookError> {
+    let start = Instant::now();
+    let num_bins = ((graph.total_length as f64 / bin_width).ceil() as usize)
+        .clamp(1, viz_width.max(1) as usize);
+    let matrix = path_depth_matrix(display_paths, num_bins, drawn_bins);
+    let bin_starts: Vec<u64> = (0..num_bins)
+        .map(|bin| (bin as f64 * bin_width) as u64)
+        .collect();
+
+    let is_npz = matrix_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("npz"));
+    let write_err = |e| GfalookError::from_io("failed to write matrix", matrix_path, e);
+    let mut out = std::io::BufWriter::new(File::create(matrix_path).map_err(write_err)?);
+    if is_npz {
+        let names: Vec<String> = display_paths.iter().map(|p| p.name.clone()).collect();
+        let depth: Vec<f64> = matrix.concat();
+        npz::write_npz(
+            &mut out,
+            &[
+                (
+                    "depth",
+                    npz::NpyArray::F64(&depth, vec![names.len(), num_bins]),
+                ),
+                ("paths", npz::NpyArray::Str(&names)),
+                ("bin_start", npz::NpyArray::U64(&bin_starts)),
+            ],
+        )
+        .map_err(write_err)?;
+    } else {
+        let mut header = String::from("path.name");
+        for bin_start in &bin_starts {
+            header.push_str(&format!("\t{}", bin_start));
+        }
+        writeln!(out, "{}", header).map_err(write_err)?;
+        for (path, row) in display_paths.iter().zip(&matrix) {
+            let mut line = path.name.clone();
+            for depth in row {
+                line.push_str(&format!("\t{}", depth));
+            }
+            writeln!(out, "{}", line).map_err(write_err)?;
+        }
+    }
+    out.flush().map_err(write_err)?;
+    logging::phase(
+        "export-matrix",
+        start,
+        &[
+            ("paths", display_paths.len() as u64),
+            ("bins", num_bins as u64),
+        ],
+    );
+    info!("Depth matrix saved to {:?}", matrix_path);
+    Ok(())
+}
+
+/// Write the paths × nodes matrix for --export-pav. `.mtx` files get Matrix Market
+/// coordinates with row and column labels in `.paths.tsv` and `.nodes.tsv` next to them;
+/// anything else a sparse TSV with one line per non-zero entry
+fn write_pav_matrix(
+    args: &Args,
+    pav_path: &Path,
+    display_paths: &[&GfaPath],
+    graph: &Graph,
+) -> Result<(), GfalookError> {
+    let start = Instant::now();
+    let segment_lengths: Vec<u64> = graph.segments.iter().map(|s| s.sequence_len).collect();
+    let counts = path_node_bp_counts(display_paths, &segment_lengths, None);
+    let all_nodes: FxHashSet<u64> = counts.iter().flat_map(|c| c.keys().copied()).collect();
+    let node_set = if args.pav_variable_only && !counts.is_empty() {
+        variable_nodes(&counts, all_nodes)
+    } else {
+        all_nodes
+    };
+    let mut nodes: Vec<u64> = node_set.iter().copied().collect();
+    nodes.sort_unstable();
+
+    let mut node_names = vec![""; graph.segments.len()];
+    for (name, &id) in &graph.segment_name_to_id {
+        node_names[id as usize] = name;
+    }
+    let value = |bp: u64| if args.pav_bp { bp } else { 1 };
+    let rows: Vec<NodeCounts> = counts
+        .par_iter()
+        .map(|c| {
+            let mut row = sorted_node_counts(c);
+            row.retain(|(node, _)| node_set.contains(node));
+            row
+        })
+        .collect();
+    let nnz: usize = rows.iter().map(|r| r.len()).sum();
+
+    fn write_err(p: &Path) -> impl Fn(std::io::Error) -> GfalookError + '_ {
+        move |e| GfalookError::from_io("failed to write PAV matrix", p, e)
+    }
+    let is_mtx = pav_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mtx"));
+    let mut out = std::io::BufWriter::new(File::create(pav_path).map_err(write_err(pav_path))?);
+    if is_mtx {
+        let column: FxHashMap<u64, usize> =
+            nodes.iter().enumerate().map(|(i, &n)| (n, i + 1)).collect();
+        let header = format!(
+            "%%MatrixMarket matrix coordinate integer general\n\
+             % rows: paths, columns: nodes ({})\n\
+             {} {} {}",
+            if args.pav_bp { "bp" } else { "presence" },
+            display_paths.len(),
+            nodes.len(),
+            nnz
+        );
+        writeln!(out, "{}", header).map_err(write_err(pav_path))?;
+        for (row, entries) in rows.iter().enumerate() {
+            for &(node, bp) in entries {
+                writeln!(out, "{} {} {}", row + 1, column[&node], value(bp))
+                    .map_err(write_err(pav_path))?;
+            }
+        }
+
+        let paths_path = pav_path.with_extension("paths.tsv");
+        let names: String = display_paths
+            .iter()
+            .map(|p| format!("{}\n", p.name))
+            .collect();
+        std::fs::write(&paths_path, names).map_err(write_err(&paths_path))?;
+        let nodes_path = pav_path.with_extension("nodes.tsv");
+        let names: String = nodes
+            .iter()
+            .map(|&n| {
+                format!(
+                    "{}\t{}\n",
+                    node_names[n as usize], segment_lengths[n as usize]
+                )
+            })
+            .collect();
+        std::fs::write(&nodes_path, names).map_err(write_err(&nodes_path))?;
+    } else {
+        writeln!(
+            out,
+            "path.name\tnode\t{}",
+            if args.pav_bp { "bp" } else { "present" }
+        )
+        .map_err(write_err(pav_path))?;
+        for (path, entries) in display_paths.iter().zip(&rows) {
+            for &(node, bp) in entries {
+                writeln!(
+                    out,
+                    "{}\t{}\t{}",
+                    path.name,
+                    node_names[node as usize],
+                    value(bp)
+                )
+                .map_err(write_err(pav_path))?;
+            }
+        }
+    }
+    out.flush().map_err(write_err(pav_path))?;
+    logging::phase(
+        "export-pav",
+        start,
+        &[
+            ("paths", display_paths.len() as u64),
+            ("nodes", nodes.len() as u64),
+            ("entries", nnz as u64),
+        ],
+    );
+    info!("PAV matrix saved to {:?}", pav_path);
+    Ok(())
+}
+
+/// Write all ordered pairs of paths, or of groups with --similarity-level (self pairs
+/// included), with the columns of `odgi similarity -d`, over the same nodes and BED
+/// regions the clustering used
+fn write_distances_tsv(
+    distances_path: &Path,
+    paths: &[&GfaPath],
+    segment_lengths: &[u64],
+    use_all_nodes: bool,
+    bed_regions: Option<&ClusteringBedRegions>,
+    level: SimilarityLevel,
+) -> Result<(), GfalookError> {
+    let sim = similarity_counts(paths, segment_lengths, bed_regions, use_all_nodes, level);
+    // Paths of a group share its counts, so the first path stands for the group
+    let mut seen = FxHashSet::default();
+    let groups: Vec<(usize, &str)> = paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| (i, level.group_key(&path.name)))
+        .filter(|(_, group)| seen.insert(*group))
+        .collect();
+    let rows: Vec<String> = groups
+        .par_iter()
+        .map(|&(i, group_a)| {
+            let mut rows = String::new();
+            for &(j, group_b) in &groups {
+                let (len_a, len_b) = (sim.total_bp[i], sim.total_bp[j]);
+                let intersection = weighted_intersection(&sim.counts[i], &sim.counts[j]);
+                let jaccard =
+                    weighted_jaccard_similarity(&sim.counts[i], &sim.counts[j], len_a, len_b);
+                let cosine = if len_a == 0 || len_b == 0 {
+                    0.0
+                } else {
+                    intersection as f64 / ((len_a as f64) * (len_b as f64)).sqrt()
+                };
+                let dice = if len_a + len_b == 0 {
+                    1.0
+                } else {
+                    2.0 * intersection as f64 / (len_a + len_b) as f64
+                };
+                rows.push_str(&format!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                    group_a,
+                    group_b,
+                    len_a,
+                    len_b,
+                    intersection,
+                    1.0 - jaccard,
+                    1.0 - cosine,
+                    1.0 - dice,
+                    jaccard_to_edr(jaccard)
+                ));
+            }
+            rows
+        })
+        .collect();
+
+    let mut content = String::from(
+        "group.a\tgroup.b\tgroup.a.length\tgroup.b.length\tintersection\t\
+         jaccard.distance\tcosine.distance\tdice.distance\testimated.difference.rate\n",
+    );
+    content.extend(rows);
+    std::fs::write(distances_path, content)
+        .map_err(|e| GfalookError::from_io("failed to write distances", distances_path, e))?;
+    info!("Pairwise distances saved to {:?}", distances_path);
+    Ok(())
+}
+
+/// Record the paths of `before` that are missing from `after`
+fn note_dropped(
+    dropped: &mut Vec<(String, &'static str)>,
+    before: &[&GfaPath],
+    after: &[&GfaPath],
+    reason: &'static str,
+) {
+    let kept: FxHashSet<&str> = after.iter().map(|p| p.name.as_str()).collect();
+    dropped.extend(
+        before
+            .iter()
+            .filter(|p| !kept.contains(p.name.as_str()))
+            .map(|p| (p.name.clone(), reason)),
+    );
+}
+
+/// Write the paths left out by the filters and why: foo.png -> foo.dropped.tsv
+fn write_dropped_tsv(output_path: &Path, dropped: &[(String, &'static str)]) {
+    let tsv_path = output_path.with_extension("dropped.tsv");
+    let mut content = String::from("path\treason\n");
+    for (name, reason) in dropped {
+        content.push_str(&format!("{}\t{}\n", name, reason));
+    }
+    match std::fs::write(&tsv_path, content) {
+        Ok(_) => info!("{} dropped paths saved to {:?}", dropped.len(), tsv_path),
+        Err(e) => warning!("could not write dropped paths TSV: {}", e),
+    }
+}
+
+/// Write cluster medoids (representatives) to a TSV file
+fn write_medoids_tsv(
+    output_path: &Path,
+    original_paths: &[&GfaPath],
+    cluster_result: &ClusteringResult,
+) {
+    // Derive TSV path from output path: foo.png -> foo.medoids.tsv
+    let tsv_path = output_path.with_extension("medoids.tsv");
+
+    let mut content = String::from("cluster\tmedoid.path\tcluster.size\tcolor");
+    if cluster_result.stability.is_some() {
+        content.push_str("\tstability");
+    }
+    content.push('\n');
+    for (cluster_id, (&medoid_idx, &size)) in cluster_result
+        .representatives
+        .iter()
+        .zip(cluster_result.cluster_sizes.iter())
+        .enumerate()
+    {
+        let medoid_name = &original_paths[medoid_idx].name;
+        let (r, g, b) = cluster_result.base_color(cluster_id);
+        content.push_str(&format!(
+            "{}\t{}\t{}\t#{:02x}{:02x}{:02x}",
+            cluster_result.cluster_label(cluster_id),
+            medoid_name,
+            size,
+            r,
+            g,
+            b
+        ));
+        if let Some(ref stability) = cluster_result.stability {
+            content.push_str(&format!("\t{:.3}", stability[cluster_id]));
+        }
+        content.push('\n');
+    }
+
+    match std::fs::write(&tsv_path, content) {
+        Ok(_) => info!("Cluster medoids saved to {:?}", tsv_path),
+        Err(e) => warning!("could not write medoids TSV: {}", e),
+    }
+}
+
+/// Segment colors for --write-colored-gfa: the color of the first cluster whose medoid
+/// traverses the segment; without clustering, red/grey by highlight status, or the depth
+/// color of the mean copy number among displayed paths. Untraversed segments get none.
+fn segment_colors(
+    graph: &Graph,
+    display_paths: &[&GfaPath],
+    medoid_colors: &[(&GfaPath, (u8, u8, u8))],
+    highlight_nodes: Option<&FxHashSet<u64>>,
+    no_grey_depth: bool,
+    depth_palette: Option<&[(u8, u8, u8)]>,
+    theme: &Theme,
+) -> Vec<Option<(u8, u8, u8)>> {
+    let mut colors = vec![None; graph.segments.len()];
+    if !medoid_colors.is_empty() {
+        for &(medoid, color) in medoid_colors {
+            for step in &medoid.steps {
+                colors[step.segment_id as usize].get_or_insert(color);
+            }
+        }
+        return colors;
+    }
+
+    // Copies of each segment over all displayed paths, and the paths that visit it
+    let mut copies = vec![0u64; graph.segments.len()];
+    let mut visitors = vec![0u64; graph.segments.len()];
+    for path in display_paths {
+        let mut seen: FxHashSet<u64> = FxHashSet::default();
+        for step in &path.steps {
+            copies[step.segment_id as usize] += 1;
+            if seen.insert(step.segment_id) {
+                visitors[step.segment_id as usize] += 1;
+            }
+        }
+    }
+    for (id, color) in colors.iter_mut().enumerate() {
+        if visitors[id] == 0 {
+            continue;
+        }
+        *color = Some(match highlight_nodes {
+            Some(nodes) if nodes.contains(&(id as u64)) => (255, 0, 0),
+            Some(_) => theme.muted(180),
+            None => {
+                let mean_depth = copies[id] as f64 / visitors[id] as f64;
+                get_depth_color(mean_depth, no_grey_depth, depth_palette)
+            }
+        });
+    }
+    colors
+}
+
+/// Copy the input GFA to `out`, replacing or appending a `CL:z:#RRGGBB` tag on every
+/// segment that has a color
+fn write_colored_gfa(
+    gfa_path: &Path,
+    out: &Path,
+    graph: &Graph,
+    colors: &[Option<(u8, u8, u8)>],
+) -> Result<(), GfalookError> {
+    let read_err = |e| GfalookError::from_io("failed to read GFA file", gfa_path, e);
+    let reader = open_gfa(gfa_path).map_err(read_err)?;
+    interrupt::write_atomically(out, |temp| {
+        let write_err = |e| GfalookError::from_io("failed to write colored GFA", out, e);
+        let mut writer = std::io::BufWriter::new(File::create(temp).map_err(write_err)?);
+        let mut segment_lines = 0u64;
+        let mut colored = 0usize;
+        for line in reader.lines() {
+            let line = line.map_err(read_err)?;
+            let color = if line.starts_with("S\t") && line.split('\t').nth(2).is_some() {
+                segment_lines += 1;
+                graph
+                    .segment_at_file_index(segment_lines - 1)
+                    .and_then(|id| colors.get(id as usize).copied().flatten())
+            } else {
+                None
+            };
+            match color {
+                Some((r, g, b)) => {
+                    let fields: Vec<&str> = line
+                        .split('\t')
+                        .filter(|field| !field.starts_with("CL:z:"))
+                        .collect();
+                    writeln!(
+                        writer,
+                        "{}\tCL:z:#{:02x}{:02x}{:02x}",
+                        fields.join("\t"),
+                        r,
+                        g,
+                        b
+                    )
+                    .map_err(write_err)?;
+                    colored += 1;
+                }
+                None => writeln!(writer, "{}", line).map_err(write_err)?,
+            }
+        }
+        writer.flush().map_err(write_err)?;
+        info!(
+            "Colored GFA ({} of {} segments) saved to {:?}",
+            colored, segment_lines, out
+        );
+        Ok(())
+    })
+}
+
+/// Format coordinate value with K/M/G suffixes for readability
+fn format_coordinate(value: u64) -> String {
+    if value >= 1_000_000_000 {
+        format!("{:.1}G", value as f64 / 1_000_000_000.0)
+    } else if value >= 1_000_000 {
+        format!("{:.1}M", value as f64 / 1_000_000.0)
+    } else if value >= 1_000 {
+        format!("{:.1}K", value as f64 / 1_000.0)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Parse the start position from a path name in "name:start-end" format.
+/// Returns 0 if the format doesn't match.
+fn parse_subpath_start(path_name: &str) -> u64 {
+    // Look for the last colon followed by "number-number"
+    if let Some(colon_pos) = path_name.rfind(':') {
+        let range_part = &path_name[colon_pos + 1..];
+        if let Some(dash_pos) = range_part.find('-') {
+            if let Ok(start) = range_part[..dash_pos].parse::<u64>() {
+                return start;
+            }
+        }
+    }
+    0
+}
+
+/// Strip the ":start-end" range from a path name if present.
+/// Returns the base name without the range.
+fn strip_subpath_range(path_name: &str) -> &str {
+    // Look for the last colon followed by "number-number"
+    if let Some(colon_pos) = path_name.rfind(':') {
+        let range_part = &path_name[colon_pos + 1..];
+        if let Some(dash_pos) = range_part.find('-') {
+            // Verify both parts are numbers
+            if range_part[..dash_pos].parse::<u64>().is_ok()
+                && range_part[dash_pos + 1..].parse::<u64>().is_ok()
+            {
+                return &path_name[..colon_pos];
+            }
+        }
+    }
+    path_name
+}
+
+/// Shorter labels for subrange paths (`name:start-end`) when several of the same base
+/// name are displayed: the base name and the interval's rank by start among them
+fn subrange_labels(display_paths: &[&GfaPath]) -> FxHashMap<String, String> {
+    let mut by_base: FxHashMap<&str, Vec<&str>> = FxHashMap::default();
+    for path in display_paths {
+        let base = strip_subpath_range(&path.name);
+        if base.len() < path.name.len() {
+            by_base.entry(base).or_default().push(&path.name);
+        }
+    }
+    let mut labels = FxHashMap::default();
+    for (base, mut names) in by_base {
+        if names.len() < 2 {
+            continue;
+        }
+        names.sort_by_key(|name| parse_subpath_start(name));
+        names.dedup();
+        for (rank, name) in names.iter().enumerate() {
+            labels.insert(
+                name.to_string(),
+                format!("{} [{}/{}]", base, rank + 1, names.len()),
+            );
+        }
+    }
+    labels
+}
+
+/// Render graph as SVG with vector fonts
+pub(crate) fn render_svg(args: &Args, graph: &Graph) -> Result<String, GfalookError> {
+    let preview;
+    let args = if args.preview {
+        preview = preview_args(args);
+        &preview
+    } else {
+        args
+    };
+    let reordered;
+    let (graph, orphan_start) = match args.show_orphans.then(|| orphans_last(graph)).flatten() {
+        Some((orphans_last, start)) => {
+            reordered = orphans_last;
+            (&reordered, Some(start))
+        }
+        None => (graph, None),
+    };
+    let grouped;
+    let (graph, components) = match args
+        .separate_components
+        .then(|| components_contiguous(graph))
+        .flatten()
+    {
+        Some((components_grouped, spans)) => {
+            grouped = components_grouped;
+            (&grouped, spans)
+        }
+        None => (graph, Vec::new()),
+    };
+    let theme = Theme::from_args(args);
+    let fg = svg_rgb(theme.foreground);
+
+    let mut display_paths: Vec<&GfaPath> = graph.paths.iter().collect();
+    let path_resolver = PathNameResolver::new(graph.paths.iter().map(|p| p.name.as_str()));
+
+    // Paths left out by the filters below, with the reason, for --write-dropped
+    let mut dropped: Vec<(String, &'static str)> = Vec::new();
+    if let Some(ref prefix) = args.ignore_prefix {
+        display_paths.retain(|p| {
+            let keep = !p.name.starts_with(prefix);
+            if !keep {
+                dropped.push((p.name.clone(), "ignore-prefix"));
+            }
+            keep
+        });
+    }
+
+    if let Some(ref ptd_file) = args.paths_to_display {
+        let ptd: Vec<String> = load_paths_to_display(ptd_file)
+            .map_err(|e| GfalookError::from_io("failed to read paths to display", ptd_file, e))?
+            .iter()
+            .map(|name| path_resolver.canonical(name))
+            .collect();
+        let ptd_set: std::collections::HashSet<_> = ptd.iter().collect();
+        display_paths.retain(|p| {
+            let keep = ptd_set.contains(&p.name);
+            if !keep {
+                dropped.push((p.name.clone(), "not in paths-to-display"));
+            }
+            keep
+        });
+        let path_map: FxHashMap<&String, &GfaPath> =
+            display_paths.iter().map(|p| (&p.name, *p)).collect();
+        display_paths = ptd
+            .iter()
+            .filter_map(|name| path_map.get(name).copied())
+            .collect();
+    }
+
+    if display_paths.is_empty() && !graph.paths.is_empty() {
+        return Err(GfalookError::EmptySelection(
+            "no paths left to display after --ignore-prefix/--paths-to-display filtering"
+                .to_string(),
+        ));
+    }
+
+    if args.preview && display_paths.len() > PREVIEW_MAX_PATHS {
+        info!(
+            "Preview: drawing {} of {} paths",
+            PREVIEW_MAX_PATHS,
+            display_paths.len()
+        );
+        let sampled = sample_paths(display_paths.clone(), PREVIEW_MAX_PATHS);
+        note_dropped(
+            &mut dropped,
+            &display_paths,
+            &sampled,
+            "not sampled by preview",
+        );
+        display_paths = sampled;
+    }
+
+    let flip_names = paths_to_flip(args, graph, &display_paths, &path_resolver)?;
+    let flipped_paths: Vec<GfaPath> = display_paths
+        .iter()
+        .filter(|p| flip_names.contains(&p.name))
+        .map(|p| reverse_complement(p))
+        .collect();
+    if !flipped_paths.is_empty() {
+        info!("Drawing {} paths reverse-complemented", flipped_paths.len());
+        display_paths = apply_flips(&display_paths, &flipped_paths);
+    }
+    let joined_paths = if args.join_subranges {
+        join_subranges(&display_paths)
+    } else {
+        Vec::new()
+    };
+    if !joined_paths.is_empty() {
+        let before = display_paths.len();
+        display_paths = apply_joins(&display_paths, &joined_paths);
+        info!(
+            "Joined subrange paths: {} rows from {} paths",
+            display_paths.len(),
+            before
+        );
+    }
+    let split_pieces: Vec<GfaPath> = match args.split_discontiguous {
+        Some(max_jump) => display_paths
+            .iter()
+            .flat_map(|p| split_at_jumps(p, graph, max_jump))
+            .collect(),
+        None => Vec::new(),
+    };
+    if !split_pieces.is_empty() {
+        let before = display_paths.len();
+        display_paths = apply_splits(&display_paths, &split_pieces);
+        info!(
+            "Split discontiguous paths: {} rows from {} paths",
+            display_paths.len(),
+            before
+        );
+    }
+
+    let compressed;
+    let (graph, invariant_breaks) = match args
+        .compress_invariant
+        .and_then(|factor| compress_invariant(args, graph, &display_paths, factor))
+    {
+        Some((compressed_graph, breaks)) => {
+            compressed = compressed_graph;
+            (&compressed, breaks)
+        }
+        None => (graph, Vec::new()),
+    };
+
+    let len_to_visualize = graph.total_length;
+
+    // Calculate width - if show_all_nodes, ensure smallest segment gets at least node_width pixels
+    let viz_width = if args.show_all_nodes {
+        show_all_nodes_viz_width(args, graph)?
+    } else {
+        args.width.min(len_to_visualize as u32)
+    };
+
+    let bin_width = args
+        .bin_width
+        .unwrap_or_else(|| len_to_visualize as f64 / viz_width as f64);
+
+    // Load BED regions for clustering if specified (SVG)
+    let bed_regions: Option<ClusteringBedRegions> = match args.cluster_bed {
+        Some(ref p) => {
+            let regions = load_clustering_bed(p)
+                .map_err(|e| GfalookError::from_io("failed to load BED file", p, e))?;
+            if regions.path_regions.is_empty() {
+                warning!("BED file is empty or has no valid regions, clustering all paths");
+                None
+            } else {
+                Some(regions)
+            }
+        }
+        None => None,
+    };
+
+    // Names of the cluster medoids, filled in when --mark-medoids is set
+    let mut medoid_names: FxHashSet<String> = FxHashSet::default();
+    // Cluster medoids with their cluster colors, for --write-colored-gfa
+    let mut medoid_colors: Vec<(&GfaPath, (u8, u8, u8))> = Vec::new();
+
+    // Cluster paths by similarity if requested (SVG rendering)
+    // Prefix groups to cluster within for --cluster-within
+    let within_groups = match args.cluster_within {
+        Some(ref p) => {
+            Some(prefix_group_annotations(read_prefixes(p).map_err(|e| {
+                GfalookError::from_io("failed to load prefix file", p, e)
+            })?))
+        }
+        None => None,
+    };
+
+    let cluster_result = if args.cluster_paths {
+        debug!(
+            "Clustering {} paths by EDR (estimated difference rate)",
+            display_paths.len()
+        );
+        // Build segment lengths vector for EDR computation
+        let segment_lengths: Vec<u64> = graph.segments.iter().map(|s| s.sequence_len).collect();
+
+        // If BED regions provided, partition paths into those to cluster vs. those excluded
+        let (paths_to_cluster, unclustered_paths): (Vec<&GfaPath>, Vec<&GfaPath>) =
+            if let Some(ref bed) = bed_regions {
+                let (to_cluster, unclustered): (Vec<_>, Vec<_>) =
+                    display_paths.iter().partition(|p| bed.has_regions(&p.name));
+                if to_cluster.is_empty() {
+                    return Err(GfalookError::EmptySelection(
+                        "no paths match BED regions, cannot cluster".to_string(),
+                    ));
+                }
+                debug!(
+                    "BED subsetting: {} paths to cluster, {} paths excluded",
+                    to_cluster.len(),
+                    unclustered.len()
+                );
+                (to_cluster, unclustered)
+            } else {
+                (display_paths.clone(), Vec::new())
+            };
+
+        let original_paths = paths_to_cluster.clone(); // Save for medoids TSV
+        let cluster_start = Instant::now();
+        let result = match within_groups {
+            Some(ref groups) => {
+                cluster_within_groups(args, &paths_to_cluster, groups, &segment_lengths)
+            }
+            None => cluster_with_cache(
+                args,
+                &paths_to_cluster,
+                &segment_lengths,
+                bed_regions.as_ref(),
+            ),
+        };
+        logging::phase(
+            "cluster",
+            cluster_start,
+            &[
+                ("paths", paths_to_cluster.len() as u64),
+                ("clusters", result.num_clusters as u64),
+            ],
+        );
+
+        // Rebuild display_paths: clustered paths in order, then unclustered
+        display_paths = result
+            .ordering
+            .iter()
+            .map(|&i| paths_to_cluster[i])
+            .collect();
+        display_paths.extend(unclustered_paths.clone());
+
+        // Extend cluster_ids for unclustered paths (use num_clusters as special "unclustered" ID)
+        let mut extended_cluster_ids = result.cluster_ids.clone();
+        extended_cluster_ids.extend(std::iter::repeat_n(
+            result.num_clusters,
+            unclustered_paths.len(),
+        ));
+
+        // Build extended result
+        let extended_result = ClusteringResult {
+            ordering: (0..display_paths.len()).collect(),
+            cluster_ids: extended_cluster_ids,
+            num_clusters: if unclustered_paths.is_empty() {
+                result.num_clusters
+            } else {
+                result.num_clusters + 1 // +1 for "unclustered" group
+            },
+            representatives: result.representatives.clone(),
+            cluster_sizes: {
+                let mut sizes = result.cluster_sizes.clone();
+                if !unclustered_paths.is_empty() {
+                    sizes.push(unclustered_paths.len());
+                }
+                sizes
+            },
+            dendrogram: result.dendrogram.clone(),
+            noise_cluster: result.noise_cluster,
+            stability: result.stability.clone(),
+        };
+
+        // Write cluster assignments to TSV (using original result for clustered paths only)
+        write_cluster_tsv(
+            args,
+            &original_paths,
+            &result,
+            &segment_lengths,
+            bed_regions.as_ref(),
+        );
+        // Write medoids TSV
+        write_medoids_tsv(&args.out, &original_paths, &result);
+        if let Some(ref dir) = args.per_cluster_images {
+            render_per_cluster_images(args, dir, graph, &original_paths, &result)?;
+        }
+        if let Some(ref distances_path) = args.write_distances {
+            write_distances_tsv(
+                distances_path,
+                &original_paths,
+                &segment_lengths,
+                args.cluster_all_nodes,
+                bed_regions.as_ref(),
+                args.similarity_level,
+            )?;
+        }
+        if args.write_colored_gfa.is_some() || args.export_dot.is_some() {
+            medoid_colors = result
+                .representatives
+                .iter()
+                .enumerate()
+                .filter(|&(cluster_id, _)| result.noise_cluster != Some(cluster_id))
+                .map(|(cluster_id, &i)| {
+                    (
+                        original_paths[i],
+                        result.cluster_color(cluster_id, args, &theme),
+                    )
+                })
+                .collect();
+        }
+        if args.mark_medoids {
+            medoid_names = result
+                .representatives
+                .iter()
+                .enumerate()
+                .filter(|&(cluster_id, _)| result.noise_cluster != Some(cluster_id))
+                .map(|(_, &i)| original_paths[i].name.clone())
+                .collect();
+        }
+
+        // Filter to representatives only if requested (SVG)
+        // Note: only applies to clustered paths, unclustered paths are not included
+        let final_result = if args.cluster_representatives {
+            let rep_set: FxHashSet<usize> =
+                pick_representatives(args, &paths_to_cluster, &result, &segment_lengths)
+                    .into_iter()
+                    .collect();
+            let mut filtered_paths = Vec::new();
+            let mut filtered_cluster_ids = Vec::new();
+            // The tree's leaves are the rows in display order; keep the representatives'
+            let mut kept_leaves =
+                vec![None; result.dendrogram.as_ref().map_or(0, |dg| dg.leaf_order.len())];
+            for (pos, &orig_idx) in result.ordering.iter().enumerate() {
+                if rep_set.contains(&orig_idx)
+                    && result.noise_cluster != Some(result.cluster_ids[pos])
+                {
+                    if let Some(dg) = &result.dendrogram {
+                        if let Some(&leaf) = dg.leaf_order.get(pos) {
+                            kept_leaves[leaf] = Some(filtered_paths.len());
+                        }
+                    }
+                    filtered_paths.push(paths_to_cluster[orig_idx]);
+                    filtered_cluster_ids.push(result.cluster_ids[pos]);
+                }
+            }
+            note_dropped(
+                &mut dropped,
+                &display_paths,
+                &filtered_paths,
+                "not a cluster representative",
+            );
+            display_paths = filtered_paths;
+            // Don't include unclustered paths when showing representatives only
+            ClusteringResult {
+                ordering: (0..display_paths.len()).collect(),
+                cluster_ids: filtered_cluster_ids,
+                num_clusters: result.num_clusters,
+                representatives: result.representatives,
+                cluster_sizes: result.cluster_sizes,
+                dendrogram: result
+                    .dendrogram
+                    .map(|dg| prune_dendrogram(&dg, &kept_leaves)),
+                noise_cluster: result.noise_cluster,
+                stability: result.stability,
+            }
+        } else {
+            extended_result
+        };
+        let pool = (!args.cluster_representatives && !unclustered_paths.is_empty())
+            .then_some(result.num_clusters);
+        Some(hide_minor_clusters(
+            args,
+            &mut display_paths,
+            final_result,
+            pool,
+            &mut dropped,
+        )?)
+    } else {
+        None
+    };
+    if args.write_dropped {
+        write_dropped_tsv(&args.out, &dropped);
+    }
+
+    // Recalculate path_count after potential filtering by cluster_representatives (SVG)
+    let path_count = display_paths.len() as u32;
+
+    if let Some(ref pav_path) = args.export_pav {
+        write_pav_matrix(args, pav_path, &display_paths, graph)?;
+    }
+    if let Some(ref frequency_path) = args.node_frequency_out {
+        write_node_frequencies(frequency_path, &display_paths, graph)?;
+    }
+    // --core-track: the core bins under the figure, and their statistics
+    let core = args.core_track.then(|| {
+        let core = core_bins(&display_paths, graph, bin_width, viz_width as usize);
+        write_core_tsv(
+            &args.out,
+            &core_stats(&display_paths, graph, &core, bin_width),
+        );
+        core
+    });
+
+    // Load colorbrewer palette if specified (SVG)
+    let depth_palette: Option<&[(u8, u8, u8)]> =
+        args.colorbrewer_palette.as_ref().and_then(|arg| {
+            if let Some((scheme, n)) = parse_colorbrewer_arg(arg) {
+                get_colorbrewer_palette(&scheme, n).or_else(|| {
+                    warning!(
+                        "unknown colorbrewer palette '{}', using default Spectral",
+                        scheme
+                    );
+                    None
+                })
+            } else {
+                warning!(
+                    "invalid colorbrewer palette format '{}', expected SCHEME:N",
+                    arg
+                );
+                None
+            }
+        });
+
+    let custom_colors: Option<FxHashMap<String, (u8, u8, u8)>> = match args.color_by_contig {
+        Some(ref mapping_file) => {
+            let mapping = match mapping_file {
+                Some(p) => Some(
+                    load_contig_map(p)
+                        .map_err(|e| GfalookError::from_io("failed to load contig map", p, e))?
+                        .into_iter()
+                        .map(|(name, contig)| (path_resolver.canonical(&name), contig))
+                        .collect(),
+                ),
+                None => None,
+            };
+            Some(contig_colors(&display_paths, mapping.as_ref()))
+        }
+        None => args
+            .path_colors
+            .as_ref()
+            .and_then(|p| load_path_colors(p).ok())
+            .map(|colors| {
+                colors
+                    .into_iter()
+                    .map(|(name, color)| (path_resolver.canonical(&name), color))
+                    .collect()
+            }),
+    };
+
+    // Load highlight node IDs if specified
+    let highlight_nodes: Option<FxHashSet<u64>> = args
+        .highlight_node_ids
+        .as_ref()
+        .and_then(|p| load_highlight_node_ids(p).ok())
+        .map(|ids| {
+            ids.into_iter()
+                .filter_map(|index| graph.segment_at_file_index(index))
+                .collect()
+        });
+
+    // --watch re-renders reuse the bins of paths whose binning inputs are unchanged
+    let binning = args
+        .watch
+        .then(|| bin_cache::binning_key(graph, bin_width, highlight_nodes.as_ref()));
+    let raw_bins = |path: &GfaPath| {
+        bin_cache::bins(binning, path, || {
+            path_bins(path, graph, bin_width, highlight_nodes.as_ref())
+        })
+    };
+    // Mean depth of every bin over the displayed paths, for --normalize-depth per-bin
+    let bin_depth_means = (args.normalize_depth == DepthNormalization::PerBin)
+        .then(|| mean_bin_depths(&display_paths, raw_bins));
+    // A path's bins as the rows are drawn
+    let drawn_bins = |path: &GfaPath| {
+        let mut bins = raw_bins(path);
+        adjust_bins(args, &mut bins, bin_depth_means.as_ref());
+        bins
+    };
+    if let Some(ref matrix_path) = args.export_matrix {
+        write_depth_matrix(
+            matrix_path,
+            &display_paths,
+            graph,
+            bin_width,
+            viz_width,
+            &drawn_bins,
+        )?;
+    }
+
+    if let Some(ref gfa_path) = args.write_colored_gfa {
+        let colors = segment_colors(
+            graph,
+            &display_paths,
+            &medoid_colors,
+            highlight_nodes.as_ref(),
+            args.no_grey_depth,
+            depth_palette,
+            &theme,
+        );
+        write_colored_gfa(&args.idx, gfa_path, graph, &colors)?;
+    }
+
+    let path_color = |path: &GfaPath| match custom_colors {
+        Some(ref colors) => colors.get(&path.name).copied().unwrap_or(theme.muted(200)),
+        None => compute_path_color(&path.name, args.color_by_prefix),
+    };
+    let edge_colors = args
+        .color_edges
+        .then(|| edge_path_colors(&display_paths, path_color));
+    if let Some(ref dot_path) = args.export_dot {
+        let node_colors =
+            if !medoid_colors.is_empty() || highlight_nodes.is_some() || args.color_by_mean_depth {
+                segment_colors(
+                    graph,
+                    &display_paths,
+                    &medoid_colors,
+                    highlight_nodes.as_ref(),
+                    args.no_grey_depth,
+                    depth_palette,
+                    &theme,
+                )
+            } else {
+                segment_path_colors(graph, &display_paths, path_color)
+            };
+        let colors = graph_export::ExportColors {
+            nodes: &node_colors,
+            edges: edge_colors.as_ref(),
+            default: theme.foreground,
+        };
+        let edges = selected_edges(args.edges, graph, &display_paths);
+        graph_export::write(dot_path, graph, &display_paths, &edges, &colors)?;
+    }
+    // --no-image: the data products above are all that was asked for
+    if args.no_image {
+        return Ok(String::new());
+    }
+
+    // Per-window cluster assignments for the recombination mosaic band (SVG)
+    let window_clusters: Option<Vec<Vec<Option<usize>>>> = args.cluster_windows.map(|size| {
+        let wc = cluster_windows(
+            &display_paths,
+            graph,
+            size,
+            args.cluster_threshold,
+            args.max_clusters,
+            args.dbscan_min_pts,
+        );
+        info!(
+            "Clustered {} paths in {} windows of {}bp",
+            display_paths.len(),
+            wc.len(),
+            size
+        );
+        wc
+    });
+
+    let node_frequency: Option<Vec<f64>> = args.color_by_frequency_class.then(|| {
+        let n = display_paths.len().max(1) as f64;
+        node_frequencies(&display_paths, graph)
+            .into_iter()
+            .map(|count| count as f64 / n)
+            .collect()
+    });
+
+    // Clustering and the exports above are written; stop here on Ctrl-C
+    interrupt::check("clustering")?;
+
+    // Load prefix grouping if specified (SVG) - must be after clustering check
+    let path_grouping: Option<PathGrouping> = args.prefix_merges.as_ref().and_then(|p| {
+        let paths_vec: Vec<GfaPath> = display_paths.iter().map(|&p| p.clone()).collect();
+        match load_prefix_merges(p, &paths_vec) {
+            Ok(grouping) => {
+                info!(
+                    "Read {} valid prefixes for {} groups",
+                    grouping.prefixes.len(),
+                    grouping.num_groups
+                );
+                Some(grouping)
+            }
+            Err(e) => {
+                warning!("failed to load prefix merges: {}", e);
+                None
+            }
+        }
+    });
+
+    // Load annotations if specified (SVG)
+    let annotations: Option<AnnotationData> = args
+        .annotation_file
+        .as_ref()
+        .and_then(|p| match load_annotations(p) {
+            Ok(ann) => {
+                info!(
+                    "Loaded {} prefixes across {} categories (SVG)",
+                    ann.prefixes.len(),
+                    ann.categories.len()
+                );
+                Some(ann)
+            }
+            Err(e) => {
+                warning!("failed to load annotations: {}", e);
+                None
+            }
+        })
+        .or(within_groups);
+
+    // Effective row count: use num_groups if grouping is enabled, the summary rows if
+    // compressed mode
+    let effective_row_count = if args.compressed_mode {
+        1 + args.compressed_rows.len() as u32
+    } else if let Some(ref pg) = path_grouping {
+        pg.num_groups as u32
+    } else {
+        path_count
+    };
+
+    // Row height: fixed by -a, or derived from --fit-height once the row count is known
+    let pix_per_path = match args.fit_height {
+        Some(target) => {
+            let cluster_gaps = cluster_result.as_ref().map_or(0, |cr| {
+                cr.num_clusters.saturating_sub(1) as u32 * args.cluster_gap
+            });
+            let legend = if annotations.is_some() {
+                args.legend_height
+            } else {
+                0
+            } + cluster_legend_band(args)
+                + downsample_band(args)
+                + jump_arc_band(args);
+            let axis = if args.x_axis.is_some() {
+                axis_height(args) + args.panel_gap
+            } else {
+                0
+            } + diversity_track_panel(args)
+                + core_track_panel(args)
+                + frequency_spectrum_panel(args)
+                + component_track_panel(args);
+            let edges = (len_to_visualize.min((args.height + 5) as u64)) as u32;
+            let spacing = effective_row_count.saturating_sub(1) * args.path_spacing;
+            let reserved =
+                legend + cluster_gaps + spacing + axis + args.panel_gap + edges + 2 * args.margin;
+            let fitted = fit_path_height(target, effective_row_count, reserved);
+            info!(
+                "Fitting {} rows into {}px: path height {}px",
+                effective_row_count, target, fitted
+            );
+            fitted
+        }
+        None => args.path_height,
+    };
+    check_rows_height(args, effective_row_count, pix_per_path)?;
+
+    // Calculate text width based on longest path/prefix name, "COMPRESSED_MODE" for compressed mode
+    let max_name_len = if args.compressed_mode {
+        "COMPRESSED_MODE".len()
+    } else if let Some(ref pg) = path_grouping {
+        pg.prefixes
+            .iter()
+            .map(|p| text_columns(p))
+            .max()
+            .unwrap_or(10)
+    } else if args.cluster_representatives {
+        // Account for " (n=X)" suffix: max cluster size determines suffix length
+        let max_size = cluster_result
+            .as_ref()
+            .map(|cr| cr.cluster_sizes.iter().max().copied().unwrap_or(1))
+            .unwrap_or(1);
+        let suffix_len = format!(" (n={})", max_size).len();
+        display_paths
+            .iter()
+            .map(|p| text_columns(&p.name) + suffix_len)
+            .max()
+            .unwrap_or(10)
+    } else {
+        display_paths
+            .iter()
+            .map(|p| text_columns(&p.name))
+            .max()
+            .unwrap_or(10)
+    };
+    // Names longer than -c are truncated, so the panel only needs room for that many characters
+    let max_name_len = args
+        .max_num_of_characters
+        .map_or(max_name_len, |max_chars| max_name_len.min(max_chars));
+    let font_size = (pix_per_path as f64 * 0.8).max(8.0);
+    let char_width = font_size * 0.6; // Approximate monospace character width
+                                      // Disable path names when pack_paths is enabled (they wouldn't make sense)
+    let text_width = if args.hide_path_names || args.pack_paths {
+        0.0
+    } else {
+        (max_name_len as f64 * char_width) + 10.0
+    };
+
+    // Cluster bar width (only if clustering is enabled)
+    let cluster_bar_width = if cluster_result.is_some() { 10.0 } else { 0.0 };
+
+    // Annotation bar width (only if annotations are loaded)
+    let annotation_bar_width: f64 = if annotations.is_some() {
+        args.annotation_bar_width as f64
+    } else {
+        0.0
+    };
+
+    // Gap between cluster bar and annotation bar when both are present
+    let bar_gap: f64 = if cluster_result.is_some() && annotations.is_some() {
+        4.0
+    } else {
+        0.0
+    };
+
+    // Legend height (only if annotations are loaded)
+    // Everything above the path rows: the annotation legend, the --cluster-legend row, the
+    // --downsample label and the --jump-arcs band
+    let legend_height: f64 =
+        if annotations.is_some() {
+            args.legend_height as f64
+        } else {
+            0.0
+        } + (cluster_legend_band(args) + downsample_band(args) + jump_arc_band(args)) as f64;
+
+    // Dendrogram width (only if dendrogram is enabled and we have a dendrogram)
+    let dendrogram_width: f64 = if args.dendrogram
+        && cluster_result
+            .as_ref()
+            .map(|cr| cr.dendrogram.is_some())
+            .unwrap_or(false)
+    {
+        args.dendrogram_width as f64
+    } else {
+        0.0
+    };
+
+    // Rows are laid out on a fixed pitch so --path-spacing leaves a gap between them
+    let row_pitch = pix_per_path + args.path_spacing;
+    let path_space = effective_row_count * pix_per_path
+        + effective_row_count.saturating_sub(1) * args.path_spacing;
+    let bottom_padding = 5u32;
+    let height_param = (args.height + bottom_padding) as u64;
+    let edge_height = (len_to_visualize.min(height_param)) as u32;
+    let scale_y_edges = edge_height as f64 / len_to_visualize as f64;
+
+    let total_width = viz_width as f64
+        + text_width
+        + cluster_bar_width
+        + bar_gap
+        + annotation_bar_width
+        + dendrogram_width;
+
+
+    // Load highlighted path names if specified; all other rows are faded
+    let highlight_paths: Option<FxHashSet<String>> = args
+        .highlight_paths
+        .as_ref()
+        .and_then(|p| load_paths_to_display(p).ok())
+        .map(|names| names.iter().map(|n| path_resolver.canonical(n)).collect());
+    // Reference paths get a black border and a bold name
+    let reference_names: FxHashSet<&str> = if args.no_reference_style {
+        FxHashSet::default()
+    } else {
+        display_paths
+            .iter()
+            .filter(|path| is_reference_path(graph, path))
+            .map(|path| path.name.as_str())
+            .collect()
+    };
+    let row_fade = row_fades(
+        args,
+        &display_paths,
+        cluster_result.as_ref(),
+        annotations.as_ref(),
+    );
+
+    // Track which groups have already been rendered (for path names)
+    let mut rendered_groups: FxHashSet<i64> = FxHashSet::default();
+
+    // Calculate max path length for longest-path option
+    let needs_length = args.longest_path || args.change_darkness || args.row_axes.is_some();
+    let max_path_length: u64 = if needs_length {
+        display_paths
+            .iter()
+            .map(|path| {
+                path.steps
+                    .iter()
+                    .map(|step| {
+                        let seg_id = step.segment_id as usize;
+                        if seg_id < graph.segments.len() {
+                            graph.segments[seg_id].sequence_len
+                        } else {
+                            0
+                        }
+                    })
+                    .sum::<u64>()
+            })
+            .max()
+            .unwrap_or(1)
+    } else {
+        1
+    };
+    let ruler_step = row_axis_step(max_path_length, args.x_ticks);
+    if args.row_axes.is_some() {
+        info!("Row axes: one tick every {} bp", ruler_step);
+    }
+
+    // Pre-compute leaf Y positions for dendrogram (accounting for cluster gaps) - SVG
+    let dendrogram_leaf_y_positions_svg: Vec<f64> = if dendrogram_width > 0.0 {
+        if let Some(ref cr) = cluster_result {
+            if let Some(ref dg) = cr.dendrogram {
+                let n_leaves = dg.leaf_order.len();
+                let mut positions = vec![0.0f64; n_leaves];
+                let mut cumulative_gap: f64 = 0.0;
+                let mut prev_cluster_id: Option<usize> = None;
+
+                for (display_pos, &orig_idx) in dg.leaf_order.iter().enumerate() {
+                    if orig_idx < n_leaves && display_pos < cr.cluster_ids.len() {
+                        // cluster_ids is indexed by display position, not original index
+                        let cluster_id = cr.cluster_ids[display_pos];
+                        if prev_cluster_id.is_some_and(|prev| prev != cluster_id) {
+                            cumulative_gap += args.cluster_gap as f64;
+                        }
+                        prev_cluster_id = Some(cluster_id);
+                        positions[orig_idx] =
+                            legend_height + display_pos as f64 * row_pitch as f64 + cumulative_gap;
+                    }
+                }
+                positions
+            } else {
+                Vec::new()
+            }
+        } else {
+            Vec::new()
+        }
+    } else {
+        Vec::new()
+    };
+
+    // The body; the header goes in front once the content height is known
+    let mut svg = String::new();
+
+    // SVG header; --margin shifts the viewBox origin so content coordinates stay unchanged
+    let margin = args.margin as f64;
+    let origin = -(args.margin as i64);
+    let svg_header = |height: f64| {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" width="{}" height="{}" viewBox="{} {} {} {}">
+<style>
+  .path-name {{ font-family: 'DejaVu Sans Mono', 'Courier New', monospace; font-size: {}px; }}
+</style>
+<rect x="{}" y="{}" width="100%" height="100%" fill="{}"/>
+"#,
+            total_width + 2.0 * margin,
+            height,
+            origin,
+            origin,
+            total_width + 2.0 * margin,
+            height,
+            font_size,
+            origin,
+            origin,
+            svg_rgb(theme.background)
+        )
+    };
+    if let Some(header) = graph.header.summary() {
+        svg.push_str(&format!(
+            "<metadata id=\"gfa-header\">{}</metadata>\n",
+            escape_xml(&header)
+        ));
+    }
+    if args.gap_style == GapStyle::Hatch || args.show_orphans {
+        svg.push_str(&format!(
+            r#"<defs><pattern id="{}" width="4" height="4" patternUnits="userSpaceOnUse" patternTransform="rotate(45)"><line x1="0" y1="0" x2="0" y2="4" stroke="{}" stroke-width="1"/></pattern></defs>
+"#,
+            SVG_GAP_HATCH_ID,
+            svg_rgb(theme.muted(190))
+        ));
+    }
+    if args.svg_interactive {
+        svg.push_str(svg_interactive::VIEWPORT_OPEN);
+    }
+
+    // Render annotation legend at the top if annotations are loaded (SVG)
+    let layer_start = svg.len();
+    if let Some(ref ann) = annotations {
+        // Filter categories to only those used by paths in the graph
+        // NA is added at the end if any path doesn't match a prefix
+        let used_categories: std::collections::HashSet<&str> = display_paths
+            .iter()
+            .map(|p| ann.get_annotation(&p.name))
+            .collect();
+        let mut filtered_categories: Vec<String> = ann
+            .categories
+            .iter()
+            .filter(|c| used_categories.contains(c.as_str()))
+            .cloned()
+            .collect();
+        // Add NA at the end if any path has it
+        if used_categories.contains("NA") && !filtered_categories.iter().any(|c| c == "NA") {
+            filtered_categories.push("NA".to_string());
+        }
+
+        let legend_svg = render_annotation_legend_svg(
+            &filtered_categories,
+            &ann.category_colors,
+            total_width,
+            args.legend_height as f64,
+            font_size,
+            theme.foreground,
+        );
+        svg.push_str(&legend_svg);
+    }
+
+    // Render the cluster legend below the annotation legend (SVG)
+    if let Some(cr) = cluster_result.as_ref().filter(|_| args.cluster_legend) {
+        let (labels, colors) = cluster_legend_entries(cr, args, &theme);
+        let y0 = if annotations.is_some() {
+            args.legend_height
+        } else {
+            0
+        };
+        svg.push_str(&format!("<g transform=\"translate(0,{})\">\n", y0));
+        svg.push_str(&render_annotation_legend_svg(
+            &labels,
+            &colors,
+            total_width,
+            args.legend_height as f64,
+            font_size,
+            theme.foreground,
+        ));
+        svg.push_str("</g>\n");
+    }
+
+    // Mark --downsample drafts in the row below the legends (SVG)
+    if let Some(n) = args.downsample.filter(|&n| n > 1) {
+        let y0 = if annotations.is_some() {
+            args.legend_height
+        } else {
+            0
+        } + cluster_legend_band(args);
+        svg.push_str(&format!(
+            r#"<text x="4" y="{}" class="path-name" fill="{}">{}</text>"#,
+            y0 as f64 + args.legend_height as f64 / 2.0 + font_size / 3.0,
+            fg,
+            downsample_label(n)
+        ));
+        svg.push('\n');
+    }
+    wrap_svg_layer(&mut svg, layer_start, "legend");
+
+    // Render dendrogram if enabled (SVG)
+    let layer_start = svg.len();
+    if dendrogram_width > 0.0 && !dendrogram_leaf_y_positions_svg.is_empty() {
+        if let Some(ref cr) = cluster_result {
+            if let Some(ref dg) = cr.dendrogram {
+                let dendro_svg = render_dendrogram_svg(
+                    dg,
+                    dendrogram_width,
+                    pix_per_path as f64,
+                    &dendrogram_leaf_y_positions_svg,
+                );
+                svg.push_str(&dendro_svg);
+                svg.push('\n');
+            }
+        }
+    }
+    wrap_svg_layer(&mut svg, layer_start, "dendrogram");
+
+    // Track max_y for edge rendering
+    let mut max_y: f64 = legend_height + path_space as f64;
+
+    // Path names are collected apart and drawn as their own layer above the rows
+    let mut names = String::new();
+    let layer_start = svg.len();
+
+    // Compressed mode: aggregate bins across all paths and render single row (SVG)
+    if args.compressed_mode {
+        // Use RdBu palette by default for compressed mode, or user-specified palette
+        let compressed_palette = depth_palette.unwrap_or(COLORBREWER_RDBU_11.as_slice());
+
+        // One row per statistic: --compressed-stat first, then --compressed-rows
+        let stats =
+            std::iter::once(args.compressed_stat).chain(args.compressed_rows.iter().copied());
+        for (row, stat) in stats.enumerate() {
+            // Aggregate bins across all paths
+            let compressed_bins = compressed_bins(&display_paths, graph, bin_width, stat);
+
+            // Render path name "COMPRESSED_MODE", or the statistic on extra rows
+            let y_start = legend_height + (row as u32 * row_pitch) as f64;
+            if !args.hide_path_names {
+                let text_y = y_start + (pix_per_path as f64 / 2.0) + (font_size / 3.0);
+                names.push_str(&format!(
+                    r#"<text x="{}" y="{}" class="path-name" fill="{}">{}</text>"#,
+                    dendrogram_width + cluster_bar_width + bar_gap + annotation_bar_width + 5.0,
+                    text_y,
+                    fg,
+                    if row == 0 {
+                        "COMPRESSED_MODE"
+                    } else {
+                        stat.label()
+                    }
+                ));
+                names.push('\n');
+            }
+
+            // Group consecutive bins with same color for rect merging
+            let mut sorted_bins: Vec<(usize, f64)> = compressed_bins.into_iter().collect();
+            sorted_bins.sort_by_key(|(k, _)| *k);
+
+            let mut prev_x: Option<usize> = None;
+            let mut run_start: usize = 0;
+            let mut run_color: (u8, u8, u8) = (0, 0, 0);
+
+            for (bin_idx, value) in &sorted_bins {
+                let (r, g, b) =
+                    compressed_bin_color(*value, stat, args.no_grey_depth, compressed_palette);
+
+                if let Some(px) = prev_x {
+                    if *bin_idx == px + 1 && (r, g, b) == run_color {
+                        // Continue the run
+                    } else {
+                        // Output the previous run
+                        let x = dendrogram_width
+                            + text_width
+                            + cluster_bar_width
+                            + bar_gap
+                            + annotation_bar_width
+                            + run_start as f64;
+                        let width = (px - run_start + 1) as f64;
+                        svg.push_str(&format!(
+                            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"/>"#,
+                            x, y_start, width, pix_per_path, run_color.0, run_color.1, run_color.2
+                        ));
+                        svg.push('\n');
+                        // Start new run
+                        run_start = *bin_idx;
+                        run_color = (r, g, b);
+                    }
+                } else {
+                    // First bin
+                    run_start = *bin_idx;
+                    run_color = (r, g, b);
+                }
+                prev_x = Some(*bin_idx);
+            }
+            // Output last run
+            if let Some(px) = prev_x {
+                let x = dendrogram_width
+                    + text_width
+                    + cluster_bar_width
+                    + bar_gap
+                    + annotation_bar_width
+                    + run_start as f64;
+                let width = (px - run_start + 1) as f64;
+                svg.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"/>"#,
+                    x, y_start, width, pix_per_path, run_color.0, run_color.1, run_color.2
+                ));
+                svg.push('\n');
+            }
+        }
+    }
+
+    // Pack-paths mode: use 2D collision detection to pack paths compactly (SVG)
+    let diversity = args.shade_variable_regions.then(|| {
+        relative_diversity(bin_diversity(
+            &display_paths,
+            graph,
+            bin_width,
+            viz_width as usize,
+        ))
+    });
+    // --show-orphans: the hatched columns of the orphan block
+    let orphan_runs: Vec<(u32, u32)> = orphan_start
+        .map(|start| vec![(((start as f64 / bin_width) as u32).min(viz_width), viz_width)])
+        .unwrap_or_default();
+    let mut mixed_bins = 0usize;
+    if args.pack_paths && !args.compressed_mode {
+        // Pre-compute bins for all paths to determine their X ranges
+        struct PathBinDataSvg {
+            min_bin: usize,
+            max_bin: usize,
+            bins: FxHashMap<usize, BinInfo>,
+            color: (u8, u8, u8),
+        }
+
+        let mut path_data: Vec<PathBinDataSvg> = Vec::with_capacity(display_paths.len());
+
+        for path in display_paths.iter() {
+            let mut bins = raw_bins(path);
+            let min_bin = bins.keys().copied().min().unwrap_or(usize::MAX);
+            let max_bin = bins.keys().copied().max().unwrap_or(0);
+            adjust_bins(args, &mut bins, bin_depth_means.as_ref());
+            assign_frequencies(&mut bins, path, graph, bin_width, node_frequency.as_deref());
+            if args.color_by_rank {
+                assign_ranks(&mut bins, path, graph, bin_width);
+            }
+
+            let color = if let Some(ref colors) = custom_colors {
+                colors.get(&path.name).copied().unwrap_or(theme.muted(200)) // Light grey for non-specified paths
+            } else {
+                compute_path_color(&path.name, args.color_by_prefix)
+            };
+
+            path_data.push(PathBinDataSvg {
+                min_bin,
+                max_bin,
+                bins,
+                color,
+            });
+        }
+
+        // Layout buffer: for each X position, track the lowest available row
+        let mut occupancy: Vec<Vec<bool>> = vec![vec![false; viz_width as usize]; 1];
+        let mut path_rows: Vec<usize> = Vec::with_capacity(path_data.len());
+
+        for pd in &path_data {
+            if pd.min_bin == usize::MAX {
+                path_rows.push(0);
+                continue;
+            }
+
+            let mut found_row = None;
+            for row in 0..occupancy.len() {
+                let mut fits = true;
+                for bin_idx in pd.min_bin..=pd.max_bin.min(viz_width as usize - 1) {
+                    if occupancy[row][bin_idx] {
+                        fits = false;
+                        break;
+                    }
+                }
+                if fits {
+                    found_row = Some(row);
+                    break;
+                }
+            }
+
+            let row = found_row.unwrap_or_else(|| {
+                occupancy.push(vec![false; viz_width as usize]);
+                occupancy.len() - 1
+            });
+
+            for bin_idx in pd.min_bin..=pd.max_bin.min(viz_width as usize - 1) {
+                occupancy[row][bin_idx] = true;
+            }
+
+            path_rows.push(row);
+        }
+
+        let packed_rows = occupancy.len() as u32;
+        let packed_path_space =
+            packed_rows * pix_per_path + packed_rows.saturating_sub(1) * args.path_spacing;
+        max_y = legend_height + packed_path_space as f64;
+
+        if let Some(ref diversity) = diversity {
+            svg.push_str(&svg_shade_columns(
+                dendrogram_width + text_width + cluster_bar_width + bar_gap + annotation_bar_width,
+                legend_height,
+                packed_path_space as f64,
+                diversity,
+                &theme,
+            ));
+        }
+        svg.push_str(&svg_gap_runs(
+            dendrogram_width + text_width + cluster_bar_width + bar_gap + annotation_bar_width,
+            legend_height,
+            packed_path_space as f64,
+            &orphan_runs,
+            GapStyle::Hatch,
+            &theme,
+        ));
+
+        // Render each path at its packed Y position
+        for (path_idx, (pd, path)) in path_data.iter().zip(display_paths.iter()).enumerate() {
+            let y_start = legend_height + path_rows[path_idx] as f64 * row_pitch as f64;
+            svg.push_str(&svg_row_open(args, "row", path_idx, &path.name));
+            let (path_r, path_g, path_b) = pd.color;
+            let dimmed = highlight_paths
+                .as_ref()
+                .is_some_and(|hp| !hp.contains(&path.name));
+            let fade = row_fade.get(&path.name).copied().unwrap_or(1.0);
+            let dim_if = |c: (u8, u8, u8)| theme.blend(if dimmed { theme.dim(c) } else { c }, fade);
+            let path_length: u64 = path
+                .steps
+                .iter()
+                .map(|step| {
+                    let seg_id = step.segment_id as usize;
+                    if seg_id < graph.segments.len() {
+                        graph.segments[seg_id].sequence_len
+                    } else {
+                        0
+                    }
+                })
+                .sum();
+            let darkness_length = if args.longest_path {
+                max_path_length
+            } else {
+                path_length
+            };
+
+            svg.push_str(&svg_gap_runs(
+                dendrogram_width + text_width + cluster_bar_width + bar_gap + annotation_bar_width,
+                y_start,
+                pix_per_path as f64,
+                &gap_runs(pd.bins.keys(), viz_width),
+                args.gap_style,
+                &theme,
+            ));
+
+            // Group bins by color for rect merging
+            let mut sorted_bins: Vec<(usize, &BinInfo)> =
+                pd.bins.iter().map(|(k, v)| (*k, v)).collect();
+            sorted_bins.sort_by_key(|(k, _)| *k);
+
+            let mut prev_x: Option<usize> = None;
+            let mut run_start: usize = 0;
+            let mut run_color: (u8, u8, u8) = (0, 0, 0);
+
+            for (bin_idx, bin_info) in &sorted_bins {
+                // Calculate color
+                let (r, g, b) = if highlight_nodes.is_some() {
+                    if bin_info.highlighted {
+                        (255, 0, 0)
+                    } else {
+                        theme.muted(180)
+                    }
+                } else if args.color_by_frequency_class {
+                    frequency_class_color(bin_info.frequency, path_count as usize, args)
+                } else if args.color_by_rank {
+                    rank_color(bin_info.rank, &theme)
+                } else if args.color_by_mean_depth {
+                    get_depth_color(bin_info.mean_depth, args.no_grey_depth, depth_palette)
+                } else if args.color_by_mean_inversion_rate {
+                    let inv_r = (bin_info.mean_inv * 255.0).min(255.0) as u8;
+                    (inv_r, 0, 0)
+                } else if args.color_by_uncalled_bases {
+                    let green = (bin_info.mean_uncalled * 255.0).min(255.0) as u8;
+                    (0, green, 0)
+                } else if args.show_strand {
+                    let apply_strand = args
+                        .alignment_prefix
+                        .as_ref()
+                        .is_none_or(|prefix| path.name.starts_with(prefix));
+                    if apply_strand {
+                        if bin_info.mean_inv > 0.5 {
+                            (200, 50, 50)
+                        } else {
+                            (50, 50, 200)
+                        }
+                    } else {
+                        (path_r, path_g, path_b)
+                    }
+                } else {
+                    (path_r, path_g, path_b)
+                };
+
+                let (r, g, b) = if args.change_darkness && highlight_nodes.is_none() {
+                    let apply_darkness = args
+                        .alignment_prefix
+                        .as_ref()
+                        .is_none_or(|prefix| path.name.starts_with(prefix));
+                    if apply_darkness && darkness_length > 0 {
+                        let pos_factor = bin_info.mean_pos / darkness_length as f64;
+                        let darkness = if bin_info.mean_inv > 0.5 {
+                            1.0 - pos_factor
+                        } else {
+                            pos_factor
+                        };
+                        darkness_shade((r, g, b), darkness, args.white_to_black, theme.linear)
+                    } else {
+                        (r, g, b)
+                    }
+                } else {
+                    (r, g, b)
+                };
+                let (r, g, b) = dim_if((r, g, b));
+
+                if let Some(px) = prev_x {
+                    if *bin_idx == px + 1 && (r, g, b) == run_color && !args.svg_data_attrs {
+                        // Continue run
+                    } else {
+                        // Output run
+                        let x = dendrogram_width
+                            + text_width
+                            + cluster_bar_width
+                            + bar_gap
+                            + annotation_bar_width
+                            + run_start as f64;
+                        let width = (px - run_start + 1) as f64;
+                        svg.push_str(&format!(
+                            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"{}/>"#,
+                            x, y_start, width, pix_per_path, run_color.0, run_color.1, run_color.2,
+                            svg_bin_attrs(args, &path.name, run_start, pd.bins.get(&run_start))
+                        ));
+                        svg.push('\n');
+                        run_start = *bin_idx;
+                        run_color = (r, g, b);
+                    }
+                } else {
+                    run_start = *bin_idx;
+                    run_color = (r, g, b);
+                }
+                prev_x = Some(*bin_idx);
+            }
+            // Output last run
+            if let Some(px) = prev_x {
+                let x = dendrogram_width
+                    + text_width
+                    + cluster_bar_width
+                    + bar_gap
+                    + annotation_bar_width
+                    + run_start as f64;
+                let width = (px - run_start + 1) as f64;
+                svg.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"{}/>"#,
+                    x,
+                    y_start,
+                    width,
+                    pix_per_path,
+                    run_color.0,
+                    run_color.1,
+                    run_color.2,
+                    svg_bin_attrs(args, &path.name, run_start, pd.bins.get(&run_start))
+                ));
+                svg.push('\n');
+            }
+            // Outline bins whose color averages forward and reverse steps
+            if strand_colored(args, &path.name) {
+                mixed_bins += pd.bins.values().filter(|b| b.mixed_strand).count();
+                if args.flag_mixed_bins {
+                    svg.push_str(&svg_mixed_runs(
+                        dendrogram_width
+                            + text_width
+                            + cluster_bar_width
+                            + bar_gap
+                            + annotation_bar_width,
+                        y_start,
+                        pix_per_path as f64,
+                        &mixed_runs(&pd.bins, viz_width),
+                    ));
+                }
+            }
+            svg.push_str(&svg_row_close(args));
+        }
+    }
+
+    if let Some(diversity) = diversity.as_deref().filter(|_| !args.pack_paths) {
+        svg.push_str(&svg_shade_columns(
+            dendrogram_width + cluster_bar_width + text_width,
+            legend_height,
+            path_space as f64,
+            diversity,
+            &theme,
+        ));
+    }
+    if !args.pack_paths {
+        svg.push_str(&svg_gap_runs(
+            dendrogram_width + text_width + cluster_bar_width + bar_gap + annotation_bar_width,
+            legend_height,
+            path_space as f64,
+            &orphan_runs,
+            GapStyle::Hatch,
+            &theme,
+        ));
+    }
+
+    // Subrange paths sharing a contig are labeled by its name and their interval rank
+    let name_labels = subrange_labels(&display_paths);
+
+    // Render each path (SVG) - skip if compressed mode or pack_paths mode
+    let mut prev_cluster_id: Option<usize> = None;
+    // Paths sharing each prefix group's row, whose colors split the row's indicator bars
+    let group_members = path_grouping
+        .as_ref()
+        .map(|pg| pg.members())
+        .unwrap_or_default();
+    let row_members = |path_idx: usize| match path_grouping {
+        Some(ref pg) => group_members[pg.path_to_group[path_idx] as usize].clone(),
+        None => vec![path_idx],
+    };
+    let mut cumulative_gap: f64 = 0.0;
+    let cluster_gap = args.cluster_gap as f64;
+
+    for (path_idx, path) in display_paths.iter().enumerate() {
+        // Skip normal rendering in compressed mode or pack_paths mode
+        if args.compressed_mode || args.pack_paths {
+            break;
+        }
+        // Check if grouping is enabled and get group index
+        let (row_idx, base_name, is_first_in_group) = if let Some(ref pg) = path_grouping {
+            let group_idx = pg.path_to_group[path_idx];
+            if group_idx < 0 {
+                // Skip paths that don't match any prefix
+                continue;
+            }
+            let first = !rendered_groups.contains(&group_idx);
+            if first {
+                rendered_groups.insert(group_idx);
+            }
+            (
+                group_idx as u32,
+                pg.prefixes[group_idx as usize].clone(),
+                first,
+            )
+        } else {
+            let label = name_labels.get(&path.name).unwrap_or(&path.name);
+            (path_idx as u32, label.clone(), true)
+        };
+
+        // Add abundance suffix for cluster representatives
+        let display_name = if args.cluster_representatives {
+            if let Some(ref cr) = cluster_result {
+                let cluster_id = cr.cluster_ids[path_idx];
+                let size = cr.cluster_sizes[cluster_id];
+                format!("{} (n={})", base_name, size)
+            } else {
+                base_name
+            }
+        } else {
+            base_name
+        };
+        let display_name = if path_grouping.is_none() && flip_names.contains(&path.name) {
+            format!("{}{}", display_name, FLIP_MARKER)
+        } else {
+            display_name
+        };
+        let display_name = match path.piece {
+            Some(piece) if path_grouping.is_none() => display_name + &piece_label(piece),
+            _ => display_name,
+        };
+
+        // Add gap before new cluster (except first)
+        if let Some(ref cr) = cluster_result {
+            let cluster_id = cr.cluster_ids[path_idx];
+            if prev_cluster_id.is_some_and(|prev| prev != cluster_id) {
+                cumulative_gap += cluster_gap;
+            }
+            prev_cluster_id = Some(cluster_id);
+        }
+
+        let y_start = legend_height + (row_idx * row_pitch) as f64 + cumulative_gap;
+        svg.push_str(&svg_row_open(args, "row", path_idx, &path.name));
+
+        // Render the row's indicator bars on the left, once per row; a prefix group's bars
+        // are split between the colors of all its paths
+        if is_first_in_group {
+            let members = row_members(path_idx);
+            let mut bars = Vec::new();
+            if let Some(ref cr) = cluster_result {
+                let colors: Vec<_> = members
+                    .iter()
+                    .map(|&i| cr.cluster_color(cr.cluster_ids[i], args, &theme))
+                    .collect();
+                bars.push((dendrogram_width, cluster_bar_width, colors));
+            }
+
+            // Annotation indicator bar (after cluster bar + gap)
+            if let Some(ref ann) = annotations {
+                let colors: Vec<_> = members
+                    .iter()
+                    .map(|&i| ann.get_color(ann.get_annotation(&display_paths[i].name)))
+                    .collect();
+                bars.push((
+                    dendrogram_width + cluster_bar_width + bar_gap,
+                    annotation_bar_width,
+                    colors,
+                ));
+            }
+
+            for (bar_x, bar_width, colors) in bars {
+                for (x0, width, (r, g, b)) in bar_stripes(&colors, bar_width as u32) {
+                    svg.push_str(&format!(
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"/>"#,
+                        bar_x + x0 as f64,
+                        y_start,
+                        width,
+                        pix_per_path,
+                        r,
+                        g,
+                        b
+                    ));
+                    svg.push('\n');
+                }
+            }
+
+            // Cluster id stacked vertically inside the cluster bar
+            if let Some(cr) = cluster_result.as_ref().filter(|_| args.cluster_bar_labels) {
+                if let Some(label) = cluster_bar_label(cr, path_idx, pix_per_path) {
+                    let color = cr.cluster_color(cr.cluster_ids[path_idx], args, &theme);
+                    let label_height = (label.len() as u32 * BAR_LABEL_CHAR) as f64;
+                    let x = dendrogram_width + cluster_bar_width / 2.0;
+                    let y = y_start + (pix_per_path as f64 - label_height) / 2.0;
+                    let digits: String = label
+                        .chars()
+                        .enumerate()
+                        .map(|(i, c)| {
+                            format!(
+                                r#"<tspan x="{}" y="{}">{}</tspan>"#,
+                                x,
+                                y + ((i + 1) as u32 * BAR_LABEL_CHAR) as f64 - 1.0,
+                                c
+                            )
+                        })
+                        .collect();
+                    svg.push_str(&format!(
+                        r#"<text font-family="'DejaVu Sans Mono', 'Courier New', monospace" font-size="{}" text-anchor="middle" fill="{}">{}</text>"#,
+                        BAR_LABEL_CHAR,
+                        svg_rgb(contrast_text_color(color)),
+                        digits
+                    ));
+                    svg.push('\n');
+                }
+            }
+        }
+
+        let (path_r, path_g, path_b) = if let Some(ref colors) = custom_colors {
+            colors.get(&path.name).copied().unwrap_or(theme.muted(200)) // Light grey for non-specified paths
+        } else {
+            compute_path_color(&path.name, args.color_by_prefix)
+        };
+        let dimmed = highlight_paths
+            .as_ref()
+            .is_some_and(|hp| !hp.contains(&path.name));
+        let fade = row_fade.get(&path.name).copied().unwrap_or(1.0);
+        let dim_if = |c: (u8, u8, u8)| theme.blend(if dimmed { theme.dim(c) } else { c }, fade);
+        let reference = reference_names.contains(path.name.as_str());
+
+        // Render path name (vector font, truncated only if -c is given) - only once per group
+        if is_first_in_group && !args.hide_path_names {
+            let display_name = match args.max_num_of_characters {
+                Some(max_chars) => truncate_name(&display_name, max_chars, args.truncate_names),
+                None => display_name,
+            };
+            let text_y = y_start + (pix_per_path as f64 / 2.0) + (font_size / 3.0);
+            names.push_str(&svg_row_open(args, "name", path_idx, &path.name));
+            let text_color = if args.color_path_names_background {
+                // Black or white text depending on background luminance
+                let (path_r, path_g, path_b) = dim_if((path_r, path_g, path_b));
+                names.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"/>"#,
+                    dendrogram_width + cluster_bar_width + bar_gap + annotation_bar_width,
+                    y_start,
+                    text_width,
+                    pix_per_path,
+                    path_r,
+                    path_g,
+                    path_b
+                ));
+                names.push('\n');
+                svg_rgb(contrast_text_color((path_r, path_g, path_b)))
+            } else {
+                svg_rgb(dim_if(theme.foreground))
+            };
+            let weight = if reference {
+                r#" font-weight="bold""#
+            } else {
+                ""
+            };
+            names.push_str(&format!(
+                r#"<text x="{}" y="{}" class="path-name"{} fill="{}">{}</text>"#,
+                dendrogram_width + cluster_bar_width + bar_gap + annotation_bar_width + 5.0,
+                text_y,
+                weight,
+                text_color,
+                escape_xml(&display_name)
+            ));
+            names.push('\n');
+            names.push_str(&svg_row_close(args));
+        }
+
+        // Calculate current path length for darkness gradient
+        let path_length: u64 = path
+            .steps
+            .iter()
+            .map(|step| {
+                let seg_id = step.segment_id as usize;
+                if seg_id < graph.segments.len() {
+                    graph.segments[seg_id].sequence_len
+                } else {
+                    0
+                }
+            })
+            .sum();
+        let darkness_length = if args.longest_path {
+            max_path_length
+        } else {
+            path_length
+        };
+
+        // Compute bins for this path (SVG rendering)
+        let mut bins = drawn_bins(path);
+        assign_frequencies(&mut bins, path, graph, bin_width, node_frequency.as_deref());
+        if args.color_by_rank {
+            assign_ranks(&mut bins, path, graph, bin_width);
+        }
+
+        // Render bins as rectangles
+        let rect_height = if args.no_path_borders || pix_per_path < 3 {
+            pix_per_path as f64
+        } else {
+            (pix_per_path - 1) as f64
+        };
+
+        svg.push_str(&svg_gap_runs(
+            dendrogram_width + cluster_bar_width + text_width,
+            y_start,
+            rect_height,
+            &gap_runs(bins.keys(), viz_width),
+            args.gap_style,
+            &theme,
+        ));
+
+        // Merge consecutive bins with same color into single rectangles
+        let mut bin_list: Vec<(&usize, &BinInfo)> = bins.iter().collect();
+        bin_list.sort_by_key(|(idx, _)| **idx);
+
+        // Helper to get color for a bin
+        let get_bin_color = |bin_info: &BinInfo| -> (u8, u8, u8) {
+            let (r, g, b) = if highlight_nodes.is_some() {
+                // Highlighting mode: red for highlighted bins, grey for others
+                if bin_info.highlighted {
+                    (255, 0, 0)
+                } else {
+                    theme.muted(180)
+                }
+            } else if args.color_by_frequency_class {
+                frequency_class_color(bin_info.frequency, path_count as usize, args)
+            } else if args.color_by_rank {
+                rank_color(bin_info.rank, &theme)
+            } else if args.color_by_mean_depth {
+                get_depth_color(bin_info.mean_depth, args.no_grey_depth, depth_palette)
+            } else if args.color_by_mean_inversion_rate {
+                let inv_r = (bin_info.mean_inv * 255.0).min(255.0) as u8;
+                (inv_r, 0, 0)
+            } else if args.color_by_uncalled_bases {
+                // Black to green gradient based on proportion of uncalled bases (N's)
+                let green = (bin_info.mean_uncalled * 255.0).min(255.0) as u8;
+                (0, green, 0)
+            } else if args.show_strand {
+                // Check if alignment_prefix applies
+                let apply_strand = args
+                    .alignment_prefix
+                    .as_ref()
+                    .is_none_or(|prefix| path.name.starts_with(prefix));
+
+                if apply_strand {
+                    if bin_info.mean_inv > 0.5 {
+                        (200, 50, 50)
+                    } else {
+                        (50, 50, 200)
+                    }
+                } else {
+                    (path_r, path_g, path_b)
+                }
+            } else {
+                (path_r, path_g, path_b)
+            };
+
+            // Apply darkness gradient if enabled
+            if args.change_darkness && highlight_nodes.is_none() {
+                let apply_darkness = args
+                    .alignment_prefix
+                    .as_ref()
+                    .is_none_or(|prefix| path.name.starts_with(prefix));
+
+                if apply_darkness && darkness_length > 0 {
+                    let pos_factor = bin_info.mean_pos / darkness_length as f64;
+                    let darkness = if bin_info.mean_inv > 0.5 {
+                        1.0 - pos_factor
+                    } else {
+                        pos_factor
+                    };
+
+                    darkness_shade((r, g, b), darkness, args.white_to_black, theme.linear)
+                } else {
+                    (r, g, b)
+                }
+            } else {
+                (r, g, b)
+            }
+        };
+
+        if !bin_list.is_empty() {
+            let mut run_start = *bin_list[0].0;
+            let mut run_color = dim_if(get_bin_color(bin_list[0].1));
+            let mut run_end = run_start;
+
+            for i in 1..bin_list.len() {
+                let (&bin_idx, bin_info) = bin_list[i];
+                let color = dim_if(get_bin_color(bin_info));
+
+                // Check if this bin continues the run (consecutive and same color)
+                if bin_idx == run_end + 1 && color == run_color && !args.svg_data_attrs {
+                    run_end = bin_idx;
+                } else {
+                    // Output the previous run
+                    let x = dendrogram_width
+                        + cluster_bar_width
+                        + text_width
+                        + (run_start as f64).min((viz_width - 1) as f64);
+                    let width = (run_end - run_start + 1) as f64;
+                    svg.push_str(&format!(
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"{}/>"#,
+                        x,
+                        y_start,
+                        width,
+                        rect_height,
+                        run_color.0,
+                        run_color.1,
+                        run_color.2,
+                        svg_bin_attrs(args, &path.name, run_start, bins.get(&run_start))
+                    ));
+                    svg.push('\n');
+
+                    // Start a new run
+                    run_start = bin_idx;
+                    run_color = color;
+                    run_end = bin_idx;
+                }
+            }
+
+            // Output the final run
+            let x = dendrogram_width
+                + cluster_bar_width
+                + text_width
+                + (run_start as f64).min((viz_width - 1) as f64);
+            let width = (run_end - run_start + 1) as f64;
+            svg.push_str(&format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"{}/>"#,
+                x,
+                y_start,
+                width,
+                rect_height,
+                run_color.0,
+                run_color.1,
+                run_color.2,
+                svg_bin_attrs(args, &path.name, run_start, bins.get(&run_start))
+            ));
+            svg.push('\n');
+        }
+
+        // Add border line if needed
+        if !args.no_path_borders && pix_per_path >= 3 {
+            let border_y = y_start + rect_height;
+            let border_color = if args.black_path_borders || reference {
+                "black".to_string()
+            } else {
+                svg_rgb(theme.background)
+            };
+            svg.push_str(&format!(
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1"/>"#,
+                dendrogram_width + cluster_bar_width + bar_gap + annotation_bar_width + text_width,
+                border_y,
+                total_width,
+                border_y,
+                border_color
+            ));
+            svg.push('\n');
+        }
+
+        // Paint this row's cluster in each window as a band along its top edge
+        if let Some(ref wc) = window_clusters {
+            let size = args.cluster_windows.unwrap_or(1) as f64;
+            let band_height = (pix_per_path as f64 / 4.0).floor().max(1.0);
+            let x_base = dendrogram_width + cluster_bar_width + text_width;
+            for (w, assignment) in wc.iter().enumerate() {
+                let Some(cluster) = assignment[path_idx] else {
+                    continue;
+                };
+                let x_start = (w as f64 * size) / bin_width;
+                let x_end = (((w + 1) as f64 * size) / bin_width).min(viz_width as f64);
+                svg.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    x_base + x_start,
+                    y_start,
+                    (x_end - x_start).max(1.0),
+                    band_height,
+                    svg_rgb(dim_if(get_cluster_color(cluster)))
+                ));
+                svg.push('\n');
+            }
+        }
+
+        // Outline the medoid row of each cluster
+        if medoid_names.contains(&path.name) {
+            let x =
+                dendrogram_width + cluster_bar_width + bar_gap + annotation_bar_width + text_width;
+            svg.push_str(&format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}" stroke-width="1"/>"#,
+                x + 0.5,
+                y_start + 0.5,
+                (total_width - x - 1.0).max(0.0),
+                (pix_per_path as f64 - 1.0).max(0.0),
+                fg
+            ));
+            svg.push('\n');
+        }
+
+        // Outline bins whose color averages forward and reverse steps
+        if strand_colored(args, &path.name) {
+            mixed_bins += bins.values().filter(|b| b.mixed_strand).count();
+            if args.flag_mixed_bins {
+                svg.push_str(&svg_mixed_runs(
+                    dendrogram_width + cluster_bar_width + text_width,
+                    y_start,
+                    pix_per_path as f64,
+                    &mixed_runs(&bins, viz_width),
+                ));
+            }
+        }
+
+        if args
+            .row_axes
+            .is_some_and(|every| (path_idx as u32).is_multiple_of(every))
+        {
+            svg.push_str(&svg_row_axis(
+                dendrogram_width + cluster_bar_width + text_width,
+                y_start,
+                pix_per_path as f64,
+                &row_axis(&bins, ruler_step, viz_width),
+                &fg,
+            ));
+        }
+
+        // Draw link lines between discontinuous path pieces
+        if let Some(link_width) = args.link_path_pieces {
+            let (path_r, path_g, path_b) = dim_if((path_r, path_g, path_b));
+            let mut sorted_bins: Vec<usize> = bins.keys().copied().collect();
+            sorted_bins.sort();
+
+            if sorted_bins.len() > 1 {
+                let link_height = (pix_per_path as f64 * link_width).max(1.0);
+                let link_y = y_start + (pix_per_path as f64 / 2.0) - (link_height / 2.0);
+
+                for i in 1..sorted_bins.len() {
+                    let prev_bin = sorted_bins[i - 1];
+                    let curr_bin = sorted_bins[i];
+
+                    // If there's a gap between bins, draw a connecting line
+                    if curr_bin > prev_bin + 1 {
+                        let x_start = dendrogram_width
+                            + cluster_bar_width
+                            + annotation_bar_width
+                            + text_width
+                            + (prev_bin as f64 + 1.0).min((viz_width - 1) as f64);
+                        let x_end = dendrogram_width
+                            + cluster_bar_width
+                            + annotation_bar_width
+                            + text_width
+                            + (curr_bin as f64).min((viz_width - 1) as f64);
+                        let line_width = x_end - x_start;
+
+                        if line_width > 0.0 {
+                            svg.push_str(&format!(
+                                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"/>"#,
+                                x_start, link_y, line_width, link_height, path_r, path_g, path_b
+                            ));
+                            svg.push('\n');
+                        }
+                    }
+                }
+            }
+        }
+        svg.push_str(&svg_row_close(args));
+    }
+    warn_mixed_bins(args, mixed_bins);
+
+    // Arcs over large within-path jumps, in the band between the legend and the rows
+    if let Some(max_jump) = args.jump_arcs {
+        let baseline = legend_height - 0.5;
+        let x_base = dendrogram_width + cluster_bar_width + text_width;
+        for (path_idx, a, b, h) in jump_arcs(&display_paths, graph, max_jump, bin_width, viz_width)
+        {
+            let path = display_paths[path_idx];
+            let color = match custom_colors {
+                Some(ref colors) => colors.get(&path.name).copied().unwrap_or(theme.muted(200)),
+                None => compute_path_color(&path.name, args.color_by_prefix),
+            };
+            svg.push_str(&format!(
+                r#"<path d="M{:.1},{:.1} Q{:.1},{:.1} {:.1},{:.1}" fill="none" stroke="{}" stroke-width="1"/>"#,
+                x_base + a,
+                baseline,
+                x_base + (a + b) / 2.0,
+                baseline - 2.0 * h,
+                x_base + b,
+                baseline,
+                svg_rgb(color)
+            ));
+            svg.push('\n');
+        }
+    }
+
+    // --compress-invariant: a break cut through the rows at each shrunk run (SVG)
+    for &pos in &invariant_breaks {
+        let x = dendrogram_width
+            + cluster_bar_width
+            + text_width
+            + (pos as f64 / bin_width).min(viz_width as f64);
+        let bottom = legend_height + path_space as f64 + cumulative_gap;
+        for dx in [-1.5, 1.5] {
+            svg.push_str(&format!(
+                r#"<line class="invariant-break" x1="{:.1}" y1="{}" x2="{:.1}" y2="{}" stroke="{}" stroke-width="1"/>"#,
+                x + dx - 1.0,
+                bottom,
+                x + dx + 1.0,
+                legend_height,
+                svg_rgb(theme.background)
+            ));
+            svg.push('\n');
+        }
+    }
+
+    wrap_svg_layer(&mut svg, layer_start, "paths");
+    let layer_start = svg.len();
+    svg.push_str(&names);
+    wrap_svg_layer(&mut svg, layer_start, "names");
+
+    // Update path space to include cumulative gap
+    let path_space_with_gap = path_space as f64 + cumulative_gap;
+    max_y = max_y.max(path_space_with_gap);
+
+    // Calculate x-axis dimensions if enabled
+    let axis_font_size = AXIS_LABEL_HEIGHT as f64;
+    let tick_height = AXIS_TICK_HEIGHT as f64;
+    let axis_padding = AXIS_PADDING as f64;
+    let axis_total_height = axis_height(args) as f64;
+
+    // Render x-axis if requested (between paths and edges)
+    let layer_start = svg.len();
+    let x_axis = args.x_axis.as_ref().map(|a| path_resolver.canonical(a));
+    if let Some(ref coord_system) = x_axis {
+        // Y position for the axis line (at the bottom of paths)
+        let axis_y = legend_height + path_space_with_gap + args.panel_gap as f64 + axis_padding;
+
+        // X start of the axis line (end will be calculated based on path's pangenomic extent)
+        let axis_x_start =
+            dendrogram_width + cluster_bar_width + bar_gap + annotation_bar_width + text_width;
+
+        // Draw axis label on the left
+        // Strip the :start-end range from the label when showing absolute coordinates
+        let label_text = if coord_system.to_lowercase() == "pangenomic" {
+            "pangenomic".to_string()
+        } else if args.x_axis_absolute {
+            strip_subpath_range(coord_system).to_string()
+        } else {
+            coord_system.clone()
+        };
+
+        // Truncate label if too long for the left panel (use same char_width as path names)
+        let max_label_chars = (text_width / char_width) as usize;
+        let display_label = if text_columns(&label_text) > max_label_chars && max_label_chars > 3 {
+            format!(
+                "{}...",
+                label_text
+                    .chars()
+                    .take(max_label_chars.saturating_sub(3))
+                    .collect::<String>()
+            )
+        } else {
+            label_text.clone()
+        };
+
+        // Position label like path names (same x offset, vertically centered in axis area)
+        let label_y = axis_y + (axis_total_height / 2.0) + (font_size / 3.0);
+        svg.push_str(&format!(
+            r#"<text x="{}" y="{}" class="path-name" font-weight="bold" fill="{}">{}</text>"#,
+            dendrogram_width + cluster_bar_width + bar_gap + annotation_bar_width + 5.0,
+            label_y,
+            fg,
+            escape_xml(&display_label)
+        ));
+        svg.push('\n');
+
+        // Calculate tick positions and labels
+        let num_ticks = args.x_ticks.max(2) as usize;
+        let is_pangenomic = coord_system.to_lowercase() == "pangenomic";
+
+        // Warn if --x-axis-absolute is used with pangenomic
+        if args.x_axis_absolute && is_pangenomic {
+            debug!("--x-axis-absolute has no effect with pangenomic coordinates");
+        }
+
+        // For pangenomic coordinates, use total graph length
+        // For path-based coordinates, find the path and use its length
+        // Also calculate pixel range where the path actually appears
+        let (coord_start, coord_end, pixel_start, pixel_end) = if is_pangenomic {
+            (
+                graph.origin.pangenomic,
+                graph.origin.pangenomic + len_to_visualize,
+                0.0f64,
+                viz_width as f64,
+            )
+        } else {
+            // Find the path with the specified name
+            if let Some(path) = graph.paths.iter().find(|p| p.name == *coord_system) {
+                // Calculate path length and pangenomic positions from its steps
+                let mut path_len: u64 = 0;
+                let mut pangenomic_start: Option<u64> = None;
+                let mut pangenomic_end: u64 = 0;
+
+                for step in &path.steps {
+                    let seg_id = step.segment_id as usize;
+                    if seg_id < graph.segments.len() {
+                        let seg_len = graph.segments[seg_id].sequence_len;
+                        let seg_offset = graph.segment_offsets[seg_id];
+
+                        // Track first segment's pangenomic position
+                        if pangenomic_start.is_none() {
+                            pangenomic_start = Some(seg_offset);
+                        }
+                        // Track last segment's end position
+                        pangenomic_end = seg_offset + seg_len;
+                        path_len += seg_len;
+                    }
+                }
+
+                let pangenomic_start = pangenomic_start.unwrap_or(0);
+
+                // Convert pangenomic positions to pixel positions
+                let pix_start = (pangenomic_start as f64 / bin_width).min(viz_width as f64);
+                let pix_end = (pangenomic_end as f64 / bin_width).min(viz_width as f64);
+
+                // Add subpath start offset if --x-axis-absolute is enabled, and the part of the
+                // path before the --path-range window
+                let offset = if args.x_axis_absolute {
+                    parse_subpath_start(coord_system)
+                } else {
+                    0
+                } + graph.origin.paths.get(&path.name).copied().unwrap_or(0);
+                (offset, offset + path_len, pix_start, pix_end)
+            } else {
+                // Path not found, fall back to pangenomic
+                debug!(
+                    "Path '{}' not found, using pangenomic coordinates",
+                    coord_system
+                );
+                (0u64, len_to_visualize, 0.0f64, viz_width as f64)
+            }
+        };
+
+        // Calculate the pixel width of the path's range
+        let path_pixel_width = pixel_end - pixel_start;
+
+        // Draw horizontal axis line only where the path exists
+        let axis_line_x_start = axis_x_start as f64 + pixel_start;
+        let axis_line_x_end = axis_x_start as f64 + pixel_end;
+        svg.push_str(&format!(
+            r#"<line x1="{:.1}" y1="{}" x2="{:.1}" y2="{}" stroke="{}" stroke-width="1"/>"#,
+            axis_line_x_start, axis_y, axis_line_x_end, axis_y, fg
+        ));
+        svg.push('\n');
+
+        // Draw ticks only where the path exists
+        for i in 0..num_ticks {
+            let t = i as f64 / (num_ticks - 1) as f64;
+            // Map tick position to the path's pixel range
+            let x_pos = axis_x_start as f64 + pixel_start + t * (path_pixel_width - 1.0).max(0.0);
+            let coord_value = coord_start as f64 + t * (coord_end - coord_start) as f64;
+            if args.svg_interactive {
+                svg.push_str(&format!("<g id=\"tick-{}\">\n", i));
+            }
+
+            // Draw tick mark
+            svg.push_str(&format!(
+                r#"<line x1="{:.1}" y1="{}" x2="{:.1}" y2="{}" stroke="{}" stroke-width="1"/>"#,
+                x_pos,
+                axis_y,
+                x_pos,
+                axis_y + tick_height,
+                fg
+            ));
+            svg.push('\n');
+
+            // Format coordinate value (use K/M/G suffixes for large numbers)
+            let label = format_coordinate(coord_value as u64);
+
+            // Calculate text anchor based on position
+            let anchor = if i == 0 {
+                "start"
+            } else if i == num_ticks - 1 {
+                "end"
+            } else {
+                "middle"
+            };
+
+            svg.push_str(&format!(
+                r#"<text x="{:.1}" y="{}" font-family="'DejaVu Sans Mono', 'Courier New', monospace" font-size="{}" font-weight="bold" text-anchor="{}" fill="{}">{}</text>"#,
+                x_pos,
+                axis_y + tick_height + axis_font_size,
+                axis_font_size,
+                anchor,
+                fg,
+                label
+            ));
+            svg.push('\n');
+            if args.svg_interactive {
+                svg.push_str("</g>\n");
+            }
+        }
+
+        // Update max_y to include axis
+        max_y = legend_height + path_space_with_gap + args.panel_gap as f64 + axis_total_height;
+    }
+    wrap_svg_layer(&mut svg, layer_start, "axis");
+
+    // Render edges as SVG paths (offset by legend, x-axis height and panel gaps)
+    let axis_panel_height = if args.x_axis.is_some() {
+        axis_total_height + args.panel_gap as f64
+    } else {
+        0.0
+    };
+
+    // Diversity strip below the axis (SVG)
+    let layer_start = svg.len();
+    if args.diversity_track {
+        let track_y =
+            legend_height + path_space_with_gap + axis_panel_height + args.panel_gap as f64;
+        let track_height = DIVERSITY_TRACK_HEIGHT as f64;
+        let x_base = dendrogram_width + cluster_bar_width + text_width;
+        let diversity = bin_diversity(&display_paths, graph, bin_width, viz_width as usize);
+        let mut x = 0;
+        while x < diversity.len() {
+            let color = theme.blend(theme.foreground, diversity[x]);
+            let mut end = x + 1;
+            while end < diversity.len() && theme.blend(theme.foreground, diversity[end]) == color {
+                end += 1;
+            }
+            svg.push_str(&format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                x_base + x as f64,
+                track_y,
+                end - x,
+                track_height,
+                svg_rgb(color)
+            ));
+            svg.push('\n');
+            x = end;
+        }
+        if !args.hide_path_names && text_width > 0.0 {
+            svg.push_str(&format!(
+                r#"<text x="{}" y="{}" class="path-name" fill="{}">diversity</text>"#,
+                dendrogram_width + cluster_bar_width + bar_gap + annotation_bar_width + 5.0,
+                track_y + track_height / 2.0 + font_size / 3.0,
+                fg
+            ));
+            svg.push('\n');
+        }
+        max_y = max_y.max(track_y + track_height);
+    }
+    let axis_panel_height = axis_panel_height + diversity_track_panel(args) as f64;
+
+    // Core bar below the axis and diversity strip (SVG)
+    if let Some(ref core) = core {
+        let track_y =
+            legend_height + path_space_with_gap + axis_panel_height + args.panel_gap as f64;
+        let x_base = dendrogram_width + cluster_bar_width + text_width;
+        let mut x = 0;
+        while x < core.len() {
+            let mut end = x + 1;
+            while end < core.len() && core[end] == core[x] {
+                end += 1;
+            }
+            if core[x] {
+                svg.push_str(&format!(
+                    r#"<rect class="core" x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    x_base + x as f64,
+                    track_y,
+                    end - x,
+                    CORE_TRACK_HEIGHT,
+                    fg
+                ));
+                svg.push('\n');
+            }
+            x = end;
+        }
+        max_y = max_y.max(track_y + CORE_TRACK_HEIGHT as f64);
+    }
+    let axis_panel_height = axis_panel_height + core_track_panel(args) as f64;
+
+    // Node frequency spectrum below the other strips (SVG)
+    if args.frequency_spectrum {
+        let track_y =
+            legend_height + path_space_with_gap + axis_panel_height + args.panel_gap as f64;
+        let track_height = FREQUENCY_SPECTRUM_HEIGHT as f64;
+        let x_base = dendrogram_width + cluster_bar_width + text_width;
+        let spectrum = frequency_spectrum(
+            &node_frequencies(&display_paths, graph),
+            display_paths.len(),
+        );
+        for (x_start, x_end, bar) in spectrum_bars(&spectrum, viz_width, FREQUENCY_SPECTRUM_HEIGHT)
+        {
+            if bar > 0 {
+                svg.push_str(&format!(
+                    r#"<rect class="spectrum" x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    x_base + x_start as f64,
+                    track_y + track_height - bar as f64,
+                    x_end - x_start,
+                    bar,
+                    fg
+                ));
+                svg.push('\n');
+            }
+        }
+        if !args.hide_path_names && text_width > 0.0 {
+            svg.push_str(&format!(
+                r#"<text x="{}" y="{}" class="path-name" fill="{}">spectrum</text>"#,
+                dendrogram_width + cluster_bar_width + bar_gap + annotation_bar_width + 5.0,
+                track_y + track_height / 2.0 + font_size / 3.0,
+                fg
+            ));
+            svg.push('\n');
+        }
+        max_y = max_y.max(track_y + track_height);
+    }
+    let axis_panel_height = axis_panel_height + frequency_spectrum_panel(args) as f64;
+
+    // Component bands below the other strips, and a separator through the rows at each
+    // component boundary (SVG)
+    if args.separate_components {
+        let track_y =
+            legend_height + path_space_with_gap + axis_panel_height + args.panel_gap as f64;
+        let track_height = COMPONENT_TRACK_HEIGHT as f64;
+        let x_base = dendrogram_width + cluster_bar_width + text_width;
+        let columns = component_columns(&components, bin_width, viz_width);
+        for (i, (span, &(x_start, x_end))) in components.iter().zip(&columns).enumerate() {
+            let band = theme.muted(if i % 2 == 0 { 215 } else { 175 });
+            svg.push_str(&format!(
+                r#"<rect class="component" x="{}" y="{}" width="{}" height="{}" fill="{}"><title>{}</title></rect>"#,
+                x_base + x_start as f64,
+                track_y,
+                x_end - x_start,
+                track_height,
+                svg_rgb(band),
+                escape_xml(&span.label)
+            ));
+            svg.push('\n');
+            if i > 0 {
+                svg.push_str(&format!(
+                    r#"<line class="component-separator" x1="{x}" y1="{}" x2="{x}" y2="{}" stroke="{}" stroke-width="1"/>"#,
+                    legend_height,
+                    legend_height + path_space_with_gap,
+                    fg,
+                    x = x_base + x_start as f64 + 0.5
+                ));
+                svg.push('\n');
+            }
+            let max_chars = ((x_end - x_start).saturating_sub(2) / 8) as usize;
+            let label = truncate_name(&span.label, max_chars, args.truncate_names);
+            if !label.is_empty() {
+                svg.push_str(&format!(
+                    r#"<text x="{}" y="{}" font-family="'DejaVu Sans Mono', 'Courier New', monospace" font-size="8" fill="{}">{}</text>"#,
+                    x_base + x_start as f64 + 1.0,
+                    track_y + track_height - 2.0,
+                    fg,
+                    escape_xml(&label)
+                ));
+                svg.push('\n');
+            }
+        }
+        if !args.hide_path_names && text_width > 0.0 {
+            svg.push_str(&format!(
+                r#"<text x="{}" y="{}" class="path-name" fill="{}">components</text>"#,
+                dendrogram_width + cluster_bar_width + bar_gap + annotation_bar_width + 5.0,
+                track_y + track_height / 2.0 + font_size / 3.0,
+                fg
+            ));
+            svg.push('\n');
+        }
+        max_y = max_y.max(track_y + track_height);
+    }
+    let axis_panel_height = axis_panel_height + component_track_panel(args) as f64;
+    wrap_svg_layer(&mut svg, layer_start, "tracks");
+    let edge_base_y =
+        legend_height + path_space_with_gap + axis_panel_height + args.panel_gap as f64;
+
+    let edges_start = svg.len();
+    for edge in selected_edges(args.edges, graph, &display_paths) {
+        let from_id = edge.from_id as usize;
+        let to_id = edge.to_id as usize;
+
+        if from_id < graph.segments.len() && to_id < graph.segments.len() {
+            let edge_stroke = edge_colors
+                .as_ref()
+                .and_then(|colors| colors.get(&edge.key()))
+                .map_or_else(|| fg.clone(), |&color| svg_rgb(color));
+            let from_offset = graph.segment_offsets[from_id];
+            let from_len = graph.segments[from_id].sequence_len;
+            let to_offset = graph.segment_offsets[to_id];
+
+            let a_pos = if edge.from_rev {
+                from_offset as f64 / bin_width
+            } else {
+                (from_offset + from_len) as f64 / bin_width
+            };
+
+            let b_pos = if edge.to_rev {
+                (to_offset + graph.segments[to_id].sequence_len) as f64 / bin_width
+            } else {
+                to_offset as f64 / bin_width
+            };
+
+            let (a, b) = if a_pos < b_pos {
+                (a_pos, b_pos)
+            } else {
+                (b_pos, a_pos)
+            };
+            let dist = (b - a) * bin_width;
+            let h = (dist * scale_y_edges).min(edge_height as f64 - 1.0);
+
+            let ax = dendrogram_width
+                + cluster_bar_width
+                + bar_gap
+                + annotation_bar_width
+                + text_width
+                + a.round();
+            let bx = dendrogram_width
+                + cluster_bar_width
+                + bar_gap
+                + annotation_bar_width
+                + text_width
+                + b.round();
+
+            // Skip degenerate edges (zero height and minimal width - not visible)
+            if h < 1.0 && (bx - ax).abs() < 2.0 {
+                continue;
+            }
+
+            // Draw U-shaped edge as SVG path
+            svg.push_str(&format!(
+                r#"<path d="M{:.1},{:.1} L{:.1},{:.1} L{:.1},{:.1} L{:.1},{:.1}" fill="none" stroke="{}" stroke-width="1"/>"#,
+                ax, edge_base_y,
+                ax, edge_base_y + h,
+                bx, edge_base_y + h,
+                bx, edge_base_y,
+                edge_stroke
+            ));
+            svg.push('\n');
+
+            max_y = max_y.max(edge_base_y + h + 1.0);
+        }
+    }
+
+    if let Some(ref edges_out) = args.edges_out {
+        // Same content coordinates as the main image; the viewBox starts at the panel, and
+        // after the left panel with --names-out
+        let panel_x = match args.names_out {
+            Some(_) => total_width - viz_width as f64,
+            None => 0.0,
+        };
+        let panel_height = (max_y - edge_base_y).max(0.0) + bottom_padding as f64;
+        let panel = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">
+<rect x="{}" y="{}" width="100%" height="100%" fill="{}"/>
+{}</svg>
+"#,
+            total_width - panel_x + 2.0 * margin,
+            panel_height + 2.0 * margin,
+            panel_x - margin,
+            edge_base_y - margin,
+            total_width - panel_x + 2.0 * margin,
+            panel_height + 2.0 * margin,
+            panel_x - margin,
+            edge_base_y - margin,
+            svg_rgb(theme.background),
+            &svg[edges_start..]
+        );
+        interrupt::write_atomically(edges_out, |temp| {
+            std::fs::write(temp, panel.as_bytes())
+                .map_err(|e| GfalookError::from_io("failed to write edge panel", edges_out, e))
+        })?;
+        info!("Edge panel saved to {:?}", edges_out);
+    }
+    wrap_svg_layer(&mut svg, edges_start, "edges");
+
+    // Close SVG
+    if args.svg_interactive {
+        svg.push_str(&svg_interactive::close_viewport());
+    }
+    svg.push_str("</svg>\n");
+
+    // The header, cropped to the actual content
+    let final_height = max_y + bottom_padding as f64 + 2.0 * margin;
+    svg.insert_str(0, &svg_header(final_height));
+
+    // --names-out moves the left panel to its own file
+    if let Some(ref names_out) = args.names_out {
+        let left_width = total_width - viz_width as f64;
+        if left_width > 0.0 {
+            let crop = |x, width| {
+                crop_svg_columns(
+                    &svg,
+                    (total_width, final_height),
+                    margin,
+                    x..x + width,
+                    &svg_rgb(theme.background),
+                )
+            };
+            let names = crop(0.0, left_width);
+            interrupt::write_atomically(names_out, |temp| {
+                std::fs::write(temp, names.as_bytes())
+                    .map_err(|e| GfalookError::from_io("failed to write left panel", names_out, e))
+            })?;
+            info!("Left panel saved to {:?}", names_out);
+            svg = crop(left_width, viz_width as f64);
+        } else {
+            warning!("--names-out: there is no left panel to write");
+        }
+    }
+
+    Ok(svg)
+}
+
+/// The finished `svg` of content size `size` showing only the content columns `columns`,
+/// within the usual --margin border: the viewBox is narrowed and the margins are painted
+/// over so that nothing from beyond the columns shows through
+fn crop_svg_columns(
+    svg: &str,
+    size: (f64, f64),
+    margin: f64,
+    columns: std::ops::Range<f64>,
+    background: &str,
+) -> String {
+    let (width, height) = size;
+    let origin = -(margin as i64);
+    let view = |x: f64, width: f64| {
+        format!(
+            r#"width="{}" height="{}" viewBox="{} {} {} {}""#,
+            width + 2.0 * margin,
+            height,
+            x - margin,
+            origin,
+            width + 2.0 * margin,
+            height
+        )
+    };
+    let backdrop = |x: f64| format!(r#"<rect x="{}" y="{}" width="100%""#, x, origin);
+    let mut cropped = svg
+        .replacen(
+            &view(0.0, width),
+            &view(columns.start, columns.end - columns.start),
+            1,
+        )
+        .replacen(&backdrop(origin as f64), &backdrop(columns.start - margin), 1);
+    if margin > 0.0 {
+        let covers: String = [columns.start - margin, columns.end]
+            .iter()
+            .map(|x| {
+                format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="100%" fill="{}"/>"#,
+                    x, origin, margin, background
+                )
+            })
+            .collect();
+        if let Some(end) = cropped.rfind("</svg>") {
+            cropped.insert_str(end, &format!("{}\n", covers));
+        }
+    }
+    cropped
+}
+
+/// Whether an output path asks for SVG rather than a raster image
+fn is_svg_path(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("svg"))
+        .unwrap_or(false)
+}
+
+/// Render the graph with the current side files and write it to `args.out`
+fn render_to_file(args: &Args, graph: &Graph) -> Result<(), GfalookError> {
+    if args.watch {
+        bin_cache::next_render();
+    }
+    if args.tile && show_all_nodes_width(args, graph) > MAX_IMAGE_WIDTH as u64 {
+        return render_tiles(args, graph);
+    }
+    if args.per_component {
+        return render_components(args, graph);
+    }
+
+    if args.no_image {
+        info!("Writing data products without an image...");
+        let start = Instant::now();
+        if is_svg_path(&args.out) {
+            render_svg(args, graph)?;
+        } else {
+            render(args, graph)?;
+        }
+        logging::phase("products", start, &[]);
+        return Ok(());
+    }
+
+    // Detect output format by file extension
+    let is_svg = is_svg_path(&args.out);
+
+    if is_svg {
+        info!("Rendering SVG...");
+    } else {
+        info!("Rendering image...");
+    }
+
+    if is_svg {
+        // SVG output
+        let render_start = Instant::now();
+        let svg_content = render_svg(args, graph)?;
+        logging::phase(
+            "render",
+            render_start,
+            &[("bytes", svg_content.len() as u64)],
+        );
+
+        info!("Saving to {:?}...", args.out);
+
+        interrupt::write_atomically(&args.out, |temp| {
+            std::fs::write(temp, svg_content.as_bytes())
+                .map_err(|e| GfalookError::from_io("failed to write SVG", &args.out, e))
+        })?;
+    } else {
+        // PNG output
+        let render_start = Instant::now();
+        let buffer = render(args, graph)?;
+
+        let width = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+        let height = u32::from_le_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]);
+        logging::phase(
+            "render",
+            render_start,
+            &[("width", width as u64), ("height", height as u64)],
+        );
+
+        info!("Saving to {:?}...", args.out);
+
+        let save_start = Instant::now();
+        let img = buffer_to_image(&buffer);
+
+        let header = graph.header.summary();
+        let text: Vec<(&str, &str)> = header.iter().map(|h| ("GFA header", h.as_str())).collect();
+        save_image(&img, &args.out, args.quality, &text)?;
+        logging::phase("write", save_start, &[]);
+    }
+    Ok(())
+}
+
+/// PNG chunk declaring the pixels sRGB with perceptual rendering intent
+fn srgb_chunk() -> Vec<u8> {
+    let mut chunk = 1u32.to_be_bytes().to_vec();
+    chunk.extend_from_slice(b"sRGB\0");
+    let crc = crc32fast::hash(&chunk[4..]);
+    chunk.extend_from_slice(&crc.to_be_bytes());
+    chunk
+}
+
+/// Largest side a WebP image can have
+const WEBP_MAX_SIDE: u32 = 16383;
+
+/// PNG text chunk holding `keyword` and `text`, with characters outside Latin-1 replaced
+fn text_chunk(keyword: &str, text: &str) -> Vec<u8> {
+    let mut data = b"tEXt".to_vec();
+    data.extend_from_slice(keyword.as_bytes());
+    data.push(0);
+    data.extend(text.chars().map(|c| u8::try_from(c).unwrap_or(b'?')));
+    let mut chunk = ((data.len() - 4) as u32).to_be_bytes().to_vec();
+    let crc = crc32fast::hash(&data);
+    chunk.extend_from_slice(&data);
+    chunk.extend_from_slice(&crc.to_be_bytes());
+    chunk
+}
+
+/// Encode `img` in the format named by the extension of `out` and write it atomically;
+/// `quality` sets the JPEG and AVIF encoders, and PNGs get an sRGB chunk after the header
+/// so viewers and printers skip guessing, then a text chunk for each of `text`
+fn save_image(
+    img: &image::RgbImage,
+    out: &Path,
+    quality: Option<u8>,
+    text: &[(&str, &str)],
+) -> Result<(), GfalookError> {
+    let save_err = |e| GfalookError::Io(format!("failed to save image {:?}: {}", out, e));
+    let format = image::ImageFormat::from_path(out).map_err(save_err)?;
+    if format == image::ImageFormat::WebP && img.width().max(img.height()) > WEBP_MAX_SIDE {
+        return Err(GfalookError::Usage(format!(
+            "a {}x{} image is too large for WebP (at most {} pixels per side); \
+             write PNG or TIFF instead, or lower -x",
+            img.width(),
+            img.height(),
+            WEBP_MAX_SIDE
+        )));
+    }
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    match (format, quality) {
+        (image::ImageFormat::Jpeg, Some(q)) => img.write_with_encoder(
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, q),
+        ),
+        (image::ImageFormat::Avif, Some(q)) => img.write_with_encoder(
+            image::codecs::avif::AvifEncoder::new_with_speed_quality(&mut bytes, 4, q),
+        ),
+        _ => img.write_to(&mut bytes, format),
+    }
+    .map_err(save_err)?;
+    let mut bytes = bytes.into_inner();
+    if format == image::ImageFormat::Png {
+        // Signature (8 bytes) and IHDR (25 bytes) come first
+        let mut chunks = srgb_chunk();
+        for (keyword, value) in text {
+            chunks.extend(text_chunk(keyword, value));
+        }
+        bytes.splice(33..33, chunks);
+    }
+    interrupt::write_atomically(out, |temp| {
+        std::fs::write(temp, &bytes).map_err(|e| GfalookError::from_io("failed to write", out, e))
+    })
+}
+
+/// Convert the renderer's header-prefixed RGBA buffer into an RGB image
+fn buffer_to_image(buffer: &[u8]) -> image::RgbImage {
+    let width = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+    let height = u32::from_le_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]);
+    let pixels = &buffer[8..];
+
+    let mut rgb_pixels = Vec::with_capacity((width * height * 3) as usize);
+    for chunk in pixels.chunks(4) {
+        if chunk.len() >= 3 {
+            rgb_pixels.push(chunk[0]);
+            rgb_pixels.push(chunk[1]);
+            rgb_pixels.push(chunk[2]);
+        }
+    }
+    image::RgbImage::from_raw(width, height, rgb_pixels)
+        .expect("Failed to create image from buffer")
+}
+
+/// Side files whose edits trigger a re-render in --watch mode
+fn watched_files(args: &Args) -> Vec<PathBuf> {
+    [
+        &args.path_colors,
+        &args.annotation_file,
+        &args.paths_to_display,
+        &args.highlight_paths,
+        &args.highlight_node_ids,
+        &args.prefix_merges,
+        &args.cluster_within,
+        &args.cluster_bed,
+        &args.flip_paths,
+    ]
+    .into_iter()
+    .flatten()
+    .chain(args.color_by_contig.iter().flatten())
+    .cloned()
+    .collect()
+}
+
+/// Poll the watched side files and re-render with the already parsed graph on every change
+fn watch_and_rerender(args: &Args, graph: &Graph) {
+    let files = watched_files(args);
+    if files.is_empty() {
+        warning!("--watch has no color, annotation, path list or BED files to watch");
+        return;
+    }
+    let modified = |f: &PathBuf| std::fs::metadata(f).and_then(|m| m.modified()).ok();
+    let mut stamps: Vec<_> = files.iter().map(modified).collect();
+    info!(
+        "Watching {} file(s) for changes, press Ctrl-C to stop",
+        files.len()
+    );
+    loop {
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        if interrupt::requested() {
+            info!("Stopped watching");
+            return;
+        }
+        let current: Vec<_> = files.iter().map(modified).collect();
+        if current == stamps {
+            continue;
+        }
+        for (f, (old, new)) in files.iter().zip(stamps.iter().zip(&current)) {
+            if old != new {
+                info!("{:?} changed, re-rendering", f);
+            }
+        }
+        stamps = current;
+        // Keep watching after a failed render so the user can fix the file and save again
+        if let Err(e) = render_to_file(args, graph) {
+            logging::error(&e.to_string(), e.exit_code());
+        }
+    }
+}
+
+/// Validate options, load the graph and write the visualization
+fn run(args: &Args) -> Result<(), GfalookError> {
+    init_thread_pool(args.threads)?;
+    if let Some(ref color) = args.background {
+        if parse_color(color).is_none() {
+            return Err(GfalookError::Usage(format!(
+                "invalid --background color '{}', expected #RRGGBB, R,G,B or a color name",
+                color
+            )));
+        }
+    }
+    if let Some(ref dir) = args.out_auto {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| GfalookError::from_io("failed to create output directory", dir, e))?;
+        info!("Writing to {:?}", args.out);
+    }
+    if args.svg_interactive && !is_svg_path(&args.out) {
+        return Err(GfalookError::Usage(
+            "--svg-interactive needs an SVG output file".to_string(),
+        ));
+    }
+    if args.svg_data_attrs && !is_svg_path(&args.out) {
+        return Err(GfalookError::Usage(
+            "--svg-data-attrs needs an SVG output file".to_string(),
+        ));
+    }
+    if let Some(ref template) = args.link_template {
+        if !is_svg_path(&args.out) {
+            return Err(GfalookError::Usage(
+                "--link-template needs an SVG output file".to_string(),
+            ));
+        }
+        if !template.contains("{path}") {
+            warning!(
+                "--link-template has no {{path}} placeholder; every row links to the same URL"
+            );
+        }
+    }
+    if args.dbscan_min_pts == 0 {
+        return Err(GfalookError::Usage(
+            "--dbscan-min-pts must be at least 1".to_string(),
+        ));
+    }
+    if let Some(fraction) = args.min_cluster_fraction {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(GfalookError::Usage(format!(
+                "--min-cluster-fraction must be in (0, 1], got {}",
+                fraction
+            )));
+        }
+    }
+    if args.quality.is_some() {
+        let lossy = matches!(
+            image::ImageFormat::from_path(&args.out),
+            Ok(image::ImageFormat::Jpeg | image::ImageFormat::Avif)
+        );
+        if !lossy {
+            warning!("--quality only applies to JPEG and AVIF output; ignoring it");
+        }
+    }
+    if !(args.core_min_fraction > 0.0 && args.core_min_fraction <= 1.0) {
+        return Err(GfalookError::Usage(format!(
+            "--core-min-fraction must be in (0, 1], got {}",
+            args.core_min_fraction
+        )));
+    }
+    if args
+        .compress_invariant
+        .is_some_and(|factor| !(factor.is_finite() && factor > 1.0))
+    {
+        return Err(GfalookError::Usage(
+            "--compress-invariant must be a factor above 1".to_string(),
+        ));
+    }
+    if args.downsample == Some(0) {
+        return Err(GfalookError::Usage(
+            "--downsample must be at least 1".to_string(),
+        ));
+    }
+    if args.flag_mixed_bins && !args.color_by_mean_inversion_rate && !args.show_strand {
+        return Err(GfalookError::Usage(
+            "--flag-mixed-bins needs --color-by-mean-inversion-rate (-z) or --show-strand (-S)"
+                .to_string(),
+        ));
+    }
+    match args.fade_by {
+        Some(FadeBy::ClusterSize) if !args.cluster_paths => {
+            return Err(GfalookError::Usage(
+                "--fade-by cluster-size needs --cluster-paths (-k)".to_string(),
+            ));
+        }
+        Some(FadeBy::AnnotationCount)
+            if args.annotation_file.is_none() && args.cluster_within.is_none() =>
+        {
+            return Err(GfalookError::Usage(
+                "--fade-by annotation-count needs --annotation-file (-E) or --cluster-within"
+                    .to_string(),
+            ));
+        }
+        _ => {}
+    }
+    if let Some(ref edges_out) = args.edges_out {
+        if is_svg_path(edges_out) != is_svg_path(&args.out) {
+            return Err(GfalookError::Usage(
+                "--edges-out must be an SVG file when the output is SVG, and a raster image otherwise"
+                    .to_string(),
+            ));
+        }
+    }
+    if let Some(ref names_out) = args.names_out {
+        if is_svg_path(names_out) != is_svg_path(&args.out) {
+            return Err(GfalookError::Usage(
+                "--names-out must be an SVG file when the output is SVG, and a raster image otherwise"
+                    .to_string(),
+            ));
+        }
+    }
+
+    let parse_start = Instant::now();
+    let graph = parse_gfa(&args.idx)
+        .map_err(|e| GfalookError::from_io("failed to load GFA file", &args.idx, e))?;
+
+    logging::phase(
+        "parse",
+        parse_start,
+        &[
+            ("segments", graph.segments.len() as u64),
+            ("paths", graph.paths.len() as u64),
+            ("edges", graph.edges.len() as u64),
+            ("total_length", graph.total_length),
+        ],
+    );
+
+    interrupt::check("parsing")?;
+    let graph = match args.path_range {
+        Some(ref spec) => path_range_graph(&graph, spec)?,
+        None => graph,
+    };
+    let sized;
+    let args = if args.auto_width {
+        sized = Args {
+            width: auto_width(args, &graph)?,
+            ..args.clone()
+        };
+        info!(
+            "Auto width: {} px at {} bp per pixel",
+            sized.width, sized.bp_per_pixel
+        );
+        &sized
+    } else {
+        args
+    };
+    let sampled;
+    let (args, graph) = match args.downsample.filter(|&n| n > 1) {
+        Some(n) => {
+            let bin_width = args.bin_width.unwrap_or_else(|| {
+                graph.total_length as f64
+                    / (args.width as u64).min(graph.total_length).max(1) as f64
+            });
+            sampled = Args {
+                width: args.width.div_ceil(n),
+                ..args.clone()
+            };
+            let sampled_graph = downsample(&graph, bin_width, n);
+            info!(
+                "Downsampled to 1 of every {} bins: {} of {} bp, {} px wide",
+                n, sampled_graph.total_length, graph.total_length, sampled.width
+            );
+            (&sampled, sampled_graph)
+        }
+        None => (args, graph),
+    };
+    let mut graph = match args.guarantee_min_feature {
+        Some(min_len) => guarantee_min_feature(args, &graph, min_len).unwrap_or(graph),
+        None => graph,
+    };
+    if args.color_by_rank && graph.segments.iter().all(|segment| segment.rank.is_none()) {
+        warning!("--color-by-rank: no segment has an SR tag, so every bin is drawn unranked");
+    }
+    let referenced;
+    let args = match reference_paths(&graph).first() {
+        Some(reference) => {
+            if graph.header.reference_samples().is_empty() {
+                info!("Reference path (rGFA rank 0): {}", reference.name);
+            } else {
+                info!(
+                    "Reference samples (RS): {}",
+                    graph.header.reference_samples().join(", ")
+                );
+            }
+            if args.orient_paths_by.is_none() {
+                info!("Orienting paths by the reference path '{}'", reference.name);
+                referenced = Args {
+                    orient_paths_by: Some(reference.name.clone()),
+                    ..args.clone()
+                };
+                &referenced
+            } else {
+                args
+            }
+        }
+        None => {
+            if !graph.header.reference_samples().is_empty() {
+                warning!(
+                    "no path belongs to the reference samples named by the header (RS: {})",
+                    graph.header.reference_samples().join(", ")
+                );
+            }
+            args
+        }
+    };
+    check_requested_paths(args, &graph)?;
+    check_bin_width(args, &graph)?;
+    report_orphans(args, &graph);
+    report_components(args, &graph);
+
+    let pathless;
+    let args = if graph.paths.is_empty() {
+        if !args.segments_only {
+            return Err(GfalookError::NoPaths(format!(
+                "no P or W lines in {:?}, so there is nothing to draw; add paths to the graph \
+                 (e.g. with `odgi paths` or `vg paths`) or pass --segments-only to draw the \
+                 segments alone",
+                args.idx
+            )));
+        }
+        graph.paths.push(segments_path(&graph));
+        info!(
+            "No paths: drawing {} segments as one row, colored by edge branching",
+            graph.segments.len()
+        );
+        pathless = Args {
+            color_by_mean_depth: true,
+            ..args.clone()
+        };
+        &pathless
+    } else {
+        args
+    };
+
+    render_to_file(args, &graph)?;
+    interrupt::check("writing the output")?;
+
+    if args.watch {
+        watch_and_rerender(args, &graph);
+    }
+    Ok(())
+}
+
+/// Size rayon's global pool, which every parallel stage runs on, to --threads
+fn init_thread_pool(threads: Option<usize>) -> Result<(), GfalookError> {
+    let Some(threads) = threads else {
+        return Ok(());
+    };
+    if threads == 0 {
+        return Err(GfalookError::Usage(
+            "--threads must be at least 1".to_string(),
+        ));
+    }
+    match rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+    {
+        Ok(()) => info!("Using {} threads", threads),
+        // The pool is built on first use; say so if something got there before us
+        Err(e) => warning!(
+            "--threads {} not applied, running on {} threads: {}",
+            threads,
+            rayon::current_num_threads(),
+            e
+        ),
+    }
+    Ok(())
+}
+
+/// Stand-in path for --segments-only: every segment once in coordinate order, stepped
+/// through as often as it has edges on its busier side, so depth coloring shows where
+/// the graph branches
+fn segments_path(graph: &Graph) -> GfaPath {
+    let mut incoming = vec![0usize; graph.segments.len()];
+    let mut outgoing = vec![0usize; graph.segments.len()];
+    for edge in &graph.edges {
+        outgoing[edge.from_id as usize] += 1;
+        incoming[edge.to_id as usize] += 1;
+    }
+    let steps = (0..graph.segments.len())
+        .flat_map(|id| {
+            let times = incoming[id].max(outgoing[id]).max(1);
+            std::iter::repeat_n(
+                PathStep {
+                    segment_id: id as u64,
+                    is_reverse: false,
+                },
+                times,
+            )
+        })
+        .collect();
+    GfaPath {
+        name: "segments".to_string(),
+        steps,
+        piece: None,
+    }
+}
+
+/// `--version-info`: one `key: value` line per capability, for bug reports and pipelines
+fn version_info() -> String {
+    let features: Vec<&str> = [("python", cfg!(feature = "python"))]
+        .into_iter()
+        .filter_map(|(name, enabled)| enabled.then_some(name))
+        .collect();
+    // Raster formats whose encoder accepts the renderer's RGB images
+    let probe = image::RgbImage::new(1, 1);
+    let mut outputs = vec!["svg"];
+    outputs.extend(
+        image::ImageFormat::all()
+            .filter(|format| format.writing_enabled())
+            .filter(|&format| {
+                probe
+                    .write_to(&mut std::io::Cursor::new(Vec::new()), format)
+                    .is_ok()
+            })
+            .filter_map(|format| format.extensions_str().first().copied()),
+    );
+    [
+        format!("gfalook {}", env!("CARGO_PKG_VERSION")),
+        format!(
+            "features: {}",
+            if features.is_empty() {
+                "none".to_string()
+            } else {
+                features.join(" ")
+            }
+        ),
+        format!("input: {}", INPUT_FORMATS.join(" ")),
+        format!("output: {}", outputs.join(" ")),
+        format!("max-width: {}", MAX_IMAGE_WIDTH),
+        format!("threads: {}", rayon::current_num_threads()),
+    ]
+    .iter()
+    .map(|line| format!("{}\n", line))
+    .collect()
+}
+
+/// Parse CLI-style options for an already loaded graph, filling in -i and, unless the
+/// caller gave one, a default -o that selects the output format
+fn embedded_args<I>(source: &Path, default_out: &str, options: I) -> Result<Args, clap::Error>
+where
+    I: IntoIterator<Item = String>,
+{
+    let options: Vec<String> = options.into_iter().collect();
+    let has_out = options
+        .iter()
+        .any(|o| o == "-o" || o == "--out" || o.starts_with("--out="));
+    let mut argv = vec![
+        "gfalook".to_string(),
+        "-i".to_string(),
+        source.to_string_lossy().into_owned(),
+    ];
+    if !has_out {
+        argv.push("-o".to_string());
+        argv.push(default_out.to_string());
+    }
+    argv.extend(options);
+    Args::try_parse_from(argv)
+}
+
+/// The graph an embedded render draws: `graph` cut down to the -r window when the options
+/// give one, else None to draw `graph` itself
+fn embedded_graph(args: &Args, graph: &Graph) -> Result<Option<Graph>, GfalookError> {
+    args.path_range
+        .as_ref()
+        .map(|spec| path_range_graph(graph, spec))
+        .transpose()
+}
+
+/// Entry point of the `gfalook` binary: the bench and palettes subcommands, else a render
+pub fn cli_main() {
+    if std::env::args_os().nth(1).is_some_and(|a| a == "bench") {
+        let bench_args = bench::BenchArgs::parse_from(std::env::args_os().skip(1));
+        logging::init(0, LogFormat::Text);
+        if let Err(e) = bench::run(&bench_args) {
+            logging::error(&e.to_string(), e.exit_code());
+            std::process::exit(e.exit_code());
+        }
+        return;
+    }
+    if std::env::args_os().nth(1).is_some_and(|a| a == "palettes") {
+        let palettes_args = palettes::PalettesArgs::parse_from(std::env::args_os().skip(1));
+        logging::init(1, LogFormat::Text);
+        if let Err(e) = palettes::run(&palettes_args) {
+            logging::error(&e.to_string(), e.exit_code());
+            std::process::exit(e.exit_code());
+        }
+        return;
+    }
+
+    let argv: Vec<OsString> = std::env::args_os().collect();
+    let (argv, compat_notes) = if argv.iter().any(|a| a == odgi_compat::FLAG) {
+        odgi_compat::translate(argv)
+    } else {
+        (argv, Vec::new())
+    };
+    let matches = Args::command().get_matches_from(argv);
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if args.version_info {
+        print!("{}", version_info());
+        return;
+    }
+    if let Some(ref dir) = args.out_auto {
+        args.out = out_auto::path(&matches, &args.idx, dir);
+    }
+
+    // Initialize logger based on verbosity
+    logging::init(args.verbose, args.log_format);
+    for note in &compat_notes {
+        warning!("--odgi-compat: {}", note);
+    }
+    interrupt::install();
+
+    info!("Starting visualization...");
+
+    if let Err(e) = run(&args) {
+        logging::error(&e.to_string(), e.exit_code());
+        std::process::exit(e.exit_code());
+    }
+
+    info!("Done.");
+}
@@ -0,0 +1,125 @@
+//! Log output: env_logger text by default, or one JSON object per line on stderr with
+//! `--log-format json` so pipeline managers can parse warnings and phase statistics.
+
+use clap::ValueEnum;
+use log::info;
+use std::io::Write;
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Format of everything gfalook writes to stderr
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable log lines and `[gfalook] warning:` messages
+    Text,
+    /// One JSON object per line with `level`, `msg` and, for phases, timings and counts
+    Json,
+}
+
+static FORMAT: OnceLock<LogFormat> = OnceLock::new();
+
+fn format() -> LogFormat {
+    FORMAT.get().copied().unwrap_or(LogFormat::Text)
+}
+
+/// Install the logger for the given verbosity (0 = error, 1 = info, 2 = debug)
+pub fn init(verbose: u8, log_format: LogFormat) {
+    let _ = FORMAT.set(log_format);
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(match verbose {
+        0 => log::LevelFilter::Error,
+        1 => log::LevelFilter::Info,
+        _ => log::LevelFilter::Debug,
+    });
+    if log_format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let level = record.level().as_str().to_ascii_lowercase();
+            let msg = record.args().to_string();
+            writeln!(buf, "{}", json_line(&level, &msg, &[]))
+        });
+    }
+    builder.init();
+}
+
+/// Report a problem that does not stop the run
+pub fn warning(msg: &str) {
+    match format() {
+        LogFormat::Text => eprintln!("[gfalook] warning: {}", msg),
+        LogFormat::Json => eprintln!("{}", json_line("warning", msg, &[])),
+    }
+}
+
+/// Report the error that ends the run, with the exit code it will produce
+pub fn error(msg: &str, exit_code: i32) {
+    match format() {
+        LogFormat::Text => eprintln!("[gfalook] error: {}", msg),
+        LogFormat::Json => {
+            let fields = [("exit_code", exit_code.to_string())];
+            eprintln!("{}", json_line("error", msg, &fields));
+        }
+    }
+}
+
+/// Report a finished phase with its wall time and any counts worth tracking
+pub fn phase(name: &str, start: Instant, counts: &[(&str, u64)]) {
+    if !log::log_enabled!(log::Level::Info) {
+        return;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    match format() {
+        LogFormat::Text => {
+            let details: Vec<String> = counts.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            if details.is_empty() {
+                info!("{} took {:.2}s", name, elapsed);
+            } else {
+                info!("{} took {:.2}s ({})", name, elapsed, details.join(", "));
+            }
+        }
+        LogFormat::Json => {
+            let mut fields = vec![
+                ("event", "\"phase\"".to_string()),
+                ("phase", json_string(name)),
+                ("duration_ms", format!("{:.1}", elapsed * 1000.0)),
+            ];
+            fields.extend(counts.iter().map(|&(k, v)| (k, v.to_string())));
+            eprintln!("{}", json_line("info", name, &fields));
+        }
+    }
+}
+
+/// Build one JSON log object; `fields` values must already be JSON-encoded
+fn json_line(level: &str, msg: &str, fields: &[(&str, String)]) -> String {
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64());
+    let mut line = format!(
+        "{{\"ts\":{:.3},\"level\":{},\"msg\":{}",
+        ts,
+        json_string(level),
+        json_string(msg)
+    );
+    for (key, value) in fields {
+        line.push_str(&format!(",{}:{}", json_string(key), value));
+    }
+    line.push('}');
+    line
+}
+
+/// Quote and escape a string for JSON
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
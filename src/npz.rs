@@ -0,0 +1,132 @@
+//! Minimal NumPy `.npz` writer: an uncompressed zip archive of `.npy` arrays, enough for
+//! `numpy.load` without pulling in a zip library.
+
+use std::io::{self, Write};
+
+/// An array to store in the archive
+pub enum NpyArray<'a> {
+    /// float64 values in row-major order with the given shape
+    F64(&'a [f64], Vec<usize>),
+    /// uint64 vector
+    U64(&'a [u64]),
+    /// Unicode string vector
+    Str(&'a [String]),
+}
+
+impl NpyArray<'_> {
+    /// Serialize to the `.npy` format (version 1.0, little-endian)
+    fn to_npy(&self) -> Vec<u8> {
+        let (descr, shape, data) = match self {
+            NpyArray::F64(values, shape) => (
+                "<f8".to_string(),
+                shape.clone(),
+                values
+                    .iter()
+                    .flat_map(|v| v.to_le_bytes())
+                    .collect::<Vec<u8>>(),
+            ),
+            NpyArray::U64(values) => (
+                "<u8".to_string(),
+                vec![values.len()],
+                values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            ),
+            NpyArray::Str(values) => {
+                let width = values
+                    .iter()
+                    .map(|s| s.chars().count())
+                    .max()
+                    .unwrap_or(0)
+                    .max(1);
+                let mut data = Vec::with_capacity(values.len() * width * 4);
+                for s in values.iter() {
+                    let mut chars = s.chars().map(|c| c as u32);
+                    for _ in 0..width {
+                        data.extend_from_slice(&chars.next().unwrap_or(0).to_le_bytes());
+                    }
+                }
+                (format!("<U{}", width), vec![values.len()], data)
+            }
+        };
+
+        let shape = match shape.as_slice() {
+            [n] => format!("({},)", n),
+            dims => format!(
+                "({})",
+                dims.iter()
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        let mut header = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+            descr, shape
+        );
+        // Pad with spaces so the data starts on a 64-byte boundary, ending in a newline
+        let unpadded = 10 + header.len() + 1;
+        header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+        header.push('\n');
+
+        let mut npy = Vec::with_capacity(10 + header.len() + data.len());
+        npy.extend_from_slice(b"\x93NUMPY\x01\x00");
+        npy.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        npy.extend_from_slice(header.as_bytes());
+        npy.extend_from_slice(&data);
+        npy
+    }
+}
+
+/// Write named arrays as an `.npz` archive (stored, no compression)
+pub fn write_npz<W: Write>(out: &mut W, arrays: &[(&str, NpyArray)]) -> io::Result<()> {
+    let too_large = || io::Error::other("array too large for a non-zip64 .npz archive");
+    let mut central = Vec::new();
+    let mut offset: u32 = 0;
+
+    for (name, array) in arrays {
+        let file_name = format!("{}.npy", name);
+        let data = array.to_npy();
+        let size = u32::try_from(data.len()).map_err(|_| too_large())?;
+        let crc = crc32fast::hash(&data);
+
+        // Fields shared by the local and central headers: version needed, flags,
+        // method (stored), mod time, mod date, CRC, sizes, name length
+        let mut common = Vec::with_capacity(26);
+        common.extend_from_slice(&20u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&0x21u16.to_le_bytes());
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&size.to_le_bytes());
+        common.extend_from_slice(&size.to_le_bytes());
+        common.extend_from_slice(&(file_name.len() as u16).to_le_bytes());
+
+        out.write_all(&0x0403_4b50u32.to_le_bytes())?;
+        out.write_all(&common)?;
+        out.write_all(&0u16.to_le_bytes())?; // extra field length
+        out.write_all(file_name.as_bytes())?;
+        out.write_all(&data)?;
+
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        central.extend_from_slice(&common);
+        central.extend_from_slice(&[0u8; 12]); // extra, comment, disk, internal/external attrs
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(file_name.as_bytes());
+
+        offset = (30 + file_name.len() as u32)
+            .checked_add(size)
+            .and_then(|len| offset.checked_add(len))
+            .ok_or_else(too_large)?;
+    }
+
+    out.write_all(&central)?;
+    out.write_all(&0x0605_4b50u32.to_le_bytes())?;
+    out.write_all(&[0u8; 4])?; // disk numbers
+    out.write_all(&(arrays.len() as u16).to_le_bytes())?;
+    out.write_all(&(arrays.len() as u16).to_le_bytes())?;
+    out.write_all(&(central.len() as u32).to_le_bytes())?;
+    out.write_all(&offset.to_le_bytes())?;
+    out.write_all(&0u16.to_le_bytes())?; // comment length
+    Ok(())
+}
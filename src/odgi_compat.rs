@@ -0,0 +1,62 @@
+//! `--odgi-compat`: accept an `odgi viz` command line unchanged. Most options already
+//! share odgi's letters and names; this rewrites the few that do not and lists the places
+//! where gfalook behaves differently, to be reported at startup.
+
+use std::ffi::{OsStr, OsString};
+
+/// The flag that turns the translation on
+pub const FLAG: &str = "--odgi-compat";
+
+fn is_option(arg: &OsStr, short: &str, long: &str) -> bool {
+    let arg = arg.to_string_lossy();
+    arg == short
+        || arg == long
+        || (arg.starts_with(short) && !arg.starts_with("--"))
+        || arg.starts_with(&format!("{}=", long))
+}
+
+/// The value of an option given as `-x V`, `-xV`, `--long V` or `--long=V`
+fn option_value(argv: &[OsString], short: &str, long: &str) -> Option<String> {
+    argv.iter().enumerate().find_map(|(i, arg)| {
+        let arg = arg.to_string_lossy();
+        if arg == short || arg == long {
+            argv.get(i + 1).map(|v| v.to_string_lossy().into_owned())
+        } else if let Some(value) = arg.strip_prefix(&format!("{}=", long)) {
+            Some(value.to_string())
+        } else if !arg.starts_with("--") {
+            arg.strip_prefix(short).map(str::to_string)
+        } else {
+            None
+        }
+    })
+}
+
+/// Rewrite odgi viz arguments for gfalook, returning them with the differences to report
+pub fn translate(argv: Vec<OsString>) -> (Vec<OsString>, Vec<String>) {
+    let mut notes = Vec::new();
+
+    // odgi is silent unless -P asks for progress; gfalook logs at info level by default
+    let progress = argv.iter().any(|a| a == "-P" || a == "--progress");
+    let mut out: Vec<OsString> = argv
+        .iter()
+        .filter(|a| *a != "-P" && *a != "--progress")
+        .cloned()
+        .collect();
+    if progress {
+        notes.push("-P/--progress logs phase timings; there is no progress bar".to_string());
+    }
+    if !out.iter().any(|a| is_option(a, "-v", "--verbose")) {
+        out.push("-v".into());
+        out.push(if progress { "1" } else { "0" }.into());
+    }
+
+    if let Some(idx) = option_value(&out, "-i", "--idx") {
+        if idx.ends_with(".og") {
+            notes.push(format!(
+                "-i reads GFA, not ODGI files; convert with `odgi view -i {} -g > graph.gfa`",
+                idx
+            ));
+        }
+    }
+    (out, notes)
+}
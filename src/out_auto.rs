@@ -0,0 +1,98 @@
+//! `--out-auto [DIR]`: name the output after the input graph and the options that shape
+//! the image (`graph.k.m.x2000.png`), so batches of variants need no hand-picked names.
+
+use super::Args;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Options that leave the image unchanged and are kept out of the name
+const IGNORED: &[&str] = &[
+    "idx",
+    "out",
+    "out_auto",
+    "watch",
+    "odgi_compat",
+    "cache",
+    "threads",
+    "verbose",
+    "log_format",
+];
+
+/// Longer summaries are replaced by a hash of the options
+const MAX_SUMMARY_LEN: usize = 40;
+
+/// Graph file name without `.gfa` and compression suffixes
+fn input_stem(input: &Path) -> String {
+    let name = input
+        .file_name()
+        .map_or("gfalook".into(), |n| n.to_string_lossy());
+    let mut stem = name.as_ref();
+    for suffix in [".gz", ".bgz", ".zst", ".gfa"] {
+        stem = stem.strip_suffix(suffix).unwrap_or(stem);
+    }
+    stem.to_string()
+}
+
+/// Keep letters, digits and dashes; anything else becomes `_`
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// One name token per option given on the command line, in `--help` order: the short
+/// letter (or long name) followed by its values, with file values reduced to their stem
+fn option_tokens(matches: &ArgMatches) -> Vec<String> {
+    let command = Args::command();
+    command
+        .get_arguments()
+        .filter(|arg| !IGNORED.contains(&arg.get_id().as_str()))
+        .filter(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine))
+        .map(|arg| {
+            let mut token = match (arg.get_short(), arg.get_long()) {
+                (Some(short), _) => short.to_string(),
+                (None, Some(long)) => long.to_string(),
+                (None, None) => arg.get_id().to_string(),
+            };
+            if arg.get_action().takes_values() {
+                let is_file = arg
+                    .get_value_names()
+                    .is_some_and(|names| names.iter().any(|n| n == "FILE"));
+                for value in matches.get_raw(arg.get_id().as_str()).into_iter().flatten() {
+                    let value = Path::new(value);
+                    let value = match value.file_stem() {
+                        Some(stem) if is_file => stem,
+                        _ => value.as_os_str(),
+                    };
+                    token.push_str(&sanitize(&value.to_string_lossy()));
+                }
+            }
+            token
+        })
+        .collect()
+}
+
+/// Output path in `dir` for the options in `matches`
+pub fn path(matches: &ArgMatches, input: &Path, dir: &Path) -> PathBuf {
+    let tokens = option_tokens(matches);
+    let mut summary = tokens.join(".");
+    if summary.len() > MAX_SUMMARY_LEN {
+        let digest = Sha256::digest(summary.as_bytes());
+        summary = digest[..4].iter().map(|b| format!("{:02x}", b)).collect();
+    }
+    let name = if summary.is_empty() {
+        format!("{}.png", input_stem(input))
+    } else {
+        format!("{}.{}.png", input_stem(input), summary)
+    };
+    dir.join(name)
+}
@@ -0,0 +1,210 @@
+//! `gfalook palettes`: draw a swatch sheet of every built-in palette, and of any
+//! --path-colors style files, so a color scheme can be picked without rendering a graph.
+
+use super::error::GfalookError;
+use super::{
+    escape_xml, get_colorbrewer_palette, interrupt, parse_color, path_color_entry, read_list,
+    ANNOTATION_COLORS, ANNOTATION_COLORS_EXTENDED, CLUSTER_COLORS, COLORBREWER_RDBU_11,
+    COLORBREWER_SPECTRAL_13, FREQUENCY_CLASS_COLORS, RANK_COLORS,
+};
+use clap::Parser;
+use log::info;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(name = "gfalook palettes", bin_name = "gfalook palettes")]
+#[command(about = "Draw swatches of the built-in color palettes.", long_about = None)]
+pub struct PalettesArgs {
+    /// Write the swatch sheet to this SVG FILE.
+    #[arg(short = 'o', long = "out", value_name = "FILE")]
+    out: PathBuf,
+
+    /// Show the ColorBrewer schemes with N classes, as `-B SCHEME:N` would use them
+    /// (default: the largest variant of each scheme).
+    #[arg(short = 'n', long = "classes", value_name = "N")]
+    classes: Option<usize>,
+
+    /// Also draw the colors of this --path-colors FILE, in file order (repeatable).
+    #[arg(short = 'p', long = "path-colors", value_name = "FILE")]
+    path_colors: Vec<PathBuf>,
+}
+
+/// The 35 ColorBrewer schemes accepted by `-B`, in ColorBrewer's order
+const COLORBREWER_SCHEMES: [&str; 35] = [
+    "Spectral", "RdYlGn", "RdBu", "PiYG", "PRGn", "RdYlBu", "BrBG", "RdGy", "PuOr", "Set2",
+    "Accent", "Set1", "Set3", "Dark2", "Paired", "Pastel2", "Pastel1", "OrRd", "PuBu", "BuPu",
+    "Oranges", "BuGn", "YlOrBr", "YlGn", "Reds", "RdPu", "Greens", "YlGnBu", "Purples", "GnBu",
+    "Greys", "YlOrRd", "PuRd", "Blues", "PuBuGn",
+];
+
+const LABEL_WIDTH: f64 = 220.0;
+const SWATCH: f64 = 22.0;
+const ROW_HEIGHT: f64 = 28.0;
+const HEADING_HEIGHT: f64 = 34.0;
+
+/// A named row of swatches, each with an optional caption shown on hover
+struct Row {
+    label: String,
+    colors: Vec<((u8, u8, u8), Option<String>)>,
+}
+
+impl Row {
+    fn new(label: impl Into<String>, colors: &[(u8, u8, u8)]) -> Self {
+        Row {
+            label: label.into(),
+            colors: colors.iter().map(|&c| (c, None)).collect(),
+        }
+    }
+}
+
+/// Colors of a --path-colors file, keeping the file's order and skipping unreadable lines
+fn path_colors_row(path: &Path) -> Result<Row, GfalookError> {
+    let lines = read_list(path)
+        .map_err(|e| GfalookError::from_io("failed to read path colors", path, e))?;
+    let colors = lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(path_color_entry)
+        .filter_map(|(name, color)| Some((parse_color(color)?, Some(name.to_string()))))
+        .collect();
+    let label = path
+        .file_name()
+        .map_or(path.to_string_lossy(), |n| n.to_string_lossy());
+    Ok(Row {
+        label: label.into_owned(),
+        colors,
+    })
+}
+
+/// Palette rows grouped under the option that uses them
+fn sections(palettes: &PalettesArgs) -> Result<Vec<(String, Vec<Row>)>, GfalookError> {
+    let classes = palettes.classes.unwrap_or(usize::MAX);
+    let colorbrewer = COLORBREWER_SCHEMES
+        .iter()
+        .filter_map(|&scheme| {
+            let colors = get_colorbrewer_palette(scheme, classes)?;
+            Some(Row::new(format!("{}:{}", scheme, colors.len()), colors))
+        })
+        .collect();
+    let mut sections = vec![
+        (
+            "Depth (-m)".to_string(),
+            vec![
+                Row::new("Spectral, grey below 1.5x", &COLORBREWER_SPECTRAL_13),
+                Row::new("RdBu (--compressed-mode)", &COLORBREWER_RDBU_11),
+            ],
+        ),
+        ("ColorBrewer (-B SCHEME:N)".to_string(), colorbrewer),
+        (
+            "Clusters (-k)".to_string(),
+            vec![Row::new("Set1", &CLUSTER_COLORS)],
+        ),
+        (
+            "Annotations (--annotation)".to_string(),
+            vec![
+                Row::new("Set2, up to 8 categories", &ANNOTATION_COLORS),
+                Row::new("Paired, more categories", &ANNOTATION_COLORS_EXTENDED),
+            ],
+        ),
+        (
+            "Frequency classes (--color-by-frequency-class)".to_string(),
+            vec![Row::new("core, shell, unique", &FREQUENCY_CLASS_COLORS)],
+        ),
+        (
+            "Segment ranks (--color-by-rank)".to_string(),
+            vec![Row::new("rank 1, 2, 3, 4+", &RANK_COLORS)],
+        ),
+    ];
+    if !palettes.path_colors.is_empty() {
+        let rows = palettes
+            .path_colors
+            .iter()
+            .map(|path| path_colors_row(path))
+            .collect::<Result<_, _>>()?;
+        sections.push(("Path colors (--path-colors)".to_string(), rows));
+    }
+    Ok(sections)
+}
+
+fn svg_sheet(sections: &[(String, Vec<Row>)]) -> String {
+    let widest = sections
+        .iter()
+        .flat_map(|(_, rows)| rows.iter().map(|r| r.colors.len()))
+        .max()
+        .unwrap_or(0);
+    let width = LABEL_WIDTH + widest as f64 * SWATCH + 20.0;
+    let mut body = String::new();
+    let mut y = 10.0;
+    for (heading, rows) in sections {
+        y += HEADING_HEIGHT;
+        body.push_str(&format!(
+            "<text x=\"10\" y=\"{}\" font-weight=\"bold\">{}</text>\n",
+            y - 12.0,
+            escape_xml(heading)
+        ));
+        for row in rows {
+            body.push_str(&format!(
+                "<text x=\"20\" y=\"{}\">{}</text>\n",
+                y + SWATCH / 2.0 + 4.0,
+                escape_xml(&row.label)
+            ));
+            for (i, ((r, g, b), caption)) in row.colors.iter().enumerate() {
+                let hex = format!("#{:02x}{:02x}{:02x}", r, g, b);
+                let title = match caption {
+                    Some(name) => format!("{} {}", escape_xml(name), hex),
+                    None => hex.clone(),
+                };
+                body.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"#ffffff\"><title>{}</title></rect>\n",
+                    LABEL_WIDTH + i as f64 * SWATCH,
+                    y,
+                    SWATCH,
+                    SWATCH,
+                    hex,
+                    title
+                ));
+            }
+            y += ROW_HEIGHT;
+        }
+    }
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" font-family="'DejaVu Sans', sans-serif" font-size="12">
+<rect width="100%" height="100%" fill="white"/>
+{}</svg>
+"#,
+        width,
+        y + 10.0,
+        width,
+        y + 10.0,
+        body
+    )
+}
+
+/// Draw the swatch sheet
+pub fn run(palettes: &PalettesArgs) -> Result<(), GfalookError> {
+    let is_svg = palettes
+        .out
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    if !is_svg {
+        return Err(GfalookError::Usage(format!(
+            "gfalook palettes writes SVG; use an .svg output file instead of {:?}",
+            palettes.out
+        )));
+    }
+    if palettes.classes.is_some_and(|n| n < 3) {
+        return Err(GfalookError::Usage(
+            "--classes must be at least 3, the smallest ColorBrewer variant".to_string(),
+        ));
+    }
+    let sheet = svg_sheet(&sections(palettes)?);
+    let out = &palettes.out;
+    interrupt::write_atomically(out, |temp| {
+        std::fs::write(temp, sheet.as_bytes())
+            .map_err(|e| GfalookError::from_io("failed to write palettes", out, e))
+    })?;
+    info!("Palettes saved to {:?}", out);
+    Ok(())
+}
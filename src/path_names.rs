@@ -0,0 +1,107 @@
+//! Matching of user-supplied path names to graph paths, tolerating common PanSN spelling
+//! variants: a missing or extra `:start-end` range, a `#0` haplotype field, and a `chr`
+//! contig prefix.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// How a requested name maps onto the graph
+pub enum Resolution<'a> {
+    /// The name is a graph path as given
+    Exact,
+    /// A single graph path matches after normalizing the name
+    Resolved(&'a str),
+    /// Several graph paths match after normalizing the name
+    Ambiguous(Vec<&'a str>),
+    /// No graph path matches
+    Missing,
+}
+
+/// Index of graph path names by their normalized form
+pub struct PathNameResolver<'a> {
+    exact: FxHashSet<&'a str>,
+    by_key: FxHashMap<String, Vec<&'a str>>,
+}
+
+impl<'a> PathNameResolver<'a> {
+    pub fn new(names: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut exact = FxHashSet::default();
+        let mut by_key: FxHashMap<String, Vec<&'a str>> = FxHashMap::default();
+        for name in names {
+            exact.insert(name);
+            by_key.entry(normalize(name)).or_default().push(name);
+        }
+        PathNameResolver { exact, by_key }
+    }
+
+    pub fn resolve(&self, name: &str) -> Resolution<'a> {
+        if self.exact.contains(name) {
+            return Resolution::Exact;
+        }
+        // A range on both sides has to agree; a range on only one side is ignored
+        let range = range_suffix(name);
+        let candidates: Vec<&'a str> = self
+            .by_key
+            .get(&normalize(name))
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|path| range.is_none() || range_suffix(path).is_none_or(|r| Some(r) == range))
+            .collect();
+        match candidates.as_slice() {
+            [] => Resolution::Missing,
+            [single] => Resolution::Resolved(single),
+            _ => Resolution::Ambiguous(candidates),
+        }
+    }
+
+    /// The graph path a name refers to, or the name unchanged when it does not resolve
+    /// to exactly one path
+    pub fn canonical(&self, name: &str) -> String {
+        match self.resolve(name) {
+            Resolution::Resolved(path) => path.to_string(),
+            _ => name.to_string(),
+        }
+    }
+}
+
+/// Normalized comparison key: drop a trailing `:start-end`, a `0` haplotype field and a
+/// `chr` prefix on the contig
+fn normalize(name: &str) -> String {
+    let name = match range_suffix(name) {
+        Some(range) => &name[..name.len() - range.len() - 1],
+        None => name,
+    };
+    let fields: Vec<&str> = name.split('#').collect();
+    let (prefix, contig) = match fields.as_slice() {
+        [sample, "0", contig] => (vec![*sample], *contig),
+        [head @ .., contig] => (head.to_vec(), *contig),
+        [] => (Vec::new(), name),
+    };
+    let contig = contig
+        .get(..3)
+        .filter(|p| p.eq_ignore_ascii_case("chr"))
+        .map_or(contig, |_| &contig[3..]);
+    let mut key = prefix.join("#");
+    if !key.is_empty() {
+        key.push('#');
+    }
+    key.push_str(contig);
+    key
+}
+
+/// The `start-end` after the last `:`, if the name has one
+fn range_suffix(name: &str) -> Option<&str> {
+    name.rsplit_once(':')
+        .map(|(_, range)| range)
+        .filter(|range| is_range(range))
+}
+
+/// Whether `s` looks like `start-end` with both ends numeric
+fn is_range(s: &str) -> bool {
+    s.split_once('-').is_some_and(|(start, end)| {
+        !start.is_empty()
+            && !end.is_empty()
+            && start.bytes().all(|b| b.is_ascii_digit())
+            && end.bytes().all(|b| b.is_ascii_digit())
+    })
+}
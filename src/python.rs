@@ -0,0 +1,174 @@
+//! Python bindings: `import gfalook` to parse a graph once, then render, bin and cluster it
+//! from a notebook without subprocess calls or temporary files.
+//!
+//! Errors raise Python exceptions instead of exiting the interpreter.
+
+use crate::error::GfalookError;
+use crate::{
+    cluster_paths_by_similarity, embedded_args, embedded_graph, parse_gfa, path_bins, render,
+    render_svg, Args, Graph, SimilarityLevel,
+};
+use clap::ValueEnum;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::path::PathBuf;
+
+/// I/O failures raise `OSError`, everything else `ValueError`
+fn to_py_err(e: GfalookError) -> PyErr {
+    match e {
+        GfalookError::Io(msg) => PyIOError::new_err(msg),
+        e => PyValueError::new_err(e.to_string()),
+    }
+}
+
+/// Per-path `(bin, mean depth)` pairs
+type PathBins = Vec<(String, Vec<(usize, f64)>)>;
+
+/// A parsed GFA graph
+#[pyclass(name = "Graph", frozen)]
+struct PyGraph {
+    source: PathBuf,
+    graph: Graph,
+}
+
+impl PyGraph {
+    fn args(&self, default_out: &str, options: Vec<String>) -> PyResult<Args> {
+        embedded_args(&self.source, default_out, options)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+}
+
+#[pymethods]
+impl PyGraph {
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        let graph = parse_gfa(&path).map_err(|e| PyIOError::new_err(e.to_string()))?;
+        Ok(PyGraph {
+            source: path,
+            graph,
+        })
+    }
+
+    /// Path names in GFA order
+    #[getter]
+    fn path_names(&self) -> Vec<String> {
+        self.graph.paths.iter().map(|p| p.name.clone()).collect()
+    }
+
+    /// Total sequence length of all segments (the pangenomic x-axis)
+    #[getter]
+    fn total_length(&self) -> u64 {
+        self.graph.total_length
+    }
+
+    /// Number of segments
+    #[getter]
+    fn num_segments(&self) -> usize {
+        self.graph.segments.len()
+    }
+
+    /// Render with gfalook CLI options (e.g. `["-x", "800", "-m"]`) and return
+    /// `(width, height, rgba_bytes)`. A `-r` option renders only that window of the graph.
+    #[pyo3(signature = (options = Vec::new()))]
+    fn render<'py>(
+        &self,
+        py: Python<'py>,
+        options: Vec<String>,
+    ) -> PyResult<(u32, u32, Bound<'py, PyBytes>)> {
+        let args = self.args("gfalook.png", options)?;
+        let buffer = py
+            .allow_threads(|| {
+                let ranged = embedded_graph(&args, &self.graph)?;
+                render(&args, ranged.as_ref().unwrap_or(&self.graph))
+            })
+            .map_err(to_py_err)?;
+        let width = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+        let height = u32::from_le_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]);
+        Ok((width, height, PyBytes::new(py, &buffer[8..])))
+    }
+
+    /// Render with gfalook CLI options and return the SVG document.
+    #[pyo3(signature = (options = Vec::new()))]
+    fn render_svg(&self, py: Python<'_>, options: Vec<String>) -> PyResult<String> {
+        let args = self.args("gfalook.svg", options)?;
+        py.allow_threads(|| {
+            let ranged = embedded_graph(&args, &self.graph)?;
+            render_svg(&args, ranged.as_ref().unwrap_or(&self.graph))
+        })
+        .map_err(to_py_err)
+    }
+
+    /// Mean depth per bin for every path, as `[(path_name, [(bin, depth), ...]), ...]`
+    /// with `width` bins over the pangenomic axis.
+    fn bins(&self, py: Python<'_>, width: u32) -> PyResult<PathBins> {
+        if width == 0 {
+            return Err(PyValueError::new_err("width must be at least 1"));
+        }
+        let graph = &self.graph;
+        let bin_width = graph.total_length.max(1) as f64 / width as f64;
+        Ok(py.allow_threads(|| {
+            graph
+                .paths
+                .iter()
+                .map(|path| {
+                    // The same binning the renderer draws
+                    let mut bins: Vec<(usize, f64)> = path_bins(path, graph, bin_width, None)
+                        .into_iter()
+                        .map(|(bin, info)| (bin, info.mean_depth))
+                        .collect();
+                    bins.sort_unstable_by_key(|&(bin, _)| bin);
+                    (path.name.clone(), bins)
+                })
+                .collect()
+        }))
+    }
+
+    /// Cluster paths by weighted Jaccard similarity, as `-k` does. `threshold` is the
+    /// similarity cutoff, or the tree cut height with `upgma=True`; `level` is `"path"`,
+    /// `"haplotype"` or `"sample"`. Returns `[(path_name, cluster_id), ...]` in cluster order.
+    #[pyo3(signature = (threshold = None, all_nodes = false, max_clusters = None, upgma = false, level = "path"))]
+    fn cluster(
+        &self,
+        py: Python<'_>,
+        threshold: Option<f64>,
+        all_nodes: bool,
+        max_clusters: Option<usize>,
+        upgma: bool,
+        level: &str,
+    ) -> PyResult<Vec<(String, usize)>> {
+        let level = SimilarityLevel::from_str(level, true).map_err(PyValueError::new_err)?;
+        let graph = &self.graph;
+        Ok(py.allow_threads(|| {
+            let paths: Vec<_> = graph.paths.iter().collect();
+            let segment_lengths: Vec<u64> = graph.segments.iter().map(|s| s.sequence_len).collect();
+            let result = cluster_paths_by_similarity(
+                &paths,
+                &segment_lengths,
+                threshold,
+                all_nodes,
+                max_clusters,
+                upgma,
+                upgma,
+                if upgma { threshold } else { None },
+                None,
+                1,
+                1,
+                0,
+                level,
+            );
+            result
+                .ordering
+                .iter()
+                .zip(&result.cluster_ids)
+                .map(|(&i, &cluster)| (paths[i].name.clone(), cluster))
+                .collect()
+        }))
+    }
+}
+
+#[pymodule]
+fn gfalook(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyGraph>()?;
+    Ok(())
+}
@@ -0,0 +1,45 @@
+//! `--svg-interactive`: wrap the figure in a group that a small inline script pans (drag)
+//! and zooms (mouse wheel, double-click to reset), so the SVG can be explored in a browser
+//! as is.
+
+/// Opens the group the script transforms; everything but the background goes inside it
+pub const VIEWPORT_OPEN: &str = "<g id=\"viewport\">\n";
+
+const SCRIPT: &str = r#"(function () {
+  var svg = document.documentElement, viewport = document.getElementById('viewport');
+  var scale = 1, tx = 0, ty = 0, drag = null;
+  function apply() {
+    viewport.setAttribute('transform', 'translate(' + tx + ',' + ty + ') scale(' + scale + ')');
+  }
+  function toSvg(e) {
+    var pt = svg.createSVGPoint();
+    pt.x = e.clientX;
+    pt.y = e.clientY;
+    return pt.matrixTransform(svg.getScreenCTM().inverse());
+  }
+  svg.addEventListener('wheel', function (e) {
+    e.preventDefault();
+    var p = toSvg(e), k = e.deltaY < 0 ? 1.2 : 1 / 1.2;
+    tx = p.x - (p.x - tx) * k;
+    ty = p.y - (p.y - ty) * k;
+    scale *= k;
+    apply();
+  }, { passive: false });
+  svg.addEventListener('mousedown', function (e) {
+    drag = { p: toSvg(e), tx: tx, ty: ty };
+  });
+  window.addEventListener('mousemove', function (e) {
+    if (!drag) return;
+    var p = toSvg(e);
+    tx = drag.tx + p.x - drag.p.x;
+    ty = drag.ty + p.y - drag.p.y;
+    apply();
+  });
+  window.addEventListener('mouseup', function () { drag = null; });
+  svg.addEventListener('dblclick', function () { scale = 1; tx = 0; ty = 0; apply(); });
+})();"#;
+
+/// Closes the viewport group and appends the pan/zoom script
+pub fn close_viewport() -> String {
+    format!("</g>\n<script><![CDATA[\n{}\n]]></script>\n", SCRIPT)
+}